@@ -0,0 +1,21 @@
+// Service worker for Web Push. The server sends the JSON built by
+// notify::web_push::video_payload: { title, body, url, icon, image, tag }.
+
+self.addEventListener('push', (event) => {
+  const data = event.data ? event.data.json() : {}
+  event.waitUntil(
+    self.registration.showNotification(data.title || 'youtube-sub-feed', {
+      body: data.body,
+      icon: data.icon || undefined,
+      image: data.image || undefined,
+      tag: data.tag,
+      data: { url: data.url },
+    }),
+  )
+})
+
+self.addEventListener('notificationclick', (event) => {
+  event.notification.close()
+  const url = event.notification.data?.url
+  if (url) event.waitUntil(self.clients.openWindow(url))
+})
@@ -0,0 +1,57 @@
+import config from '$lib/config.js'
+import fetcher from '$lib/fetcher.js'
+import { getBasePath } from '$lib/router.svelte.js'
+
+// Decode a base64url VAPID key into the Uint8Array PushManager expects.
+export function urlBase64ToUint8Array(value) {
+  const base64 = (value + '='.repeat((4 - (value.length % 4)) % 4))
+    .replace(/-/g, '+')
+    .replace(/_/g, '/')
+  return Uint8Array.from(atob(base64), (c) => c.charCodeAt(0))
+}
+
+export function isPushSupported() {
+  return (
+    typeof navigator !== 'undefined' &&
+    'serviceWorker' in navigator &&
+    typeof window !== 'undefined' &&
+    'PushManager' in window
+  )
+}
+
+async function registration() {
+  return navigator.serviceWorker.register(`${getBasePath()}/sw.js`)
+}
+
+export async function currentSubscription() {
+  if (!isPushSupported()) return null
+  const reg = await navigator.serviceWorker.getRegistration(`${getBasePath()}/`)
+  return reg ? reg.pushManager.getSubscription() : null
+}
+
+export async function enablePush() {
+  const permission = await Notification.requestPermission()
+  if (permission !== 'granted') throw new Error('通知が許可されていません')
+  const { public_key } = await fetcher(`${config.path.api}/push/vapid-public-key`)
+  const reg = await registration()
+  const subscription = await reg.pushManager.subscribe({
+    userVisibleOnly: true,
+    applicationServerKey: urlBase64ToUint8Array(public_key),
+  })
+  return fetcher(`${config.path.api}/push/subscriptions`, {
+    method: 'POST',
+    headers: { 'Content-Type': 'application/json' },
+    body: JSON.stringify(subscription.toJSON()),
+  })
+}
+
+export async function disablePush() {
+  const subscription = await currentSubscription()
+  if (!subscription) return
+  const registered = await fetcher(`${config.path.api}/push/subscriptions`)
+  const mine = registered.find((s) => s.endpoint === subscription.endpoint)
+  if (mine) {
+    await fetcher(`${config.path.api}/push/subscriptions/${mine.id}`, { method: 'DELETE' })
+  }
+  await subscription.unsubscribe()
+}
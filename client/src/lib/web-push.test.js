@@ -0,0 +1,18 @@
+import { describe, test, expect, vi } from 'vitest'
+
+vi.mock('$lib/router.svelte.js', () => ({ getBasePath: () => '', navigate: vi.fn() }))
+
+import { urlBase64ToUint8Array } from './web-push.js'
+
+describe('urlBase64ToUint8Array', () => {
+  test('decodes unpadded base64url', () => {
+    expect(Array.from(urlBase64ToUint8Array('-_8'))).toEqual([0xfb, 0xff])
+  })
+
+  test('decodes a 65-byte uncompressed P-256 key', () => {
+    const key = 'B' + 'A'.repeat(86)
+    const bytes = urlBase64ToUint8Array(key)
+    expect(bytes.length).toBe(65)
+    expect(bytes[0]).toBe(4)
+  })
+})
@@ -0,0 +1,270 @@
+//! Personal API tokens for scripts and third-party clients.
+//!
+//! A token is `ysf_` followed by 64 hex characters. Only its SHA-256 is stored
+//! (the plaintext is shown once, on creation), next to a short prefix the UI
+//! can display. `auth_middleware` accepts it as `Authorization: Bearer …`;
+//! [`authorize`] resolves the owner and checks the scope the request needs:
+//!
+//! - `feed:read`: GET on the feed, history, Watch Later, news, channels,
+//!   groups and `/api/auth/me`
+//! - `videos:hide`: hide / unhide a video, bulk hide and its undo, edit the
+//!   Watch Later queue, mark the feed as seen
+//! - `channels:manage`: add, update, remove and sync channels and groups
+//! - `admin`: everything above plus the remaining endpoints (settings,
+//!   notifications, and `/api/admin/*` for a master user)
+//!
+//! Token management itself (`/api/tokens`) is never reachable with a token,
+//! so a leaked token cannot mint new ones.
+
+use crate::error::AppError;
+use axum::http::Method;
+use rand::RngCore;
+use rusqlite::{Connection, OptionalExtension};
+use sha2::{Digest, Sha256};
+
+pub const FEED_READ: &str = "feed:read";
+pub const VIDEOS_HIDE: &str = "videos:hide";
+pub const CHANNELS_MANAGE: &str = "channels:manage";
+pub const ADMIN: &str = "admin";
+pub const SCOPES: [&str; 4] = [FEED_READ, VIDEOS_HIDE, CHANNELS_MANAGE, ADMIN];
+
+const TOKEN_PREFIX: &str = "ysf_";
+/// `last_used_at` is only rewritten when older than this, so a busy script
+/// does not turn every request into a write.
+const LAST_USED_RESOLUTION_SECS: i64 = 60;
+
+/// A fresh token: `ysf_` + 32 random bytes in hex.
+pub fn generate() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    format!("{TOKEN_PREFIX}{}", hex::encode(bytes))
+}
+
+/// What is stored: the token's SHA-256 in hex.
+pub fn hash(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// The leading characters shown in token lists to tell tokens apart.
+pub fn display_prefix(token: &str) -> String {
+    token.chars().take(TOKEN_PREFIX.len() + 6).collect()
+}
+
+/// Scope a request needs, or `None` when tokens may not call it at all.
+pub fn required_scope(method: &Method, path: &str) -> Option<&'static str> {
+    let under = |prefix: &str| {
+        path == prefix
+            || path
+                .strip_prefix(prefix)
+                .is_some_and(|rest| rest.starts_with('/'))
+    };
+    if under("/api/tokens") {
+        return None;
+    }
+    if path.starts_with("/api/videos/") && (path.ends_with("/hide") || path.ends_with("/unhide")) {
+        return Some(VIDEOS_HIDE);
+    }
+    if under("/api/videos/bulk-hide") || under("/api/feed/last-seen") {
+        return Some(VIDEOS_HIDE);
+    }
+    if *method != Method::GET && under("/api/watch-later") {
+        return Some(VIDEOS_HIDE);
+    }
+    let content = ["/api/channels", "/api/groups"].into_iter().any(under);
+    if *method == Method::GET
+        && (content
+            || [
+                "/api/feed",
+                "/api/history",
+                "/api/search",
+                "/api/watch-later",
+                "/api/news",
+                "/api/auth/me",
+            ]
+            .into_iter()
+            .any(under))
+    {
+        return Some(FEED_READ);
+    }
+    if content {
+        return Some(CHANNELS_MANAGE);
+    }
+    Some(ADMIN)
+}
+
+/// Resolve a bearer token to its (active) owner and check that it carries
+/// the scope the request needs. Records the use.
+pub fn authorize(
+    conn: &Connection,
+    token: &str,
+    method: &Method,
+    path: &str,
+    now: i64,
+) -> Result<i64, AppError> {
+    let row: Option<(i64, i64, String, Option<i64>)> = conn
+        .query_row(
+            "SELECT t.id, t.user_id, t.scopes, t.last_used_at
+             FROM api_tokens t
+             JOIN users u ON u.id = t.user_id
+             WHERE t.token_hash = ?1
+               AND (t.expires_at IS NULL OR t.expires_at > ?2)
+               AND u.disabled_at IS NULL",
+            rusqlite::params![hash(token), now],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )
+        .optional()?;
+    let Some((id, user_id, scopes, last_used_at)) = row else {
+        return Err(AppError::Unauthorized(
+            "Invalid or expired token".to_string(),
+        ));
+    };
+
+    let granted: Vec<&str> = scopes.split(',').collect();
+    match required_scope(method, path) {
+        Some(scope) if granted.contains(&scope) || granted.contains(&ADMIN) => {}
+        Some(scope) => {
+            return Err(AppError::Forbidden(format!(
+                "Token lacks the {scope} scope"
+            )))
+        }
+        None => {
+            return Err(AppError::Forbidden(
+                "API tokens cannot manage tokens".to_string(),
+            ))
+        }
+    }
+
+    if last_used_at.is_none_or(|t| now - t >= LAST_USED_RESOLUTION_SECS) {
+        conn.execute(
+            "UPDATE api_tokens SET last_used_at = ?1 WHERE id = ?2",
+            rusqlite::params![now, id],
+        )?;
+    }
+    Ok(user_id)
+}
+
+#[cfg(test)]
+mod tests {
+    // API Token Spec
+    //
+    // - Tokens are ysf_ + 64 hex; only the SHA-256 is stored.
+    // - Each request maps to one scope; `admin` grants every scope; token
+    //   management is unreachable with a token.
+    // - Expired tokens and tokens of disabled users are rejected; a valid use
+    //   updates last_used_at (at most once a minute).
+
+    use super::*;
+
+    #[test]
+    fn generated_tokens_are_prefixed_random_hex() {
+        let token = generate();
+        assert!(token.starts_with("ysf_"));
+        assert_eq!(token.len(), 4 + 64);
+        assert_ne!(token, generate());
+        assert_eq!(hash(&token).len(), 64);
+        assert_eq!(display_prefix(&token).len(), 10);
+    }
+
+    #[test]
+    fn requests_map_to_scopes() {
+        let cases = [
+            (Method::GET, "/api/feed", Some(FEED_READ)),
+            (Method::GET, "/api/channels/UC1/videos", Some(FEED_READ)),
+            (Method::GET, "/api/groups", Some(FEED_READ)),
+            (Method::GET, "/api/auth/me", Some(FEED_READ)),
+            (Method::GET, "/api/search", Some(FEED_READ)),
+            (Method::PATCH, "/api/videos/v1/hide", Some(VIDEOS_HIDE)),
+            (Method::PATCH, "/api/videos/v1/unhide", Some(VIDEOS_HIDE)),
+            (
+                Method::POST,
+                "/api/videos/bulk-hide/undo",
+                Some(VIDEOS_HIDE),
+            ),
+            (Method::GET, "/api/feed/unread-counts", Some(FEED_READ)),
+            (Method::GET, "/api/feed/new-count", Some(FEED_READ)),
+            (Method::PUT, "/api/feed/last-seen", Some(VIDEOS_HIDE)),
+            (Method::GET, "/api/watch-later/play-all", Some(FEED_READ)),
+            (Method::POST, "/api/watch-later/next", Some(VIDEOS_HIDE)),
+            (Method::DELETE, "/api/watch-later/v1", Some(VIDEOS_HIDE)),
+            (Method::POST, "/api/channels", Some(CHANNELS_MANAGE)),
+            (Method::PUT, "/api/groups/1/channels", Some(CHANNELS_MANAGE)),
+            (Method::GET, "/api/webhooks", Some(ADMIN)),
+            (Method::PATCH, "/api/auth/me", Some(ADMIN)),
+            (Method::GET, "/api/admin/users", Some(ADMIN)),
+            (Method::GET, "/api/feedback", Some(ADMIN)),
+            (Method::GET, "/api/tokens", None),
+            (Method::DELETE, "/api/tokens/1", None),
+        ];
+        for (method, path, scope) in cases {
+            assert_eq!(required_scope(&method, path), scope, "{method} {path}");
+        }
+    }
+
+    fn setup(scopes: &str, expires_at: Option<i64>) -> (Connection, String) {
+        let conn = crate::db::open_memory();
+        conn.execute("INSERT INTO users (email) VALUES ('a@example.com')", [])
+            .unwrap();
+        let token = generate();
+        conn.execute(
+            "INSERT INTO api_tokens (user_id, name, token_hash, token_prefix, scopes, expires_at, created_at)
+             VALUES (1, 'cli', ?1, ?2, ?3, ?4, 0)",
+            rusqlite::params![hash(&token), display_prefix(&token), scopes, expires_at],
+        )
+        .unwrap();
+        (conn, token)
+    }
+
+    fn last_used(conn: &Connection) -> Option<i64> {
+        conn.query_row("SELECT last_used_at FROM api_tokens", [], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn scoped_token_is_limited_to_its_scopes() {
+        let (conn, token) = setup("feed:read,videos:hide", None);
+        assert_eq!(
+            authorize(&conn, &token, &Method::GET, "/api/feed", 1000).unwrap(),
+            1
+        );
+        assert_eq!(last_used(&conn), Some(1000));
+        assert!(authorize(&conn, &token, &Method::PATCH, "/api/videos/v/hide", 1010).is_ok());
+        assert_eq!(last_used(&conn), Some(1000), "recorded once a minute");
+        assert!(matches!(
+            authorize(&conn, &token, &Method::POST, "/api/channels", 1100),
+            Err(AppError::Forbidden(_))
+        ));
+        assert!(matches!(
+            authorize(&conn, "ysf_wrong", &Method::GET, "/api/feed", 1100),
+            Err(AppError::Unauthorized(_))
+        ));
+    }
+
+    #[test]
+    fn admin_scope_grants_everything_but_token_management() {
+        let (conn, token) = setup("admin", None);
+        assert!(authorize(&conn, &token, &Method::POST, "/api/channels", 0).is_ok());
+        assert!(authorize(&conn, &token, &Method::PUT, "/api/digest", 0).is_ok());
+        assert!(matches!(
+            authorize(&conn, &token, &Method::POST, "/api/tokens", 0),
+            Err(AppError::Forbidden(_))
+        ));
+    }
+
+    #[test]
+    fn expired_token_and_disabled_owner_are_rejected() {
+        let (conn, token) = setup("feed:read", Some(2000));
+        assert!(authorize(&conn, &token, &Method::GET, "/api/feed", 1999).is_ok());
+        assert!(matches!(
+            authorize(&conn, &token, &Method::GET, "/api/feed", 2000),
+            Err(AppError::Unauthorized(_))
+        ));
+
+        let (conn, token) = setup("feed:read", None);
+        conn.execute("UPDATE users SET disabled_at = 1", [])
+            .unwrap();
+        assert!(matches!(
+            authorize(&conn, &token, &Method::GET, "/api/feed", 0),
+            Err(AppError::Unauthorized(_))
+        ));
+    }
+}
@@ -0,0 +1,78 @@
+//! Audit log of user and system actions, shown to the master by
+//! `/api/admin/audit-log`.
+//!
+//! Entries are written by the code that makes the change, on the same
+//! connection (and inside its transaction when there is one), so the log
+//! records exactly what was committed. `user_id` is the acting user, or NULL
+//! for the server's own actions (WebSub hub traffic). It is deliberately not
+//! a foreign key: entries outlive the users they mention.
+
+use rusqlite::Connection;
+use serde_json::{json, Value};
+
+pub const CHANNEL_ADD: &str = "channel.add";
+pub const CHANNEL_REMOVE: &str = "channel.remove";
+/// A subscription sync diff (browser sync, account import, account deletion).
+pub const CHANNEL_SYNC: &str = "channel.sync";
+pub const GROUP_CREATE: &str = "group.create";
+pub const GROUP_UPDATE: &str = "group.update";
+pub const GROUP_DELETE: &str = "group.delete";
+pub const GROUP_REORDER: &str = "group.reorder";
+pub const GROUP_CHANNELS: &str = "group.channels";
+pub const VIDEO_HIDE: &str = "video.hide";
+pub const VIDEO_UNHIDE: &str = "video.unhide";
+/// A bulk hide (`/api/videos/bulk-hide`), with the filter and the count.
+pub const VIDEO_BULK_HIDE: &str = "video.bulk_hide";
+/// An undone bulk hide, with the number of videos restored.
+pub const VIDEO_BULK_UNDO: &str = "video.bulk_undo";
+pub const TOKEN_CREATE: &str = "token.create";
+pub const TOKEN_DELETE: &str = "token.delete";
+pub const RSS_TOKEN_ROTATE: &str = "rss_token.rotate";
+pub const RSS_TOKEN_REVOKE: &str = "rss_token.revoke";
+pub const FEED_TOKEN_CREATE: &str = "feed_token.create";
+pub const FEED_TOKEN_ROTATE: &str = "feed_token.rotate";
+pub const FEED_TOKEN_DELETE: &str = "feed_token.delete";
+pub const USER_INVITE: &str = "user.invite";
+pub const USER_UPDATE: &str = "user.update";
+pub const USER_DELETE: &str = "user.delete";
+pub const ACCOUNT_IMPORT: &str = "account.import";
+pub const ACCOUNT_DELETE: &str = "account.delete";
+/// Outcome of a subscribe request sent to the hub (new channel or renewal).
+pub const WEBSUB_SUBSCRIBE: &str = "websub.subscribe";
+/// Outcome of an unsubscribe request sent to the hub.
+pub const WEBSUB_UNSUBSCRIBE: &str = "websub.unsubscribe";
+/// The hub's verification callback (subscription confirmed or removed).
+pub const WEBSUB_VERIFY: &str = "websub.verify";
+
+/// Append an entry. Never fails the caller: a full or locked log must not
+/// break the action it describes.
+pub fn record(conn: &Connection, user_id: Option<i64>, action: &str, target: &str, detail: Value) {
+    let detail = (!detail.is_null()).then(|| detail.to_string());
+    if let Err(e) = conn.execute(
+        "INSERT INTO audit_log (user_id, action, target, detail, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+        rusqlite::params![user_id, action, target, detail, crate::util::now_unix()],
+    ) {
+        tracing::warn!("[audit] failed to record {}: {}", action, e);
+    }
+}
+
+/// `{channel_id, title}` of a channel, for entries about channels that may be
+/// deleted right after.
+pub fn channel_ref(conn: &Connection, channel_id: &str) -> Value {
+    let title: Option<String> = conn
+        .query_row(
+            "SELECT title FROM channels WHERE id = ?1",
+            [channel_id],
+            |row| row.get(0),
+        )
+        .ok();
+    json!({"channel_id": channel_id, "title": title})
+}
+
+/// Hub request outcome as entry detail.
+pub fn hub_outcome<E: std::fmt::Display>(result: &Result<(), E>) -> Value {
+    match result {
+        Ok(()) => json!({"ok": true}),
+        Err(e) => json!({"ok": false, "error": e.to_string()}),
+    }
+}
@@ -51,6 +51,18 @@ impl Cache {
         );
     }
 
+    pub fn remove(&self, key: &str) {
+        self.store.lock().unwrap().remove(key);
+    }
+
+    /// Drop every entry whose key starts with `prefix`.
+    pub fn remove_prefix(&self, prefix: &str) {
+        self.store
+            .lock()
+            .unwrap()
+            .retain(|key, _| !key.starts_with(prefix));
+    }
+
     fn sweep(&self) {
         let mut store = self.store.lock().unwrap();
         let now = Instant::now();
@@ -76,7 +88,8 @@ mod tests {
     // Cache Spec
     //
     // serde_json::Value-based TTL in-memory cache. Max 10,000 entries.
-    // Thread-safe with Mutex. Sweeps every hour.
+    // Thread-safe with Mutex. Sweeps every hour. Entries can be dropped by
+    // key or key prefix for explicit invalidation.
 
     use super::*;
     use serde_json::json;
@@ -106,6 +119,20 @@ mod tests {
         assert_eq!(store.len(), MAX_ENTRIES);
     }
 
+    #[test]
+    fn test_remove_and_remove_prefix() {
+        let cache = Cache::new();
+        cache.set("a:1", json!(1), None);
+        cache.set("a:2", json!(2), None);
+        cache.set("b:1", json!(3), None);
+        cache.remove("a:1");
+        assert_eq!(cache.get("a:1"), None);
+        assert_eq!(cache.get("a:2"), Some(json!(2)));
+        cache.remove_prefix("a:");
+        assert_eq!(cache.get("a:2"), None);
+        assert_eq!(cache.get("b:1"), Some(json!(3)));
+    }
+
     #[test]
     fn test_ttl_override() {
         let cache = Cache::new();
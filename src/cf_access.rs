@@ -0,0 +1,476 @@
+//! Cloudflare Access JWT verification.
+//!
+//! Access signs every request it lets through with a `Cf-Access-Jwt-Assertion`
+//! header (RS256). When `CF_ACCESS_TEAM_DOMAIN` and `CF_ACCESS_AUD` are set,
+//! the auth middleware only trusts the `email` claim of a token that verifies
+//! against the team's JWKS with the expected issuer and audience. The plain
+//! `Cf-Access-Authenticated-User-Email` header is then ignored, so a request
+//! that reaches the origin without going through Cloudflare cannot spoof it.
+//! Signature and claims are checked by `jsonwebtoken`; this module only
+//! fetches and caches the keys.
+//!
+//! The JWKS is kept in the shared [`Cache`](crate::cache::Cache) and re-fetched
+//! after [`JWKS_TTL_SECS`]. Access rotates its signing keys and publishes the
+//! new key before using it, so a token whose `kid` is unknown triggers an
+//! immediate re-fetch — at most once per [`REFETCH_COOLDOWN_SECS`], so tokens
+//! with made-up `kid`s cannot hammer the endpoint. If the endpoint is down,
+//! the last fetched keys stay in use.
+
+use crate::state::AppState;
+use jsonwebtoken::errors::ErrorKind;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use serde_json::{json, Value};
+
+/// How long a fetched JWKS is used before it is re-fetched.
+pub const JWKS_TTL_SECS: i64 = 60 * 60;
+/// Minimum gap between fetches triggered by a stale cache or an unknown `kid`.
+pub const REFETCH_COOLDOWN_SECS: u64 = 30;
+/// Clock skew tolerated on `exp` / `nbf`.
+const LEEWAY_SECS: u64 = 60;
+const JWKS_CACHE_KEY: &str = "cf_access:jwks";
+const REFETCH_CACHE_KEY: &str = "cf_access:jwks_refetch";
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CfAccessConfig {
+    /// `https://<team>.cloudflareaccess.com` — the expected `iss`.
+    pub issuer: String,
+    /// Application Audience (AUD) tag — must appear in `aud`.
+    pub audience: String,
+    /// JWKS endpoint, `<issuer>/cdn-cgi/access/certs` unless overridden.
+    pub certs_url: String,
+}
+
+impl CfAccessConfig {
+    /// `team_domain` may be the bare team name, the
+    /// `<team>.cloudflareaccess.com` host or the full issuer URL.
+    pub fn new(team_domain: &str, audience: &str, certs_url: Option<String>) -> Self {
+        let domain = team_domain.trim().trim_end_matches('/');
+        let issuer = if domain.contains("://") {
+            domain.to_string()
+        } else if domain.contains('.') {
+            format!("https://{domain}")
+        } else {
+            format!("https://{domain}.cloudflareaccess.com")
+        };
+        let certs_url = certs_url.unwrap_or_else(|| format!("{issuer}/cdn-cgi/access/certs"));
+        Self {
+            issuer,
+            audience: audience.trim().to_string(),
+            certs_url,
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum AccessError {
+    #[error("malformed token")]
+    Malformed,
+    #[error("unsupported algorithm {0}")]
+    UnsupportedAlgorithm(String),
+    #[error("no signing key with kid {0}")]
+    UnknownKey(String),
+    #[error("invalid signature")]
+    BadSignature,
+    #[error("token expired")]
+    Expired,
+    #[error("token not yet valid")]
+    NotYetValid,
+    #[error("unexpected issuer")]
+    WrongIssuer,
+    #[error("unexpected audience")]
+    WrongAudience,
+    #[error("token has no email (service token?)")]
+    NoEmail,
+}
+
+/// The RSA key with `kid` from a JWKS `keys` array.
+fn find_key(keys: &Value, kid: &str) -> Option<DecodingKey> {
+    let jwk = keys
+        .as_array()?
+        .iter()
+        .find(|k| k["kid"].as_str() == Some(kid) && k["kty"].as_str() == Some("RSA"))?;
+    DecodingKey::from_rsa_components(jwk["n"].as_str()?, jwk["e"].as_str()?).ok()
+}
+
+async fn fetch_keys(http: &reqwest::Client, url: &str) -> Result<Value, reqwest::Error> {
+    let jwks: Value = http
+        .get(url)
+        .timeout(std::time::Duration::from_secs(10))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    Ok(jwks["keys"].clone())
+}
+
+/// Signing key for `kid`, from the cache or a (rate-limited) JWKS fetch.
+async fn signing_key(
+    state: &AppState,
+    config: &CfAccessConfig,
+    kid: &str,
+) -> Result<DecodingKey, AccessError> {
+    let now = crate::util::now_unix();
+    let mut cached = state.cache.get(JWKS_CACHE_KEY);
+    let fresh = cached
+        .as_ref()
+        .and_then(|c| c["fetched_at"].as_i64())
+        .is_some_and(|fetched_at| now - fetched_at < JWKS_TTL_SECS);
+    if fresh {
+        if let Some(key) = cached.as_ref().and_then(|c| find_key(&c["keys"], kid)) {
+            return Ok(key);
+        }
+    }
+
+    // Stale, never fetched, or a key we have not seen yet (rotation).
+    if state.cache.get(REFETCH_CACHE_KEY).is_none() {
+        state
+            .cache
+            .set(REFETCH_CACHE_KEY, json!(true), Some(REFETCH_COOLDOWN_SECS));
+        match fetch_keys(&state.http, &config.certs_url).await {
+            Ok(keys) => {
+                let entry = json!({"fetched_at": now, "keys": keys});
+                state.cache.set(JWKS_CACHE_KEY, entry.clone(), None);
+                cached = Some(entry);
+            }
+            Err(e) => tracing::warn!("[cf-access] failed to fetch JWKS: {}", e),
+        }
+    }
+
+    cached
+        .and_then(|c| find_key(&c["keys"], kid))
+        .ok_or_else(|| AccessError::UnknownKey(kid.to_string()))
+}
+
+fn validation(config: &CfAccessConfig) -> Validation {
+    // Only RS256: never let the token pick a weaker (or no) algorithm.
+    let mut validation = Validation::new(Algorithm::RS256);
+    validation.leeway = LEEWAY_SECS;
+    validation.validate_nbf = true;
+    validation.set_issuer(&[&config.issuer]);
+    validation.set_audience(&[&config.audience]);
+    validation.set_required_spec_claims(&["exp", "iss", "aud"]);
+    validation
+}
+
+fn access_error(e: jsonwebtoken::errors::Error) -> AccessError {
+    match e.kind() {
+        ErrorKind::InvalidSignature => AccessError::BadSignature,
+        ErrorKind::InvalidAlgorithm => AccessError::UnsupportedAlgorithm("not RS256".to_string()),
+        ErrorKind::ExpiredSignature => AccessError::Expired,
+        ErrorKind::ImmatureSignature => AccessError::NotYetValid,
+        ErrorKind::InvalidIssuer => AccessError::WrongIssuer,
+        ErrorKind::InvalidAudience => AccessError::WrongAudience,
+        ErrorKind::MissingRequiredClaim(claim) => match claim.as_str() {
+            "iss" => AccessError::WrongIssuer,
+            "aud" => AccessError::WrongAudience,
+            _ => AccessError::Expired,
+        },
+        _ => AccessError::Malformed,
+    }
+}
+
+/// Verify a `Cf-Access-Jwt-Assertion` token and return its email.
+pub async fn verify(
+    state: &AppState,
+    config: &CfAccessConfig,
+    token: &str,
+) -> Result<String, AccessError> {
+    let token = token.trim();
+    // `alg: none` is not an `Algorithm` at all, so it fails here already.
+    let header = jsonwebtoken::decode_header(token).map_err(|_| AccessError::Malformed)?;
+    if header.alg != Algorithm::RS256 {
+        return Err(AccessError::UnsupportedAlgorithm(format!(
+            "{:?}",
+            header.alg
+        )));
+    }
+    let kid = header.kid.unwrap_or_default();
+    let key = signing_key(state, config, &kid).await?;
+    let claims = jsonwebtoken::decode::<Value>(token, &key, &validation(config))
+        .map_err(access_error)?
+        .claims;
+    claims["email"]
+        .as_str()
+        .filter(|email| !email.is_empty())
+        .map(str::to_string)
+        .ok_or(AccessError::NoEmail)
+}
+
+/// Locally generated signing keys and tokens (tests only).
+#[cfg(test)]
+pub(crate) mod testing {
+    use super::*;
+    use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    use base64::Engine;
+    use rsa::pkcs1v15::SigningKey;
+    use rsa::signature::{SignatureEncoding, Signer};
+    use rsa::traits::PublicKeyParts;
+    use rsa::RsaPrivateKey;
+    use std::sync::OnceLock;
+
+    pub const ISSUER: &str = "https://team.cloudflareaccess.com";
+    pub const AUDIENCE: &str = "aud-tag";
+
+    /// Two 2048-bit keys (ring refuses shorter RSA keys), generated once per
+    /// test run (keygen is slow unoptimised).
+    pub fn keys() -> &'static [RsaPrivateKey; 2] {
+        static KEYS: OnceLock<[RsaPrivateKey; 2]> = OnceLock::new();
+        KEYS.get_or_init(|| {
+            let mut rng = rand::thread_rng();
+            [
+                RsaPrivateKey::new(&mut rng, 2048).unwrap(),
+                RsaPrivateKey::new(&mut rng, 2048).unwrap(),
+            ]
+        })
+    }
+
+    /// JWKS document publishing each key under its `kid`.
+    pub fn jwks(keys: &[(&str, &RsaPrivateKey)]) -> String {
+        let keys: Vec<Value> = keys
+            .iter()
+            .map(|(kid, key)| {
+                json!({
+                    "kid": kid,
+                    "kty": "RSA",
+                    "alg": "RS256",
+                    "use": "sig",
+                    "n": URL_SAFE_NO_PAD.encode(key.n().to_bytes_be()),
+                    "e": URL_SAFE_NO_PAD.encode(key.e().to_bytes_be()),
+                })
+            })
+            .collect();
+        json!({"keys": keys}).to_string()
+    }
+
+    pub fn sign(kid: &str, key: &RsaPrivateKey, claims: &Value) -> String {
+        let signing_input = format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(json!({"alg": "RS256", "kid": kid, "typ": "JWT"}).to_string()),
+            URL_SAFE_NO_PAD.encode(claims.to_string())
+        );
+        let signature = SigningKey::<sha2::Sha256>::new(key.clone()).sign(signing_input.as_bytes());
+        format!(
+            "{signing_input}.{}",
+            URL_SAFE_NO_PAD.encode(signature.to_vec())
+        )
+    }
+
+    pub fn claims(email: &str) -> Value {
+        let now = crate::util::now_unix();
+        json!({
+            "iss": ISSUER,
+            "aud": [AUDIENCE],
+            "email": email,
+            "iat": now,
+            "nbf": now,
+            "exp": now + 3600,
+        })
+    }
+
+    pub fn config(certs_url: String) -> CfAccessConfig {
+        CfAccessConfig::new(ISSUER, AUDIENCE, Some(certs_url))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // Cloudflare Access JWT Spec
+    //
+    // - Only RS256 tokens whose signature verifies against the JWKS key named
+    //   by `kid` are accepted; `iss` must equal the team issuer, `aud` must
+    //   contain the application AUD tag, exp/nbf are checked (60s leeway), and
+    //   the `email` claim is returned. `alg: none`, HS256 (even keyed with
+    //   the public key) and a `kid` missing from the JWKS are rejected.
+    // - The JWKS is fetched once and cached; an unknown kid re-fetches it
+    //   (rotation), at most once per cooldown. A stale cache is still used
+    //   when the endpoint is unreachable.
+
+    use super::testing::*;
+    use super::*;
+    use crate::notify::stand_in::HttpStandIn;
+    use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    use base64::Engine;
+
+    #[test]
+    fn config_accepts_team_name_host_or_issuer_url() {
+        for domain in [
+            "team",
+            "team.cloudflareaccess.com",
+            "https://team.cloudflareaccess.com/",
+        ] {
+            let config = CfAccessConfig::new(domain, " aud-tag ", None);
+            assert_eq!(config.issuer, "https://team.cloudflareaccess.com");
+            assert_eq!(config.audience, "aud-tag");
+            assert_eq!(
+                config.certs_url,
+                "https://team.cloudflareaccess.com/cdn-cgi/access/certs"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn valid_token_yields_its_email_and_jwks_is_cached() {
+        let [key, _] = keys();
+        let jwks_server = HttpStandIn::serving(jwks(&[("k1", key)])).await;
+        let state = AppState::test();
+        let config = config(jwks_server.url("/certs"));
+
+        let token = sign("k1", key, &claims("alice@example.com"));
+        for _ in 0..2 {
+            let email = verify(&state, &config, &token).await.unwrap();
+            assert_eq!(email, "alice@example.com");
+        }
+        assert_eq!(jwks_server.requests().len(), 1, "keys are cached");
+    }
+
+    #[tokio::test]
+    async fn forged_or_mismatched_tokens_are_rejected() {
+        let [key, other] = keys();
+        let jwks_server = HttpStandIn::serving(jwks(&[("k1", key)])).await;
+        let state = AppState::test();
+        let config = config(jwks_server.url("/certs"));
+        let now = crate::util::now_unix();
+
+        let mut wrong_issuer = claims("a@example.com");
+        wrong_issuer["iss"] = json!("https://evil.cloudflareaccess.com");
+        let mut wrong_audience = claims("a@example.com");
+        wrong_audience["aud"] = json!(["other-app"]);
+        let mut expired = claims("a@example.com");
+        expired["exp"] = json!(now - 120);
+        let mut not_yet = claims("a@example.com");
+        not_yet["nbf"] = json!(now + 600);
+        let mut service_token = claims("a@example.com");
+        service_token.as_object_mut().unwrap().remove("email");
+
+        let valid = sign("k1", key, &claims("a@example.com"));
+        let (head, _) = valid.split_once('.').unwrap();
+        let tampered = format!(
+            "{head}.{}.{}",
+            URL_SAFE_NO_PAD.encode(claims("admin@example.com").to_string()),
+            valid.rsplit('.').next().unwrap()
+        );
+        let alg_none = format!(
+            "{}.{}.",
+            URL_SAFE_NO_PAD.encode(r#"{"alg":"none","kid":"k1"}"#),
+            URL_SAFE_NO_PAD.encode(claims("a@example.com").to_string())
+        );
+
+        let cases = [
+            (sign("k1", other, &claims("a@example.com")), "wrong key"),
+            (tampered, "tampered claims"),
+            (alg_none, "alg none"),
+            (sign("k1", key, &wrong_issuer), "issuer"),
+            (sign("k1", key, &wrong_audience), "audience"),
+            (sign("k1", key, &expired), "expired"),
+            (sign("k1", key, &not_yet), "nbf"),
+            (sign("k1", key, &service_token), "no email"),
+            ("garbage".to_string(), "malformed"),
+        ];
+        for (token, case) in cases {
+            assert!(
+                verify(&state, &config, &token).await.is_err(),
+                "{case} must be rejected"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn other_algorithms_and_unknown_kids_are_rejected() {
+        let [key, _] = keys();
+        let jwks_server = HttpStandIn::serving(jwks(&[("k1", key)])).await;
+        let state = AppState::test();
+        let config = config(jwks_server.url("/certs"));
+        let claims = claims("a@example.com");
+
+        let alg_none = format!(
+            "{}.{}.",
+            URL_SAFE_NO_PAD.encode(r#"{"alg":"none","kid":"k1","typ":"JWT"}"#),
+            URL_SAFE_NO_PAD.encode(claims.to_string())
+        );
+        assert!(matches!(
+            verify(&state, &config, &alg_none).await,
+            Err(AccessError::Malformed)
+        ));
+
+        // Key confusion: HMAC keyed with the published modulus.
+        let mut header = jsonwebtoken::Header::new(Algorithm::HS256);
+        header.kid = Some("k1".to_string());
+        let jwks_doc: Value = serde_json::from_str(&jwks(&[("k1", key)])).unwrap();
+        let secret = jwks_doc["keys"][0]["n"].as_str().unwrap();
+        let hs256 = jsonwebtoken::encode(
+            &header,
+            &claims,
+            &jsonwebtoken::EncodingKey::from_secret(secret.as_bytes()),
+        )
+        .unwrap();
+        assert!(matches!(
+            verify(&state, &config, &hs256).await,
+            Err(AccessError::UnsupportedAlgorithm(_))
+        ));
+
+        let wrong_kid = sign("k2", key, &claims);
+        assert!(matches!(
+            verify(&state, &config, &wrong_kid).await,
+            Err(AccessError::UnknownKey(kid)) if kid == "k2"
+        ));
+        assert_eq!(
+            verify(&state, &config, &sign("k1", key, &claims))
+                .await
+                .unwrap(),
+            "a@example.com"
+        );
+    }
+
+    #[tokio::test]
+    async fn unknown_kid_refetches_jwks_once_per_cooldown() {
+        let [old, new] = keys();
+        let jwks_server = HttpStandIn::serving(jwks(&[("old", old)])).await;
+        let state = AppState::test();
+        let config = config(jwks_server.url("/certs"));
+
+        let token = sign("old", old, &claims("a@example.com"));
+        verify(&state, &config, &token).await.unwrap();
+
+        // Access publishes the rotated key, then starts signing with it.
+        jwks_server.set_body(jwks(&[("old", old), ("new", new)]));
+        // Let the cooldown of the first fetch lapse.
+        state.cache.set(REFETCH_CACHE_KEY, json!(true), Some(0));
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        let token = sign("new", new, &claims("a@example.com"));
+        assert_eq!(
+            verify(&state, &config, &token).await.unwrap(),
+            "a@example.com"
+        );
+        assert_eq!(jwks_server.requests().len(), 2);
+
+        // A made-up kid right after does not trigger another fetch.
+        let token = sign("bogus", new, &claims("a@example.com"));
+        assert!(matches!(
+            verify(&state, &config, &token).await,
+            Err(AccessError::UnknownKey(_))
+        ));
+        assert_eq!(jwks_server.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn stale_keys_are_used_when_jwks_endpoint_is_down() {
+        let [key, _] = keys();
+        let jwks_server = HttpStandIn::serving(jwks(&[("k1", key)])).await;
+        let url = jwks_server.url("/certs");
+        drop(jwks_server);
+        let state = AppState::test();
+        let keys: Value = serde_json::from_str(&jwks(&[("k1", key)])).unwrap();
+        state.cache.set(
+            JWKS_CACHE_KEY,
+            json!({"fetched_at": 0, "keys": keys["keys"]}),
+            None,
+        );
+
+        let token = sign("k1", key, &claims("a@example.com"));
+        assert_eq!(
+            verify(&state, &config(url), &token).await.unwrap(),
+            "a@example.com"
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use crate::cf_access::CfAccessConfig;
 use std::env;
 
 #[derive(Clone)]
@@ -10,10 +11,36 @@ pub struct Config {
     /// Not secret — safe to embed in client JS.
     pub gis_client_id: String,
     pub discord_webhook_url: Option<String>,
+    /// Additional notification backends, one URL each (see `notify` for the
+    /// supported schemes).
+    pub notifier_urls: Vec<String>,
     pub websub_callback_url: String,
     /// YouTube Data API key for video detail enrichment (duration / Shorts /
     /// livestream). API-key-only endpoints — no OAuth involved.
     pub youtube_api_key: Option<String>,
+    /// Contact (`mailto:` or https URL) sent to browser push services in the
+    /// VAPID JWT. Falls back to PUBLIC_BASE_URL.
+    pub vapid_subject: Option<String>,
+    /// Cloudflare Access JWT verification (CF_ACCESS_TEAM_DOMAIN +
+    /// CF_ACCESS_AUD). When unset, the email header is trusted as-is.
+    pub cf_access: Option<CfAccessConfig>,
+    /// Header carrying the authenticated user's email, set by the reverse
+    /// proxy in front of the app (Cloudflare Access, Authelia, oauth2-proxy,
+    /// Tailscale Serve, …).
+    pub auth_header: String,
+    /// Peers allowed to set `auth_header`. `None` trusts every peer (the app
+    /// is only reachable through the proxy).
+    pub trusted_proxies: Option<Vec<ipnet::IpNet>>,
+    /// Serve the first user's favorites on `/api/rss` without `?token=`
+    /// (RSS_TOKENLESS_FALLBACK, on by default for old RSS consumers).
+    pub rss_tokenless_fallback: bool,
+    /// SMTP notifier URL (`from=` required, `to=` ignored) used to mail
+    /// magic login links (LOGIN_SMTP_URL). Unset disables email login.
+    pub login_smtp_url: Option<String>,
+    /// Let user-supplied webhook / notifier / push URLs reach loopback and
+    /// private addresses (ALLOW_PRIVATE_TARGETS, off by default; see
+    /// `outbound`).
+    pub allow_private_targets: bool,
     pub is_production: bool,
 }
 
@@ -38,6 +65,13 @@ impl Config {
             .ok()
             .filter(|s| !s.is_empty());
 
+        // Whitespace-separated: SMTP URLs use commas between recipients.
+        let notifier_urls: Vec<String> = env::var("NOTIFIER_URLS")
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(str::to_string)
+            .collect();
+
         let websub_callback_url = env::var("WEBSUB_CALLBACK_URL")
             .unwrap_or_else(|_| "http://localhost:3000/api/websub/callback".to_string());
 
@@ -46,10 +80,86 @@ impl Config {
             .map(|s| s.trim().to_string())
             .filter(|s| !s.is_empty());
 
+        let vapid_subject = env::var("VAPID_SUBJECT")
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .or_else(|| public_base_url.clone());
+
+        let non_empty = |name: &str| {
+            env::var(name)
+                .ok()
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+        };
+        let cf_access = match (
+            non_empty("CF_ACCESS_TEAM_DOMAIN"),
+            non_empty("CF_ACCESS_AUD"),
+        ) {
+            (Some(team_domain), Some(audience)) => Some(CfAccessConfig::new(
+                &team_domain,
+                &audience,
+                non_empty("CF_ACCESS_CERTS_URL"),
+            )),
+            (None, None) => None,
+            _ => {
+                tracing::warn!(
+                    "CF_ACCESS_TEAM_DOMAIN and CF_ACCESS_AUD must both be set; Cloudflare Access JWT verification is disabled."
+                );
+                None
+            }
+        };
+
+        let auth_header = non_empty("AUTH_HEADER")
+            .unwrap_or_else(|| "Cf-Access-Authenticated-User-Email".to_string());
+
+        let trusted_proxies = env::var("TRUSTED_PROXIES")
+            .ok()
+            .filter(|s| !s.trim().is_empty())
+            .map(|s| parse_trusted_proxies(&s));
+
+        let rss_tokenless_fallback = env::var("RSS_TOKENLESS_FALLBACK")
+            .map(|v| !matches!(v.trim(), "0" | "false" | "off"))
+            .unwrap_or(true);
+
+        let login_smtp_url = non_empty("LOGIN_SMTP_URL").filter(|url| {
+            match crate::notify::smtp_mailer(url, "check@example.com") {
+                Ok(_) => true,
+                Err(e) => {
+                    tracing::warn!("LOGIN_SMTP_URL: email login is disabled ({})", e);
+                    false
+                }
+            }
+        });
+
+        let allow_private_targets = env::var("ALLOW_PRIVATE_TARGETS")
+            .map(|v| matches!(v.trim(), "1" | "true" | "on"))
+            .unwrap_or(false);
+
         let is_production = env::var("NODE_ENV")
             .map(|v| v == "production")
             .unwrap_or(false);
 
+        if is_production && cf_access.is_none() && trusted_proxies.is_none() {
+            tracing::warn!(
+                "Neither CF_ACCESS_TEAM_DOMAIN / CF_ACCESS_AUD nor TRUSTED_PROXIES is set. The {} header is trusted from any peer; never expose the port directly.",
+                auth_header
+            );
+        }
+
+        if is_production && rss_tokenless_fallback {
+            tracing::info!(
+                "RSS_TOKENLESS_FALLBACK is on: /api/rss without ?token= serves the first user's favorites. Set RSS_TOKENLESS_FALLBACK=0 once all RSS consumers use a token."
+            );
+        }
+
+        if is_production && login_smtp_url.is_some() && public_base_url.is_none() {
+            tracing::warn!(
+                "LOGIN_SMTP_URL is set but PUBLIC_BASE_URL is not; login links will point to http://localhost:{}.",
+                port
+            );
+        }
+
         if gis_client_id.is_empty() {
             tracing::info!(
                 "GIS_CLIENT_ID not set. Browser-side channel sync will not work until it is configured."
@@ -62,15 +172,66 @@ impl Config {
             );
         }
 
+        for url in &notifier_urls {
+            if let Err(e) = crate::notify::parse_notifier_url(url) {
+                tracing::warn!("NOTIFIER_URLS: ignoring entry ({})", e);
+            }
+        }
+
         Self {
             port,
             db_path,
             public_base_url,
             gis_client_id,
             discord_webhook_url,
+            notifier_urls,
             websub_callback_url,
             youtube_api_key,
+            vapid_subject,
+            cf_access,
+            auth_header,
+            trusted_proxies,
+            rss_tokenless_fallback,
+            login_smtp_url,
+            allow_private_targets,
             is_production,
         }
     }
 }
+
+/// Comma- or whitespace-separated CIDRs; a bare address means that host only.
+/// Invalid entries are skipped, so a typo never widens the allowlist.
+pub fn parse_trusted_proxies(value: &str) -> Vec<ipnet::IpNet> {
+    value
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|s| !s.is_empty())
+        .filter_map(|entry| {
+            let parsed = entry
+                .parse::<ipnet::IpNet>()
+                .or_else(|_| entry.parse::<std::net::IpAddr>().map(ipnet::IpNet::from));
+            if parsed.is_err() {
+                tracing::warn!("TRUSTED_PROXIES: ignoring invalid entry {}", entry);
+            }
+            parsed.ok()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trusted_proxies_accept_cidrs_and_bare_addresses() {
+        let nets = parse_trusted_proxies("10.0.0.0/8, 172.17.0.1 fd7a:115c:a1e0::/48 bogus");
+        let contains = |ip: &str| {
+            nets.iter()
+                .any(|n| n.contains(&ip.parse::<std::net::IpAddr>().unwrap()))
+        };
+        assert_eq!(nets.len(), 3);
+        assert!(contains("10.1.2.3"));
+        assert!(contains("172.17.0.1"));
+        assert!(!contains("172.17.0.2"));
+        assert!(contains("fd7a:115c:a1e0::1"));
+    }
+}
@@ -0,0 +1,204 @@
+//! Opaque keyset cursors for paginated lists (`/api/feed`, `/api/history`,
+//! `/api/channels/{id}/videos`).
+//!
+//! A cursor is the sort key and id of the last row of a page, so the next page
+//! starts strictly after it no matter how many rows were inserted above or
+//! hidden in between (OFFSET pagination shifts in both cases). Lists sort by
+//! `key DESC NULLS LAST, id DESC`; a NULL key (unknown publication date) is
+//! part of the cursor so those rows are reached too. Keys are raw columns,
+//! so the ORDER BY and the predicate can use their indexes; timestamps are
+//! INTEGER Unix seconds by the time queries run (see
+//! `db::migrate_timestamps_to_unix`).
+
+use crate::error::AppError;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde_json::{json, Value};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cursor {
+    pub key: Option<i64>,
+    pub id: String,
+}
+
+impl Cursor {
+    pub fn encode(&self) -> String {
+        let key = self.key.map(|k| k.to_string()).unwrap_or_default();
+        URL_SAFE_NO_PAD.encode(format!("{key}:{}", self.id))
+    }
+
+    /// The `cursor` query parameter: absent or empty (first page) is `None`.
+    pub fn from_param(param: Option<&str>) -> Result<Option<Self>, AppError> {
+        param
+            .filter(|c| !c.is_empty())
+            .map(Self::decode)
+            .transpose()
+    }
+
+    pub fn decode(cursor: &str) -> Result<Self, AppError> {
+        let invalid = || AppError::BadRequest("Invalid cursor".to_string());
+        let raw = URL_SAFE_NO_PAD.decode(cursor).map_err(|_| invalid())?;
+        let raw = String::from_utf8(raw).map_err(|_| invalid())?;
+        let (key, id) = raw.split_once(':').ok_or_else(invalid)?;
+        let key = match key {
+            "" => None,
+            key => Some(key.parse().map_err(|_| invalid())?),
+        };
+        if id.is_empty() {
+            return Err(invalid());
+        }
+        Ok(Cursor {
+            key,
+            id: id.to_string(),
+        })
+    }
+
+    /// `AND (...)` selecting the rows after this cursor in
+    /// `key_expr DESC NULLS LAST, id_expr DESC` order. Binds its values by
+    /// appending them to `params`.
+    pub fn after(
+        &self,
+        key_expr: &str,
+        id_expr: &str,
+        params: &mut Vec<rusqlite::types::Value>,
+    ) -> String {
+        params.push(self.id.clone().into());
+        let id = params.len();
+        match self.key {
+            Some(key) => {
+                params.push(key.into());
+                let key = params.len();
+                format!(
+                    "AND ({key_expr} < ?{key} OR ({key_expr} = ?{key} AND {id_expr} < ?{id}) OR {key_expr} IS NULL)"
+                )
+            }
+            None => format!("AND ({key_expr} IS NULL AND {id_expr} < ?{id})"),
+        }
+    }
+}
+
+/// Pagination clauses for a list sorted by `key_expr DESC NULLS LAST,
+/// id_expr DESC`: in cursor mode, the rows after `cursor` plus one extra row
+/// to detect the next page; otherwise legacy LIMIT/OFFSET. Returns the
+/// `AND ...` filter (empty without a cursor) and the LIMIT clause.
+pub fn paginate(
+    cursor: Option<&Cursor>,
+    cursor_mode: bool,
+    key_expr: &str,
+    id_expr: &str,
+    limit: i64,
+    offset: i64,
+    params: &mut Vec<rusqlite::types::Value>,
+) -> (String, String) {
+    let filter = cursor
+        .map(|c| c.after(key_expr, id_expr, params))
+        .unwrap_or_default();
+    let (limit, offset) = if cursor_mode {
+        (limit + 1, 0)
+    } else {
+        (limit, offset)
+    };
+    params.push(limit.into());
+    params.push(offset.into());
+    let limit_clause = format!("LIMIT ?{} OFFSET ?{}", params.len() - 1, params.len());
+    (filter, limit_clause)
+}
+
+/// Response body of a list: a page in cursor mode, the legacy bare array
+/// otherwise.
+pub fn response(rows: Vec<(Value, Cursor)>, cursor_mode: bool, limit: i64) -> Value {
+    if cursor_mode {
+        page(rows, limit)
+    } else {
+        Value::Array(rows.into_iter().map(|(item, _)| item).collect())
+    }
+}
+
+/// `{items, next_cursor}` from up to `limit + 1` rows: the extra row only
+/// tells whether another page exists.
+pub fn page(mut rows: Vec<(Value, Cursor)>, limit: i64) -> Value {
+    let more = rows.len() as i64 > limit;
+    rows.truncate(limit as usize);
+    let next_cursor = more
+        .then(|| rows.last().map(|(_, cursor)| cursor.encode()))
+        .flatten();
+    let items: Vec<Value> = rows.into_iter().map(|(item, _)| item).collect();
+    json!({"items": items, "next_cursor": next_cursor})
+}
+
+#[cfg(test)]
+mod tests {
+    // Cursor Spec
+    //
+    // - Cursors round-trip (including a NULL key) and are URL-safe.
+    // - Anything else is a 400 "Invalid cursor".
+    // - page() returns next_cursor only when a row beyond the page exists.
+
+    use super::*;
+
+    #[test]
+    fn cursor_round_trips() {
+        for cursor in [
+            Cursor {
+                key: Some(1_700_000_000),
+                id: "dQw4w9WgXcQ".to_string(),
+            },
+            Cursor {
+                key: None,
+                id: "a-b_c".to_string(),
+            },
+            Cursor {
+                key: Some(-5),
+                id: "x:y".to_string(),
+            },
+        ] {
+            let encoded = cursor.encode();
+            assert!(encoded
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+            assert_eq!(Cursor::decode(&encoded).unwrap(), cursor);
+        }
+    }
+
+    #[test]
+    fn malformed_cursors_are_rejected() {
+        for bad in [
+            "!!!",
+            "",
+            &URL_SAFE_NO_PAD.encode("no-colon"),
+            &URL_SAFE_NO_PAD.encode("abc:v1"),
+            &URL_SAFE_NO_PAD.encode("1:"),
+        ] {
+            assert!(
+                matches!(Cursor::decode(bad), Err(AppError::BadRequest(_))),
+                "{bad}"
+            );
+        }
+    }
+
+    #[test]
+    fn page_sets_next_cursor_only_when_more_rows_exist() {
+        let rows = |n: i64| -> Vec<(Value, Cursor)> {
+            (0..n)
+                .map(|i| {
+                    (
+                        json!(i),
+                        Cursor {
+                            key: Some(100 - i),
+                            id: format!("v{i}"),
+                        },
+                    )
+                })
+                .collect()
+        };
+        let full = page(rows(3), 2);
+        assert_eq!(full["items"], json!([0, 1]));
+        let next = Cursor::decode(full["next_cursor"].as_str().unwrap()).unwrap();
+        assert_eq!(next.id, "v1");
+
+        assert_eq!(
+            page(rows(2), 2),
+            json!({"items": [0, 1], "next_cursor": null})
+        );
+    }
+}
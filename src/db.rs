@@ -12,6 +12,7 @@ pub fn open(path: &str) -> Connection {
          PRAGMA foreign_keys = ON;",
     )
     .expect("Failed to set PRAGMA");
+    crate::visibility::register_functions(&conn).expect("Failed to register SQL functions");
 
     migrate(&conn);
     create_tables(&conn);
@@ -20,14 +21,180 @@ pub fn open(path: &str) -> Connection {
     add_videos_is_members_only(&conn);
     migrate_timestamps_to_unix(&conn);
     add_videos_details_checked_at(&conn);
+    add_user_videos_reason(&conn);
     add_videos_shorts_classifier_version(&conn);
     decode_video_titles_xml_entities(&conn);
     drop_users_oauth_token_columns(&conn);
     add_users_email_unique_index(&conn);
+    add_users_timezone(&conn);
+    add_videos_scheduled_start_at(&conn);
+    add_videos_live_started_at(&conn);
+    add_users_disabled_at(&conn);
+    add_users_last_seen_at(&conn);
+    add_videos_duration_seconds(&conn);
+    outbox_targets_to_references(&conn);
+    create_search_index(&conn);
+    crate::search::sync_index(&conn);
 
     conn
 }
 
+/// FTS5 index of video titles and channel names (see `search`). Created after
+/// the migrations because rebuilding `videos` drops its triggers.
+fn create_search_index(conn: &Connection) {
+    conn.execute_batch(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS video_search USING fts5(
+            video_id UNINDEXED,
+            title,
+            channel_title,
+            tokenize = 'trigram'
+        );
+
+        CREATE TRIGGER IF NOT EXISTS videos_search_delete AFTER DELETE ON videos BEGIN
+            DELETE FROM video_search WHERE rowid = old.rowid;
+        END;",
+    )
+    .expect("Failed to create search index");
+}
+
+/// `duration` in seconds, for the feed's duration filters. Filled by
+/// enrichment; existing rows are backfilled when the column is added. Runs
+/// after migrate_timestamps_to_unix, which rebuilds `videos` without it.
+/// The index is created here rather than in create_tables because older
+/// databases do not have the column yet at that point. Idempotent.
+fn add_videos_duration_seconds(conn: &Connection) {
+    if !column_exists(conn, "videos", "duration_seconds") {
+        match conn.execute("ALTER TABLE videos ADD COLUMN duration_seconds INTEGER", []) {
+            Ok(_) => {
+                let filled = backfill_videos_duration_seconds(conn);
+                tracing::info!(
+                    "[migrate] Added videos.duration_seconds column ({} rows backfilled)",
+                    filled
+                );
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "[migrate] Failed to add videos.duration_seconds column: {}",
+                    e
+                );
+                return;
+            }
+        }
+    }
+    if let Err(e) = conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_videos_duration ON videos(duration_seconds)",
+        [],
+    ) {
+        tracing::warn!("[migrate] Failed to create idx_videos_duration: {}", e);
+    }
+}
+
+fn backfill_videos_duration_seconds(conn: &Connection) -> usize {
+    let rows: Vec<(String, String)> = match conn
+        .prepare("SELECT id, duration FROM videos WHERE duration IS NOT NULL")
+        .and_then(|mut stmt| {
+            stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .collect::<Result<Vec<_>, _>>()
+        }) {
+        Ok(rows) => rows,
+        Err(e) => {
+            tracing::warn!("[migrate] Failed to read video durations: {}", e);
+            return 0;
+        }
+    };
+    let mut filled = 0;
+    for (id, duration) in rows {
+        let Some(seconds) = crate::duration::duration_seconds(&duration) else {
+            continue;
+        };
+        if conn
+            .execute(
+                "UPDATE videos SET duration_seconds = ?1 WHERE id = ?2",
+                rusqlite::params![seconds, id],
+            )
+            .is_ok()
+        {
+            filled += 1;
+        }
+    }
+    filled
+}
+
+/// When the master disabled the account (NULL = active). Runs after
+/// migrate_timestamps_to_unix, which rebuilds `users` without it. Idempotent.
+fn add_users_disabled_at(conn: &Connection) {
+    if column_exists(conn, "users", "disabled_at") {
+        return;
+    }
+    match conn.execute("ALTER TABLE users ADD COLUMN disabled_at INTEGER", []) {
+        Ok(_) => tracing::info!("[migrate] Added users.disabled_at column"),
+        Err(e) => tracing::warn!("[migrate] Failed to add users.disabled_at column: {}", e),
+    }
+}
+
+/// Announced start of an upcoming livestream/premiere, used by the stream
+/// reminders. Runs after migrate_timestamps_to_unix, which rebuilds `videos`
+/// without it. Idempotent.
+fn add_videos_scheduled_start_at(conn: &Connection) {
+    if column_exists(conn, "videos", "scheduled_start_at") {
+        return;
+    }
+    match conn.execute(
+        "ALTER TABLE videos ADD COLUMN scheduled_start_at INTEGER",
+        [],
+    ) {
+        Ok(_) => tracing::info!("[migrate] Added videos.scheduled_start_at column"),
+        Err(e) => tracing::warn!(
+            "[migrate] Failed to add videos.scheduled_start_at column: {}",
+            e
+        ),
+    }
+}
+
+/// When an upcoming livestream/premiere was seen live (actualStartTime), so
+/// the "live now" reminder fires once. Idempotent.
+fn add_videos_live_started_at(conn: &Connection) {
+    if column_exists(conn, "videos", "live_started_at") {
+        return;
+    }
+    match conn.execute("ALTER TABLE videos ADD COLUMN live_started_at INTEGER", []) {
+        Ok(_) => tracing::info!("[migrate] Added videos.live_started_at column"),
+        Err(e) => tracing::warn!(
+            "[migrate] Failed to add videos.live_started_at column: {}",
+            e
+        ),
+    }
+}
+
+/// The user's "seen the feed up to" cursor, shared by all their devices.
+/// NULL until the first visit is recorded. Runs after
+/// migrate_timestamps_to_unix, which rebuilds `users` without it. Idempotent.
+fn add_users_last_seen_at(conn: &Connection) {
+    if column_exists(conn, "users", "last_seen_at") {
+        return;
+    }
+    match conn.execute("ALTER TABLE users ADD COLUMN last_seen_at INTEGER", []) {
+        Ok(_) => tracing::info!("[migrate] Added users.last_seen_at column"),
+        Err(e) => tracing::warn!("[migrate] Failed to add users.last_seen_at column: {}", e),
+    }
+}
+
+/// Add the user's IANA time zone, used to evaluate notification quiet hours.
+/// Runs after migrate_timestamps_to_unix, which rebuilds `users` without it.
+/// Idempotent.
+fn add_users_timezone(conn: &Connection) {
+    if column_exists(conn, "users", "timezone") {
+        return;
+    }
+    match conn.execute(
+        "ALTER TABLE users ADD COLUMN timezone TEXT NOT NULL DEFAULT 'UTC'",
+        [],
+    ) {
+        Ok(_) => tracing::info!("[migrate] Added users.timezone column"),
+        Err(e) => tracing::warn!("[migrate] Failed to add users.timezone column: {}", e),
+    }
+}
+
 /// Add the per-user, per-channel Shorts suppression preference.
 ///
 /// Existing subscriptions keep showing Shorts because the column defaults to
@@ -70,6 +237,66 @@ fn add_videos_details_checked_at(conn: &Connection) {
     }
 }
 
+/// The outbox used to store notifier URLs (tokens and secrets included) in
+/// `target`; replace them with references (see `notify::outbox`). A URL that a
+/// notification rule or reminder setting still uses points there; anything
+/// else was an instance-wide target and gets its hash reference, which stays
+/// unresolvable if that URL is no longer configured. Idempotent.
+fn outbox_targets_to_references(conn: &Connection) {
+    let result = (|| -> rusqlite::Result<usize> {
+        let mut updated = conn.execute(
+            "UPDATE notification_outbox SET target = 'rule:' || (
+               SELECT MIN(r.id) FROM notification_rules r WHERE r.target_url = notification_outbox.target)
+             WHERE instr(target, '://') > 0
+               AND EXISTS (SELECT 1 FROM notification_rules r WHERE r.target_url = notification_outbox.target)",
+            [],
+        )?;
+        updated += conn.execute(
+            "UPDATE notification_outbox SET target = 'reminder:' || (
+               SELECT MIN(s.user_id) FROM reminder_settings s WHERE s.target_url = notification_outbox.target)
+             WHERE instr(target, '://') > 0
+               AND EXISTS (SELECT 1 FROM reminder_settings s WHERE s.target_url = notification_outbox.target)",
+            [],
+        )?;
+        let rows: Vec<(i64, String)> = conn
+            .prepare("SELECT id, target FROM notification_outbox WHERE instr(target, '://') > 0")?
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<_, _>>()?;
+        for (id, url) in rows {
+            updated += conn.execute(
+                "UPDATE notification_outbox SET target = ?1 WHERE id = ?2",
+                rusqlite::params![crate::notify::outbox::instance_target(&url), id],
+            )?;
+        }
+        Ok(updated)
+    })();
+    match result {
+        Ok(0) => {}
+        Ok(n) => tracing::info!(
+            "[migrate] Replaced {} outbox target URL(s) with references",
+            n
+        ),
+        Err(e) => tracing::warn!("[migrate] Failed to replace outbox target URLs: {}", e),
+    }
+}
+
+/// Why a video was hidden: `watched`, `dismissed`, `auto-rule` or `bulk`
+/// (NULL while not hidden). Every hide used to mean "watched", so existing
+/// hidden rows are backfilled as such. Runs after migrate_timestamps_to_unix,
+/// which rebuilds `user_videos` without this column. Idempotent.
+fn add_user_videos_reason(conn: &Connection) {
+    if column_exists(conn, "user_videos", "reason") {
+        return;
+    }
+    match conn.execute_batch(
+        "ALTER TABLE user_videos ADD COLUMN reason TEXT;
+         UPDATE user_videos SET reason = 'watched' WHERE is_hidden = 1;",
+    ) {
+        Ok(_) => tracing::info!("[migrate] Added user_videos.reason column"),
+        Err(e) => tracing::warn!("[migrate] Failed to add user_videos.reason column: {}", e),
+    }
+}
+
 /// Version the persisted Shorts verdict so classifier fixes can requeue rows
 /// that were previously marked checked. Version zero means the current
 /// classifier has never evaluated the row.
@@ -253,8 +480,11 @@ pub fn open_memory() -> Connection {
 
     conn.execute_batch("PRAGMA foreign_keys = ON;")
         .expect("Failed to set PRAGMA");
+    crate::visibility::register_functions(&conn).expect("Failed to register SQL functions");
 
     create_tables(&conn);
+    add_videos_duration_seconds(&conn);
+    create_search_index(&conn);
 
     conn
 }
@@ -268,7 +498,10 @@ fn create_tables(conn: &Connection) {
             role TEXT NOT NULL DEFAULT 'member',
             rss_token TEXT,
             created_at INTEGER DEFAULT (unixepoch()),
-            updated_at INTEGER
+            updated_at INTEGER,
+            timezone TEXT NOT NULL DEFAULT 'UTC',
+            disabled_at INTEGER,
+            last_seen_at INTEGER
         );
 
         CREATE TABLE IF NOT EXISTS channels (
@@ -293,6 +526,9 @@ fn create_tables(conn: &Connection) {
             fetched_at INTEGER,
             details_checked_at INTEGER,
             shorts_classifier_version INTEGER NOT NULL DEFAULT 0,
+            scheduled_start_at INTEGER,
+            live_started_at INTEGER,
+            duration_seconds INTEGER,
             FOREIGN KEY (channel_id) REFERENCES channels(id) ON DELETE CASCADE
         );
 
@@ -313,11 +549,37 @@ fn create_tables(conn: &Connection) {
             video_id TEXT NOT NULL,
             is_hidden INTEGER NOT NULL DEFAULT 0,
             created_at INTEGER DEFAULT (unixepoch()),
+            reason TEXT,
+            PRIMARY KEY (user_id, video_id),
+            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+            FOREIGN KEY (video_id) REFERENCES videos(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS watch_later (
+            user_id INTEGER NOT NULL,
+            video_id TEXT NOT NULL,
+            position INTEGER NOT NULL,
+            created_at INTEGER,
             PRIMARY KEY (user_id, video_id),
             FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
             FOREIGN KEY (video_id) REFERENCES videos(id) ON DELETE CASCADE
         );
 
+        CREATE TABLE IF NOT EXISTS bulk_hides (
+            token TEXT PRIMARY KEY,
+            user_id INTEGER NOT NULL,
+            expires_at INTEGER NOT NULL,
+            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS bulk_hide_videos (
+            token TEXT NOT NULL,
+            video_id TEXT NOT NULL,
+            had_row INTEGER NOT NULL,
+            PRIMARY KEY (token, video_id),
+            FOREIGN KEY (token) REFERENCES bulk_hides(token) ON DELETE CASCADE
+        );
+
         CREATE TABLE IF NOT EXISTS groups (
             id INTEGER PRIMARY KEY AUTOINCREMENT,
             user_id INTEGER NOT NULL,
@@ -345,6 +607,182 @@ fn create_tables(conn: &Connection) {
             FOREIGN KEY (channel_id) REFERENCES channels(id) ON DELETE CASCADE
         );
 
+        CREATE TABLE IF NOT EXISTS notification_rules (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            user_id INTEGER NOT NULL,
+            name TEXT NOT NULL DEFAULT '',
+            target_url TEXT NOT NULL,
+            channel_id TEXT,
+            group_id INTEGER,
+            keyword TEXT,
+            is_regex INTEGER NOT NULL DEFAULT 0,
+            exclude_shorts INTEGER NOT NULL DEFAULT 0,
+            exclude_livestreams INTEGER NOT NULL DEFAULT 0,
+            quiet_start INTEGER,
+            quiet_end INTEGER,
+            is_enabled INTEGER NOT NULL DEFAULT 1,
+            created_at INTEGER,
+            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+            FOREIGN KEY (channel_id) REFERENCES channels(id) ON DELETE CASCADE,
+            FOREIGN KEY (group_id) REFERENCES groups(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS mute_rules (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            user_id INTEGER NOT NULL,
+            pattern TEXT NOT NULL,
+            is_regex INTEGER NOT NULL DEFAULT 0,
+            channel_id TEXT,
+            group_id INTEGER,
+            created_at INTEGER,
+            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+            FOREIGN KEY (channel_id) REFERENCES channels(id) ON DELETE CASCADE,
+            FOREIGN KEY (group_id) REFERENCES groups(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS notification_queue (
+            rule_id INTEGER NOT NULL,
+            video_id TEXT NOT NULL,
+            deliver_at INTEGER NOT NULL,
+            created_at INTEGER,
+            PRIMARY KEY (rule_id, video_id),
+            FOREIGN KEY (rule_id) REFERENCES notification_rules(id) ON DELETE CASCADE,
+            FOREIGN KEY (video_id) REFERENCES videos(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS digest_settings (
+            user_id INTEGER PRIMARY KEY,
+            frequency TEXT NOT NULL,
+            format TEXT NOT NULL DEFAULT 'markdown',
+            target_url TEXT NOT NULL,
+            last_sent_at INTEGER,
+            created_at INTEGER,
+            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS vapid_keys (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            private_key TEXT NOT NULL,
+            public_key TEXT NOT NULL,
+            created_at INTEGER
+        );
+
+        CREATE TABLE IF NOT EXISTS push_subscriptions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            user_id INTEGER NOT NULL,
+            endpoint TEXT NOT NULL UNIQUE,
+            p256dh TEXT NOT NULL,
+            auth TEXT NOT NULL,
+            created_at INTEGER,
+            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS reminder_settings (
+            user_id INTEGER PRIMARY KEY,
+            minutes_before INTEGER NOT NULL DEFAULT 10,
+            target_url TEXT NOT NULL,
+            created_at INTEGER,
+            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS stream_reminders_sent (
+            user_id INTEGER NOT NULL,
+            video_id TEXT NOT NULL,
+            kind TEXT NOT NULL,
+            sent_at INTEGER,
+            PRIMARY KEY (user_id, video_id, kind),
+            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+            FOREIGN KEY (video_id) REFERENCES videos(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS notification_outbox (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            target TEXT NOT NULL,
+            title TEXT NOT NULL,
+            payload TEXT NOT NULL,
+            status TEXT NOT NULL DEFAULT 'pending',
+            attempts INTEGER NOT NULL DEFAULT 0,
+            last_error TEXT,
+            next_attempt_at INTEGER NOT NULL,
+            created_at INTEGER,
+            sent_at INTEGER
+        );
+
+        CREATE TABLE IF NOT EXISTS webhooks (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            user_id INTEGER NOT NULL,
+            url TEXT NOT NULL,
+            secret TEXT NOT NULL,
+            events TEXT NOT NULL,
+            is_enabled INTEGER NOT NULL DEFAULT 1,
+            created_at INTEGER,
+            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS webhook_deliveries (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            webhook_id INTEGER NOT NULL,
+            event TEXT NOT NULL,
+            payload TEXT NOT NULL,
+            status TEXT NOT NULL DEFAULT 'pending',
+            attempts INTEGER NOT NULL DEFAULT 0,
+            response_status INTEGER,
+            last_error TEXT,
+            next_attempt_at INTEGER NOT NULL,
+            created_at INTEGER,
+            delivered_at INTEGER,
+            FOREIGN KEY (webhook_id) REFERENCES webhooks(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS api_tokens (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            user_id INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            token_hash TEXT NOT NULL,
+            token_prefix TEXT NOT NULL,
+            scopes TEXT NOT NULL,
+            expires_at INTEGER,
+            last_used_at INTEGER,
+            created_at INTEGER,
+            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS rss_feed_tokens (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            user_id INTEGER NOT NULL,
+            label TEXT NOT NULL,
+            token TEXT NOT NULL,
+            group_id INTEGER,
+            created_at INTEGER,
+            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+            FOREIGN KEY (group_id) REFERENCES groups(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS login_links (
+            token_hash TEXT PRIMARY KEY,
+            user_id INTEGER NOT NULL,
+            created_at INTEGER NOT NULL,
+            expires_at INTEGER NOT NULL,
+            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS login_sessions (
+            token_hash TEXT PRIMARY KEY,
+            user_id INTEGER NOT NULL,
+            created_at INTEGER NOT NULL,
+            expires_at INTEGER NOT NULL,
+            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS audit_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            user_id INTEGER,
+            action TEXT NOT NULL,
+            target TEXT NOT NULL DEFAULT '',
+            detail TEXT,
+            created_at INTEGER NOT NULL
+        );
+
         CREATE UNIQUE INDEX IF NOT EXISTS idx_users_email ON users(email);
         CREATE UNIQUE INDEX IF NOT EXISTS idx_users_rss_token ON users(rss_token);
         CREATE INDEX IF NOT EXISTS idx_videos_published ON videos (published_at DESC);
@@ -354,7 +792,21 @@ fn create_tables(conn: &Connection) {
         CREATE INDEX IF NOT EXISTS idx_user_videos_user ON user_videos(user_id);
         CREATE INDEX IF NOT EXISTS idx_user_videos_hidden ON user_videos(user_id, is_hidden);
         CREATE INDEX IF NOT EXISTS idx_groups_user ON groups(user_id);
-        CREATE INDEX IF NOT EXISTS idx_channel_subscriptions_expires ON channel_subscriptions(expires_at);",
+        CREATE INDEX IF NOT EXISTS idx_channel_subscriptions_expires ON channel_subscriptions(expires_at);
+        CREATE INDEX IF NOT EXISTS idx_notification_rules_user ON notification_rules(user_id);
+        CREATE INDEX IF NOT EXISTS idx_mute_rules_user ON mute_rules(user_id);
+        CREATE INDEX IF NOT EXISTS idx_notification_queue_deliver ON notification_queue(deliver_at);
+        CREATE INDEX IF NOT EXISTS idx_push_subscriptions_user ON push_subscriptions(user_id);
+        CREATE INDEX IF NOT EXISTS idx_notification_outbox_due ON notification_outbox(status, next_attempt_at);
+        CREATE INDEX IF NOT EXISTS idx_webhooks_user ON webhooks(user_id);
+        CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_webhook ON webhook_deliveries(webhook_id, id DESC);
+        CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_due ON webhook_deliveries(status, next_attempt_at);
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_api_tokens_hash ON api_tokens(token_hash);
+        CREATE INDEX IF NOT EXISTS idx_api_tokens_user ON api_tokens(user_id);
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_rss_feed_tokens_token ON rss_feed_tokens(token);
+        CREATE INDEX IF NOT EXISTS idx_rss_feed_tokens_user ON rss_feed_tokens(user_id);
+        CREATE INDEX IF NOT EXISTS idx_audit_log_user ON audit_log(user_id, id DESC);
+        CREATE INDEX IF NOT EXISTS idx_audit_log_target ON audit_log(target, id DESC);",
     )
     .expect("Failed to create tables");
 }
@@ -808,14 +1260,34 @@ mod tests {
         // sessions テーブルは OAuth 撤去・Cloudflare Access 移行に伴い削除された。
         // 新規 DB には sessions テーブルは存在しない。
         let expected = [
+            "api_tokens",
+            "audit_log",
+            "bulk_hide_videos",
+            "bulk_hides",
             "channel_groups",
             "channel_subscriptions",
             "channels",
+            "digest_settings",
             "groups",
+            "login_links",
+            "login_sessions",
+            "mute_rules",
+            "notification_outbox",
+            "notification_queue",
+            "notification_rules",
+            "push_subscriptions",
+            "reminder_settings",
+            "rss_feed_tokens",
+            "stream_reminders_sent",
             "user_channels",
             "user_videos",
             "users",
+            "vapid_keys",
+            "video_search",
             "videos",
+            "watch_later",
+            "webhook_deliveries",
+            "webhooks",
         ];
         for name in &expected {
             assert!(
@@ -850,8 +1322,19 @@ mod tests {
         // stale expectation list. idx_users_rss_token and
         // idx_channel_subscriptions_expires were previously missing here.
         let expected = vec![
+            "idx_api_tokens_hash",
+            "idx_api_tokens_user",
+            "idx_audit_log_target",
+            "idx_audit_log_user",
             "idx_channel_subscriptions_expires",
             "idx_groups_user",
+            "idx_mute_rules_user",
+            "idx_notification_outbox_due",
+            "idx_notification_queue_deliver",
+            "idx_notification_rules_user",
+            "idx_push_subscriptions_user",
+            "idx_rss_feed_tokens_token",
+            "idx_rss_feed_tokens_user",
             "idx_user_channels_favorite",
             "idx_user_channels_user",
             "idx_user_videos_hidden",
@@ -859,7 +1342,11 @@ mod tests {
             "idx_users_email",
             "idx_users_rss_token",
             "idx_videos_channel",
+            "idx_videos_duration",
             "idx_videos_published",
+            "idx_webhook_deliveries_due",
+            "idx_webhook_deliveries_webhook",
+            "idx_webhooks_user",
         ];
         assert_eq!(
             indexes, expected,
@@ -970,6 +1457,130 @@ mod tests {
         assert!(super::column_exists(&conn, "videos", "is_members_only"));
     }
 
+    #[test]
+    fn test_add_users_timezone_defaults_existing_users_to_utc() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE users (id INTEGER PRIMARY KEY AUTOINCREMENT, email TEXT NOT NULL);
+             INSERT INTO users (email) VALUES ('a@example.com');",
+        )
+        .unwrap();
+
+        super::add_users_timezone(&conn);
+        super::add_users_timezone(&conn);
+
+        let tz: String = conn
+            .query_row("SELECT timezone FROM users WHERE id = 1", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(tz, "UTC");
+    }
+
+    #[test]
+    fn leftover_text_timestamps_become_unix_seconds_on_open() {
+        let conn = open_memory();
+        conn.execute_batch(
+            "INSERT INTO users (email) VALUES ('a@example.com');
+             INSERT INTO channels (id, title) VALUES ('UC1', 'Ch');
+             INSERT INTO videos (id, channel_id, title, published_at) VALUES
+                ('t', 'UC1', 't', '1970-01-01T00:06:40+00:00'), ('i', 'UC1', 'i', 300);
+             INSERT INTO user_videos (user_id, video_id, is_hidden, created_at) VALUES
+                (1, 't', 1, '1970-01-01T00:00:30Z');",
+        )
+        .unwrap();
+
+        super::migrate_timestamps_to_unix(&conn);
+
+        let published: Vec<(String, i64)> = conn
+            .prepare("SELECT id, published_at FROM videos ORDER BY published_at DESC")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            published,
+            vec![("t".to_string(), 400), ("i".to_string(), 300)]
+        );
+        let watched: i64 = conn
+            .query_row("SELECT created_at FROM user_videos", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(watched, 30);
+    }
+
+    #[test]
+    fn add_users_disabled_at_keeps_existing_users_active() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE users (id INTEGER PRIMARY KEY AUTOINCREMENT, email TEXT NOT NULL);
+             INSERT INTO users (email) VALUES ('a@example.com');",
+        )
+        .unwrap();
+
+        super::add_users_disabled_at(&conn);
+        super::add_users_disabled_at(&conn);
+
+        let disabled_at: Option<i64> = conn
+            .query_row("SELECT disabled_at FROM users WHERE id = 1", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(disabled_at, None);
+    }
+
+    #[test]
+    fn add_videos_live_schedule_columns_default_to_null_and_are_idempotent() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE videos (id TEXT PRIMARY KEY, channel_id TEXT NOT NULL, title TEXT NOT NULL);
+             INSERT INTO videos (id, channel_id, title) VALUES ('v_legacy', 'UC1', 'T');",
+        )
+        .unwrap();
+
+        for _ in 0..2 {
+            super::add_videos_scheduled_start_at(&conn);
+            super::add_videos_live_started_at(&conn);
+        }
+
+        let row: (Option<i64>, Option<i64>) = conn
+            .query_row(
+                "SELECT scheduled_start_at, live_started_at FROM videos WHERE id = 'v_legacy'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(row, (None, None));
+    }
+
+    #[test]
+    fn add_videos_duration_seconds_backfills_existing_rows() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE videos (id TEXT PRIMARY KEY, channel_id TEXT NOT NULL, title TEXT NOT NULL, duration TEXT);
+             INSERT INTO videos (id, channel_id, title, duration) VALUES
+               ('v_long', 'UC1', 'T', 'PT1H2M3S'),
+               ('v_live', 'UC1', 'T', 'P0D'),
+               ('v_new', 'UC1', 'T', NULL);",
+        )
+        .unwrap();
+
+        super::add_videos_duration_seconds(&conn);
+        super::add_videos_duration_seconds(&conn);
+
+        let seconds = |id: &str| -> Option<i64> {
+            conn.query_row(
+                "SELECT duration_seconds FROM videos WHERE id = ?1",
+                [id],
+                |row| row.get(0),
+            )
+            .unwrap()
+        };
+        assert_eq!(seconds("v_long"), Some(3723));
+        assert_eq!(seconds("v_live"), None);
+        assert_eq!(seconds("v_new"), None);
+    }
+
     #[test]
     fn test_add_videos_is_members_only_is_idempotent() {
         // Fresh DB already has the column from create_tables.
@@ -1292,6 +1903,72 @@ mod tests {
         assert_eq!(version, 0);
     }
 
+    #[test]
+    fn outbox_targets_to_references_removes_stored_urls() {
+        let conn = open_memory();
+        conn.execute_batch(
+            "INSERT INTO users (email) VALUES ('a@example.com');
+             INSERT INTO notification_rules (user_id, target_url) VALUES (1, 'ntfy+https://ntfy.sh/rule-secret');
+             INSERT INTO reminder_settings (user_id, target_url) VALUES (1, 'gotify+https://g.example.com/?token=rem');
+             INSERT INTO notification_outbox (target, title, payload, next_attempt_at) VALUES
+               ('ntfy+https://ntfy.sh/rule-secret', 't', '{}', 0),
+               ('gotify+https://g.example.com/?token=rem', 't', '{}', 0),
+               ('discord+https://discord.com/api/webhooks/1/instance-secret', 't', '{}', 0),
+               ('rule:1', 't', '{}', 0);",
+        )
+        .unwrap();
+
+        super::outbox_targets_to_references(&conn);
+        super::outbox_targets_to_references(&conn);
+
+        let targets: Vec<String> = conn
+            .prepare("SELECT target FROM notification_outbox ORDER BY id")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            targets,
+            [
+                "rule:1".to_string(),
+                "reminder:1".to_string(),
+                crate::notify::outbox::instance_target(
+                    "discord+https://discord.com/api/webhooks/1/instance-secret"
+                ),
+                "rule:1".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn add_user_videos_reason_backfills_hidden_rows_as_watched() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE user_videos (
+                user_id INTEGER NOT NULL,
+                video_id TEXT NOT NULL,
+                is_hidden INTEGER NOT NULL DEFAULT 0,
+                created_at INTEGER,
+                PRIMARY KEY (user_id, video_id)
+            );
+            INSERT INTO user_videos (user_id, video_id, is_hidden) VALUES (1, 'v1', 1), (1, 'v2', 0);",
+        )
+        .unwrap();
+
+        super::add_user_videos_reason(&conn);
+        super::add_user_videos_reason(&conn);
+
+        let reasons: Vec<Option<String>> = conn
+            .prepare("SELECT reason FROM user_videos ORDER BY video_id")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(reasons, [Some("watched".to_string()), None]);
+    }
+
     #[test]
     fn test_user_videos_defaults_is_hidden_to_zero() {
         // When a user_videos row is created without an explicit is_hidden, the
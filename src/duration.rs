@@ -15,6 +15,13 @@ pub fn parse_iso_duration(iso: &str) -> u64 {
     hours * 3600 + minutes * 60 + seconds
 }
 
+/// Length in seconds as stored in `videos.duration_seconds`. `None` for a
+/// zero or unparseable duration (e.g. `P0D` while a stream is live).
+pub fn duration_seconds(iso: &str) -> Option<i64> {
+    let seconds = parse_iso_duration(iso);
+    (seconds > 0).then_some(seconds as i64)
+}
+
 pub fn is_short_duration(iso: &str) -> bool {
     let seconds = parse_iso_duration(iso);
     seconds > 0 && seconds <= 180
@@ -29,6 +36,13 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn duration_seconds_is_none_for_zero_or_garbage() {
+        assert_eq!(duration_seconds("PT1H2M3S"), Some(3723));
+        assert_eq!(duration_seconds("P0D"), None);
+        assert_eq!(duration_seconds("garbage"), None);
+    }
+
     #[test]
     fn test_parse_full() {
         assert_eq!(parse_iso_duration("PT1H2M3S"), 3723);
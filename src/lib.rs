@@ -1,20 +1,30 @@
+pub(crate) mod api_tokens;
+pub(crate) mod audit;
 pub mod cache;
+pub(crate) mod cf_access;
 pub mod config;
+pub(crate) mod cursor;
 pub mod db;
 pub mod duration;
 pub(crate) mod error;
+pub(crate) mod login;
 pub(crate) mod middleware;
 pub(crate) mod notify;
 pub(crate) mod openapi;
+pub(crate) mod outbound;
 pub mod routes;
+pub(crate) mod search;
 pub(crate) mod spa;
 pub mod state;
 pub mod sync;
 pub(crate) mod util;
+pub(crate) mod visibility;
+pub(crate) mod webhooks;
 pub mod websub;
 pub(crate) mod youtube;
 // NOTE: auth.rs (OAuth URL generation) has been removed — authentication is delegated to
-//       Cloudflare Access (Cf-Access-Authenticated-User-Email header).
+//       Cloudflare Access or another trusted reverse proxy (identity header, see middleware).
 // NOTE: sync::token (server-side token refresh) has been removed — browser-side GIS only.
-// NOTE: session module has been removed — no server-side session cookies needed.
+// NOTE: session module has been removed — the only server-side sessions left are those of the
+//       optional magic-link login (login.rs, LOGIN_SMTP_URL).
 // NOTE: sync::video_fetcher has been removed — new videos arrive via WebSub push only.
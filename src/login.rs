@@ -0,0 +1,228 @@
+//! Magic-link email login, for deployments without an authenticating reverse
+//! proxy (enabled by LOGIN_SMTP_URL).
+//!
+//! A registered, active user asks for a link; the server mails a one-time
+//! token that expires after [`LINK_TTL_SECS`]. The link opens a confirmation
+//! page; submitting it consumes the token and opens a session whose token is set as the [`SESSION_COOKIE`]
+//! cookie. Only SHA-256 hashes of link and session tokens are stored, so a
+//! database dump cannot be replayed. `auth_middleware` accepts the cookie
+//! when no proxy identity is present.
+
+use crate::error::AppError;
+use axum::http::HeaderMap;
+use rand::RngCore;
+use rusqlite::{Connection, OptionalExtension};
+
+pub const SESSION_COOKIE: &str = "ysf_session";
+pub const LINK_TTL_SECS: i64 = 15 * 60;
+pub const SESSION_TTL_SECS: i64 = 30 * 86400;
+/// One link per user per minute, so the endpoint cannot flood a mailbox.
+const RESEND_COOLDOWN_SECS: i64 = 60;
+
+fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+/// Create a link token for `email`, returned with the registered address to
+/// mail it to. `None` when the email is not an active registered user or a
+/// link was sent less than a minute ago; callers must not reveal which.
+pub fn issue_link(
+    conn: &Connection,
+    email: &str,
+    now: i64,
+) -> Result<Option<(String, String)>, AppError> {
+    let user: Option<(i64, String)> = conn
+        .query_row(
+            "SELECT id, email FROM users WHERE email = ?1 COLLATE NOCASE AND disabled_at IS NULL",
+            [email.trim()],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+    let Some((user_id, registered)) = user else {
+        return Ok(None);
+    };
+    let recent: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM login_links WHERE user_id = ?1 AND created_at > ?2)",
+        rusqlite::params![user_id, now - RESEND_COOLDOWN_SECS],
+        |row| row.get(0),
+    )?;
+    if recent {
+        return Ok(None);
+    }
+
+    conn.execute("DELETE FROM login_links WHERE expires_at <= ?1", [now])?;
+    let token = random_token();
+    conn.execute(
+        "INSERT INTO login_links (token_hash, user_id, created_at, expires_at) VALUES (?1, ?2, ?3, ?4)",
+        rusqlite::params![
+            crate::api_tokens::hash(&token),
+            user_id,
+            now,
+            now + LINK_TTL_SECS
+        ],
+    )?;
+    Ok(Some((registered, token)))
+}
+
+/// Consume a link token (valid once) and open a session for its user.
+/// Returns the session token.
+pub fn redeem_link(conn: &Connection, token: &str, now: i64) -> Result<String, AppError> {
+    let link_hash = crate::api_tokens::hash(token);
+    let user_id: Option<i64> = conn
+        .query_row(
+            "SELECT l.user_id FROM login_links l
+             JOIN users u ON u.id = l.user_id
+             WHERE l.token_hash = ?1 AND l.expires_at > ?2 AND u.disabled_at IS NULL",
+            rusqlite::params![link_hash, now],
+            |row| row.get(0),
+        )
+        .optional()?;
+    conn.execute(
+        "DELETE FROM login_links WHERE token_hash = ?1",
+        [&link_hash],
+    )?;
+    let user_id = user_id
+        .ok_or_else(|| AppError::Unauthorized("Invalid or expired login link".to_string()))?;
+
+    conn.execute("DELETE FROM login_sessions WHERE expires_at <= ?1", [now])?;
+    let session = random_token();
+    conn.execute(
+        "INSERT INTO login_sessions (token_hash, user_id, created_at, expires_at) VALUES (?1, ?2, ?3, ?4)",
+        rusqlite::params![
+            crate::api_tokens::hash(&session),
+            user_id,
+            now,
+            now + SESSION_TTL_SECS
+        ],
+    )?;
+    Ok(session)
+}
+
+/// Active user of a session token, if the session has not expired.
+pub fn session_user(conn: &Connection, token: &str, now: i64) -> Result<Option<i64>, AppError> {
+    Ok(conn
+        .query_row(
+            "SELECT s.user_id FROM login_sessions s
+             JOIN users u ON u.id = s.user_id
+             WHERE s.token_hash = ?1 AND s.expires_at > ?2 AND u.disabled_at IS NULL",
+            rusqlite::params![crate::api_tokens::hash(token), now],
+            |row| row.get(0),
+        )
+        .optional()?)
+}
+
+pub fn end_session(conn: &Connection, token: &str) -> Result<(), AppError> {
+    conn.execute(
+        "DELETE FROM login_sessions WHERE token_hash = ?1",
+        [crate::api_tokens::hash(token)],
+    )?;
+    Ok(())
+}
+
+/// Session token from the request's `Cookie` header.
+pub fn session_token(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get_all(axum::http::header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .find_map(|pair| {
+            let (name, value) = pair.trim().split_once('=')?;
+            (name == SESSION_COOKIE && !value.is_empty()).then(|| value.to_string())
+        })
+}
+
+/// `Set-Cookie` value for a new session (`None` clears the cookie). `Secure`
+/// is added when the app is served over https.
+pub fn session_cookie(token: Option<&str>, secure: bool) -> String {
+    let (value, max_age) = match token {
+        Some(token) => (token, SESSION_TTL_SECS),
+        None => ("", 0),
+    };
+    let secure = if secure { "; Secure" } else { "" };
+    format!("{SESSION_COOKIE}={value}; Path=/; HttpOnly; SameSite=Lax; Max-Age={max_age}{secure}")
+}
+
+#[cfg(test)]
+mod tests {
+    // Magic-Link Login Spec
+    //
+    // - Links are only issued to active registered users (email matched
+    //   case-insensitively), at most one per user per minute.
+    // - A link is valid for 15 minutes and can be redeemed once; redeeming
+    //   opens a 30-day session.
+    // - Sessions of disabled users, expired sessions and ended sessions do not
+    //   authenticate.
+
+    use super::*;
+
+    fn setup() -> Connection {
+        let conn = crate::db::open_memory();
+        conn.execute("INSERT INTO users (email) VALUES ('a@example.com')", [])
+            .unwrap();
+        conn
+    }
+
+    #[test]
+    fn links_are_only_issued_to_registered_users_once_a_minute() {
+        let conn = setup();
+        assert!(issue_link(&conn, "nobody@example.com", 0)
+            .unwrap()
+            .is_none());
+        let (email, _) = issue_link(&conn, "A@Example.com", 0).unwrap().unwrap();
+        assert_eq!(email, "a@example.com", "mailed to the registered address");
+        assert!(
+            issue_link(&conn, "a@example.com", 30).unwrap().is_none(),
+            "cooldown"
+        );
+        assert!(issue_link(&conn, "a@example.com", 61).unwrap().is_some());
+
+        conn.execute("UPDATE users SET disabled_at = 1", [])
+            .unwrap();
+        assert!(issue_link(&conn, "a@example.com", 200).unwrap().is_none());
+    }
+
+    #[test]
+    fn link_is_redeemed_once_into_a_session() {
+        let conn = setup();
+        let (_, link) = issue_link(&conn, "a@example.com", 0).unwrap().unwrap();
+        let session = redeem_link(&conn, &link, 10).unwrap();
+        assert_eq!(session_user(&conn, &session, 20).unwrap(), Some(1));
+        assert!(matches!(
+            redeem_link(&conn, &link, 20),
+            Err(AppError::Unauthorized(_))
+        ));
+
+        assert_eq!(
+            session_user(&conn, &session, 10 + SESSION_TTL_SECS).unwrap(),
+            None,
+            "expired"
+        );
+        end_session(&conn, &session).unwrap();
+        assert_eq!(session_user(&conn, &session, 20).unwrap(), None);
+    }
+
+    #[test]
+    fn expired_link_is_rejected() {
+        let conn = setup();
+        let (_, link) = issue_link(&conn, "a@example.com", 0).unwrap().unwrap();
+        assert!(redeem_link(&conn, &link, LINK_TTL_SECS).is_err());
+    }
+
+    #[test]
+    fn session_cookie_round_trips_through_the_cookie_header() {
+        let cookie = session_cookie(Some("abc"), true);
+        assert!(cookie.starts_with("ysf_session=abc; "));
+        assert!(cookie.contains("HttpOnly") && cookie.ends_with("; Secure"));
+        assert!(session_cookie(None, false).contains("Max-Age=0"));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            axum::http::header::COOKIE,
+            "theme=dark; ysf_session=abc".parse().unwrap(),
+        );
+        assert_eq!(session_token(&headers).as_deref(), Some("abc"));
+    }
+}
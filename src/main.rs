@@ -35,5 +35,11 @@ async fn main() {
         .await
         .expect("Failed to bind");
     tracing::info!("Server running on http://localhost:{}", config.port);
-    axum::serve(listener, app).await.expect("Server error");
+    // Peer addresses are needed to check TRUSTED_PROXIES.
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
+    )
+    .await
+    .expect("Server error");
 }
@@ -1,9 +1,11 @@
+use crate::error::AppError;
 use crate::state::AppState;
-use axum::extract::{Request, State};
+use axum::extract::{ConnectInfo, Request, State};
 use axum::http::StatusCode;
 use axum::middleware::Next;
 use axum::response::{IntoResponse, Response};
 use serde_json::json;
+use std::net::SocketAddr;
 
 #[derive(Clone, Copy)]
 pub struct UserId(pub i64);
@@ -11,12 +13,30 @@ pub struct UserId(pub i64);
 /// Authentication middleware.
 ///
 /// Production (is_production=true):
-///   Reads the `Cf-Access-Authenticated-User-Email` header injected by Cloudflare
-///   Access. If the header is absent the request is rejected (Access should have
-///   blocked it already, but defence in depth).
+///   Reads the identity header injected by the reverse proxy — by default
+///   `Cf-Access-Authenticated-User-Email` from Cloudflare Access, or any header
+///   set with `AUTH_HEADER` (Authelia's `Remote-Email`, oauth2-proxy's
+///   `X-Forwarded-Email`, Tailscale Serve's `Tailscale-User-Login`, …). If the
+///   header is absent the request is rejected (the proxy should have blocked it
+///   already, but defence in depth).
+///   - With `TRUSTED_PROXIES` configured, the header is only honoured on
+///     connections from those CIDRs; from any other peer it is ignored.
+///   - With `CF_ACCESS_TEAM_DOMAIN` / `CF_ACCESS_AUD` configured, the email is
+///     taken from the verified `Cf-Access-Jwt-Assertion` JWT instead and the
+///     email header is ignored; an invalid token is rejected with 401.
 ///   - First user to appear (users table empty) is registered with role='master'
 ///     and a fresh rss_token.
-///   - Subsequent unregistered emails are rejected with 403.
+///   - Subsequent unregistered emails are rejected; members are added by the
+///     master through the admin API (invitation), and disabled users are
+///     rejected like unregistered ones.
+///   - `/api/admin/*` requires role='master' (403 otherwise).
+///
+/// `Authorization: Bearer <personal API token>` (any mode) authenticates as
+/// the token's owner, limited to its scopes (see `api_tokens`); an invalid or
+/// expired token is rejected with 401, a missing scope with 403.
+///
+/// With LOGIN_SMTP_URL configured, a magic-link session cookie (see `login`)
+/// authenticates when no proxy identity is present.
 ///
 /// Development (is_production=false):
 ///   Cloudflare Access is not present, so:
@@ -27,50 +47,158 @@ pub struct UserId(pub i64);
 ///     without any authentication infrastructure.
 pub async fn auth_middleware(
     State(state): State<AppState>,
-    mut request: Request,
+    request: Request,
     next: Next,
 ) -> Response {
-    let cf_email = request
-        .headers()
-        .get("Cf-Access-Authenticated-User-Email")
-        .and_then(|v| v.to_str().ok())
-        .map(|s| s.to_string());
+    // Personal API token (scripts, extensions): takes precedence over the proxy
+    // identity and never falls back to it or to the dev bypass.
+    let bearer = header_value(&request, "Authorization")
+        .and_then(|v| v.strip_prefix("Bearer ").map(|t| t.trim().to_string()));
+    if let Some(token) = bearer {
+        let authorized = {
+            let conn = state.db.lock().unwrap();
+            crate::api_tokens::authorize(
+                &conn,
+                &token,
+                request.method(),
+                request.uri().path(),
+                crate::util::now_unix(),
+            )
+        };
+        return match authorized {
+            Ok(id) => authenticated(&state, request, next, id).await,
+            Err(e) => e.into_response(),
+        };
+    }
+
+    let email_header = if from_trusted_proxy(&state, &request) {
+        header_value(&request, &state.config.auth_header)
+    } else {
+        None
+    };
+    let jwt_header = header_value(&request, "Cf-Access-Jwt-Assertion");
+
+    let cf_email = match &state.config.cf_access {
+        Some(access) => match jwt_header {
+            Some(token) => match crate::cf_access::verify(&state, access, &token).await {
+                Ok(email) => Some(email),
+                Err(e) => {
+                    tracing::warn!("[auth] Rejecting Cloudflare Access token: {}", e);
+                    return unauthorized();
+                }
+            },
+            None => None,
+        },
+        None => email_header,
+    };
 
     let user_id = match cf_email {
         Some(email) => resolve_or_register_user(&state, &email),
-        None if !state.config.is_production => {
-            // Dev bypass: use the first user in the DB.
-            let conn = state.db.lock().unwrap();
-            conn.query_row("SELECT id FROM users ORDER BY id ASC LIMIT 1", [], |row| {
-                row.get::<_, i64>(0)
-            })
-            .ok()
-        }
-        None => None, // production + no header → reject
+        None => match login_session_user(&state, &request) {
+            Some(id) => Some(id),
+            None if !state.config.is_production => {
+                // Dev bypass: use the first user in the DB.
+                let conn = state.db.lock().unwrap();
+                conn.query_row(
+                    "SELECT id FROM users WHERE disabled_at IS NULL ORDER BY id ASC LIMIT 1",
+                    [],
+                    |row| row.get::<_, i64>(0),
+                )
+                .ok()
+            }
+            None => None, // production + no header → reject
+        },
     };
 
     match user_id {
-        Some(id) => {
-            request.extensions_mut().insert(UserId(id));
-            next.run(request).await
+        Some(id) => authenticated(&state, request, next, id).await,
+        None => unauthorized(),
+    }
+}
+
+async fn authenticated(state: &AppState, mut request: Request, next: Next, id: i64) -> Response {
+    // Admin endpoints are master-only; enforced here for the whole prefix
+    // (handlers check again via require_master).
+    if request.uri().path().starts_with("/api/admin/") {
+        let conn = state.db.lock().unwrap();
+        if let Err(e) = require_master(&conn, UserId(id)) {
+            return e.into_response();
+        }
+    }
+    request.extensions_mut().insert(UserId(id));
+    next.run(request).await
+}
+
+/// User of the magic-link session cookie, when email login is enabled.
+fn login_session_user(state: &AppState, request: &Request) -> Option<i64> {
+    state.config.login_smtp_url.as_ref()?;
+    let token = crate::login::session_token(request.headers())?;
+    let conn = state.db.lock().unwrap();
+    crate::login::session_user(&conn, &token, crate::util::now_unix())
+        .ok()
+        .flatten()
+}
+
+/// Whether the peer may set the identity header (always, unless
+/// TRUSTED_PROXIES restricts it).
+fn from_trusted_proxy(state: &AppState, request: &Request) -> bool {
+    let Some(trusted) = &state.config.trusted_proxies else {
+        return true;
+    };
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip().to_canonical());
+    match peer {
+        Some(ip) if trusted.iter().any(|net| net.contains(&ip)) => true,
+        _ => {
+            if request
+                .headers()
+                .contains_key(state.config.auth_header.as_str())
+            {
+                tracing::warn!(
+                    "[auth] Ignoring {} header from untrusted peer {:?}",
+                    state.config.auth_header,
+                    peer
+                );
+            }
+            false
         }
-        None => (
-            StatusCode::UNAUTHORIZED,
-            axum::Json(json!({"error": "Unauthorized"})),
-        )
-            .into_response(),
     }
 }
 
+fn header_value(request: &Request, name: &str) -> Option<String> {
+    request
+        .headers()
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.to_string())
+}
+
+fn unauthorized() -> Response {
+    (
+        StatusCode::UNAUTHORIZED,
+        axum::Json(json!({"error": "Unauthorized"})),
+    )
+        .into_response()
+}
+
 /// Resolve user_id by email, or register as master on first call.
 /// Returns None if email is not registered and users already exist (→ 403 via caller).
 fn resolve_or_register_user(state: &AppState, email: &str) -> Option<i64> {
     let conn = state.db.lock().unwrap();
 
-    // Existing user?
-    if let Ok(id) = conn.query_row("SELECT id FROM users WHERE email = ?1", [email], |row| {
-        row.get::<_, i64>(0)
-    }) {
+    // Existing (or invited) user? Emails are matched case-insensitively so an
+    // invitation typed with different casing still matches the proxy's header.
+    if let Ok((id, disabled)) = conn.query_row(
+        "SELECT id, disabled_at IS NOT NULL FROM users WHERE email = ?1 COLLATE NOCASE",
+        [email],
+        |row| Ok((row.get::<_, i64>(0)?, row.get::<_, bool>(1)?)),
+    ) {
+        if disabled {
+            tracing::warn!("[auth] Disabled user {} tried to access; rejecting", email);
+            return None;
+        }
         return Some(id);
     }
 
@@ -110,13 +238,34 @@ fn resolve_or_register_user(state: &AppState, email: &str) -> Option<i64> {
     }
 }
 
+/// Reject (403) callers whose role is not 'master' — the instance owner
+/// registered first. Used by the admin endpoints.
+pub fn require_master(conn: &rusqlite::Connection, user_id: UserId) -> Result<(), AppError> {
+    let role: Option<String> = conn
+        .query_row("SELECT role FROM users WHERE id = ?1", [user_id.0], |row| {
+            row.get(0)
+        })
+        .ok();
+    match role.as_deref() {
+        Some("master") => Ok(()),
+        _ => Err(AppError::Forbidden("Master role required".to_string())),
+    }
+}
+
 // Auth Middleware Spec
 //
 // Cloudflare Access-based authentication (production).
 // Dev bypass via first DB user when Cf-Access header is absent (development only).
 // - Production: Cf-Access-Authenticated-User-Email header required
+// - AUTH_HEADER renames the identity header; with TRUSTED_PROXIES it is only
+//   honoured from peers inside those CIDRs
+// - With CF_ACCESS_TEAM_DOMAIN / CF_ACCESS_AUD: email comes from the verified
+//   Cf-Access-Jwt-Assertion JWT; the email header alone is not trusted
 // - First email (empty users table) → registered as master
 // - Subsequent unregistered emails → 403
+// - Invited emails (case-insensitive) resolve; disabled users are rejected
+// - /api/admin/* → 403 unless the user is master
+// - Bearer API token → its owner, within the token's scopes
 // - dev: no header → first DB user used (devbypass)
 
 #[cfg(test)]
@@ -340,4 +489,144 @@ mod tests {
         let tok = rss_token.unwrap();
         assert_eq!(tok.len(), 36, "rss_token should be a UUID v4 (36 chars)");
     }
+
+    #[tokio::test]
+    async fn cf_access_jwt_is_required_when_configured() {
+        // With JWT verification configured, a spoofed email header alone is
+        // not enough; the verified token's email picks the user.
+        use crate::cf_access::testing::*;
+        let [key, other] = keys();
+        let jwks_server = crate::notify::stand_in::HttpStandIn::serving(jwks(&[("k1", key)])).await;
+        let mut state = setup_state();
+        state.config.is_production = true;
+        state.config.cf_access = Some(config(jwks_server.url("/certs")));
+        {
+            let conn = state.db.lock().unwrap();
+            conn.execute(
+                "INSERT INTO users (email, role) VALUES ('alice@example.com', 'master'), ('bob@example.com', 'member')",
+                [],
+            )
+            .unwrap();
+        }
+        let app = build_test_router(state);
+        let call = |email: Option<&str>, token: Option<String>| {
+            let mut req = Request::builder().uri("/whoami");
+            if let Some(email) = email {
+                req = req.header("Cf-Access-Authenticated-User-Email", email);
+            }
+            if let Some(token) = token {
+                req = req.header("Cf-Access-Jwt-Assertion", token);
+            }
+            app.clone()
+                .oneshot(req.body(axum::body::Body::empty()).unwrap())
+        };
+
+        let resp = call(Some("alice@example.com"), None).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        let forged = sign("k1", other, &claims("alice@example.com"));
+        let resp = call(None, Some(forged)).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        // The header disagrees with the token: the token wins.
+        let token = sign("k1", key, &claims("bob@example.com"));
+        let resp = call(Some("alice@example.com"), Some(token)).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body.as_ref(), b"2");
+    }
+
+    #[tokio::test]
+    async fn custom_identity_header_is_honoured_only_from_trusted_proxies() {
+        let mut state = setup_state();
+        state.config.is_production = true;
+        state.config.auth_header = "Remote-Email".to_string();
+        state.config.trusted_proxies = Some(crate::config::parse_trusted_proxies("10.0.0.0/8"));
+        let app = build_test_router(state);
+        let call = |header: &'static str, peer: Option<&str>| {
+            let mut req = Request::builder()
+                .uri("/whoami")
+                .header(header, "alice@example.com")
+                .body(axum::body::Body::empty())
+                .unwrap();
+            if let Some(peer) = peer {
+                req.extensions_mut()
+                    .insert(ConnectInfo(peer.parse::<SocketAddr>().unwrap()));
+            }
+            app.clone().oneshot(req)
+        };
+
+        // First user through the trusted proxy still becomes master.
+        let resp = call("Remote-Email", Some("10.1.2.3:5000")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body.as_ref(), b"1");
+
+        // IPv4-mapped IPv6 peers are matched against IPv4 CIDRs.
+        let resp = call("Remote-Email", Some("[::ffff:10.1.2.3]:5000"))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        for (header, peer) in [
+            ("Remote-Email", Some("192.168.1.5:5000")),
+            ("Remote-Email", None),
+            ("Cf-Access-Authenticated-User-Email", Some("10.1.2.3:5000")),
+        ] {
+            let resp = call(header, peer).await.unwrap();
+            assert_eq!(
+                resp.status(),
+                StatusCode::UNAUTHORIZED,
+                "{header} from {peer:?} must be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn invited_email_resolves_case_insensitively_and_disabled_user_is_rejected() {
+        let state = setup_state();
+        {
+            let conn = state.db.lock().unwrap();
+            conn.execute_batch(
+                "INSERT INTO users (email, role) VALUES ('alice@example.com', 'master');
+                 INSERT INTO users (email, role) VALUES ('bob@example.com', 'member');
+                 INSERT INTO users (email, role, disabled_at) VALUES ('carol@example.com', 'member', 1);",
+            )
+            .unwrap();
+        }
+        assert_eq!(resolve_or_register_user(&state, "Bob@Example.com"), Some(2));
+        assert_eq!(resolve_or_register_user(&state, "carol@example.com"), None);
+    }
+
+    #[tokio::test]
+    async fn admin_paths_require_master_role() {
+        let state = setup_state();
+        {
+            let conn = state.db.lock().unwrap();
+            conn.execute_batch(
+                "INSERT INTO users (email, role) VALUES ('alice@example.com', 'master');
+                 INSERT INTO users (email, role) VALUES ('bob@example.com', 'member');",
+            )
+            .unwrap();
+        }
+        let app = Router::new()
+            .route("/api/admin/ping", get(|| async { "pong" }))
+            .layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                auth_middleware,
+            ))
+            .with_state(state);
+        for (email, expected) in [
+            ("alice@example.com", StatusCode::OK),
+            ("bob@example.com", StatusCode::FORBIDDEN),
+        ] {
+            let req = Request::builder()
+                .uri("/api/admin/ping")
+                .header("Cf-Access-Authenticated-User-Email", email)
+                .body(axum::body::Body::empty())
+                .unwrap();
+            let resp = app.clone().oneshot(req).await.unwrap();
+            assert_eq!(resp.status(), expected, "{email}");
+        }
+    }
 }
@@ -0,0 +1,610 @@
+//! Daily / weekly digest of unwatched favourite videos.
+//!
+//! Users opt in through `digest_settings` (frequency, format, target URL in
+//! any `NOTIFIER_URLS` scheme). The periodic refresh loop calls [`send_due`];
+//! each due user gets one message listing the favourite-channel videos
+//! received since their previous digest, grouped by channel, filtered with
+//! the same visibility rules as the RSS feed. `last_sent_at` is stored per
+//! user, so a restart never re-sends a window that was already delivered.
+
+use super::{format_duration, Level, Message};
+use crate::state::AppState;
+use rusqlite::Connection;
+
+const DAY_SECS: i64 = 24 * 60 * 60;
+/// The refresh loop runs every 24h, so its start time drifts a little each
+/// cycle. Treat a digest as due slightly early rather than a whole cycle late.
+const DUE_GRACE_SECS: i64 = 60 * 60;
+/// Chat backends cap message length (Discord embeds: 4096 characters).
+const MAX_MARKDOWN_CHARS: usize = 3500;
+
+pub const FREQUENCIES: [&str; 2] = ["daily", "weekly"];
+pub const FORMATS: [&str; 2] = ["markdown", "html"];
+
+pub fn period_secs(frequency: &str) -> i64 {
+    match frequency {
+        "weekly" => 7 * DAY_SECS,
+        _ => DAY_SECS,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DigestVideo {
+    pub id: String,
+    pub title: String,
+    pub duration: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChannelSection {
+    pub channel_id: String,
+    pub channel_title: String,
+    pub videos: Vec<DigestVideo>,
+}
+
+#[derive(Debug)]
+struct DueDigest {
+    user_id: i64,
+    format: String,
+    target_url: String,
+    since: i64,
+}
+
+/// Active users whose previous digest is at least one period old. A first
+/// digest covers the last period.
+fn due_digests(conn: &Connection, now: i64) -> Vec<DueDigest> {
+    let result = conn.prepare(
+        "SELECT d.user_id, d.frequency, d.format, d.target_url, d.last_sent_at
+         FROM digest_settings d
+         JOIN users u ON u.id = d.user_id
+         WHERE u.disabled_at IS NULL",
+    );
+    let mut stmt = match result {
+        Ok(stmt) => stmt,
+        Err(e) => {
+            tracing::warn!("[digest] settings query failed: {}", e);
+            return Vec::new();
+        }
+    };
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, Option<i64>>(4)?,
+            ))
+        })
+        .map(|rows| rows.filter_map(|r| r.ok()).collect::<Vec<_>>())
+        .unwrap_or_default();
+
+    rows.into_iter()
+        .filter_map(|(user_id, frequency, format, target_url, last_sent_at)| {
+            let period = period_secs(&frequency);
+            match last_sent_at {
+                Some(last) if now - last < period - DUE_GRACE_SECS => None,
+                _ => Some(DueDigest {
+                    user_id,
+                    format,
+                    target_url,
+                    since: last_sent_at.unwrap_or(now - period),
+                }),
+            }
+        })
+        .collect()
+}
+
+/// Favourite-channel videos first seen (`fetched_at`) in `(since, until]`,
+/// grouped by channel. The window is on arrival rather than `published_at` so
+/// a video pushed or enriched after its publish time's window closed still
+/// makes the next digest. Channels are ordered by their newest video, videos
+/// newest published first.
+pub fn collect(conn: &Connection, user_id: i64, since: i64, until: i64) -> Vec<ChannelSection> {
+    let result = conn.prepare(&format!(
+        "SELECT v.id, v.title, v.duration, c.id, c.title
+         FROM videos v
+         JOIN channels c ON v.channel_id = c.id
+         JOIN user_channels uc ON uc.channel_id = c.id AND uc.user_id = ?1
+         LEFT JOIN user_videos uv ON uv.video_id = v.id AND uv.user_id = ?1
+         WHERE uc.is_favorite = 1
+           AND {visible}
+           AND v.fetched_at > ?2 AND v.fetched_at <= ?3
+         ORDER BY v.published_at DESC, v.id",
+        visible = crate::visibility::VISIBLE,
+    ));
+    let mut stmt = match result {
+        Ok(stmt) => stmt,
+        Err(e) => {
+            tracing::warn!("[digest] video query failed: {}", e);
+            return Vec::new();
+        }
+    };
+    let rows = stmt
+        .query_map(rusqlite::params![user_id, since, until], |row| {
+            Ok((
+                DigestVideo {
+                    id: row.get(0)?,
+                    title: row.get(1)?,
+                    duration: row.get(2)?,
+                },
+                row.get::<_, String>(3)?,
+                row.get::<_, String>(4)?,
+            ))
+        })
+        .map(|rows| rows.filter_map(|r| r.ok()).collect::<Vec<_>>())
+        .unwrap_or_default();
+
+    let mut sections: Vec<ChannelSection> = Vec::new();
+    for (video, channel_id, channel_title) in rows {
+        match sections.iter_mut().find(|s| s.channel_id == channel_id) {
+            Some(section) => section.videos.push(video),
+            None => sections.push(ChannelSection {
+                channel_id,
+                channel_title,
+                videos: vec![video],
+            }),
+        }
+    }
+    sections
+}
+
+fn video_url(id: &str) -> String {
+    format!("https://www.youtube.com/watch?v={id}")
+}
+
+fn with_duration(title: String, duration: Option<&str>) -> String {
+    match format_duration(duration) {
+        Some(d) => format!("{title} ({d})"),
+        None => title,
+    }
+}
+
+/// Markdown body, truncated to fit chat backends. Anything cut is summarised
+/// in a final "…and N more" line.
+pub fn render_markdown(sections: &[ChannelSection]) -> String {
+    let total: usize = sections.iter().map(|s| s.videos.len()).sum();
+    let mut out = String::new();
+    let mut shown = 0;
+    'outer: for section in sections {
+        let heading = format!("**{}**\n", section.channel_title);
+        if out.len() + heading.len() > MAX_MARKDOWN_CHARS {
+            break;
+        }
+        out.push_str(&heading);
+        for video in &section.videos {
+            let line = format!(
+                "- {}\n",
+                with_duration(
+                    format!(
+                        "[{}]({})",
+                        video.title.replace(']', "\\]"),
+                        video_url(&video.id)
+                    ),
+                    video.duration.as_deref()
+                )
+            );
+            if out.len() + line.len() > MAX_MARKDOWN_CHARS {
+                break 'outer;
+            }
+            out.push_str(&line);
+            shown += 1;
+        }
+        out.push('\n');
+    }
+    if shown < total {
+        out.push_str(&format!("…and {} more\n", total - shown));
+    }
+    out.trim_end().to_string()
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Full HTML body (no length limit; sent as the email HTML alternative).
+pub fn render_html(sections: &[ChannelSection]) -> String {
+    let mut out = String::new();
+    for section in sections {
+        out.push_str(&format!(
+            "<h2>{}</h2>\n<ul>\n",
+            escape_html(&section.channel_title)
+        ));
+        for video in &section.videos {
+            let link = format!(
+                "<a href=\"{}\">{}</a>",
+                video_url(&video.id),
+                escape_html(&video.title)
+            );
+            out.push_str(&format!(
+                "<li>{}</li>\n",
+                with_duration(link, video.duration.as_deref())
+            ));
+        }
+        out.push_str("</ul>\n");
+    }
+    out
+}
+
+pub fn digest_message(sections: &[ChannelSection], format: &str) -> Message {
+    let total: usize = sections.iter().map(|s| s.videos.len()).sum();
+    Message {
+        level: Level::Info,
+        title: format!(
+            "Digest: {} new video{} from {} channel{}",
+            total,
+            if total == 1 { "" } else { "s" },
+            sections.len(),
+            if sections.len() == 1 { "" } else { "s" }
+        ),
+        description: render_markdown(sections),
+        url: None,
+        image_url: None,
+        author: None,
+        fields: Vec::new(),
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        html: (format == "html").then(|| render_html(sections)),
+    }
+}
+
+/// Build and deliver every due digest. `last_sent_at` only advances after a
+/// successful delivery (or when there is nothing to send), so a failed send
+/// is retried with the same window on the next cycle.
+pub async fn send_due(state: &AppState) {
+    let now = crate::util::now_unix();
+    let due = {
+        let conn = state.db.lock().unwrap();
+        due_digests(&conn, now)
+            .into_iter()
+            .map(|d| {
+                let sections = collect(&conn, d.user_id, d.since, now);
+                (d, sections)
+            })
+            .collect::<Vec<_>>()
+    };
+
+    for (digest, sections) in due {
+        if !sections.is_empty() {
+            let (notifier, http) =
+                match super::user_notifier(&digest.target_url, &state.config).await {
+                    Ok(sender) => sender,
+                    Err(e) => {
+                        tracing::warn!("[digest] user {}: {}", digest.user_id, e);
+                        continue;
+                    }
+                };
+            let message = digest_message(&sections, &digest.format);
+            if let Err(e) = notifier.send(&http, &message).await {
+                tracing::error!(
+                    "[digest] {} delivery for user {} failed: {}",
+                    notifier.kind(),
+                    digest.user_id,
+                    e
+                );
+                continue;
+            }
+            tracing::info!(
+                "[digest] Sent digest to user {} ({})",
+                digest.user_id,
+                message.title
+            );
+        }
+        let conn = state.db.lock().unwrap();
+        if let Err(e) = conn.execute(
+            "UPDATE digest_settings SET last_sent_at = ?1 WHERE user_id = ?2",
+            rusqlite::params![now, digest.user_id],
+        ) {
+            tracing::warn!("[digest] failed to record last_sent_at: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // Digest Spec
+    //
+    // - Opt-in per user (digest_settings): daily or weekly, markdown or html,
+    //   delivered to the user's own notifier URL.
+    // - Lists favourite-channel videos received (fetched_at) since the
+    //   previous digest (first digest: the last period), newest published
+    //   first, with the RSS visibility rules: not hidden, not members-only,
+    //   livestreams / Shorts per channel setting.
+    // - Grouped by channel ID (titles need not be unique); one message per
+    //   user per period.
+    // - last_sent_at advances only after a successful send, so restarts and
+    //   retries never duplicate or drop a window.
+
+    use super::*;
+    use crate::notify::stand_in::HttpStandIn;
+
+    const NOW: i64 = 1_750_000_000;
+
+    fn setup_conn() -> Connection {
+        let conn = crate::db::open_memory();
+        conn.execute("INSERT INTO users (email) VALUES ('a@example.com')", [])
+            .unwrap();
+        for (id, title, favorite) in [("UC1", "Ch1", 1), ("UC2", "Ch2", 1), ("UC3", "Ch3", 0)] {
+            conn.execute(
+                "INSERT INTO channels (id, title) VALUES (?1, ?2)",
+                rusqlite::params![id, title],
+            )
+            .unwrap();
+            conn.execute(
+                "INSERT INTO user_channels (user_id, channel_id, is_favorite) VALUES (1, ?1, ?2)",
+                rusqlite::params![id, favorite],
+            )
+            .unwrap();
+        }
+        conn
+    }
+
+    /// Received the moment it was published.
+    fn insert_video(conn: &Connection, id: &str, channel_id: &str, published_at: i64) {
+        conn.execute(
+            "INSERT INTO videos (id, channel_id, title, published_at, fetched_at, duration)
+             VALUES (?1, ?2, ?1, ?3, ?3, 'PT3M')",
+            rusqlite::params![id, channel_id, published_at],
+        )
+        .unwrap();
+    }
+
+    fn set_digest(conn: &Connection, frequency: &str, target: &str, last_sent_at: Option<i64>) {
+        conn.execute(
+            "INSERT OR REPLACE INTO digest_settings (user_id, frequency, format, target_url, last_sent_at)
+             VALUES (1, ?1, 'markdown', ?2, ?3)",
+            rusqlite::params![frequency, target, last_sent_at],
+        )
+        .unwrap();
+    }
+
+    fn section_ids(sections: &[ChannelSection]) -> Vec<(String, Vec<String>)> {
+        sections
+            .iter()
+            .map(|s| {
+                (
+                    s.channel_title.clone(),
+                    s.videos.iter().map(|v| v.id.clone()).collect(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn due_digests_respects_frequency_and_first_run_window() {
+        let conn = setup_conn();
+        set_digest(&conn, "daily", "webhook+http://a", None);
+        let due = due_digests(&conn, NOW);
+        assert_eq!(due.len(), 1);
+        assert_eq!(
+            due[0].since,
+            NOW - DAY_SECS,
+            "first digest covers one period"
+        );
+
+        set_digest(&conn, "daily", "webhook+http://a", Some(NOW - 2 * 60 * 60));
+        assert!(due_digests(&conn, NOW).is_empty());
+        // A slightly-short cycle (drift) still counts as due.
+        set_digest(
+            &conn,
+            "daily",
+            "webhook+http://a",
+            Some(NOW - DAY_SECS + 60),
+        );
+        assert_eq!(due_digests(&conn, NOW)[0].since, NOW - DAY_SECS + 60);
+
+        set_digest(
+            &conn,
+            "weekly",
+            "webhook+http://a",
+            Some(NOW - 3 * DAY_SECS),
+        );
+        assert!(due_digests(&conn, NOW).is_empty());
+        set_digest(
+            &conn,
+            "weekly",
+            "webhook+http://a",
+            Some(NOW - 7 * DAY_SECS),
+        );
+        assert_eq!(due_digests(&conn, NOW).len(), 1);
+    }
+
+    #[test]
+    fn collect_groups_visible_favorite_videos_in_the_window() {
+        let conn = setup_conn();
+        insert_video(&conn, "old", "UC1", NOW - 2 * DAY_SECS);
+        insert_video(&conn, "a1", "UC1", NOW - 300);
+        insert_video(&conn, "b1", "UC2", NOW - 200);
+        insert_video(&conn, "a2", "UC1", NOW - 100);
+        insert_video(&conn, "notfav", "UC3", NOW - 100);
+        insert_video(&conn, "hidden", "UC1", NOW - 100);
+        insert_video(&conn, "members", "UC1", NOW - 100);
+        insert_video(&conn, "short", "UC2", NOW - 100);
+        insert_video(&conn, "live", "UC2", NOW - 100);
+        insert_video(&conn, "future", "UC1", NOW + 100);
+        conn.execute_batch(
+            "INSERT INTO user_videos (user_id, video_id, is_hidden) VALUES (1, 'hidden', 1);
+             UPDATE videos SET is_members_only = 1 WHERE id = 'members';
+             UPDATE videos SET is_short = 1 WHERE id = 'short';
+             UPDATE videos SET is_livestream = 1 WHERE id = 'live';
+             UPDATE user_channels SET hide_shorts = 1 WHERE channel_id = 'UC2';",
+        )
+        .unwrap();
+
+        assert_eq!(
+            section_ids(&collect(&conn, 1, NOW - DAY_SECS, NOW)),
+            vec![
+                ("Ch1".to_string(), vec!["a2".to_string(), "a1".to_string()]),
+                ("Ch2".to_string(), vec!["b1".to_string()]),
+            ]
+        );
+
+        conn.execute("UPDATE user_channels SET show_livestreams = 1", [])
+            .unwrap();
+        let sections = collect(&conn, 1, NOW - DAY_SECS, NOW);
+        assert!(section_ids(&sections)[1].1.contains(&"live".to_string()));
+    }
+
+    #[test]
+    fn collect_windows_on_arrival_and_keeps_same_titled_channels_apart() {
+        let conn = setup_conn();
+        conn.execute(
+            "UPDATE channels SET title = 'Same' WHERE id IN ('UC1', 'UC2')",
+            [],
+        )
+        .unwrap();
+        insert_video(&conn, "a1", "UC1", NOW - 300);
+        insert_video(&conn, "b1", "UC2", NOW - 200);
+        // Published two days ago but only pushed now: the earlier digest
+        // never saw it.
+        insert_video(&conn, "late", "UC1", NOW - 2 * DAY_SECS);
+        conn.execute(
+            "UPDATE videos SET fetched_at = ?1 WHERE id = 'late'",
+            [NOW - 10],
+        )
+        .unwrap();
+        // Published in the window but received before it: already sent.
+        insert_video(&conn, "sent", "UC1", NOW - 100);
+        conn.execute(
+            "UPDATE videos SET fetched_at = ?1 WHERE id = 'sent'",
+            [NOW - 2 * DAY_SECS],
+        )
+        .unwrap();
+
+        let sections = collect(&conn, 1, NOW - DAY_SECS, NOW);
+        assert_eq!(
+            section_ids(&sections),
+            vec![
+                ("Same".to_string(), vec!["b1".to_string()]),
+                (
+                    "Same".to_string(),
+                    vec!["a1".to_string(), "late".to_string()]
+                ),
+            ]
+        );
+        assert_eq!(sections[0].channel_id, "UC2");
+        assert_eq!(sections[1].channel_id, "UC1");
+    }
+
+    fn sample() -> Vec<ChannelSection> {
+        vec![ChannelSection {
+            channel_id: "UC1".to_string(),
+            channel_title: "A & B".to_string(),
+            videos: vec![DigestVideo {
+                id: "v1".to_string(),
+                title: "<Live> [Part 1]".to_string(),
+                duration: Some("PT1M5S".to_string()),
+            }],
+        }]
+    }
+
+    #[test]
+    fn render_markdown_and_html_list_videos_under_their_channel() {
+        assert_eq!(
+            render_markdown(&sample()),
+            "**A & B**\n- [<Live> [Part 1\\]](https://www.youtube.com/watch?v=v1) (1:05)"
+        );
+        let html = render_html(&sample());
+        assert!(html.contains("<h2>A &amp; B</h2>"));
+        assert!(html.contains(
+            "<li><a href=\"https://www.youtube.com/watch?v=v1\">&lt;Live&gt; [Part 1]</a> (1:05)</li>"
+        ));
+    }
+
+    #[test]
+    fn render_markdown_truncates_long_digests() {
+        let videos = (0..200)
+            .map(|i| DigestVideo {
+                id: format!("video{i:04}"),
+                title: "x".repeat(40),
+                duration: None,
+            })
+            .collect();
+        let sections = vec![ChannelSection {
+            channel_id: "UC1".to_string(),
+            channel_title: "Ch".to_string(),
+            videos,
+        }];
+        let markdown = render_markdown(&sections);
+        assert!(markdown.len() <= MAX_MARKDOWN_CHARS + 32);
+        assert!(markdown.ends_with("more"));
+        assert!(markdown.contains("…and "));
+    }
+
+    #[test]
+    fn digest_message_attaches_html_only_for_html_format() {
+        let message = digest_message(&sample(), "markdown");
+        assert_eq!(message.title, "Digest: 1 new video from 1 channel");
+        assert!(message.html.is_none());
+        assert!(digest_message(&sample(), "html").html.is_some());
+    }
+
+    #[tokio::test]
+    async fn send_due_delivers_once_and_records_last_sent_at() {
+        let stand_in = HttpStandIn::start().await;
+        let state = AppState::test();
+        let now = crate::util::now_unix();
+        {
+            let mut conn = state.db.lock().unwrap();
+            *conn = setup_conn();
+            insert_video(&conn, "v1", "UC1", now - 60);
+            set_digest(
+                &conn,
+                "daily",
+                &format!("webhook+{}", stand_in.url("/digest")),
+                None,
+            );
+        }
+
+        send_due(&state).await;
+        send_due(&state).await;
+
+        let requests = stand_in.requests();
+        assert_eq!(requests.len(), 1, "a sent window must not be resent");
+        let body = requests[0].json();
+        assert_eq!(body["title"], "Digest: 1 new video from 1 channel");
+        assert!(body["description"].as_str().unwrap().contains("**Ch1**"));
+
+        let last: Option<i64> = state
+            .db
+            .lock()
+            .unwrap()
+            .query_row("SELECT last_sent_at FROM digest_settings", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert!(last.unwrap() >= now);
+    }
+
+    #[tokio::test]
+    async fn send_due_keeps_the_window_when_delivery_fails() {
+        let stand_in = HttpStandIn::start_with_status(500).await;
+        let state = AppState::test();
+        let now = crate::util::now_unix();
+        {
+            let mut conn = state.db.lock().unwrap();
+            *conn = setup_conn();
+            insert_video(&conn, "v1", "UC1", now - 60);
+            set_digest(
+                &conn,
+                "daily",
+                &format!("webhook+{}", stand_in.url("/digest")),
+                None,
+            );
+        }
+
+        send_due(&state).await;
+
+        let last: Option<i64> = state
+            .db
+            .lock()
+            .unwrap()
+            .query_row("SELECT last_sent_at FROM digest_settings", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(last, None, "failed delivery must be retried next cycle");
+    }
+}
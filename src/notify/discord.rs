@@ -0,0 +1,147 @@
+use super::{Message, Notifier, NotifyError};
+use serde_json::{json, Value};
+
+/// Discord webhook: one embed per message.
+pub struct Discord {
+    url: reqwest::Url,
+}
+
+impl Discord {
+    pub fn new(url: reqwest::Url) -> Self {
+        Self { url }
+    }
+}
+
+/// Discord rejects `null` for several embed keys, so optional parts are only
+/// inserted when present.
+fn embed(message: &Message) -> Value {
+    let mut embed = json!({
+        "title": message.title,
+        "color": message.level.color(),
+        "timestamp": message.timestamp,
+    });
+    if !message.description.is_empty() {
+        embed["description"] = json!(message.description);
+    }
+    if let Some(url) = &message.url {
+        embed["url"] = json!(url);
+    }
+    if let Some(image) = &message.image_url {
+        embed["thumbnail"] = json!({ "url": image });
+    }
+    if let Some(author) = &message.author {
+        let mut value = json!({ "name": author.name });
+        if let Some(url) = &author.url {
+            value["url"] = json!(url);
+        }
+        if let Some(icon) = &author.icon_url {
+            value["icon_url"] = json!(icon);
+        }
+        embed["author"] = value;
+    }
+    if !message.fields.is_empty() {
+        embed["fields"] = message
+            .fields
+            .iter()
+            .map(|f| json!({ "name": f.name, "value": f.value, "inline": true }))
+            .collect();
+    }
+    embed
+}
+
+#[async_trait::async_trait]
+impl Notifier for Discord {
+    fn kind(&self) -> &'static str {
+        "discord"
+    }
+
+    async fn send(&self, http: &reqwest::Client, message: &Message) -> Result<(), NotifyError> {
+        let body = json!({ "embeds": [embed(message)] });
+        http.post(self.url.clone())
+            .json(&body)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // Discord backend: POST {"embeds": [embed]} to the webhook URL.
+    // Warnings keep the original orange (0xffa000) embed; optional parts
+    // (description, url, thumbnail, author, fields) are omitted when absent.
+
+    use super::*;
+    use crate::notify::stand_in::HttpStandIn;
+    use crate::notify::{new_video_message, Level, NewVideo};
+
+    fn video() -> NewVideo {
+        NewVideo {
+            id: "vid1".to_string(),
+            title: "Live now".to_string(),
+            channel_id: "UC1".to_string(),
+            channel_title: "Ch1".to_string(),
+            channel_thumbnail: Some("https://example.com/icon.jpg".to_string()),
+            duration: Some("PT1H2M3S".to_string()),
+            is_short: false,
+            is_livestream: true,
+            published_at: Some(1750000000),
+        }
+    }
+
+    #[tokio::test]
+    async fn posts_warning_embed() {
+        let stand_in = HttpStandIn::start().await;
+        let discord = Discord::new(stand_in.url("/api/webhooks/1/x").parse().unwrap());
+        discord
+            .send(
+                &reqwest::Client::new(),
+                &Message::warning("Warn", "details"),
+            )
+            .await
+            .unwrap();
+
+        let req = &stand_in.requests()[0];
+        assert_eq!(req.method, "POST");
+        assert_eq!(req.path, "/api/webhooks/1/x");
+        let embed = &req.json()["embeds"][0];
+        assert_eq!(embed["title"], "Warn");
+        assert_eq!(embed["description"], "details");
+        assert_eq!(embed["color"], Level::Warning.color());
+        assert!(embed.get("author").is_none());
+        assert!(embed.get("url").is_none());
+    }
+
+    #[tokio::test]
+    async fn posts_new_video_embed_with_author_thumbnail_and_duration() {
+        let stand_in = HttpStandIn::start().await;
+        let discord = Discord::new(stand_in.url("/hook").parse().unwrap());
+        discord
+            .send(&reqwest::Client::new(), &new_video_message(&video()))
+            .await
+            .unwrap();
+
+        let embed = stand_in.requests()[0].json()["embeds"][0].clone();
+        assert_eq!(embed["title"], "[🔴 LIVE] Live now");
+        assert_eq!(embed["url"], "https://www.youtube.com/watch?v=vid1");
+        assert_eq!(embed["author"]["name"], "Ch1");
+        assert_eq!(embed["author"]["icon_url"], "https://example.com/icon.jpg");
+        assert_eq!(
+            embed["thumbnail"]["url"],
+            "https://i.ytimg.com/vi/vid1/hqdefault.jpg"
+        );
+        assert_eq!(embed["fields"][0]["value"], "1:02:03");
+        assert!(embed.get("description").is_none());
+    }
+
+    #[tokio::test]
+    async fn non_success_status_is_an_error() {
+        let stand_in = HttpStandIn::start_with_status(404).await;
+        let discord = Discord::new(stand_in.url("/gone").parse().unwrap());
+        let result = discord
+            .send(&reqwest::Client::new(), &Message::warning("t", "d"))
+            .await;
+        assert!(matches!(result, Err(NotifyError::Http(_))));
+    }
+}
@@ -0,0 +1,107 @@
+use super::{Level, Message, Notifier, NotifyError};
+use serde_json::json;
+
+/// Gotify: POST `<server>/message` with the application token in
+/// `X-Gotify-Key`.
+///
+/// URL: `gotify+https://host[/prefix]/?token=<app token>`.
+pub struct Gotify {
+    endpoint: reqwest::Url,
+    token: String,
+}
+
+impl Gotify {
+    pub fn from_url(mut url: reqwest::Url) -> Result<Self, NotifyError> {
+        let token = url
+            .query_pairs()
+            .find(|(k, _)| k == "token")
+            .map(|(_, v)| v.into_owned())
+            .filter(|t| !t.is_empty())
+            .ok_or_else(|| NotifyError::InvalidUrl("gotify: token missing".to_string()))?;
+        url.set_query(None);
+        if !url.path().ends_with('/') {
+            let path = format!("{}/", url.path());
+            url.set_path(&path);
+        }
+        let endpoint = url
+            .join("message")
+            .map_err(|_| NotifyError::InvalidUrl("gotify: malformed URL".to_string()))?;
+        Ok(Self { endpoint, token })
+    }
+}
+
+#[async_trait::async_trait]
+impl Notifier for Gotify {
+    fn kind(&self) -> &'static str {
+        "gotify"
+    }
+
+    async fn send(&self, http: &reqwest::Client, message: &Message) -> Result<(), NotifyError> {
+        let priority = match message.level {
+            Level::Info => 5,
+            Level::Warning => 8,
+        };
+        let mut body = json!({
+            "title": message.title,
+            "message": message.plain_text(),
+            "priority": priority,
+        });
+        if let Some(url) = &message.url {
+            body["extras"] = json!({ "client::notification": { "click": { "url": url } } });
+        }
+
+        http.post(self.endpoint.clone())
+            .header("X-Gotify-Key", &self.token)
+            .json(&body)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // Gotify backend: POST JSON {title, message, priority, extras} to
+    // <server>/message with X-Gotify-Key. Warnings are priority 8.
+
+    use super::*;
+    use crate::notify::stand_in::HttpStandIn;
+
+    #[test]
+    fn from_url_requires_token_and_keeps_prefix() {
+        let gotify =
+            Gotify::from_url("https://example.com/gotify?token=A1".parse().unwrap()).unwrap();
+        assert_eq!(
+            gotify.endpoint.as_str(),
+            "https://example.com/gotify/message"
+        );
+        assert_eq!(gotify.token, "A1");
+
+        assert!(Gotify::from_url("https://example.com/".parse().unwrap()).is_err());
+    }
+
+    #[tokio::test]
+    async fn posts_message_with_app_token_header() {
+        let stand_in = HttpStandIn::start().await;
+        let gotify =
+            Gotify::from_url(format!("{}?token=A1", stand_in.url("/")).parse().unwrap()).unwrap();
+        let mut message = Message::warning("t", "d");
+        message.url = Some("https://example.com/v".to_string());
+        gotify
+            .send(&reqwest::Client::new(), &message)
+            .await
+            .unwrap();
+
+        let req = &stand_in.requests()[0];
+        assert_eq!(req.path, "/message");
+        assert_eq!(req.header("x-gotify-key"), Some("A1"));
+        let body = req.json();
+        assert_eq!(body["title"], "t");
+        assert_eq!(body["priority"], 8);
+        assert_eq!(
+            body["extras"]["client::notification"]["click"]["url"],
+            "https://example.com/v"
+        );
+    }
+}
@@ -0,0 +1,127 @@
+use super::{Level, Message, Notifier, NotifyError};
+use serde_json::json;
+
+/// ntfy via JSON publishing: the message is POSTed to the server root with
+/// the topic in the body, which keeps non-ASCII titles out of HTTP headers.
+///
+/// URL: `ntfy+https://[user:pass@]host[/prefix]/<topic>[?token=<access token>]`.
+pub struct Ntfy {
+    server: reqwest::Url,
+    topic: String,
+    token: Option<String>,
+}
+
+impl Ntfy {
+    pub fn from_url(mut url: reqwest::Url) -> Result<Self, NotifyError> {
+        let token = url
+            .query_pairs()
+            .find(|(k, _)| k == "token")
+            .map(|(_, v)| v.into_owned());
+        url.set_query(None);
+
+        let topic = url
+            .path_segments()
+            .and_then(|mut segments| segments.next_back())
+            .filter(|topic| !topic.is_empty())
+            .map(str::to_string)
+            .ok_or_else(|| NotifyError::InvalidUrl("ntfy: topic missing".to_string()))?;
+        // Publish to the directory containing the topic (the server root,
+        // or a reverse-proxy prefix).
+        let server = url
+            .join("./")
+            .map_err(|_| NotifyError::InvalidUrl("ntfy: malformed URL".to_string()))?;
+
+        Ok(Self {
+            server,
+            topic,
+            token,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl Notifier for Ntfy {
+    fn kind(&self) -> &'static str {
+        "ntfy"
+    }
+
+    async fn send(&self, http: &reqwest::Client, message: &Message) -> Result<(), NotifyError> {
+        let (priority, tags) = match message.level {
+            Level::Info => (3, vec!["tv"]),
+            Level::Warning => (4, vec!["warning"]),
+        };
+        let mut body = json!({
+            "topic": self.topic,
+            "title": message.title,
+            "message": message.plain_text(),
+            "priority": priority,
+            "tags": tags,
+        });
+        if let Some(url) = &message.url {
+            body["click"] = json!(url);
+        }
+        if let Some(image) = &message.image_url {
+            body["attach"] = json!(image);
+        }
+        if let Some(icon) = message.author.as_ref().and_then(|a| a.icon_url.as_ref()) {
+            body["icon"] = json!(icon);
+        }
+
+        let mut request = http.post(self.server.clone()).json(&body);
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+        request.send().await?.error_for_status()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // ntfy backend: POST JSON {topic, title, message, priority, tags, click,
+    // attach, icon} to the server root. Warnings are priority 4 ("high").
+
+    use super::*;
+    use crate::notify::stand_in::HttpStandIn;
+
+    #[test]
+    fn from_url_splits_server_topic_and_token() {
+        let ntfy = Ntfy::from_url(
+            "https://ntfy.example.com/prefix/alerts?token=tk_abc"
+                .parse()
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(ntfy.server.as_str(), "https://ntfy.example.com/prefix/");
+        assert_eq!(ntfy.topic, "alerts");
+        assert_eq!(ntfy.token.as_deref(), Some("tk_abc"));
+    }
+
+    #[tokio::test]
+    async fn publishes_json_to_server_root_with_bearer_token() {
+        let stand_in = HttpStandIn::start().await;
+        let ntfy = Ntfy::from_url(
+            format!("{}?token=tk_abc", stand_in.url("/alerts"))
+                .parse()
+                .unwrap(),
+        )
+        .unwrap();
+        ntfy.send(
+            &reqwest::Client::new(),
+            &Message::warning("WebSub 購読失敗", "3 channels"),
+        )
+        .await
+        .unwrap();
+
+        let req = &stand_in.requests()[0];
+        assert_eq!(req.method, "POST");
+        assert_eq!(req.path, "/");
+        assert_eq!(req.header("authorization"), Some("Bearer tk_abc"));
+        let body = req.json();
+        assert_eq!(body["topic"], "alerts");
+        assert_eq!(body["title"], "WebSub 購読失敗");
+        assert_eq!(body["message"], "3 channels");
+        assert_eq!(body["priority"], 4);
+        assert_eq!(body["tags"][0], "warning");
+    }
+}
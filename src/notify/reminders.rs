@@ -0,0 +1,413 @@
+//! Reminders for upcoming livestreams and premieres.
+//!
+//! Users opt in via `reminder_settings` (lead time + their own notifier URL)
+//! and get reminders for channels where they show livestreams
+//! (`user_channels.show_livestreams = 1`), unless they hid or muted the video:
+//!
+//! - `upcoming`: once the stream's `scheduled_start_at` is within
+//!   `minutes_before` of now.
+//! - `live`: once enrichment has seen the stream go live (`live_started_at`).
+//!
+//! The WebSub push only announces a stream once, and the daily backfill is
+//! far too slow to notice it going live, so [`run`] re-queries streams that
+//! are due to start (at most every [`RECHECK_INTERVAL_SECS`]) before looking
+//! for due reminders. Each (user, video, kind) is recorded in
+//! `stream_reminders_sent` before it is handed to the outbox, so a reminder is
+//! never sent twice.
+
+use super::{new_video_message, Field, Message, NewVideo};
+use crate::state::AppState;
+use rusqlite::Connection;
+
+/// How often reminders are evaluated.
+pub const CHECK_INTERVAL_SECS: u64 = 60;
+/// Allowed `minutes_before` (1 minute to 1 day).
+pub const MINUTES_BEFORE_RANGE: std::ops::RangeInclusive<i64> = 1..=1440;
+/// Minimum gap between videos.list re-checks of a stream waiting to start.
+const RECHECK_INTERVAL_SECS: i64 = 5 * 60;
+/// Streams still not live this long after their schedule are given up on
+/// (cancelled or rescheduled without notice); the daily backfill still
+/// picks up a new schedule.
+const GIVE_UP_SECS: i64 = 6 * 60 * 60;
+/// A "live now" reminder older than this is stale (e.g. the start was only
+/// noticed by the daily backfill) and is skipped.
+const LIVE_NOTICE_WINDOW_SECS: i64 = 60 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Upcoming,
+    Live,
+}
+
+impl Kind {
+    fn as_str(self) -> &'static str {
+        match self {
+            Kind::Upcoming => "upcoming",
+            Kind::Live => "live",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reminder {
+    pub user_id: i64,
+    pub target_url: String,
+    pub timezone: String,
+    pub kind: Kind,
+    pub starts_at: i64,
+    pub video: NewVideo,
+}
+
+/// Streams someone wants a reminder for that should have started (or start
+/// within the next check) but have not been seen live, grouped by channel.
+pub fn awaiting_start(conn: &Connection, now: i64) -> Vec<(String, Vec<String>)> {
+    let rows: Vec<(String, String)> = conn
+        .prepare(
+            "SELECT v.channel_id, v.id FROM videos v
+             WHERE v.is_livestream = 1
+               AND v.live_started_at IS NULL
+               AND v.livestream_ended_at IS NULL
+               AND v.scheduled_start_at BETWEEN ?1 AND ?2
+               AND COALESCE(v.details_checked_at, 0) <= ?3
+               AND EXISTS (
+                 SELECT 1 FROM user_channels uc
+                 JOIN reminder_settings rs ON rs.user_id = uc.user_id
+                 JOIN users u ON u.id = uc.user_id
+                 WHERE uc.channel_id = v.channel_id AND uc.show_livestreams = 1
+                   AND u.disabled_at IS NULL
+               )
+             ORDER BY v.channel_id, v.id",
+        )
+        .and_then(|mut stmt| {
+            stmt.query_map(
+                rusqlite::params![
+                    now - GIVE_UP_SECS,
+                    now + CHECK_INTERVAL_SECS as i64,
+                    now - RECHECK_INTERVAL_SECS
+                ],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )?
+            .collect()
+        })
+        .unwrap_or_default();
+
+    let mut grouped: Vec<(String, Vec<String>)> = Vec::new();
+    for (channel_id, video_id) in rows {
+        match grouped.last_mut() {
+            Some((ch, ids)) if *ch == channel_id => ids.push(video_id),
+            _ => grouped.push((channel_id, vec![video_id])),
+        }
+    }
+    grouped
+}
+
+/// Reminders due at `now` that have not been sent yet.
+pub fn due_reminders(conn: &Connection, now: i64) -> Vec<Reminder> {
+    // Both kinds share the audience; only the timing condition differs.
+    let sql = format!(
+        "SELECT rs.user_id, rs.target_url, u.timezone, ?2,
+                      CASE WHEN ?2 = 'live' THEN v.live_started_at ELSE v.scheduled_start_at END,
+                      v.id
+               FROM reminder_settings rs
+               JOIN users u ON u.id = rs.user_id
+               JOIN user_channels uc ON uc.user_id = rs.user_id AND uc.show_livestreams = 1
+               JOIN videos v ON v.channel_id = uc.channel_id
+               LEFT JOIN user_videos uv ON uv.user_id = rs.user_id AND uv.video_id = v.id
+               WHERE v.is_livestream = 1
+                 AND v.is_members_only = 0
+                 AND v.livestream_ended_at IS NULL
+                 AND u.disabled_at IS NULL
+                 AND COALESCE(uv.is_hidden, 0) = 0
+                 AND {not_muted}
+                 AND CASE WHEN ?2 = 'live'
+                       THEN v.live_started_at BETWEEN ?1 - ?3 AND ?1
+                       ELSE v.live_started_at IS NULL
+                            AND v.scheduled_start_at > ?1
+                            AND v.scheduled_start_at <= ?1 + rs.minutes_before * 60
+                     END
+                 AND NOT EXISTS (
+                   SELECT 1 FROM stream_reminders_sent s
+                   WHERE s.user_id = rs.user_id AND s.video_id = v.id AND s.kind = ?2
+                 )
+               ORDER BY rs.user_id, v.id",
+        not_muted = crate::visibility::NOT_MUTED,
+    );
+
+    let mut reminders = Vec::new();
+    for kind in [Kind::Upcoming, Kind::Live] {
+        let rows: Vec<(i64, String, String, i64, String)> = conn
+            .prepare(&sql)
+            .and_then(|mut stmt| {
+                stmt.query_map(
+                    rusqlite::params![now, kind.as_str(), LIVE_NOTICE_WINDOW_SECS],
+                    |row| {
+                        Ok((
+                            row.get(0)?,
+                            row.get(1)?,
+                            row.get(2)?,
+                            row.get(4)?,
+                            row.get(5)?,
+                        ))
+                    },
+                )?
+                .collect()
+            })
+            .unwrap_or_else(|e| {
+                tracing::warn!("[reminder] due query failed: {}", e);
+                Vec::new()
+            });
+        for (user_id, target_url, timezone, starts_at, video_id) in rows {
+            if let Some(video) = super::load_new_videos(conn, &[video_id]).pop() {
+                reminders.push(Reminder {
+                    user_id,
+                    target_url,
+                    timezone,
+                    kind,
+                    starts_at,
+                    video,
+                });
+            }
+        }
+    }
+    reminders
+}
+
+/// Render a reminder: the new-video embed with a timing prefix and the
+/// start time in the user's time zone.
+pub fn reminder_message(reminder: &Reminder, now: i64) -> Message {
+    let mut message = new_video_message(&reminder.video);
+    let tz: chrono_tz::Tz = reminder.timezone.parse().unwrap_or(chrono_tz::UTC);
+    let start = chrono::DateTime::from_timestamp(reminder.starts_at, 0)
+        .map(|t| t.with_timezone(&tz).format("%H:%M %Z").to_string())
+        .unwrap_or_default();
+    match reminder.kind {
+        Kind::Upcoming => {
+            let minutes = ((reminder.starts_at - now + 59) / 60).max(1);
+            message.title = format!("[Starts in {} min] {}", minutes, reminder.video.title);
+            message.fields.push(Field {
+                name: "Scheduled start".to_string(),
+                value: start,
+            });
+        }
+        Kind::Live => {
+            message.title = format!("[🔴 LIVE now] {}", reminder.video.title);
+            message.fields.push(Field {
+                name: "Started".to_string(),
+                value: start,
+            });
+        }
+    }
+    message
+}
+
+/// Re-check streams due to start, then send every due reminder.
+pub async fn run(state: &AppState) {
+    if state.config.youtube_api_key.is_some() {
+        let waiting = {
+            let conn = state.db.lock().unwrap();
+            awaiting_start(&conn, crate::util::now_unix())
+        };
+        for (channel_id, ids) in waiting {
+            if let Err(e) = crate::sync::video_enrich::enrich_videos(state, &channel_id, &ids).await
+            {
+                tracing::warn!("[reminder] re-check failed for {}: {}", channel_id, e);
+            }
+        }
+    }
+
+    let now = crate::util::now_unix();
+    let due = {
+        let conn = state.db.lock().unwrap();
+        due_reminders(&conn, now)
+            .into_iter()
+            .filter(|r| {
+                // Claim first: a crash after this point loses one reminder
+                // rather than repeating it every minute.
+                conn.execute(
+                    "INSERT OR IGNORE INTO stream_reminders_sent (user_id, video_id, kind, sent_at)
+                     VALUES (?1, ?2, ?3, ?4)",
+                    rusqlite::params![r.user_id, r.video.id, r.kind.as_str(), now],
+                )
+                .is_ok_and(|n| n == 1)
+            })
+            .collect::<Vec<_>>()
+    };
+    for reminder in due {
+        tracing::info!(
+            "[reminder] {} reminder for {} to user {}",
+            reminder.kind.as_str(),
+            reminder.video.id,
+            reminder.user_id
+        );
+        super::outbox::send(
+            state,
+            &super::outbox::reminder_target(reminder.user_id),
+            &reminder_message(&reminder, now),
+        )
+        .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // Stream Reminder Spec
+    //
+    // - Opt-in per user (reminder_settings: minutes_before, target_url).
+    // - Audience: subscribers with show_livestreams = 1 who did not hide the
+    //   video; members-only streams never qualify.
+    // - "upcoming" fires once scheduled_start_at is within minutes_before,
+    //   "live" once live_started_at is set (within the last hour).
+    // - Each (user, video, kind) is sent at most once.
+    // - Streams due to start are re-checked via videos.list at most every
+    //   5 minutes, for up to 6 hours past their schedule.
+
+    use super::*;
+    use crate::notify::stand_in::HttpStandIn;
+
+    const NOW: i64 = 1_750_000_000;
+
+    fn setup(state: &AppState, target: &str) {
+        let conn = state.db.lock().unwrap();
+        conn.execute_batch(
+            "INSERT INTO users (email, timezone) VALUES ('a@example.com', 'Asia/Tokyo');
+             INSERT INTO channels (id, title) VALUES ('UC1', 'Ch1');
+             INSERT INTO user_channels (user_id, channel_id, show_livestreams) VALUES (1, 'UC1', 1);",
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO reminder_settings (user_id, minutes_before, target_url) VALUES (1, 15, ?1)",
+            [target],
+        )
+        .unwrap();
+    }
+
+    fn insert_stream(state: &AppState, id: &str, scheduled: i64, started: Option<i64>) {
+        state
+            .db
+            .lock()
+            .unwrap()
+            .execute(
+                "INSERT INTO videos (id, channel_id, title, is_livestream, scheduled_start_at, live_started_at, details_checked_at)
+                 VALUES (?1, 'UC1', ?1, 1, ?2, ?3, ?4)",
+                rusqlite::params![id, scheduled, started, NOW],
+            )
+            .unwrap();
+    }
+
+    fn due_ids(state: &AppState, now: i64) -> Vec<(String, Kind)> {
+        let conn = state.db.lock().unwrap();
+        due_reminders(&conn, now)
+            .into_iter()
+            .map(|r| (r.video.id, r.kind))
+            .collect()
+    }
+
+    #[test]
+    fn upcoming_fires_within_the_lead_time_only() {
+        let state = AppState::test();
+        setup(&state, "https://example.com/hook");
+        insert_stream(&state, "soon", NOW + 10 * 60, None);
+        insert_stream(&state, "later", NOW + 60 * 60, None);
+        insert_stream(&state, "past", NOW - 60, None);
+
+        assert_eq!(
+            due_ids(&state, NOW),
+            vec![("soon".to_string(), Kind::Upcoming)]
+        );
+    }
+
+    #[test]
+    fn live_fires_once_the_stream_has_started_recently() {
+        let state = AppState::test();
+        setup(&state, "https://example.com/hook");
+        insert_stream(&state, "live", NOW - 300, Some(NOW - 60));
+        insert_stream(&state, "stale", NOW - 7200, Some(NOW - 7200));
+
+        assert_eq!(due_ids(&state, NOW), vec![("live".to_string(), Kind::Live)]);
+    }
+
+    #[test]
+    fn audience_respects_show_livestreams_hidden_and_members_only() {
+        let state = AppState::test();
+        setup(&state, "https://example.com/hook");
+        insert_stream(&state, "hidden", NOW + 60, None);
+        insert_stream(&state, "members", NOW + 60, None);
+        {
+            let conn = state.db.lock().unwrap();
+            conn.execute_batch(
+                "INSERT INTO user_videos (user_id, video_id, is_hidden) VALUES (1, 'hidden', 1);
+                 UPDATE videos SET is_members_only = 1 WHERE id = 'members';",
+            )
+            .unwrap();
+        }
+        assert!(due_ids(&state, NOW).is_empty());
+
+        insert_stream(&state, "visible", NOW + 60, None);
+        state
+            .db
+            .lock()
+            .unwrap()
+            .execute("UPDATE user_channels SET show_livestreams = 0", [])
+            .unwrap();
+        assert!(due_ids(&state, NOW).is_empty());
+    }
+
+    #[test]
+    fn awaiting_start_rechecks_due_streams_at_most_every_five_minutes() {
+        let state = AppState::test();
+        setup(&state, "https://example.com/hook");
+        insert_stream(&state, "due", NOW - 60, None);
+        insert_stream(&state, "future", NOW + 3600, None);
+        insert_stream(&state, "abandoned", NOW - 7 * 3600, None);
+        insert_stream(&state, "started", NOW - 60, Some(NOW - 30));
+
+        let conn = state.db.lock().unwrap();
+        // Just checked at NOW: nothing to re-query yet.
+        assert!(awaiting_start(&conn, NOW).is_empty());
+        assert_eq!(
+            awaiting_start(&conn, NOW + RECHECK_INTERVAL_SECS),
+            vec![("UC1".to_string(), vec!["due".to_string()])]
+        );
+    }
+
+    #[test]
+    fn reminder_message_shows_lead_time_and_local_start() {
+        let state = AppState::test();
+        setup(&state, "https://example.com/hook");
+        insert_stream(&state, "soon", NOW + 10 * 60, None);
+        let reminder = state.db.lock().map(|c| due_reminders(&c, NOW)).unwrap()[0].clone();
+
+        let message = reminder_message(&reminder, NOW);
+        assert_eq!(message.title, "[Starts in 10 min] soon");
+        // NOW + 10min = 2025-06-15T15:16:40Z = 00:16 JST
+        assert_eq!(message.fields.last().unwrap().value, "00:16 JST");
+    }
+
+    #[tokio::test]
+    async fn run_sends_each_reminder_once() {
+        let stand_in = HttpStandIn::start().await;
+        let state = AppState::test();
+        setup(&state, &format!("webhook+{}", stand_in.url("/hook")));
+        let now = crate::util::now_unix();
+        insert_stream(&state, "soon", now + 5 * 60, None);
+
+        run(&state).await;
+        run(&state).await;
+        let requests = stand_in.requests();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].json()["title"]
+            .as_str()
+            .unwrap()
+            .starts_with("[Starts in 5 min]"));
+
+        // Going live is a separate, second reminder.
+        state
+            .db
+            .lock()
+            .unwrap()
+            .execute("UPDATE videos SET live_started_at = ?1", [now])
+            .unwrap();
+        run(&state).await;
+        assert_eq!(stand_in.requests().len(), 2);
+    }
+}
@@ -0,0 +1,602 @@
+//! Per-user notification rules.
+//!
+//! Each row in `notification_rules` sends matching new videos to the user's
+//! own notifier URL (same schemes as `NOTIFIER_URLS`). A rule can be scoped to
+//! one channel or one group, triggered by a title keyword or regex, exclude
+//! Shorts / livestreams, and define quiet hours in the user's time zone.
+//! Matches that land inside quiet hours are parked in `notification_queue`
+//! and flushed as a single batch once the quiet hours end.
+
+use super::{load_new_videos, new_video_message, Level, Message, NewVideo};
+use crate::state::AppState;
+use chrono::{DateTime, NaiveTime, TimeZone, Timelike, Utc};
+use rusqlite::Connection;
+use std::collections::{BTreeMap, HashSet};
+
+/// How often the quiet-hours queue is checked for due batches.
+pub const FLUSH_INTERVAL_SECS: u64 = 60;
+
+#[derive(Debug, Clone, PartialEq)]
+struct MatchedRule {
+    id: i64,
+    user_id: i64,
+    target_url: String,
+    quiet: Option<(u32, u32)>,
+    timezone: String,
+}
+
+/// Parse `HH:MM` (24h) into minutes after midnight.
+pub fn parse_hhmm(value: &str) -> Option<u32> {
+    NaiveTime::parse_from_str(value, "%H:%M")
+        .ok()
+        .map(|t| t.hour() * 60 + t.minute())
+}
+
+pub fn format_hhmm(minutes: i64) -> String {
+    format!("{:02}:{:02}", minutes / 60, minutes % 60)
+}
+
+/// If `now` falls within the quiet window `[start, end)` (minutes after
+/// midnight, local to `timezone`, wrapping past midnight when start > end),
+/// return the Unix time at which the window ends. `start == end` disables it.
+pub fn quiet_until(now: DateTime<Utc>, timezone: &str, start: u32, end: u32) -> Option<i64> {
+    if start == end {
+        return None;
+    }
+    let tz: chrono_tz::Tz = timezone.parse().unwrap_or(chrono_tz::UTC);
+    let local = now.with_timezone(&tz);
+    let minute = local.hour() * 60 + local.minute();
+
+    let inside = if start < end {
+        start <= minute && minute < end
+    } else {
+        minute >= start || minute < end
+    };
+    if !inside {
+        return None;
+    }
+
+    // The window ends later today, unless it wraps and we are before midnight.
+    let mut end_date = local.date_naive();
+    if start > end && minute >= start {
+        end_date = end_date.succ_opt()?;
+    }
+    let end_time = NaiveTime::from_hms_opt(end / 60, end % 60, 0)?;
+    let naive = end_date.and_time(end_time);
+    // A DST gap can swallow the end time; fall forward an hour in that case.
+    tz.from_local_datetime(&naive)
+        .earliest()
+        .or_else(|| {
+            tz.from_local_datetime(&(naive + chrono::Duration::hours(1)))
+                .earliest()
+        })
+        .map(|t| t.timestamp())
+}
+
+/// Enabled rules of the video's (not disabled) subscribers that accept it. A rule without a
+/// keyword matches every title; a keyword matches like a mute pattern
+/// ([`crate::visibility::title_matches`]). The user's own channel visibility
+/// settings (hide_shorts / show_livestreams) and mute rules always apply on
+/// top of the rule's exclusions.
+fn matching_rules(conn: &Connection, video: &NewVideo) -> Vec<MatchedRule> {
+    let result = conn.prepare(&format!(
+        "SELECT r.id, r.user_id, r.target_url, r.quiet_start, r.quiet_end, u.timezone
+         FROM notification_rules r
+         JOIN users u ON u.id = r.user_id
+         JOIN {}
+         JOIN user_channels uc ON uc.user_id = r.user_id AND uc.channel_id = v.channel_id
+         WHERE r.is_enabled = 1
+           AND u.disabled_at IS NULL
+           AND (r.channel_id IS NULL OR r.channel_id = v.channel_id)
+           AND (r.group_id IS NULL OR EXISTS (
+                 SELECT 1 FROM channel_groups cg
+                 WHERE cg.group_id = r.group_id AND cg.channel_id = v.channel_id))
+           AND (v.is_short = 0 OR r.exclude_shorts = 0)
+           AND (v.is_livestream = 0 OR r.exclude_livestreams = 0)
+           AND (r.keyword IS NULL OR title_matches(r.keyword, r.is_regex, v.title))
+           AND {}
+           AND {}
+         ORDER BY r.id",
+        crate::visibility::BOUND_VIDEO,
+        crate::visibility::CHANNEL_SETTINGS,
+        crate::visibility::NOT_MUTED,
+    ));
+    let mut stmt = match result {
+        Ok(stmt) => stmt,
+        Err(e) => {
+            tracing::warn!("[notify] rule query failed: {}", e);
+            return Vec::new();
+        }
+    };
+    stmt.query_map(
+        rusqlite::params![
+            video.channel_id,
+            video.title,
+            video.is_short,
+            video.is_livestream
+        ],
+        |row| {
+            let quiet = match (row.get::<_, Option<u32>>(3)?, row.get::<_, Option<u32>>(4)?) {
+                (Some(start), Some(end)) => Some((start, end)),
+                _ => None,
+            };
+            Ok(MatchedRule {
+                id: row.get(0)?,
+                user_id: row.get(1)?,
+                target_url: row.get(2)?,
+                quiet,
+                timezone: row.get(5)?,
+            })
+        },
+    )
+    .map(|rows| rows.filter_map(|r| r.ok()).collect())
+    .unwrap_or_default()
+}
+
+/// Decide, for each new video, which rules fire now and which are parked
+/// until their quiet hours end. A user never gets the same video twice on the
+/// same target, even when several of their rules match.
+fn plan(
+    conn: &Connection,
+    video_ids: &[String],
+    now: DateTime<Utc>,
+) -> Vec<(MatchedRule, NewVideo)> {
+    let mut immediate = Vec::new();
+    for video in load_new_videos(conn, video_ids) {
+        let mut seen = HashSet::new();
+        for rule in matching_rules(conn, &video) {
+            if !seen.insert((rule.user_id, rule.target_url.clone())) {
+                continue;
+            }
+            match rule
+                .quiet
+                .and_then(|(start, end)| quiet_until(now, &rule.timezone, start, end))
+            {
+                Some(deliver_at) => {
+                    if let Err(e) = conn.execute(
+                        "INSERT OR IGNORE INTO notification_queue (rule_id, video_id, deliver_at, created_at)
+                         VALUES (?1, ?2, ?3, ?4)",
+                        rusqlite::params![rule.id, video.id, deliver_at, now.timestamp()],
+                    ) {
+                        tracing::warn!("[notify] failed to queue {} for rule {}: {}", video.id, rule.id, e);
+                    }
+                }
+                None => immediate.push((rule, video.clone())),
+            }
+        }
+    }
+    immediate
+}
+
+/// Evaluate every user's rules against freshly enriched videos: deliver now,
+/// or queue for the end of the user's quiet hours.
+pub async fn dispatch(state: &AppState, video_ids: &[String]) {
+    let immediate = {
+        let conn = state.db.lock().unwrap();
+        plan(&conn, video_ids, Utc::now())
+    };
+    for (rule, video) in immediate {
+        super::outbox::send(
+            state,
+            &super::outbox::rule_target(rule.id),
+            &new_video_message(&video),
+        )
+        .await;
+    }
+}
+
+/// One message summarising everything held back during quiet hours.
+fn batch_message(videos: &[NewVideo]) -> Message {
+    if let [video] = videos {
+        return new_video_message(video);
+    }
+    let description = videos
+        .iter()
+        .map(|v| {
+            format!(
+                "• {}: {} https://www.youtube.com/watch?v={}",
+                v.channel_title, v.title, v.id
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    Message {
+        level: Level::Info,
+        title: format!("{} new videos", videos.len()),
+        description,
+        url: None,
+        image_url: None,
+        author: None,
+        fields: Vec::new(),
+        timestamp: Utc::now().to_rfc3339(),
+        html: None,
+    }
+}
+
+/// Take every queued entry due at `now`, grouped per (user, target) so each
+/// destination receives a single batch, sent through the lowest of the
+/// grouped rule IDs. Taken rows are removed; those of users disabled since
+/// they were queued are dropped.
+fn take_due_batches(conn: &Connection, now: i64) -> Vec<(i64, Vec<NewVideo>)> {
+    let rows: Vec<(i64, i64, String, String)> = conn
+        .prepare(
+            "SELECT r.id, r.user_id, r.target_url, q.video_id
+             FROM notification_queue q
+             JOIN notification_rules r ON r.id = q.rule_id
+             JOIN users u ON u.id = r.user_id
+             WHERE q.deliver_at <= ?1 AND u.disabled_at IS NULL
+             ORDER BY q.created_at, q.video_id",
+        )
+        .and_then(|mut stmt| {
+            stmt.query_map([now], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })?
+            .collect()
+        })
+        .unwrap_or_default();
+    if rows.is_empty() {
+        return Vec::new();
+    }
+
+    let mut grouped: BTreeMap<(i64, String), (i64, Vec<String>)> = BTreeMap::new();
+    for (rule_id, user_id, target_url, video_id) in rows {
+        let (first_rule, ids) = grouped
+            .entry((user_id, target_url))
+            .or_insert((rule_id, Vec::new()));
+        *first_rule = (*first_rule).min(rule_id);
+        if !ids.contains(&video_id) {
+            ids.push(video_id);
+        }
+    }
+    let _ = conn.execute(
+        "DELETE FROM notification_queue WHERE deliver_at <= ?1",
+        [now],
+    );
+
+    grouped
+        .into_iter()
+        .map(|(_, (rule_id, ids))| (rule_id, load_new_videos(conn, &ids)))
+        .filter(|(_, videos)| !videos.is_empty())
+        .collect()
+}
+
+/// Send the batches whose quiet hours have ended.
+pub async fn flush_due(state: &AppState) {
+    let batches = {
+        let conn = state.db.lock().unwrap();
+        take_due_batches(&conn, crate::util::now_unix())
+    };
+    for (rule_id, videos) in batches {
+        tracing::info!(
+            "[notify] Quiet hours over, sending {} queued video(s)",
+            videos.len()
+        );
+        super::outbox::send(
+            state,
+            &super::outbox::rule_target(rule_id),
+            &batch_message(&videos),
+        )
+        .await;
+    }
+}
+
+/// Whether `timezone` is a known IANA zone name.
+pub fn is_valid_timezone(timezone: &str) -> bool {
+    timezone.parse::<chrono_tz::Tz>().is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    // Notification Rules Spec
+    //
+    // - A rule fires for new videos of channels its owner subscribes to,
+    //   optionally limited to one channel or one group.
+    // - keyword: case-insensitive substring, or regex when is_regex=1,
+    //   matched exactly like mute patterns (visibility::title_matches).
+    // - exclude_shorts / exclude_livestreams, plus the user's own
+    //   hide_shorts / show_livestreams channel settings.
+    // - Quiet hours [start, end) in the user's time zone (may wrap midnight):
+    //   matches are queued and sent as one batch per target when they end.
+    // - The same video is sent at most once per (user, target).
+    // - Disabled users get nothing: no rule fires, and they count as no
+    //   audience for push, webhooks, digests and reminders either.
+
+    use super::*;
+    use crate::notify::stand_in::HttpStandIn;
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    fn setup_conn() -> Connection {
+        let conn = crate::db::open_memory();
+        conn.execute("INSERT INTO users (email) VALUES ('a@example.com')", [])
+            .unwrap();
+        for (id, title) in [("UC1", "Ch1"), ("UC2", "Ch2")] {
+            conn.execute(
+                "INSERT INTO channels (id, title) VALUES (?1, ?2)",
+                rusqlite::params![id, title],
+            )
+            .unwrap();
+            conn.execute(
+                "INSERT INTO user_channels (user_id, channel_id) VALUES (1, ?1)",
+                [id],
+            )
+            .unwrap();
+        }
+        conn
+    }
+
+    fn insert_video(conn: &Connection, id: &str, channel_id: &str, title: &str, is_short: i64) {
+        conn.execute(
+            "INSERT INTO videos (id, channel_id, title, published_at, is_short)
+             VALUES (?1, ?2, ?3, 1750000000, ?4)",
+            rusqlite::params![id, channel_id, title, is_short],
+        )
+        .unwrap();
+    }
+
+    fn insert_rule(conn: &Connection, target: &str, extra: &[(&str, rusqlite::types::Value)]) {
+        conn.execute(
+            "INSERT INTO notification_rules (user_id, name, target_url, created_at)
+             VALUES (1, 'r', ?1, 0)",
+            [target],
+        )
+        .unwrap();
+        let id = conn.last_insert_rowid();
+        for (column, value) in extra {
+            conn.execute(
+                &format!("UPDATE notification_rules SET {column} = ?1 WHERE id = ?2"),
+                rusqlite::params![value, id],
+            )
+            .unwrap();
+        }
+    }
+
+    fn planned(conn: &Connection, ids: &[&str], now: &str) -> Vec<(String, String)> {
+        let ids: Vec<String> = ids.iter().map(|s| s.to_string()).collect();
+        plan(conn, &ids, utc(now))
+            .into_iter()
+            .map(|(rule, video)| (rule.target_url, video.id))
+            .collect()
+    }
+
+    fn text(s: &str) -> rusqlite::types::Value {
+        rusqlite::types::Value::Text(s.to_string())
+    }
+
+    fn int(n: i64) -> rusqlite::types::Value {
+        rusqlite::types::Value::Integer(n)
+    }
+
+    const NOON: &str = "2025-06-01T12:00:00Z";
+
+    #[test]
+    fn parse_and_format_hhmm_round_trip() {
+        assert_eq!(parse_hhmm("00:00"), Some(0));
+        assert_eq!(parse_hhmm("23:59"), Some(23 * 60 + 59));
+        assert_eq!(parse_hhmm("24:00"), None);
+        assert_eq!(parse_hhmm("7pm"), None);
+        assert_eq!(format_hhmm(7 * 60 + 5), "07:05");
+    }
+
+    #[test]
+    fn quiet_until_handles_same_day_and_wrapping_windows() {
+        // 09:00-17:00 UTC, now 12:00 → ends 17:00 today.
+        assert_eq!(
+            quiet_until(utc(NOON), "UTC", 9 * 60, 17 * 60),
+            Some(utc("2025-06-01T17:00:00Z").timestamp())
+        );
+        assert_eq!(quiet_until(utc(NOON), "UTC", 13 * 60, 17 * 60), None);
+
+        // 23:00-07:00 wraps midnight: 23:30 ends tomorrow, 03:00 ends today.
+        assert_eq!(
+            quiet_until(utc("2025-06-01T23:30:00Z"), "UTC", 23 * 60, 7 * 60),
+            Some(utc("2025-06-02T07:00:00Z").timestamp())
+        );
+        assert_eq!(
+            quiet_until(utc("2025-06-02T03:00:00Z"), "UTC", 23 * 60, 7 * 60),
+            Some(utc("2025-06-02T07:00:00Z").timestamp())
+        );
+        assert_eq!(quiet_until(utc(NOON), "UTC", 23 * 60, 7 * 60), None);
+
+        // An empty window is disabled.
+        assert_eq!(quiet_until(utc(NOON), "UTC", 600, 600), None);
+    }
+
+    #[test]
+    fn quiet_until_uses_the_users_time_zone() {
+        // 15:00 UTC is 00:00 in Tokyo, inside a 23:00-07:00 window that ends
+        // at 07:00 JST = 22:00 UTC.
+        assert_eq!(
+            quiet_until(utc("2025-06-01T15:00:00Z"), "Asia/Tokyo", 23 * 60, 7 * 60),
+            Some(utc("2025-06-01T22:00:00Z").timestamp())
+        );
+        assert_eq!(quiet_until(utc(NOON), "Asia/Tokyo", 23 * 60, 7 * 60), None);
+    }
+
+    #[test]
+    fn disabled_users_are_not_notified() {
+        let conn = setup_conn();
+        insert_video(&conn, "v1", "UC1", "Hello", 0);
+        insert_rule(&conn, "ntfy+https://ntfy.sh/a", &[]);
+        conn.execute("UPDATE user_channels SET is_favorite = 1", [])
+            .unwrap();
+        let ids = vec!["v1".to_string()];
+        assert_eq!(planned(&conn, &["v1"], NOON).len(), 1);
+        assert_eq!(crate::notify::notifiable_new_videos(&conn, &ids).len(), 1);
+
+        conn.execute("UPDATE users SET disabled_at = 1", [])
+            .unwrap();
+        assert!(planned(&conn, &["v1"], NOON).is_empty());
+        assert!(crate::notify::notifiable_new_videos(&conn, &ids).is_empty());
+        assert!(crate::webhooks::channel_subscribers(&conn, "UC1").is_empty());
+    }
+
+    #[test]
+    fn plan_sends_matching_videos_immediately() {
+        let conn = setup_conn();
+        insert_video(&conn, "v1", "UC1", "Hello", 0);
+        insert_rule(&conn, "ntfy+https://ntfy.sh/a", &[]);
+        assert_eq!(
+            planned(&conn, &["v1"], NOON),
+            vec![("ntfy+https://ntfy.sh/a".to_string(), "v1".to_string())]
+        );
+    }
+
+    #[test]
+    fn plan_respects_channel_and_group_scope() {
+        let conn = setup_conn();
+        insert_video(&conn, "v1", "UC1", "a", 0);
+        insert_video(&conn, "v2", "UC2", "b", 0);
+        conn.execute(
+            "INSERT INTO groups (user_id, name, sort_order, created_at) VALUES (1, 'g', 0, 0)",
+            [],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO channel_groups (channel_id, group_id) VALUES ('UC2', 1)",
+            [],
+        )
+        .unwrap();
+        insert_rule(&conn, "webhook+http://a", &[("channel_id", text("UC1"))]);
+        insert_rule(&conn, "webhook+http://b", &[("group_id", int(1))]);
+
+        let mut got = planned(&conn, &["v1", "v2"], NOON);
+        got.sort();
+        assert_eq!(
+            got,
+            vec![
+                ("webhook+http://a".to_string(), "v1".to_string()),
+                ("webhook+http://b".to_string(), "v2".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn plan_skips_unsubscribed_disabled_and_excluded_videos() {
+        let conn = setup_conn();
+        conn.execute("INSERT INTO channels (id, title) VALUES ('UC3', 'Ch3')", [])
+            .unwrap();
+        insert_video(&conn, "other", "UC3", "not subscribed", 0);
+        insert_video(&conn, "short", "UC1", "short", 1);
+        insert_video(&conn, "plain", "UC1", "plain", 0);
+        insert_rule(&conn, "webhook+http://a", &[("exclude_shorts", int(1))]);
+        insert_rule(&conn, "webhook+http://off", &[("is_enabled", int(0))]);
+        assert_eq!(
+            planned(&conn, &["other", "short", "plain"], NOON),
+            vec![("webhook+http://a".to_string(), "plain".to_string())]
+        );
+
+        // The user's own hide_shorts applies even without exclude_shorts.
+        conn.execute("UPDATE notification_rules SET exclude_shorts = 0", [])
+            .unwrap();
+        conn.execute("UPDATE user_channels SET hide_shorts = 1", [])
+            .unwrap();
+        assert_eq!(
+            planned(&conn, &["short"], NOON),
+            Vec::<(String, String)>::new()
+        );
+    }
+
+    #[test]
+    fn plan_applies_keyword_and_dedupes_per_target() {
+        let conn = setup_conn();
+        insert_video(&conn, "v1", "UC1", "Weekly LIVE", 0);
+        insert_video(&conn, "v2", "UC1", "Cooking", 0);
+        insert_rule(&conn, "webhook+http://a", &[("keyword", text("live"))]);
+        insert_rule(&conn, "webhook+http://a", &[]);
+        let mut got = planned(&conn, &["v1", "v2"], NOON);
+        got.sort();
+        assert_eq!(
+            got,
+            vec![
+                ("webhook+http://a".to_string(), "v1".to_string()),
+                ("webhook+http://a".to_string(), "v2".to_string()),
+            ],
+            "two matching rules on one target must not send v1 twice"
+        );
+    }
+
+    #[test]
+    fn plan_matches_regex_keyword_like_mute_patterns() {
+        let conn = setup_conn();
+        insert_video(&conn, "v1", "UC1", "Part 12", 0);
+        insert_video(&conn, "v2", "UC1", "Part twelve", 0);
+        insert_rule(
+            &conn,
+            "webhook+http://a",
+            &[("keyword", text(r"^part \d+$")), ("is_regex", int(1))],
+        );
+        insert_rule(
+            &conn,
+            "webhook+http://b",
+            &[("keyword", text("(")), ("is_regex", int(1))],
+        );
+        assert_eq!(
+            planned(&conn, &["v1", "v2"], NOON),
+            vec![("webhook+http://a".to_string(), "v1".to_string())],
+            "an invalid regex matches nothing"
+        );
+    }
+
+    #[test]
+    fn plan_queues_during_quiet_hours_and_flushes_one_batch_per_target() {
+        let conn = setup_conn();
+        conn.execute("UPDATE users SET timezone = 'Asia/Tokyo'", [])
+            .unwrap();
+        insert_video(&conn, "v1", "UC1", "first", 0);
+        insert_video(&conn, "v2", "UC2", "second", 0);
+        // 22:00-08:00 JST; 15:00 UTC is midnight in Tokyo.
+        insert_rule(
+            &conn,
+            "webhook+http://a",
+            &[("quiet_start", int(22 * 60)), ("quiet_end", int(8 * 60))],
+        );
+        assert!(planned(&conn, &["v1", "v2"], "2025-06-01T15:00:00Z").is_empty());
+
+        let end = utc("2025-06-01T23:00:00Z").timestamp();
+        assert!(take_due_batches(&conn, end - 1).is_empty());
+        let batches = take_due_batches(&conn, end);
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].0, 1, "sent through the rule's target");
+        let ids: Vec<&str> = batches[0].1.iter().map(|v| v.id.as_str()).collect();
+        assert_eq!(ids, vec!["v1", "v2"]);
+        assert!(
+            take_due_batches(&conn, end).is_empty(),
+            "flushed entries are removed"
+        );
+
+        let message = batch_message(&batches[0].1);
+        assert_eq!(message.title, "2 new videos");
+        assert!(message.description.contains("Ch1: first"));
+        assert!(message.description.contains("watch?v=v2"));
+    }
+
+    #[tokio::test]
+    async fn dispatch_delivers_to_the_rule_target() {
+        let stand_in = HttpStandIn::start().await;
+        let state = AppState::test();
+        {
+            let conn = state.db.lock().unwrap();
+            conn.execute("INSERT INTO users (email) VALUES ('a@example.com')", [])
+                .unwrap();
+            conn.execute("INSERT INTO channels (id, title) VALUES ('UC1', 'Ch1')", [])
+                .unwrap();
+            conn.execute(
+                "INSERT INTO user_channels (user_id, channel_id) VALUES (1, 'UC1')",
+                [],
+            )
+            .unwrap();
+            insert_video(&conn, "v1", "UC1", "Hello", 0);
+            insert_rule(&conn, &format!("webhook+{}", stand_in.url("/hook")), &[]);
+        }
+
+        dispatch(&state, &["v1".to_string()]).await;
+
+        let requests = stand_in.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].path, "/hook");
+        assert_eq!(requests[0].json()["title"], "Hello");
+    }
+}
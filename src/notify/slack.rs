@@ -0,0 +1,104 @@
+use super::{Message, Notifier, NotifyError};
+use serde_json::{json, Value};
+
+/// Slack-compatible incoming webhook (Slack, Mattermost, Rocket.Chat). Uses
+/// legacy attachments, which every compatible server renders.
+pub struct Slack {
+    url: reqwest::Url,
+}
+
+impl Slack {
+    pub fn new(url: reqwest::Url) -> Self {
+        Self { url }
+    }
+}
+
+fn payload(message: &Message) -> Value {
+    let mut attachment = json!({
+        "fallback": message.title,
+        "color": format!("#{:06x}", message.level.color()),
+        "title": message.title,
+        "text": message.description,
+        "ts": chrono::DateTime::parse_from_rfc3339(&message.timestamp)
+            .map(|t| t.timestamp())
+            .unwrap_or_default(),
+        "fields": message
+            .fields
+            .iter()
+            .map(|f| json!({ "title": f.name, "value": f.value, "short": true }))
+            .collect::<Vec<_>>(),
+    });
+    if let Some(url) = &message.url {
+        attachment["title_link"] = json!(url);
+    }
+    if let Some(image) = &message.image_url {
+        attachment["thumb_url"] = json!(image);
+    }
+    if let Some(author) = &message.author {
+        attachment["author_name"] = json!(author.name);
+        if let Some(url) = &author.url {
+            attachment["author_link"] = json!(url);
+        }
+        if let Some(icon) = &author.icon_url {
+            attachment["author_icon"] = json!(icon);
+        }
+    }
+    json!({ "text": message.title, "attachments": [attachment] })
+}
+
+#[async_trait::async_trait]
+impl Notifier for Slack {
+    fn kind(&self) -> &'static str {
+        "slack"
+    }
+
+    async fn send(&self, http: &reqwest::Client, message: &Message) -> Result<(), NotifyError> {
+        http.post(self.url.clone())
+            .json(&payload(message))
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // Slack backend: POST {"text", "attachments": [attachment]} to the webhook.
+
+    use super::*;
+    use crate::notify::stand_in::HttpStandIn;
+    use crate::notify::{Author, Field};
+
+    #[tokio::test]
+    async fn posts_attachment_with_link_author_and_fields() {
+        let stand_in = HttpStandIn::start().await;
+        let slack = Slack::new(stand_in.url("/services/A/B/C").parse().unwrap());
+        let mut message = Message::warning("New video", "");
+        message.url = Some("https://www.youtube.com/watch?v=v1".to_string());
+        message.author = Some(Author {
+            name: "Ch1".to_string(),
+            url: None,
+            icon_url: None,
+        });
+        message.fields.push(Field {
+            name: "Duration".to_string(),
+            value: "3:00".to_string(),
+        });
+        slack.send(&reqwest::Client::new(), &message).await.unwrap();
+
+        let req = &stand_in.requests()[0];
+        assert_eq!(req.path, "/services/A/B/C");
+        let body = req.json();
+        assert_eq!(body["text"], "New video");
+        let attachment = &body["attachments"][0];
+        assert_eq!(attachment["color"], "#ffa000");
+        assert_eq!(
+            attachment["title_link"],
+            "https://www.youtube.com/watch?v=v1"
+        );
+        assert_eq!(attachment["author_name"], "Ch1");
+        assert_eq!(attachment["fields"][0]["title"], "Duration");
+        assert_eq!(attachment["fields"][0]["value"], "3:00");
+    }
+}
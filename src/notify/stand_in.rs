@@ -0,0 +1,247 @@
+//! Local TCP stand-ins for notifier endpoints (tests only).
+//!
+//! `HttpStandIn` answers every request with `200 OK` and records it, so a test
+//! can assert on the method, path, headers and body a backend actually sent.
+//! It can also serve a fixed body (e.g. a JWKS document) that the test may
+//! swap out with [`HttpStandIn::set_body`], or never answer at all to stand
+//! in for a hung endpoint ([`HttpStandIn::silent`]).
+//! `SmtpStandIn` speaks just enough SMTP to accept one message per session.
+
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+
+#[derive(Debug, Clone)]
+pub struct CapturedRequest {
+    pub method: String,
+    pub path: String,
+    /// Header names are lower-cased.
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl CapturedRequest {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn json(&self) -> serde_json::Value {
+        serde_json::from_slice(&self.body).expect("request body must be JSON")
+    }
+}
+
+pub struct HttpStandIn {
+    pub addr: std::net::SocketAddr,
+    pub requests: Arc<Mutex<Vec<CapturedRequest>>>,
+    body: Arc<Mutex<String>>,
+    server: tokio::task::JoinHandle<()>,
+}
+
+impl HttpStandIn {
+    pub async fn start() -> Self {
+        Self::start_with_status(200).await
+    }
+
+    /// Every request is answered with the given status code.
+    pub async fn start_with_status(status: u16) -> Self {
+        Self::start_inner(Some(status), String::new()).await
+    }
+
+    /// Every request is read and recorded but never answered.
+    pub async fn silent() -> Self {
+        Self::start_inner(None, String::new()).await
+    }
+
+    /// Every request is answered with `200 OK` and `body` as JSON.
+    pub async fn serving(body: impl Into<String>) -> Self {
+        Self::start_inner(Some(200), body.into()).await
+    }
+
+    async fn start_inner(status: Option<u16>, body: String) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let body = Arc::new(Mutex::new(body));
+        let captured = requests.clone();
+        let served = body.clone();
+        let server = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let captured = captured.clone();
+                let served = served.clone();
+                tokio::spawn(async move {
+                    let (read, mut write) = stream.into_split();
+                    let mut reader = BufReader::new(read);
+                    if let Some(req) = read_request(&mut reader).await {
+                        captured.lock().unwrap().push(req);
+                    }
+                    let Some(status) = status else {
+                        // Hold the connection open without a response.
+                        let _write = write;
+                        std::future::pending::<()>().await;
+                        return;
+                    };
+                    let body = served.lock().unwrap().clone();
+                    let response = format!(
+                        "HTTP/1.1 {status} Stand-In\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                        body.len()
+                    );
+                    let _ = write.write_all(response.as_bytes()).await;
+                });
+            }
+        });
+        Self {
+            addr,
+            requests,
+            body,
+            server,
+        }
+    }
+
+    /// Replace the body served to subsequent requests.
+    pub fn set_body(&self, body: impl Into<String>) {
+        *self.body.lock().unwrap() = body.into();
+    }
+
+    pub fn url(&self, path: &str) -> String {
+        format!("http://{}{}", self.addr, path)
+    }
+
+    pub fn requests(&self) -> Vec<CapturedRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+impl Drop for HttpStandIn {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+async fn read_request<R: tokio::io::AsyncBufRead + Unpin>(
+    reader: &mut R,
+) -> Option<CapturedRequest> {
+    let mut line = String::new();
+    reader.read_line(&mut line).await.ok()?;
+    let mut parts = line.split_whitespace();
+    let method = parts.next()?.to_string();
+    let path = parts.next()?.to_string();
+
+    let mut headers = Vec::new();
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).await.ok()?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((k, v)) = line.split_once(':') {
+            headers.push((k.trim().to_ascii_lowercase(), v.trim().to_string()));
+        }
+    }
+
+    let len = headers
+        .iter()
+        .find(|(k, _)| k == "content-length")
+        .and_then(|(_, v)| v.parse::<usize>().ok())
+        .unwrap_or(0);
+    let mut body = vec![0; len];
+    reader.read_exact(&mut body).await.ok()?;
+
+    Some(CapturedRequest {
+        method,
+        path,
+        headers,
+        body,
+    })
+}
+
+#[derive(Debug, Clone)]
+pub struct CapturedMail {
+    pub from: String,
+    pub to: Vec<String>,
+    /// Raw DATA section (headers + body).
+    pub data: String,
+}
+
+pub struct SmtpStandIn {
+    pub addr: std::net::SocketAddr,
+    pub messages: Arc<Mutex<Vec<CapturedMail>>>,
+    server: tokio::task::JoinHandle<()>,
+}
+
+impl SmtpStandIn {
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let messages = Arc::new(Mutex::new(Vec::new()));
+        let captured = messages.clone();
+        let server = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let captured = captured.clone();
+                tokio::spawn(async move {
+                    let (read, mut write) = stream.into_split();
+                    let mut reader = BufReader::new(read);
+                    let _ = write.write_all(b"220 stand-in ESMTP\r\n").await;
+                    let (mut from, mut to) = (String::new(), Vec::new());
+                    loop {
+                        let mut line = String::new();
+                        if reader.read_line(&mut line).await.unwrap_or(0) == 0 {
+                            break;
+                        }
+                        let upper = line.to_ascii_uppercase();
+                        let reply: &[u8] = if upper.starts_with("EHLO") {
+                            b"250-stand-in\r\n250 AUTH PLAIN LOGIN\r\n"
+                        } else if upper.starts_with("AUTH") {
+                            b"235 ok\r\n"
+                        } else if upper.starts_with("MAIL FROM:") {
+                            from = line[10..].trim().to_string();
+                            b"250 ok\r\n"
+                        } else if upper.starts_with("RCPT TO:") {
+                            to.push(line[8..].trim().to_string());
+                            b"250 ok\r\n"
+                        } else if upper.starts_with("DATA") {
+                            let _ = write.write_all(b"354 go ahead\r\n").await;
+                            let mut data = String::new();
+                            loop {
+                                let mut l = String::new();
+                                if reader.read_line(&mut l).await.unwrap_or(0) == 0 || l == ".\r\n"
+                                {
+                                    break;
+                                }
+                                data.push_str(&l);
+                            }
+                            captured.lock().unwrap().push(CapturedMail {
+                                from: std::mem::take(&mut from),
+                                to: std::mem::take(&mut to),
+                                data,
+                            });
+                            b"250 queued\r\n"
+                        } else if upper.starts_with("QUIT") {
+                            let _ = write.write_all(b"221 bye\r\n").await;
+                            break;
+                        } else {
+                            b"250 ok\r\n"
+                        };
+                        if write.write_all(reply).await.is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+        });
+        Self {
+            addr,
+            messages,
+            server,
+        }
+    }
+}
+
+impl Drop for SmtpStandIn {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
@@ -0,0 +1,58 @@
+use super::{Message, Notifier, NotifyError};
+
+/// Generic JSON webhook: POSTs the [`Message`] itself, for home-grown
+/// receivers (n8n, Node-RED, a small script, …).
+pub struct Webhook {
+    url: reqwest::Url,
+}
+
+impl Webhook {
+    pub fn new(url: reqwest::Url) -> Self {
+        Self { url }
+    }
+}
+
+#[async_trait::async_trait]
+impl Notifier for Webhook {
+    fn kind(&self) -> &'static str {
+        "webhook"
+    }
+
+    async fn send(&self, http: &reqwest::Client, message: &Message) -> Result<(), NotifyError> {
+        http.post(self.url.clone())
+            .json(message)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // Generic webhook backend: POST the Message as JSON
+    // {level, title, description, url, image_url, author, fields, timestamp}.
+
+    use super::*;
+    use crate::notify::stand_in::HttpStandIn;
+
+    #[tokio::test]
+    async fn posts_message_as_json() {
+        let stand_in = HttpStandIn::start().await;
+        let webhook = Webhook::new(stand_in.url("/hook?k=v").parse().unwrap());
+        webhook
+            .send(&reqwest::Client::new(), &Message::warning("t", "d"))
+            .await
+            .unwrap();
+
+        let req = &stand_in.requests()[0];
+        assert_eq!(req.path, "/hook?k=v");
+        assert_eq!(req.header("content-type"), Some("application/json"));
+        let body = req.json();
+        assert_eq!(body["level"], "warning");
+        assert_eq!(body["title"], "t");
+        assert_eq!(body["description"], "d");
+        assert!(body["url"].is_null());
+        assert_eq!(body["fields"], serde_json::json!([]));
+    }
+}
@@ -44,6 +44,16 @@ pub struct FeedItem {
     pub channel_title: String,
     /// チャンネルアイコンURL
     pub channel_thumbnail: Option<String>,
+    /// 前回の閲覧以降に受信した動画か (0/1。/api/feed のみ)
+    pub is_new: Option<i64>,
+}
+
+/// フィードの1ページ (cursor 指定時)
+#[derive(Serialize, ToSchema)]
+pub struct FeedPage {
+    pub items: Vec<FeedItem>,
+    /// 次のページの cursor (null = 最後のページ)
+    pub next_cursor: Option<String>,
 }
 
 /// 視聴履歴アイテム
@@ -61,6 +71,113 @@ pub struct HistoryItem {
     pub channel_thumbnail: Option<String>,
     /// 視聴済みとして記録した日時 (ISO 8601)
     pub watched_at: Option<String>,
+    /// 非表示の理由: watched / dismissed / auto-rule / bulk
+    pub reason: Option<String>,
+}
+
+/// 視聴履歴の1ページ (cursor 指定時)
+#[derive(Serialize, ToSchema)]
+pub struct HistoryPage {
+    pub items: Vec<HistoryItem>,
+    /// 次のページの cursor (null = 最後のページ)
+    pub next_cursor: Option<String>,
+}
+
+/// 視聴統計
+#[derive(Serialize, ToSchema)]
+pub struct HistoryStats {
+    /// 視聴済みにした動画の数 (reason=watched のみ)
+    pub watched: i64,
+    /// 非表示の理由ごとの件数 (watched / dismissed / auto-rule / bulk)
+    pub by_reason: std::collections::HashMap<String, i64>,
+}
+
+/// 未視聴件数
+#[derive(Serialize, ToSchema)]
+pub struct UnreadCounts {
+    /// フィード全体の件数
+    pub total: i64,
+    /// グループIDごとの件数 (すべてのグループ)
+    pub groups: std::collections::HashMap<String, i64>,
+    /// チャンネルIDごとの件数 (未視聴の動画があるチャンネルのみ)
+    pub channels: std::collections::HashMap<String, i64>,
+}
+
+/// 新着件数
+#[derive(Serialize, ToSchema)]
+pub struct NewCount {
+    /// 前回の閲覧以降に受信した動画の数
+    pub count: i64,
+    /// 閲覧位置 (ISO 8601。未記録なら null)
+    pub last_seen_at: Option<String>,
+}
+
+/// 閲覧位置
+#[derive(Serialize, ToSchema)]
+pub struct LastSeenResponse {
+    /// 閲覧位置 (ISO 8601)
+    pub last_seen_at: Option<String>,
+}
+
+/// 一括非表示の結果
+#[derive(Serialize, ToSchema)]
+pub struct BulkHideResult {
+    /// 非表示にした動画の数
+    pub hidden: i64,
+    /// 取り消し用トークン (0件のときは null)
+    pub undo_token: Option<String>,
+    /// 取り消しの期限 (ISO 8601)
+    pub undo_expires_at: Option<String>,
+}
+
+/// 一括非表示の取り消し結果
+#[derive(Serialize, ToSchema)]
+pub struct BulkUndoResult {
+    /// フィードに戻した動画の数
+    pub restored: i64,
+}
+
+/// 後で見るアイテム
+#[derive(Serialize, ToSchema)]
+pub struct WatchLaterItem {
+    pub id: String,
+    pub channel_id: String,
+    pub title: String,
+    pub published_at: Option<String>,
+    pub duration: Option<String>,
+    pub is_short: i64,
+    pub is_livestream: i64,
+    pub livestream_ended_at: Option<String>,
+    pub channel_title: String,
+    pub channel_thumbnail: Option<String>,
+    /// 後で見るに追加した日時 (ISO 8601)
+    pub added_at: Option<String>,
+}
+
+/// 後で見るの連続再生 URL
+#[derive(Serialize, ToSchema)]
+pub struct PlayAllResponse {
+    /// `https://www.youtube.com/watch_videos?video_ids=…` (キューが空なら null)
+    pub url: Option<String>,
+    /// URL に含めた動画数 (最大50)
+    pub count: i64,
+}
+
+/// 検索結果アイテム
+#[derive(Serialize, ToSchema)]
+pub struct SearchItem {
+    pub id: String,
+    pub channel_id: String,
+    pub title: String,
+    pub published_at: Option<String>,
+    pub duration: Option<String>,
+    pub is_short: i64,
+    pub is_livestream: i64,
+    pub livestream_ended_at: Option<String>,
+    pub channel_title: String,
+    pub channel_thumbnail: Option<String>,
+    /// 非表示 (視聴済み) か (0: フィードに表示中, 1: 視聴履歴)
+    pub is_hidden: i64,
 }
 
 /// チャンネルアイテム
@@ -110,6 +227,14 @@ pub struct ChannelVideoItem {
     pub is_hidden: i64,
 }
 
+/// チャンネル動画一覧の1ページ (cursor 指定時)
+#[derive(Serialize, ToSchema)]
+pub struct ChannelVideoPage {
+    pub items: Vec<ChannelVideoItem>,
+    /// 次のページの cursor (null = 最後のページ)
+    pub next_cursor: Option<String>,
+}
+
 /// グループアイテム
 #[derive(Serialize, ToSchema)]
 pub struct GroupItem {
@@ -128,6 +253,310 @@ pub struct GroupItem {
 pub struct MeResponse {
     /// メールアドレス
     pub email: String,
+    /// タイムゾーン (IANA 名, 例: Asia/Tokyo)。通知ルールの静音時間の基準
+    pub timezone: String,
+}
+
+/// 通知ルール
+#[derive(Serialize, ToSchema)]
+pub struct NotificationRuleItem {
+    /// ルールID
+    pub id: i64,
+    /// ルール名
+    pub name: String,
+    /// 通知先 URL
+    pub target_url: String,
+    /// 対象チャンネルID (null: 限定なし)
+    pub channel_id: Option<String>,
+    /// 対象グループID (null: 限定なし)
+    pub group_id: Option<i64>,
+    /// タイトルのキーワード (null: 全動画)
+    pub keyword: Option<String>,
+    /// keyword を正規表現として扱うか (0/1)
+    pub is_regex: i64,
+    /// Shorts を除外 (0/1)
+    pub exclude_shorts: i64,
+    /// ライブ配信を除外 (0/1)
+    pub exclude_livestreams: i64,
+    /// 静音時間の開始 (HH:MM)
+    pub quiet_start: Option<String>,
+    /// 静音時間の終了 (HH:MM)
+    pub quiet_end: Option<String>,
+    /// 有効 (0/1)
+    pub is_enabled: i64,
+    /// 作成日時 (ISO 8601)
+    pub created_at: String,
+}
+
+/// ミュートルール
+#[derive(Serialize, ToSchema)]
+pub struct MuteRuleItem {
+    /// ルールID
+    pub id: i64,
+    /// タイトルのキーワード
+    pub pattern: String,
+    /// pattern を正規表現として扱うか (0/1)
+    pub is_regex: i64,
+    /// 対象チャンネルID (null: 限定なし)
+    pub channel_id: Option<String>,
+    /// 対象グループID (null: 限定なし)
+    pub group_id: Option<i64>,
+    /// 作成日時 (ISO 8601)
+    pub created_at: String,
+}
+
+/// ミュートルールのプレビュー結果
+#[derive(Serialize, ToSchema)]
+pub struct MutePreview {
+    /// 隠れる動画の件数
+    pub count: i64,
+    /// 隠れる動画 (最大50件)
+    pub items: Vec<FeedItem>,
+}
+
+/// ダイジェスト設定
+#[derive(Serialize, ToSchema)]
+pub struct DigestSettings {
+    /// 送信頻度 (daily / weekly)
+    pub frequency: String,
+    /// 本文の形式 (markdown / html)
+    pub format: String,
+    /// 送信先 URL
+    pub target_url: String,
+    /// 前回の送信日時 (ISO 8601, 未送信なら null)
+    pub last_sent_at: Option<String>,
+}
+
+/// 配信リマインダー設定
+#[derive(Serialize, ToSchema)]
+pub struct ReminderSettings {
+    /// 開始何分前に通知するか
+    pub minutes_before: i64,
+    /// 送信先 URL
+    pub target_url: String,
+}
+
+/// VAPID 公開鍵
+#[derive(Serialize, ToSchema)]
+pub struct VapidPublicKeyResponse {
+    /// 非圧縮 P-256 公開鍵 (base64url)
+    pub public_key: String,
+}
+
+/// プッシュ購読
+#[derive(Serialize, ToSchema)]
+pub struct PushSubscriptionItem {
+    /// 購読ID
+    pub id: i64,
+    /// プッシュサービスのエンドポイント URL
+    pub endpoint: String,
+    /// 登録日時 (ISO 8601)
+    pub created_at: Option<String>,
+}
+
+/// RSSトークン
+#[derive(Serialize, ToSchema)]
+pub struct RssTokenResponse {
+    /// RSSトークン (UUID, 失効済みなら null)
+    pub token: Option<String>,
+    /// 購読 URL (失効済みなら null)
+    pub url: Option<String>,
+}
+
+/// ラベル付きフィードトークン
+#[derive(Serialize, ToSchema)]
+pub struct FeedTokenItem {
+    /// フィードトークンID
+    pub id: i64,
+    /// ラベル
+    pub label: String,
+    /// 購読 URL
+    pub url: String,
+    /// トークン (UUID)
+    pub token: String,
+    /// 対象グループID (null ならお気に入り)
+    pub group_id: Option<i64>,
+    /// 対象グループ名
+    pub group_name: Option<String>,
+    /// 発行日時 (ISO 8601)
+    pub created_at: Option<String>,
+}
+
+/// API トークン
+#[derive(Serialize, ToSchema)]
+pub struct ApiTokenItem {
+    /// トークンID
+    pub id: i64,
+    /// トークン名
+    pub name: String,
+    /// トークンの先頭 (識別用)
+    pub prefix: String,
+    /// スコープ
+    pub scopes: Vec<String>,
+    /// 有効期限 (ISO 8601, 無期限なら null)
+    pub expires_at: Option<String>,
+    /// 最終使用日時 (ISO 8601, 未使用なら null)
+    pub last_used_at: Option<String>,
+    /// 発行日時 (ISO 8601)
+    pub created_at: Option<String>,
+}
+
+/// 発行直後の API トークン (token はこの時だけ返される)
+#[derive(Serialize, ToSchema)]
+pub struct CreatedApiToken {
+    /// トークンID
+    pub id: i64,
+    /// トークン名
+    pub name: String,
+    /// トークンの先頭 (識別用)
+    pub prefix: String,
+    /// スコープ
+    pub scopes: Vec<String>,
+    /// 有効期限 (ISO 8601, 無期限なら null)
+    pub expires_at: Option<String>,
+    /// 最終使用日時 (ISO 8601, 未使用なら null)
+    pub last_used_at: Option<String>,
+    /// 発行日時 (ISO 8601)
+    pub created_at: Option<String>,
+    /// トークン本体 (`Authorization: Bearer <token>`)
+    pub token: String,
+}
+
+/// Webhook
+#[derive(Serialize, ToSchema)]
+pub struct WebhookItem {
+    /// Webhook ID
+    pub id: i64,
+    /// 送信先 URL
+    pub url: String,
+    /// 購読するイベント
+    pub events: Vec<String>,
+    /// 有効/無効 (0: 無効, 1: 有効)
+    pub is_enabled: i64,
+    /// 登録日時 (ISO 8601)
+    pub created_at: Option<String>,
+}
+
+/// 登録直後の Webhook (secret はこの時だけ返される)
+#[derive(Serialize, ToSchema)]
+pub struct CreatedWebhook {
+    /// Webhook ID
+    pub id: i64,
+    /// 送信先 URL
+    pub url: String,
+    /// 購読するイベント
+    pub events: Vec<String>,
+    /// 有効/無効 (0: 無効, 1: 有効)
+    pub is_enabled: i64,
+    /// 登録日時 (ISO 8601)
+    pub created_at: Option<String>,
+    /// 署名用シークレット (X-Hub-Signature-256 の HMAC-SHA256 鍵)
+    pub secret: String,
+}
+
+/// Webhook 配信ログ
+#[derive(Serialize, ToSchema)]
+pub struct WebhookDeliveryItem {
+    /// 配信ID (X-Webhook-Delivery ヘッダの値)
+    pub id: i64,
+    /// イベント名
+    pub event: String,
+    /// pending / delivered / failed
+    pub status: String,
+    /// 送信試行回数
+    pub attempts: i64,
+    /// 最後の応答の HTTP ステータス
+    pub response_status: Option<i64>,
+    /// 最後のエラー
+    pub last_error: Option<String>,
+    /// 次回送信予定日時 (pending のみ, ISO 8601)
+    pub next_attempt_at: Option<String>,
+    /// イベント発生日時 (ISO 8601)
+    pub created_at: Option<String>,
+    /// 配信成功日時 (ISO 8601)
+    pub delivered_at: Option<String>,
+    /// 送信した JSON 本文
+    #[schema(value_type = Object)]
+    pub payload: serde_json::Value,
+}
+
+/// ユーザー (管理)
+#[derive(Serialize, ToSchema)]
+pub struct UserItem {
+    /// ユーザーID
+    pub id: i64,
+    /// メールアドレス
+    pub email: String,
+    /// ロール (master / member)
+    pub role: String,
+    /// 無効化 (0: 有効, 1: 無効)
+    pub is_disabled: i64,
+    /// 登録 (招待) 日時 (ISO 8601)
+    pub created_at: Option<String>,
+    /// 登録チャンネル数
+    pub channel_count: i64,
+}
+
+/// 通知の配信 (outbox)
+#[derive(Serialize, ToSchema)]
+pub struct OutboxItem {
+    /// 配信ID
+    pub id: i64,
+    /// 送信先 (scheme://host のみ。削除済みなら参照)
+    pub target: String,
+    /// 通知タイトル
+    pub title: String,
+    /// pending / sent / failed
+    pub status: String,
+    /// 送信試行回数
+    pub attempts: i64,
+    /// 最後のエラー
+    pub last_error: Option<String>,
+    /// 次回送信予定日時 (pending のみ, ISO 8601)
+    pub next_attempt_at: Option<String>,
+    /// 作成日時 (ISO 8601)
+    pub created_at: Option<String>,
+    /// 送信成功日時 (ISO 8601)
+    pub sent_at: Option<String>,
+}
+
+/// 監査ログのエントリ
+#[derive(Serialize, ToSchema)]
+pub struct AuditLogItem {
+    /// エントリID
+    pub id: i64,
+    /// 操作したユーザーID (サーバー自身の操作は null)
+    pub user_id: Option<i64>,
+    /// 操作したユーザーのメールアドレス (削除済みユーザーは null)
+    pub email: Option<String>,
+    /// 操作 (channel.add, video.hide, websub.subscribe など)
+    pub action: String,
+    /// 対象 (チャンネルID・動画ID・グループID など。なければ空文字)
+    pub target: String,
+    /// 操作の詳細 (操作ごとに異なる JSON)
+    #[schema(value_type = Option<Object>)]
+    pub detail: Option<serde_json::Value>,
+    /// 日時 (ISO 8601)
+    pub created_at: Option<String>,
+}
+
+/// アカウントのインポート結果
+#[derive(Serialize, ToSchema)]
+pub struct AccountImportResult {
+    /// 復元した購読チャンネル数
+    pub subscriptions: i64,
+    /// 復元したグループ数
+    pub groups: i64,
+    /// 復元した非表示・視聴履歴の動画数
+    pub videos: i64,
+    /// 復元した後で見るの動画数
+    pub watch_later: i64,
+    /// 未取得の動画、または購読していないチャンネルの動画のためスキップした数 (非表示・視聴履歴と後で見るの合計)
+    pub skipped_videos: i64,
+    /// 復元した通知ルール数
+    pub notification_rules: i64,
+    /// 復元したミュートルール数
+    pub mute_rules: i64,
 }
 
 // RefreshResponse removed (refresh_channel endpoint was removed with OAuth)
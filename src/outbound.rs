@@ -0,0 +1,262 @@
+//! Guard for requests the server makes to URLs chosen by users: webhooks,
+//! notification rule / digest / reminder targets and Web Push endpoints.
+//!
+//! Without it any user could make the server POST to loopback, link-local or
+//! private addresses (cloud metadata, the API itself on localhost, other hosts
+//! on the LAN). A URL is checked when it is saved, so the user gets a 400, and
+//! again when it is sent to, because DNS can change in between. Sends use
+//! [`client_for`], which connects only to the addresses it just checked and
+//! follows no redirects, so neither DNS rebinding nor a redirect gets past it.
+//! Backends that do not connect through reqwest (SMTP) take the checked
+//! addresses from [`clear`] instead.
+//! Its requests also time out, so one slow endpoint cannot stall the
+//! deliveries queued behind it.
+//!
+//! `ALLOW_PRIVATE_TARGETS=1` turns the guard off for instances whose users
+//! may reach the LAN (a self-hosted ntfy or Gotify). Instance-wide
+//! `NOTIFIER_URLS` come from the operator and are never checked.
+
+use crate::config::Config;
+use reqwest::Url;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::time::Duration;
+
+/// Limit on each request to a user-supplied URL, as for webhook deliveries.
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Whether `ip` is a globally routable unicast address.
+pub fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => {
+            if let Some(v4) = ip.to_ipv4_mapped() {
+                return is_public_v4(v4);
+            }
+            let segments = ip.segments();
+            // NAT64 (64:ff9b::/96) reaches whatever IPv4 address it embeds.
+            if segments[..6] == [0x64, 0xff9b, 0, 0, 0, 0] {
+                let [a, b] = segments[6].to_be_bytes();
+                let [c, d] = segments[7].to_be_bytes();
+                return is_public_v4(Ipv4Addr::new(a, b, c, d));
+            }
+            !(ip.is_unspecified()
+                || ip.is_loopback()
+                || ip.is_multicast()
+                || ip.is_unique_local()
+                || ip.is_unicast_link_local()
+                // Documentation (2001:db8::/32)
+                || (segments[0] == 0x2001 && segments[1] == 0xdb8))
+        }
+    }
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_multicast()
+        || ip.is_documentation()
+        // "This network" (0.0.0.0/8)
+        || a == 0
+        // Carrier-grade NAT (100.64.0.0/10)
+        || (a == 100 && (64..128).contains(&b))
+        // IETF protocol assignments (192.0.0.0/24)
+        || (a == 192 && b == 0 && ip.octets()[2] == 0)
+        // Benchmarking (198.18.0.0/15)
+        || (a == 198 && (b == 18 || b == 19))
+        // Reserved (240.0.0.0/4)
+        || a >= 240)
+}
+
+/// The URL a notifier target connects to: `ntfy+https://…` becomes
+/// `https://…`; http(s) and SMTP URLs are used as they are.
+pub fn endpoint(target: &str) -> Option<Url> {
+    let target = target.trim();
+    let (scheme, rest) = target.split_once("://")?;
+    match scheme.split_once('+') {
+        Some((_, inner)) if inner == "http" || inner == "https" => {
+            Url::parse(&format!("{inner}://{rest}")).ok()
+        }
+        _ => Url::parse(target).ok(),
+    }
+}
+
+/// Resolve `url`'s host and require every address to be public.
+async fn resolve_public(url: &Url) -> Result<Vec<SocketAddr>, String> {
+    let host = url
+        .host_str()
+        .ok_or_else(|| "URL has no host".to_string())?
+        .trim_start_matches('[')
+        .trim_end_matches(']');
+    let port = url.port_or_known_default().unwrap_or(0);
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, port))
+        .await
+        .map_err(|_| format!("cannot resolve {host}"))?
+        .collect();
+    if addrs.is_empty() {
+        return Err(format!("cannot resolve {host}"));
+    }
+    if let Some(addr) = addrs.iter().find(|addr| !is_public(addr.ip())) {
+        return Err(format!(
+            "{host} resolves to a non-public address ({})",
+            addr.ip()
+        ));
+    }
+    Ok(addrs)
+}
+
+/// Save-time check of a user-supplied http(s) or notifier URL. A URL that
+/// does not parse passes; the caller's own validation rejects it.
+pub async fn check(target: &str, config: &Config) -> Result<(), String> {
+    if config.allow_private_targets {
+        return Ok(());
+    }
+    match endpoint(target) {
+        Some(url) => resolve_public(&url).await.map(|_| ()),
+        None => Ok(()),
+    }
+}
+
+/// A user-supplied target checked for one send.
+pub struct Cleared {
+    /// See [`client_for`].
+    pub http: reqwest::Client,
+    /// The addresses just checked, which a backend connecting on its own must
+    /// use instead of resolving the host again. `None` with
+    /// `ALLOW_PRIVATE_TARGETS`.
+    pub addrs: Option<Vec<SocketAddr>>,
+}
+
+/// Check a user-supplied URL (an http(s) URL or a notifier target) for one
+/// send; see [`client_for`] and [`Cleared::addrs`].
+pub async fn clear(target: &str, config: &Config) -> Result<Cleared, String> {
+    let builder = reqwest::Client::builder().timeout(REQUEST_TIMEOUT);
+    if config.allow_private_targets {
+        let http = builder.build().map_err(|e| e.to_string())?;
+        return Ok(Cleared { http, addrs: None });
+    }
+    let url = endpoint(target).ok_or_else(|| "invalid URL".to_string())?;
+    let addrs = resolve_public(&url).await?;
+    let mut builder = builder.redirect(reqwest::redirect::Policy::none());
+    if let Some(domain) = url.domain() {
+        builder = builder.resolve_to_addrs(domain, &addrs);
+    }
+    let http = builder.build().map_err(|e| e.to_string())?;
+    Ok(Cleared {
+        http,
+        addrs: Some(addrs),
+    })
+}
+
+/// Client for one request to a user-supplied URL (an http(s) URL or a
+/// notifier target): checks the addresses now, connects only to them,
+/// follows no redirects and gives up after [`REQUEST_TIMEOUT`]. With
+/// `ALLOW_PRIVATE_TARGETS` only the timeout applies.
+pub async fn client_for(target: &str, config: &Config) -> Result<reqwest::Client, String> {
+    clear(target, config).await.map(|cleared| cleared.http)
+}
+
+#[cfg(test)]
+mod tests {
+    // Outbound Guard Spec
+    //
+    // - User-supplied URLs may only reach public unicast addresses: loopback,
+    //   private, link-local, CGNAT, multicast and reserved ranges are refused,
+    //   including IPv4-mapped and NAT64 IPv6 forms.
+    // - Notifier targets are checked by the host they connect to
+    //   (kind+http(s)://…, smtp://…).
+    // - ALLOW_PRIVATE_TARGETS disables the check.
+    // - Requests through client_for give up after REQUEST_TIMEOUT, so a
+    //   target that never answers cannot hold up later deliveries.
+
+    use super::*;
+    use crate::state::AppState;
+
+    #[test]
+    fn is_public_refuses_internal_ranges() {
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "224.0.0.1",
+            "255.255.255.255",
+            "::1",
+            "::",
+            "fe80::1",
+            "fd00::1",
+            "::ffff:127.0.0.1",
+            "64:ff9b::a9fe:a9fe",
+        ] {
+            assert!(!is_public(ip.parse().unwrap()), "{ip} must be refused");
+        }
+        for ip in ["1.1.1.1", "8.8.8.8", "2606:4700:4700::1111"] {
+            assert!(is_public(ip.parse().unwrap()), "{ip} is public");
+        }
+    }
+
+    #[test]
+    fn endpoint_strips_the_backend_prefix() {
+        let host = |target: &str| endpoint(target).and_then(|u| u.host_str().map(str::to_string));
+        assert_eq!(
+            host("ntfy+https://ntfy.example.com/topic").as_deref(),
+            Some("ntfy.example.com")
+        );
+        assert_eq!(
+            host("smtp://u:p@10.0.0.5:587/?to=a@example.com").as_deref(),
+            Some("10.0.0.5")
+        );
+        assert_eq!(
+            host("https://hooks.example.com/x").as_deref(),
+            Some("hooks.example.com")
+        );
+        assert_eq!(host("garbage"), None);
+    }
+
+    #[tokio::test]
+    async fn check_refuses_private_targets_unless_allowed() {
+        let mut config = AppState::test().config;
+        config.allow_private_targets = false;
+        for target in [
+            "http://127.0.0.1:3000/api/users",
+            "webhook+http://169.254.169.254/latest/meta-data",
+            "gotify+http://[::1]/message?token=t",
+            "smtp://u:p@192.168.0.10:25/?from=a@example.com&to=b@example.com",
+        ] {
+            assert!(
+                check(target, &config).await.is_err(),
+                "{target} must be refused"
+            );
+        }
+        assert!(check("https://1.1.1.1/hook", &config).await.is_ok());
+
+        config.allow_private_targets = true;
+        assert!(check("http://127.0.0.1:3000/", &config).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn sends_to_a_silent_target_time_out() {
+        let stand_in = crate::notify::stand_in::HttpStandIn::silent().await;
+        let target = format!("webhook+{}", stand_in.url("/hook"));
+        let mut config = AppState::test().config;
+        config.allow_private_targets = true;
+
+        let http = client_for(&target, &config).await.unwrap();
+        let notifier = crate::notify::parse_notifier_url(&target).unwrap();
+        let message = crate::notify::Message::warning("t", "d");
+        let result = tokio::time::timeout(
+            REQUEST_TIMEOUT + Duration::from_secs(5),
+            notifier.send(&http, &message),
+        )
+        .await
+        .expect("the send must give up on its own");
+        assert!(result.is_err());
+        assert_eq!(stand_in.requests().len(), 1);
+    }
+}
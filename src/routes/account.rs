@@ -0,0 +1,1093 @@
+use crate::error::AppError;
+use crate::middleware::UserId;
+use crate::notify::rules::format_hhmm;
+use crate::openapi::*;
+use crate::state::AppState;
+use crate::sync::channel_sync;
+use crate::sync::periodic_refresh::register_new_subscription;
+use axum::extract::{Extension, State};
+use axum::routing::{delete, get, post};
+use axum::{Json, Router};
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+
+use super::digest::DigestBody;
+use super::mute_rules::MuteRuleBody;
+use super::notification_rules::RuleBody;
+use super::reminders::ReminderBody;
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/account", delete(delete_account))
+        .route("/api/account/export", get(export_account))
+        .route("/api/account/import", post(import_account))
+}
+
+pub(crate) const EXPORT_FORMAT: &str = "youtube-sub-feed";
+/// Bump when the document changes incompatibly; import rejects other versions.
+pub(crate) const EXPORT_VERSION: i64 = 2;
+
+/// アカウントのエクスポート文書。インスタンス間の移行に使う。
+/// Webhook と Web Push の購読 (署名シークレット・端末に紐づく情報) は含まない。
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+pub(crate) struct AccountExport {
+    /// 形式名 (常に "youtube-sub-feed")
+    format: String,
+    /// 形式のバージョン (現在は 2)
+    version: i64,
+    /// エクスポート日時
+    #[serde(default)]
+    exported_at: Option<String>,
+    #[serde(default)]
+    settings: ExportSettings,
+    #[serde(default)]
+    subscriptions: Vec<ExportSubscription>,
+    #[serde(default)]
+    groups: Vec<ExportGroup>,
+    /// 非表示・視聴履歴に入っている動画
+    #[serde(default)]
+    videos: Vec<ExportVideo>,
+    /// 後で見る (キューの順)
+    #[serde(default)]
+    watch_later: Vec<ExportWatchLater>,
+    #[serde(default)]
+    notification_rules: Vec<ExportRule>,
+    #[serde(default)]
+    mute_rules: Vec<ExportMuteRule>,
+}
+
+#[derive(Default, Serialize, Deserialize, utoipa::ToSchema)]
+pub(crate) struct ExportSettings {
+    /// タイムゾーン (IANA 名)
+    timezone: Option<String>,
+    /// ダイジェスト設定 (未設定なら null)
+    digest: Option<DigestBody>,
+    /// 配信リマインダー設定 (未設定なら null)
+    reminders: Option<ReminderBody>,
+}
+
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+pub(crate) struct ExportSubscription {
+    channel_id: String,
+    title: String,
+    thumbnail_url: Option<String>,
+    /// お気に入り (0/1)
+    #[serde(default)]
+    is_favorite: i64,
+    /// ライブ配信を表示 (0/1)
+    #[serde(default)]
+    show_livestreams: i64,
+    /// Shorts を非表示 (0/1)
+    #[serde(default)]
+    hide_shorts: i64,
+    /// 購読日時
+    subscribed_at: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+pub(crate) struct ExportGroup {
+    name: String,
+    #[serde(default)]
+    sort_order: i64,
+    /// 所属チャンネルID
+    #[serde(default)]
+    channel_ids: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+pub(crate) struct ExportVideo {
+    video_id: String,
+    channel_id: String,
+    title: String,
+    published_at: Option<String>,
+    /// 非表示 (0/1)
+    #[serde(default)]
+    is_hidden: i64,
+    /// 非表示の理由 (watched / dismissed / auto-rule / bulk)。省略時は watched
+    #[serde(default)]
+    reason: Option<String>,
+    /// 非表示・視聴にした日時
+    created_at: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+pub(crate) struct ExportWatchLater {
+    video_id: String,
+    channel_id: String,
+    title: String,
+    /// 後で見るに追加した日時
+    added_at: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+pub(crate) struct ExportRule {
+    #[serde(default)]
+    name: String,
+    target_url: String,
+    channel_id: Option<String>,
+    /// 対象グループ名 (ID はインスタンスごとに異なるため名前で参照する)
+    group: Option<String>,
+    keyword: Option<String>,
+    #[serde(default)]
+    is_regex: i64,
+    #[serde(default)]
+    exclude_shorts: i64,
+    #[serde(default)]
+    exclude_livestreams: i64,
+    /// 静音時間の開始 (HH:MM)
+    quiet_start: Option<String>,
+    /// 静音時間の終了 (HH:MM)
+    quiet_end: Option<String>,
+    #[serde(default = "enabled")]
+    is_enabled: i64,
+}
+
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+pub(crate) struct ExportMuteRule {
+    pattern: String,
+    #[serde(default)]
+    is_regex: i64,
+    channel_id: Option<String>,
+    /// 対象グループ名
+    group: Option<String>,
+}
+
+fn enabled() -> i64 {
+    1
+}
+
+fn load_export(conn: &Connection, user_id: i64) -> Result<AccountExport, AppError> {
+    let timezone: String = conn.query_row(
+        "SELECT timezone FROM users WHERE id = ?1",
+        [user_id],
+        |row| row.get(0),
+    )?;
+    let digest = conn
+        .query_row(
+            "SELECT frequency, format, target_url FROM digest_settings WHERE user_id = ?1",
+            [user_id],
+            |row| {
+                Ok(DigestBody {
+                    frequency: row.get(0)?,
+                    format: row.get(1)?,
+                    target_url: row.get(2)?,
+                })
+            },
+        )
+        .optional()?;
+    let reminders = conn
+        .query_row(
+            "SELECT minutes_before, target_url FROM reminder_settings WHERE user_id = ?1",
+            [user_id],
+            |row| {
+                Ok(ReminderBody {
+                    minutes_before: row.get(0)?,
+                    target_url: row.get(1)?,
+                })
+            },
+        )
+        .optional()?;
+
+    let mut stmt = conn.prepare(
+        "SELECT c.id, c.title, c.thumbnail_url, uc.is_favorite, uc.show_livestreams,
+                uc.hide_shorts, uc.created_at
+         FROM user_channels uc JOIN channels c ON c.id = uc.channel_id
+         WHERE uc.user_id = ?1 ORDER BY uc.created_at, c.id",
+    )?;
+    let subscriptions = stmt
+        .query_map([user_id], |row| {
+            Ok(ExportSubscription {
+                channel_id: row.get(0)?,
+                title: row.get(1)?,
+                thumbnail_url: row.get(2)?,
+                is_favorite: row.get(3)?,
+                show_livestreams: row.get(4)?,
+                hide_shorts: row.get(5)?,
+                subscribed_at: crate::util::row_timestamp_to_rfc3339(row, 6)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut stmt = conn.prepare(
+        "SELECT g.name, g.sort_order,
+                (SELECT group_concat(cg.channel_id) FROM channel_groups cg WHERE cg.group_id = g.id)
+         FROM groups g WHERE g.user_id = ?1 ORDER BY g.sort_order, g.id",
+    )?;
+    let groups = stmt
+        .query_map([user_id], |row| {
+            let channel_ids: Option<String> = row.get(2)?;
+            let mut channel_ids: Vec<String> = channel_ids
+                .map(|ids| ids.split(',').map(str::to_string).collect())
+                .unwrap_or_default();
+            channel_ids.sort();
+            Ok(ExportGroup {
+                name: row.get(0)?,
+                sort_order: row.get(1)?,
+                channel_ids,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut stmt = conn.prepare(
+        "SELECT v.id, v.channel_id, v.title, v.published_at, uv.is_hidden, uv.created_at,
+                uv.reason
+         FROM user_videos uv JOIN videos v ON v.id = uv.video_id
+         WHERE uv.user_id = ?1 ORDER BY uv.created_at, v.id",
+    )?;
+    let videos = stmt
+        .query_map([user_id], |row| {
+            Ok(ExportVideo {
+                video_id: row.get(0)?,
+                channel_id: row.get(1)?,
+                title: row.get(2)?,
+                published_at: crate::util::row_timestamp_to_rfc3339(row, 3)?,
+                is_hidden: row.get(4)?,
+                created_at: crate::util::row_timestamp_to_rfc3339(row, 5)?,
+                reason: row.get(6)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut stmt = conn.prepare(
+        "SELECT v.id, v.channel_id, v.title, wl.created_at
+         FROM watch_later wl JOIN videos v ON v.id = wl.video_id
+         WHERE wl.user_id = ?1 ORDER BY wl.position, wl.created_at",
+    )?;
+    let watch_later = stmt
+        .query_map([user_id], |row| {
+            Ok(ExportWatchLater {
+                video_id: row.get(0)?,
+                channel_id: row.get(1)?,
+                title: row.get(2)?,
+                added_at: crate::util::row_timestamp_to_rfc3339(row, 3)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut stmt = conn.prepare(
+        "SELECT r.name, r.target_url, r.channel_id, g.name, r.keyword, r.is_regex,
+                r.exclude_shorts, r.exclude_livestreams, r.quiet_start, r.quiet_end, r.is_enabled
+         FROM notification_rules r LEFT JOIN groups g ON g.id = r.group_id
+         WHERE r.user_id = ?1 ORDER BY r.id",
+    )?;
+    let notification_rules = stmt
+        .query_map([user_id], |row| {
+            Ok(ExportRule {
+                name: row.get(0)?,
+                target_url: row.get(1)?,
+                channel_id: row.get(2)?,
+                group: row.get(3)?,
+                keyword: row.get(4)?,
+                is_regex: row.get(5)?,
+                exclude_shorts: row.get(6)?,
+                exclude_livestreams: row.get(7)?,
+                quiet_start: row.get::<_, Option<i64>>(8)?.map(format_hhmm),
+                quiet_end: row.get::<_, Option<i64>>(9)?.map(format_hhmm),
+                is_enabled: row.get(10)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut stmt = conn.prepare(
+        "SELECT m.pattern, m.is_regex, m.channel_id, g.name
+         FROM mute_rules m LEFT JOIN groups g ON g.id = m.group_id
+         WHERE m.user_id = ?1 ORDER BY m.id",
+    )?;
+    let mute_rules = stmt
+        .query_map([user_id], |row| {
+            Ok(ExportMuteRule {
+                pattern: row.get(0)?,
+                is_regex: row.get(1)?,
+                channel_id: row.get(2)?,
+                group: row.get(3)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(AccountExport {
+        format: EXPORT_FORMAT.to_string(),
+        version: EXPORT_VERSION,
+        exported_at: crate::util::unix_to_rfc3339(crate::util::now_unix()),
+        settings: ExportSettings {
+            timezone: Some(timezone),
+            digest,
+            reminders,
+        },
+        subscriptions,
+        groups,
+        videos,
+        watch_later,
+        notification_rules,
+        mute_rules,
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/account/export",
+    tag = "アカウント",
+    summary = "アカウントのエクスポート",
+    description = "自分が所有するデータ (購読チャンネルとチャンネルごとの設定、グループと所属、非表示・視聴履歴、後で見る、タイムゾーン・ダイジェスト・配信リマインダー・通知ルール・ミュートルール) を1つのバージョン付き JSON 文書として返す。別のインスタンスの POST /api/account/import にそのまま渡せる。",
+    responses(
+        (status = 200, description = "エクスポート文書", body = AccountExport),
+        (status = 401, description = "未認証", body = ErrorResponse),
+    ),
+)]
+async fn export_account(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+) -> Result<Json<AccountExport>, AppError> {
+    let conn = state.db.lock().unwrap();
+    Ok(Json(load_export(&conn, user_id.0)?))
+}
+
+struct ImportCounts {
+    subscriptions: Vec<String>,
+    groups: usize,
+    videos: usize,
+    watch_later: usize,
+    skipped_videos: usize,
+    notification_rules: usize,
+    mute_rules: usize,
+}
+
+/// Restore `doc` into the account. Runs inside the caller's transaction, which
+/// also covers the empty-account check, so two concurrent imports cannot both
+/// pass it.
+fn restore(conn: &Connection, user_id: i64, doc: AccountExport) -> Result<ImportCounts, AppError> {
+    let empty: bool = conn.query_row(
+        "SELECT NOT EXISTS(SELECT 1 FROM user_channels WHERE user_id = ?1)
+            AND NOT EXISTS(SELECT 1 FROM groups WHERE user_id = ?1)
+            AND NOT EXISTS(SELECT 1 FROM user_videos WHERE user_id = ?1)
+            AND NOT EXISTS(SELECT 1 FROM watch_later WHERE user_id = ?1)",
+        [user_id],
+        |row| row.get(0),
+    )?;
+    if !empty {
+        return Err(AppError::BadRequest(
+            "Import requires an empty account".to_string(),
+        ));
+    }
+
+    let now = crate::util::now_unix();
+    let meta: HashMap<String, channel_sync::ChannelMeta> = doc
+        .subscriptions
+        .iter()
+        .map(|s| {
+            (
+                s.channel_id.clone(),
+                channel_sync::ChannelMeta {
+                    title: s.title.clone(),
+                    thumbnail_url: s.thumbnail_url.clone(),
+                },
+            )
+        })
+        .collect();
+    let mut subscriptions = Vec::new();
+    for sub in &doc.subscriptions {
+        if channel_sync::subscribe(conn, user_id, &sub.channel_id, &meta, now)? {
+            subscriptions.push(sub.channel_id.clone());
+        }
+    }
+    let subscribed: HashSet<&str> = doc
+        .subscriptions
+        .iter()
+        .map(|s| s.channel_id.as_str())
+        .collect();
+
+    for sub in &doc.subscriptions {
+        let subscribed_at = sub
+            .subscribed_at
+            .as_deref()
+            .and_then(crate::util::rfc3339_to_unix);
+        conn.execute(
+            "UPDATE user_channels SET is_favorite = ?1, show_livestreams = ?2, hide_shorts = ?3,
+                 created_at = COALESCE(?4, created_at)
+             WHERE user_id = ?5 AND channel_id = ?6",
+            rusqlite::params![
+                (sub.is_favorite != 0) as i64,
+                (sub.show_livestreams != 0) as i64,
+                (sub.hide_shorts != 0) as i64,
+                subscribed_at,
+                user_id,
+                sub.channel_id
+            ],
+        )?;
+    }
+
+    if let Some(timezone) = &doc.settings.timezone {
+        conn.execute(
+            "UPDATE users SET timezone = ?1 WHERE id = ?2",
+            rusqlite::params![timezone, user_id],
+        )?;
+    }
+    if let Some(digest) = doc.settings.digest {
+        super::digest::save_digest(conn, user_id, digest)?;
+    }
+    if let Some(reminders) = doc.settings.reminders {
+        super::reminders::save_reminders(conn, user_id, reminders)?;
+    }
+
+    let mut group_ids: HashMap<String, i64> = HashMap::new();
+    for group in &doc.groups {
+        let name = super::groups::validate_group_name(Some(group.name.clone()))?;
+        conn.execute(
+            "INSERT INTO groups (user_id, name, sort_order, created_at) VALUES (?1, ?2, ?3, ?4)",
+            rusqlite::params![user_id, name, group.sort_order, now],
+        )?;
+        let id = conn.last_insert_rowid();
+        group_ids.entry(name).or_insert(id);
+        for channel_id in &group.channel_ids {
+            if subscribed.contains(channel_id.as_str()) {
+                conn.execute(
+                    "INSERT OR IGNORE INTO channel_groups (channel_id, group_id) VALUES (?1, ?2)",
+                    rusqlite::params![channel_id, id],
+                )?;
+            }
+        }
+    }
+
+    // Only the caller's own history is restored. `videos` rows are shared by
+    // every subscriber, so the document may not create or change them: a
+    // video this instance has not fetched (or of a channel the document does
+    // not subscribe to) is skipped and reported in `skipped_videos`.
+    let mut videos = 0;
+    for video in &doc.videos {
+        if !subscribed.contains(video.channel_id.as_str()) {
+            continue;
+        }
+        // Documents from before hide reasons only had watched videos.
+        let reason = (video.is_hidden != 0).then(|| {
+            video
+                .reason
+                .as_deref()
+                .filter(|r| super::feed::HIDE_REASONS.contains(r))
+                .unwrap_or(super::feed::WATCHED)
+        });
+        let restored = conn.execute(
+            "INSERT OR IGNORE INTO user_videos (user_id, video_id, is_hidden, created_at, reason)
+             SELECT ?1, v.id, ?3, ?4, ?5 FROM videos v WHERE v.id = ?2 AND v.channel_id = ?6",
+            rusqlite::params![
+                user_id,
+                video.video_id,
+                (video.is_hidden != 0) as i64,
+                video
+                    .created_at
+                    .as_deref()
+                    .and_then(crate::util::rfc3339_to_unix)
+                    .unwrap_or(now),
+                reason,
+                video.channel_id
+            ],
+        )?;
+        videos += restored;
+    }
+
+    // The queue is restored the same way, in its exported order.
+    let mut watch_later = 0;
+    for item in &doc.watch_later {
+        if !subscribed.contains(item.channel_id.as_str()) {
+            continue;
+        }
+        watch_later += conn.execute(
+            "INSERT OR IGNORE INTO watch_later (user_id, video_id, position, created_at)
+             SELECT ?1, v.id, ?3, ?4 FROM videos v WHERE v.id = ?2 AND v.channel_id = ?5",
+            rusqlite::params![
+                user_id,
+                item.video_id,
+                watch_later as i64,
+                item.added_at
+                    .as_deref()
+                    .and_then(crate::util::rfc3339_to_unix)
+                    .unwrap_or(now),
+                item.channel_id
+            ],
+        )?;
+    }
+
+    for rule in &doc.notification_rules {
+        let group_id = match &rule.group {
+            Some(name) => Some(
+                *group_ids
+                    .get(name)
+                    .ok_or_else(|| AppError::BadRequest("Group not found".to_string()))?,
+            ),
+            None => None,
+        };
+        let body = RuleBody {
+            name: Some(rule.name.clone()),
+            target_url: Some(rule.target_url.clone()),
+            channel_id: rule.channel_id.clone(),
+            group_id,
+            keyword: rule.keyword.clone(),
+            is_regex: Some(rule.is_regex),
+            exclude_shorts: Some(rule.exclude_shorts),
+            exclude_livestreams: Some(rule.exclude_livestreams),
+            quiet_start: rule.quiet_start.clone(),
+            quiet_end: rule.quiet_end.clone(),
+            is_enabled: Some(rule.is_enabled),
+        };
+        let valid = super::notification_rules::validate_rule(conn, user_id, body)?;
+        super::notification_rules::insert_rule(conn, user_id, &valid)?;
+    }
+
+    for rule in &doc.mute_rules {
+        let group_id = match &rule.group {
+            Some(name) => Some(
+                *group_ids
+                    .get(name)
+                    .ok_or_else(|| AppError::BadRequest("Group not found".to_string()))?,
+            ),
+            None => None,
+        };
+        let body = MuteRuleBody {
+            pattern: Some(rule.pattern.clone()),
+            is_regex: Some(rule.is_regex),
+            channel_id: rule.channel_id.clone(),
+            group_id,
+        };
+        let valid = super::mute_rules::validate_mute_rule(conn, user_id, body)?;
+        super::mute_rules::insert_mute_rule(conn, user_id, &valid)?;
+    }
+
+    Ok(ImportCounts {
+        subscriptions,
+        groups: doc.groups.len(),
+        videos,
+        watch_later,
+        skipped_videos: doc.videos.len() - videos + doc.watch_later.len() - watch_later,
+        notification_rules: doc.notification_rules.len(),
+        mute_rules: doc.mute_rules.len(),
+    })
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/account/import",
+    tag = "アカウント",
+    summary = "アカウントのインポート",
+    description = "GET /api/account/export の文書を自分のアカウントに復元する。\n\n- 購読チャンネル・グループ・非表示履歴・後で見るが1件もない空のアカウントにのみインポートできる\n- 形式名とバージョンが一致しない文書は拒否する\n- 非表示履歴と後で見るは、このサーバーに取り込み済みの動画の分だけ復元する。動画そのものは作成・変更しない\n- 未取得の動画や購読していないチャンネルの動画はスキップし、件数を skipped_videos で返す\n- 途中でエラーになった場合は何も復元せず、アカウントは空のまま",
+    request_body(content = AccountExport),
+    responses(
+        (status = 200, description = "復元した件数", body = AccountImportResult),
+        (status = 400, description = "非対応の文書・空でないアカウント・バリデーションエラー", body = ErrorResponse),
+        (status = 401, description = "未認証", body = ErrorResponse),
+    ),
+)]
+async fn import_account(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Json(doc): Json<AccountExport>,
+) -> Result<Json<Value>, AppError> {
+    if doc.format != EXPORT_FORMAT || doc.version != EXPORT_VERSION {
+        return Err(AppError::BadRequest(
+            "Unsupported export format or version".to_string(),
+        ));
+    }
+    for sub in &doc.subscriptions {
+        super::channels::validate_channel_id(&sub.channel_id).map_err(AppError::BadRequest)?;
+    }
+    if let Some(timezone) = &doc.settings.timezone {
+        if !crate::notify::rules::is_valid_timezone(timezone) {
+            return Err(AppError::BadRequest("Invalid timezone".to_string()));
+        }
+    }
+    let targets = doc
+        .settings
+        .digest
+        .iter()
+        .filter_map(|d| d.target_url.as_deref())
+        .chain(
+            doc.settings
+                .reminders
+                .iter()
+                .filter_map(|r| r.target_url.as_deref()),
+        )
+        .chain(doc.notification_rules.iter().map(|r| r.target_url.as_str()));
+    for target in targets {
+        crate::outbound::check(target, &state.config)
+            .await
+            .map_err(AppError::BadRequest)?;
+    }
+    let counts = {
+        let conn = state.db.lock().unwrap();
+        conn.execute_batch("BEGIN")?;
+        match restore(&conn, user_id.0, doc) {
+            Ok(counts) => {
+                crate::audit::record(
+                    &conn,
+                    Some(user_id.0),
+                    crate::audit::ACCOUNT_IMPORT,
+                    &user_id.0.to_string(),
+                    json!({
+                        "subscriptions": counts.subscriptions.len(),
+                        "groups": counts.groups,
+                        "videos": counts.videos,
+                        "watch_later": counts.watch_later,
+                        "notification_rules": counts.notification_rules,
+                        "mute_rules": counts.mute_rules,
+                    }),
+                );
+                conn.execute_batch("COMMIT")?;
+                counts
+            }
+            Err(e) => {
+                let _ = conn.execute_batch("ROLLBACK");
+                return Err(e);
+            }
+        }
+    };
+    super::unread::invalidate(&state.cache, user_id.0);
+
+    // Subscribe newly added channels to WebSub hub (fire and forget)
+    let added = counts.subscriptions.clone();
+    let state_clone = state.clone();
+    tokio::spawn(async move {
+        let callback = state_clone.config.websub_callback_url.clone();
+        for ch_id in added {
+            register_new_subscription(&state_clone, &ch_id, &callback).await;
+        }
+    });
+
+    Ok(Json(json!({
+        "subscriptions": counts.subscriptions.len(),
+        "groups": counts.groups,
+        "videos": counts.videos,
+        "watch_later": counts.watch_later,
+        "skipped_videos": counts.skipped_videos,
+        "notification_rules": counts.notification_rules,
+        "mute_rules": counts.mute_rules,
+    })))
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub(crate) struct DeleteAccountBody {
+    /// 確認のため自分のメールアドレスを入力する
+    email: String,
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/account",
+    tag = "アカウント",
+    summary = "アカウント削除",
+    description = "自分のアカウントと所有するすべてのデータを削除する。他に購読者のいないチャンネルも削除され、WebSub の購読を解除する。\n\n- 確認のため自分のメールアドレスの入力が必要\n- 他のユーザーがいる間は、最後の master は削除できない (先に別のユーザーを master にする)",
+    request_body(content = DeleteAccountBody),
+    responses(
+        (status = 200, description = "成功", body = OkResponse),
+        (status = 400, description = "メールアドレス不一致・最後の master", body = ErrorResponse),
+        (status = 401, description = "未認証", body = ErrorResponse),
+    ),
+)]
+async fn delete_account(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Json(body): Json<DeleteAccountBody>,
+) -> Result<Json<Value>, AppError> {
+    let email = {
+        let conn = state.db.lock().unwrap();
+        let (email, role): (String, String) = conn.query_row(
+            "SELECT email, role FROM users WHERE id = ?1",
+            [user_id.0],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        if !email.eq_ignore_ascii_case(body.email.trim()) {
+            return Err(AppError::BadRequest("Email does not match".to_string()));
+        }
+        // Leaving the instance without an administrator would lock everyone
+        // out of invitations and user management.
+        let last_master: bool = conn.query_row(
+            "SELECT ?2 = 'master'
+                AND NOT EXISTS(SELECT 1 FROM users WHERE id != ?1 AND role = 'master' AND disabled_at IS NULL)
+                AND EXISTS(SELECT 1 FROM users WHERE id != ?1)",
+            rusqlite::params![user_id.0, role],
+            |row| row.get(0),
+        )?;
+        if last_master {
+            return Err(AppError::BadRequest(
+                "Promote another master before deleting the last master account".to_string(),
+            ));
+        }
+        email
+    };
+
+    let removed = super::users::remove_user(&state, user_id.0).await?;
+    crate::audit::record(
+        &state.db.lock().unwrap(),
+        Some(user_id.0),
+        crate::audit::ACCOUNT_DELETE,
+        &user_id.0.to_string(),
+        json!({"email": email, "channels_removed": removed}),
+    );
+    tracing::info!(
+        "[account] User {} deleted their account ({} channels removed)",
+        user_id.0,
+        removed
+    );
+    Ok(Json(json!({"ok": true})))
+}
+
+#[cfg(test)]
+mod tests {
+    // Account Export / Import / Deletion Spec
+    //
+    // - Export returns a versioned document of everything the caller owns;
+    //   importing it into an empty account on another instance (here: another
+    //   user) reproduces the same document.
+    // - Import rejects unknown formats/versions and non-empty accounts, and
+    //   leaves the account empty when any part of the document is invalid.
+    // - Import restores hidden/watched history and the Watch Later queue (in
+    //   order) only for videos this instance already has, and never creates or changes `videos` rows, which are
+    //   shared with every other subscriber.
+    // - Delete requires the caller's own email; the last master cannot leave
+    //   while other users exist.
+
+    use super::routes;
+    use crate::middleware::auth_middleware;
+    use crate::state::AppState;
+    use axum::body::to_bytes;
+    use axum::http::{Request, StatusCode};
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+
+    const CH1: &str = "UC0000000000000000000001";
+    const CH2: &str = "UC0000000000000000000002";
+
+    fn setup_state() -> AppState {
+        let state = AppState::test();
+        let conn = state.db.lock().unwrap();
+        conn.execute_batch(&format!(
+            "INSERT INTO users (email, role, timezone) VALUES ('a@example.com', 'master', 'Asia/Tokyo');
+             INSERT INTO users (email) VALUES ('b@example.com');
+             INSERT INTO channels (id, title, thumbnail_url) VALUES ('{CH1}', 'One', 'https://i/1'), ('{CH2}', 'Two', NULL);
+             INSERT INTO user_channels (user_id, channel_id, is_favorite, hide_shorts, created_at)
+               VALUES (1, '{CH1}', 1, 0, 1700000000), (1, '{CH2}', 0, 1, 1700000100);
+             INSERT INTO groups (user_id, name, sort_order) VALUES (1, 'Music', 0);
+             INSERT INTO channel_groups (channel_id, group_id) VALUES ('{CH2}', 1);
+             INSERT INTO videos (id, channel_id, title, published_at) VALUES
+               ('v1', '{CH1}', 'Hello', 1700000200), ('v2', '{CH1}', 'Later', 1700000250),
+               ('v3', '{CH2}', 'Sooner', 1700000260);
+             INSERT INTO user_videos (user_id, video_id, is_hidden, created_at, reason) VALUES (1, 'v1', 1, 1700000300, 'dismissed');
+             INSERT INTO watch_later (user_id, video_id, position, created_at)
+               VALUES (1, 'v3', 0, 1700000400), (1, 'v2', 1, 1700000350);
+             INSERT INTO digest_settings (user_id, frequency, format, target_url) VALUES (1, 'weekly', 'html', 'ntfy+https://ntfy.sh/d');
+             INSERT INTO notification_rules (user_id, name, target_url, group_id, keyword, quiet_start, quiet_end)
+               VALUES (1, 'music', 'ntfy+https://ntfy.sh/r', 1, 'live', 1320, 420);
+             INSERT INTO mute_rules (user_id, pattern, is_regex, group_id) VALUES (1, '^clip', 1, 1);"
+        ))
+        .unwrap();
+        drop(conn);
+        state
+    }
+
+    async fn call(
+        state: &AppState,
+        method: &str,
+        uri: &str,
+        as_email: &str,
+        body: Value,
+    ) -> (StatusCode, Value) {
+        let resp = axum::Router::new()
+            .merge(routes())
+            .layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                auth_middleware,
+            ))
+            .with_state(state.clone())
+            .oneshot(
+                Request::builder()
+                    .method(method)
+                    .uri(uri)
+                    .header("Cf-Access-Authenticated-User-Email", as_email)
+                    .header("content-type", "application/json")
+                    .body(axum::body::Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let status = resp.status();
+        let bytes = to_bytes(resp.into_body(), 1024 * 1024).await.unwrap();
+        (
+            status,
+            serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+        )
+    }
+
+    fn without_exported_at(mut doc: Value) -> Value {
+        doc.as_object_mut().unwrap().remove("exported_at");
+        doc
+    }
+
+    #[tokio::test]
+    async fn export_round_trips_into_an_empty_account() {
+        let state = setup_state();
+        let (status, exported) = call(
+            &state,
+            "GET",
+            "/api/account/export",
+            "a@example.com",
+            Value::Null,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(exported["format"], "youtube-sub-feed");
+        assert_eq!(exported["version"], 2);
+        assert_eq!(exported["settings"]["timezone"], "Asia/Tokyo");
+        assert_eq!(exported["groups"][0]["channel_ids"], json!([CH2]));
+        assert_eq!(exported["notification_rules"][0]["group"], "Music");
+        assert_eq!(exported["notification_rules"][0]["quiet_start"], "22:00");
+        assert_eq!(exported["mute_rules"][0]["group"], "Music");
+        assert_eq!(exported["videos"][0]["is_hidden"], 1);
+        assert_eq!(exported["videos"][0]["reason"], "dismissed");
+        assert_eq!(exported["watch_later"][0]["video_id"], "v3");
+        assert_eq!(exported["watch_later"][1]["video_id"], "v2");
+
+        let (status, counts) = call(
+            &state,
+            "POST",
+            "/api/account/import",
+            "b@example.com",
+            exported.clone(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{counts}");
+        assert_eq!(
+            counts,
+            json!({"subscriptions": 2, "groups": 1, "videos": 1, "watch_later": 2, "skipped_videos": 0, "notification_rules": 1, "mute_rules": 1})
+        );
+
+        let (_, reimported) = call(
+            &state,
+            "GET",
+            "/api/account/export",
+            "b@example.com",
+            Value::Null,
+        )
+        .await;
+        assert_eq!(
+            without_exported_at(reimported),
+            without_exported_at(exported)
+        );
+    }
+
+    #[tokio::test]
+    async fn import_rejects_other_versions_and_non_empty_accounts() {
+        let state = setup_state();
+        let (_, exported) = call(
+            &state,
+            "GET",
+            "/api/account/export",
+            "a@example.com",
+            Value::Null,
+        )
+        .await;
+
+        let mut future = exported.clone();
+        future["version"] = json!(3);
+        let (status, body) = call(
+            &state,
+            "POST",
+            "/api/account/import",
+            "b@example.com",
+            future,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "Unsupported export format or version");
+
+        let (status, body) = call(
+            &state,
+            "POST",
+            "/api/account/import",
+            "a@example.com",
+            exported,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "Import requires an empty account");
+    }
+
+    #[tokio::test]
+    async fn import_cannot_create_or_change_shared_videos() {
+        let state = setup_state();
+        let (_, mut exported) = call(
+            &state,
+            "GET",
+            "/api/account/export",
+            "a@example.com",
+            Value::Null,
+        )
+        .await;
+        let before: (String, Option<i64>, i64) = state
+            .db
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT title, fetched_at, published_at FROM videos WHERE id = 'v1'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        exported["videos"][0]["title"] = json!("Hijacked");
+        exported["videos"][0]["published_at"] = json!("2030-01-01T00:00:00Z");
+        let mut unknown = exported["videos"][0].clone();
+        unknown["video_id"] = json!("v9");
+        exported["videos"].as_array_mut().unwrap().push(unknown);
+        let mut unknown_queued = exported["watch_later"][0].clone();
+        unknown_queued["video_id"] = json!("v8");
+        exported["watch_later"]
+            .as_array_mut()
+            .unwrap()
+            .push(unknown_queued);
+
+        let (status, counts) = call(
+            &state,
+            "POST",
+            "/api/account/import",
+            "b@example.com",
+            exported,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{counts}");
+        assert_eq!(counts["videos"], 1);
+        assert_eq!(counts["watch_later"], 2);
+        assert_eq!(counts["skipped_videos"], 2);
+
+        let conn = state.db.lock().unwrap();
+        let after: (String, Option<i64>, i64) = conn
+            .query_row(
+                "SELECT title, fetched_at, published_at FROM videos WHERE id = 'v1'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!(after, before);
+        let created: bool = conn
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM videos WHERE id IN ('v8', 'v9'))",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert!(!created);
+        let hidden: i64 = conn
+            .query_row(
+                "SELECT is_hidden FROM user_videos WHERE user_id = 2 AND video_id = 'v1'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(hidden, 1);
+    }
+
+    #[tokio::test]
+    async fn failed_import_leaves_the_account_empty() {
+        let state = setup_state();
+        let (_, mut exported) = call(
+            &state,
+            "GET",
+            "/api/account/export",
+            "a@example.com",
+            Value::Null,
+        )
+        .await;
+        exported["notification_rules"][0]["target_url"] = json!("not a url");
+
+        let (status, _) = call(
+            &state,
+            "POST",
+            "/api/account/import",
+            "b@example.com",
+            exported.clone(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        {
+            let conn = state.db.lock().unwrap();
+            let owned: i64 = conn
+                .query_row(
+                    "SELECT (SELECT COUNT(*) FROM user_channels WHERE user_id = 2)
+                          + (SELECT COUNT(*) FROM groups WHERE user_id = 2)",
+                    [],
+                    |row| row.get(0),
+                )
+                .unwrap();
+            assert_eq!(owned, 0);
+        }
+
+        exported["notification_rules"][0]["target_url"] = json!("ntfy+https://ntfy.sh/r");
+        let (status, _) = call(
+            &state,
+            "POST",
+            "/api/account/import",
+            "b@example.com",
+            exported,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "the corrected document imports");
+    }
+
+    #[tokio::test]
+    async fn delete_requires_own_email_and_keeps_a_master() {
+        let state = setup_state();
+        let (status, _) = call(
+            &state,
+            "DELETE",
+            "/api/account",
+            "b@example.com",
+            json!({"email": "a@example.com"}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, body) = call(
+            &state,
+            "DELETE",
+            "/api/account",
+            "a@example.com",
+            json!({"email": "a@example.com"}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().contains("master"));
+
+        // Member b imports a copy of a's data, then leaves.
+        let (_, exported) = call(
+            &state,
+            "GET",
+            "/api/account/export",
+            "a@example.com",
+            Value::Null,
+        )
+        .await;
+        call(
+            &state,
+            "POST",
+            "/api/account/import",
+            "b@example.com",
+            exported,
+        )
+        .await;
+        let (status, _) = call(
+            &state,
+            "DELETE",
+            "/api/account",
+            "b@example.com",
+            json!({"email": "B@example.com"}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let conn = state.db.lock().unwrap();
+        let (users, channels): (i64, i64) = conn
+            .query_row(
+                "SELECT (SELECT COUNT(*) FROM users), (SELECT COUNT(*) FROM channels)",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((users, channels), (1, 2), "a's channels survive");
+    }
+}
@@ -0,0 +1,327 @@
+use crate::error::AppError;
+use crate::middleware::{require_master, UserId};
+use crate::openapi::*;
+use crate::state::AppState;
+use axum::extract::{Extension, Query, State};
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+pub fn routes() -> Router<AppState> {
+    Router::new().route("/api/admin/audit-log", get(get_audit_log))
+}
+
+fn entry_json(row: &rusqlite::Row) -> rusqlite::Result<Value> {
+    let detail: Option<String> = row.get(5)?;
+    Ok(json!({
+        "id": row.get::<_, i64>(0)?,
+        "user_id": row.get::<_, Option<i64>>(1)?,
+        "email": row.get::<_, Option<String>>(2)?,
+        "action": row.get::<_, String>(3)?,
+        "target": row.get::<_, String>(4)?,
+        "detail": detail.and_then(|d| serde_json::from_str::<Value>(&d).ok()),
+        "created_at": crate::util::row_timestamp_to_rfc3339(row, 6)?,
+    }))
+}
+
+#[derive(Deserialize)]
+struct AuditLogQuery {
+    user_id: Option<i64>,
+    action: Option<String>,
+    target: Option<String>,
+    since: Option<String>,
+    until: Option<String>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+}
+
+fn parse_time(value: Option<String>, name: &str) -> Result<Option<i64>, AppError> {
+    value
+        .map(|v| {
+            crate::util::rfc3339_to_unix(&v).ok_or_else(|| {
+                AppError::BadRequest(format!("{name} must be an RFC 3339 timestamp"))
+            })
+        })
+        .transpose()
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/audit-log",
+    tag = "管理",
+    summary = "監査ログ",
+    description = "ユーザーとサーバーの操作履歴を新しい順に返す。master ユーザーのみ。\n\n- channel.add / channel.remove / channel.sync (同期の差分。追加・削除されたチャンネルのタイトル付き)\n- group.create / group.update / group.delete / group.reorder / group.channels\n- video.hide / video.unhide / video.bulk_hide / video.bulk_undo\n- token.* / rss_token.* / feed_token.* (API トークン・RSS トークンの発行・再発行・失効)\n- user.invite / user.update / user.delete / account.import / account.delete\n- websub.subscribe / websub.unsubscribe (ハブへのリクエスト結果) / websub.verify (ハブからの確認)。サーバー自身の操作は user_id が null",
+    params(
+        ("user_id" = Option<i64>, Query, description = "操作したユーザーID"),
+        ("action" = Option<String>, Query, description = "操作 (完全一致、または `channel` のように `.` の前までで前方一致)"),
+        ("target" = Option<String>, Query, description = "対象 (チャンネルID・動画ID・グループID など)"),
+        ("since" = Option<String>, Query, description = "この日時以降 (RFC 3339)"),
+        ("until" = Option<String>, Query, description = "この日時より前 (RFC 3339)"),
+        ("limit" = Option<i64>, Query, description = "取得件数 (デフォルト: 50, 最大: 200)"),
+        ("offset" = Option<i64>, Query, description = "オフセット (デフォルト: 0)"),
+    ),
+    responses(
+        (status = 200, description = "監査ログ", body = Vec<AuditLogItem>),
+        (status = 400, description = "不正な日時", body = ErrorResponse),
+        (status = 401, description = "未認証", body = ErrorResponse),
+        (status = 403, description = "master 以外", body = ErrorResponse),
+    ),
+)]
+async fn get_audit_log(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Query(query): Query<AuditLogQuery>,
+) -> Result<Json<Value>, AppError> {
+    let since = parse_time(query.since, "since")?;
+    let until = parse_time(query.until, "until")?;
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    let conn = state.db.lock().unwrap();
+    require_master(&conn, user_id)?;
+    let mut stmt = conn.prepare(
+        "SELECT a.id, a.user_id, u.email, a.action, a.target, a.detail, a.created_at
+         FROM audit_log a LEFT JOIN users u ON u.id = a.user_id
+         WHERE (?1 IS NULL OR a.user_id = ?1)
+           AND (?2 IS NULL OR a.action = ?2 OR a.action LIKE ?2 || '.%')
+           AND (?3 IS NULL OR a.target = ?3)
+           AND (?4 IS NULL OR a.created_at >= ?4)
+           AND (?5 IS NULL OR a.created_at < ?5)
+         ORDER BY a.id DESC
+         LIMIT ?6 OFFSET ?7",
+    )?;
+    let rows = stmt
+        .query_map(
+            rusqlite::params![
+                query.user_id,
+                query.action,
+                query.target,
+                since,
+                until,
+                limit,
+                offset
+            ],
+            entry_json,
+        )?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Json(Value::Array(rows)))
+}
+
+#[cfg(test)]
+mod tests {
+    // Audit Log Spec
+    //
+    // - Channel adds/removes, sync diffs, group changes, hide/unhide and token
+    //   changes are recorded with the acting user.
+    // - A sync that drops channels records their titles, even when the
+    //   channels themselves are deleted as orphans.
+    // - GET /api/admin/audit-log is master-only, newest first, and filters by
+    //   user, action (exact or `prefix.`), target and time range.
+
+    use crate::middleware::auth_middleware;
+    use crate::state::AppState;
+    use axum::body::to_bytes;
+    use axum::http::{Request, StatusCode};
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+
+    const CH1: &str = "UC0000000000000000000001";
+    const CH2: &str = "UC0000000000000000000002";
+
+    fn setup_state() -> AppState {
+        let state = AppState::test();
+        state
+            .db
+            .lock()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO users (email, role) VALUES ('admin@example.com', 'master');
+                 INSERT INTO users (email) VALUES ('member@example.com');",
+            )
+            .unwrap();
+        state
+    }
+
+    async fn call(
+        state: &AppState,
+        method: &str,
+        uri: &str,
+        as_email: &str,
+        body: Value,
+    ) -> (StatusCode, Value) {
+        let resp = axum::Router::new()
+            .merge(super::routes())
+            .merge(crate::routes::channels::routes())
+            .merge(crate::routes::groups::routes())
+            .merge(crate::routes::feed::routes())
+            .layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                auth_middleware,
+            ))
+            .with_state(state.clone())
+            .oneshot(
+                Request::builder()
+                    .method(method)
+                    .uri(uri)
+                    .header("Cf-Access-Authenticated-User-Email", as_email)
+                    .header("content-type", "application/json")
+                    .body(axum::body::Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let status = resp.status();
+        let bytes = to_bytes(resp.into_body(), 1024 * 1024).await.unwrap();
+        (
+            status,
+            serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+        )
+    }
+
+    async fn log(state: &AppState, query: &str) -> Value {
+        let (status, body) = call(
+            state,
+            "GET",
+            &format!("/api/admin/audit-log{query}"),
+            "admin@example.com",
+            Value::Null,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        body
+    }
+
+    fn actions(entries: &Value) -> Vec<&str> {
+        entries
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| e["action"].as_str().unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn records_member_actions_newest_first() {
+        let state = setup_state();
+        let member = "member@example.com";
+        call(
+            &state,
+            "POST",
+            "/api/channels",
+            member,
+            json!({"channel_id": CH1, "title": "One"}),
+        )
+        .await;
+        call(&state, "POST", "/api/groups", member, json!({"name": "G"})).await;
+        state
+            .db
+            .lock()
+            .unwrap()
+            .execute(
+                &format!("INSERT INTO videos (id, channel_id, title) VALUES ('v1', '{CH1}', 'V')"),
+                [],
+            )
+            .unwrap();
+        call(&state, "PATCH", "/api/videos/v1/hide", member, Value::Null).await;
+        call(
+            &state,
+            "PATCH",
+            "/api/videos/v1/unhide",
+            member,
+            Value::Null,
+        )
+        .await;
+
+        let entries = log(&state, "?user_id=2").await;
+        assert_eq!(
+            actions(&entries),
+            vec!["video.unhide", "video.hide", "group.create", "channel.add"]
+        );
+        assert_eq!(entries[3]["email"], "member@example.com");
+        assert_eq!(entries[3]["target"], CH1);
+        assert_eq!(entries[3]["detail"]["title"], "One");
+        assert_eq!(entries[2]["detail"], json!({"name": "G"}));
+    }
+
+    #[tokio::test]
+    async fn sync_diff_keeps_titles_of_removed_channels() {
+        let state = setup_state();
+        {
+            let conn = state.db.lock().unwrap();
+            conn.execute_batch(&format!(
+                "INSERT INTO channels (id, title) VALUES ('{CH1}', 'Gone'), ('{CH2}', 'Kept');
+                 INSERT INTO user_channels (user_id, channel_id) VALUES (1, '{CH1}'), (1, '{CH2}');"
+            ))
+            .unwrap();
+        }
+        // A partial list from the browser drops CH1.
+        let (status, _) = call(
+            &state,
+            "POST",
+            "/api/channels/sync",
+            "admin@example.com",
+            json!({"channel_ids": [CH2]}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let entries = log(&state, "?action=channel").await;
+        assert_eq!(actions(&entries), vec!["channel.sync"]);
+        assert_eq!(
+            entries[0]["detail"],
+            json!({"added": [], "removed": [{"channel_id": CH1, "title": "Gone"}]})
+        );
+        assert_eq!(
+            log(&state, "?action=chan").await,
+            json!([]),
+            "prefix stops at '.'"
+        );
+    }
+
+    #[tokio::test]
+    async fn filters_by_target_and_time_and_pages() {
+        let state = setup_state();
+        {
+            let conn = state.db.lock().unwrap();
+            for (i, target) in ["a", "b", "a"].iter().enumerate() {
+                crate::audit::record(&conn, None, crate::audit::VIDEO_HIDE, target, Value::Null);
+                conn.execute(
+                    "UPDATE audit_log SET created_at = ?1 WHERE id = last_insert_rowid()",
+                    [1_700_000_000 + i as i64 * 100],
+                )
+                .unwrap();
+            }
+        }
+        assert_eq!(log(&state, "?target=a").await.as_array().unwrap().len(), 2);
+        let recent = log(&state, "?since=2023-11-14T22:14:20Z").await;
+        assert_eq!(recent.as_array().unwrap().len(), 2);
+        let page = log(&state, "?limit=1&offset=1").await;
+        assert_eq!(page[0]["id"], 2);
+        assert_eq!(page[0]["user_id"], Value::Null);
+
+        let (status, _) = call(
+            &state,
+            "GET",
+            "/api/admin/audit-log?since=yesterday",
+            "admin@example.com",
+            Value::Null,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn audit_log_is_master_only() {
+        let state = setup_state();
+        let (status, _) = call(
+            &state,
+            "GET",
+            "/api/admin/audit-log",
+            "member@example.com",
+            Value::Null,
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
+}
@@ -16,10 +16,11 @@ use crate::state::AppState;
 use axum::extract::{Extension, State};
 use axum::routing::get;
 use axum::{Json, Router};
+use serde::Deserialize;
 use serde_json::json;
 
 pub fn routes() -> Router<AppState> {
-    Router::new().route("/api/auth/me", get(me))
+    Router::new().route("/api/auth/me", get(me).patch(update_me))
 }
 
 #[utoipa::path(
@@ -36,15 +37,52 @@ async fn me(
     State(state): State<AppState>,
     Extension(user_id): Extension<UserId>,
 ) -> Result<Json<serde_json::Value>, AppError> {
-    let email: String = {
+    let (email, timezone): (String, String) = {
         let conn = state.db.lock().unwrap();
         conn.query_row(
-            "SELECT email FROM users WHERE id = ?1",
+            "SELECT email, timezone FROM users WHERE id = ?1",
             [user_id.0],
-            |row| row.get(0),
+            |row| Ok((row.get(0)?, row.get(1)?)),
         )
         .map_err(|_| AppError::Unauthorized("Unauthorized".to_string()))?
     };
 
-    Ok(Json(json!({"email": email})))
+    Ok(Json(json!({"email": email, "timezone": timezone})))
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub(crate) struct UpdateMeBody {
+    /// タイムゾーン (IANA 名, 例: Asia/Tokyo)
+    timezone: Option<String>,
+}
+
+#[utoipa::path(
+    patch,
+    path = "/api/auth/me",
+    tag = "認証",
+    summary = "ユーザー設定更新",
+    description = "タイムゾーンを設定する。通知ルールの静音時間はこのタイムゾーンで判定される。",
+    request_body(content = UpdateMeBody),
+    responses(
+        (status = 200, description = "更新後のユーザー情報", body = MeResponse),
+        (status = 400, description = "不正なタイムゾーン", body = ErrorResponse),
+        (status = 401, description = "未認証", body = ErrorResponse),
+    ),
+)]
+async fn update_me(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Json(body): Json<UpdateMeBody>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    if let Some(timezone) = &body.timezone {
+        if !crate::notify::rules::is_valid_timezone(timezone) {
+            return Err(AppError::BadRequest("Invalid timezone".to_string()));
+        }
+        let conn = state.db.lock().unwrap();
+        conn.execute(
+            "UPDATE users SET timezone = ?1 WHERE id = ?2",
+            rusqlite::params![timezone, user_id.0],
+        )?;
+    }
+    me(State(state), Extension(user_id)).await
 }
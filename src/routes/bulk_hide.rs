@@ -0,0 +1,554 @@
+use super::feed::{bind, parse_date, BULK};
+use crate::cursor::Cursor;
+use crate::error::AppError;
+use crate::middleware::UserId;
+use crate::openapi::*;
+use crate::state::AppState;
+use axum::extract::{Extension, State};
+use axum::routing::post;
+use axum::{Json, Router};
+use rand::RngCore;
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/videos/bulk-hide", post(bulk_hide))
+        .route("/api/videos/bulk-hide/undo", post(undo_bulk_hide))
+}
+
+/// How long a bulk hide can be undone.
+const UNDO_WINDOW_SECS: i64 = 15 * 60;
+
+#[derive(Deserialize, Serialize, utoipa::ToSchema)]
+pub(crate) struct BulkHideBody {
+    /// この動画より下 (フィードの並びで後ろ) の動画。指定した動画自体は含まない
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    before_video: Option<String>,
+    /// この日時より前に公開された動画 (RFC 3339、または YYYY-MM-DD でユーザーのタイムゾーンの0時)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    before: Option<String>,
+    /// グループID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    group: Option<i64>,
+    /// チャンネルID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    channel_id: Option<String>,
+    /// Shorts のみ
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    shorts_only: bool,
+}
+
+impl BulkHideBody {
+    /// `AND ...` conditions for the filters that are set, binding their values
+    /// by appending to `params` (`?1` is the user ID).
+    fn conditions(
+        &self,
+        conn: &Connection,
+        user_id: i64,
+        params: &mut Vec<rusqlite::types::Value>,
+    ) -> Result<String, AppError> {
+        if self.before_video.is_none()
+            && self.before.is_none()
+            && self.group.is_none()
+            && self.channel_id.is_none()
+            && !self.shorts_only
+        {
+            return Err(AppError::BadRequest(
+                "At least one filter is required".to_string(),
+            ));
+        }
+        let mut sql = String::new();
+
+        if let Some(id) = &self.before_video {
+            // Read the key the same way the feed reads its cursor keys.
+            let key = conn
+                .query_row(
+                    "SELECT published_at FROM videos WHERE id = ?1",
+                    [id],
+                    |row| crate::util::row_timestamp_to_unix(row, 0),
+                )
+                .optional()?
+                .ok_or_else(|| AppError::NotFound("Video not found".to_string()))?;
+            let cursor = Cursor {
+                key,
+                id: id.clone(),
+            };
+            sql += " ";
+            sql += &cursor.after("v.published_at", "v.id", params);
+        }
+        if let Some(before) = &self.before {
+            let timezone: String = conn.query_row(
+                "SELECT timezone FROM users WHERE id = ?1",
+                [user_id],
+                |row| row.get(0),
+            )?;
+            let unix = parse_date(before, "before", &timezone)?;
+            sql += &format!(" AND v.published_at < ?{}", bind(params, unix.into()));
+        }
+        if let Some(group) = self.group {
+            let owned: bool = conn.query_row(
+                "SELECT EXISTS(SELECT 1 FROM groups WHERE id = ?1 AND user_id = ?2)",
+                [group, user_id],
+                |row| row.get(0),
+            )?;
+            if !owned {
+                return Err(AppError::NotFound("Group not found".to_string()));
+            }
+            sql += &format!(
+                " AND v.channel_id IN (SELECT channel_id FROM channel_groups WHERE group_id = ?{})",
+                bind(params, group.into())
+            );
+        }
+        if let Some(channel_id) = &self.channel_id {
+            sql += &format!(
+                " AND v.channel_id = ?{}",
+                bind(params, channel_id.clone().into())
+            );
+        }
+        if self.shorts_only {
+            sql += " AND v.is_short = 1";
+        }
+        Ok(sql)
+    }
+}
+
+fn generate_undo_token() -> String {
+    let mut bytes = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/videos/bulk-hide",
+    tag = "動画フィード",
+    summary = "動画を一括で非表示にする",
+    description = "フィードに表示されている動画のうち、条件にすべて一致するものをまとめて非表示 (reason=bulk) にする。条件は1つ以上必須。後で見るに入っている動画は対象外。\n\n1つのトランザクションで実行し、15分間有効な undo_token を返す。POST /api/videos/bulk-hide/undo に渡すと、この操作で非表示にした動画だけを元に戻す。",
+    request_body(content = BulkHideBody, example = json!({"before_video": "dQw4w9WgXcQ", "shorts_only": true})),
+    responses(
+        (status = 200, description = "非表示にした件数と取り消し用トークン", body = BulkHideResult),
+        (status = 400, description = "条件がない、または不正な日時", body = ErrorResponse),
+        (status = 401, description = "未認証", body = ErrorResponse),
+        (status = 404, description = "before_video の動画、または group が見つからない", body = ErrorResponse),
+    ),
+)]
+async fn bulk_hide(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Json(body): Json<BulkHideBody>,
+) -> Result<Json<Value>, AppError> {
+    let conn = state.db.lock().unwrap();
+    let token = generate_undo_token();
+    let now = crate::util::now_unix();
+    let expires_at = now + UNDO_WINDOW_SECS;
+
+    // ?1 user, ?2 token; the filters bind after them.
+    let mut params: Vec<rusqlite::types::Value> = vec![user_id.0.into(), token.clone().into()];
+    let conditions = body.conditions(&conn, user_id.0, &mut params)?;
+    let select = format!(
+        "SELECT ?2, v.id, uv.user_id IS NOT NULL
+         FROM videos v
+         JOIN user_channels uc ON uc.channel_id = v.channel_id AND uc.user_id = ?1
+         LEFT JOIN user_videos uv ON uv.video_id = v.id AND uv.user_id = ?1
+         WHERE {visible} AND {not_queued}
+           {conditions}",
+        visible = crate::visibility::VISIBLE,
+        not_queued = crate::visibility::NOT_QUEUED,
+    );
+
+    conn.execute_batch("BEGIN")?;
+    let result = (|| -> rusqlite::Result<usize> {
+        conn.execute("DELETE FROM bulk_hides WHERE expires_at <= ?1", [now])?;
+        conn.execute(
+            "INSERT INTO bulk_hides (token, user_id, expires_at) VALUES (?1, ?2, ?3)",
+            rusqlite::params![token, user_id.0, expires_at],
+        )?;
+        // The snapshot is taken first so the undo knows which rows existed.
+        let hidden = conn.execute(
+            &format!("INSERT INTO bulk_hide_videos (token, video_id, had_row) {select}"),
+            rusqlite::params_from_iter(&params),
+        )?;
+        conn.execute(
+            &format!(
+                "INSERT INTO user_videos (user_id, video_id, is_hidden, reason, created_at)
+                 SELECT ?1, video_id, 1, '{BULK}', ?3 FROM bulk_hide_videos WHERE token = ?2
+                 ON CONFLICT(user_id, video_id) DO UPDATE SET is_hidden = 1, reason = excluded.reason"
+            ),
+            rusqlite::params![user_id.0, token, now],
+        )?;
+        if hidden == 0 {
+            // Nothing to undo.
+            conn.execute("DELETE FROM bulk_hides WHERE token = ?1", [&token])?;
+        } else {
+            crate::audit::record(
+                &conn,
+                Some(user_id.0),
+                crate::audit::VIDEO_BULK_HIDE,
+                "",
+                json!({"filter": body, "count": hidden}),
+            );
+        }
+        Ok(hidden)
+    })();
+    let hidden = match result {
+        Ok(hidden) => hidden,
+        Err(e) => {
+            let _ = conn.execute_batch("ROLLBACK");
+            return Err(e.into());
+        }
+    };
+    conn.execute_batch("COMMIT")?;
+    super::unread::invalidate(&state.cache, user_id.0);
+
+    if hidden == 0 {
+        return Ok(Json(json!({
+            "hidden": 0,
+            "undo_token": null,
+            "undo_expires_at": null,
+        })));
+    }
+    Ok(Json(json!({
+        "hidden": hidden,
+        "undo_token": token,
+        "undo_expires_at": crate::util::unix_to_rfc3339(expires_at),
+    })))
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub(crate) struct UndoBulkHideBody {
+    /// 一括非表示で返された undo_token
+    undo_token: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/videos/bulk-hide/undo",
+    tag = "動画フィード",
+    summary = "一括非表示の取り消し",
+    description = "一括非表示で非表示にした動画をフィードに戻す。その後に個別に非表示・復元した動画はそのまま。トークンは一度だけ使える。",
+    request_body(content = UndoBulkHideBody),
+    responses(
+        (status = 200, description = "フィードに戻した件数", body = BulkUndoResult),
+        (status = 401, description = "未認証", body = ErrorResponse),
+        (status = 404, description = "トークンが存在しない、または期限切れ", body = ErrorResponse),
+    ),
+)]
+async fn undo_bulk_hide(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Json(body): Json<UndoBulkHideBody>,
+) -> Result<Json<Value>, AppError> {
+    let conn = state.db.lock().unwrap();
+    let valid: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM bulk_hides
+                       WHERE token = ?1 AND user_id = ?2 AND expires_at > ?3)",
+        rusqlite::params![body.undo_token, user_id.0, crate::util::now_unix()],
+        |row| row.get(0),
+    )?;
+    if !valid {
+        return Err(AppError::NotFound(
+            "Undo token not found or expired".to_string(),
+        ));
+    }
+
+    // Only rows still hidden by this bulk hide are touched: a video hidden
+    // again by hand (another reason) or restored since stays as it is.
+    let still_bulk = format!(
+        "user_id = ?1 AND is_hidden = 1 AND reason = '{BULK}'
+         AND video_id IN (SELECT video_id FROM bulk_hide_videos WHERE token = ?2 AND had_row = ?3)"
+    );
+    conn.execute_batch("BEGIN")?;
+    let result = (|| -> rusqlite::Result<usize> {
+        let deleted = conn.execute(
+            &format!("DELETE FROM user_videos WHERE {still_bulk}"),
+            rusqlite::params![user_id.0, body.undo_token, 0],
+        )?;
+        let reset = conn.execute(
+            &format!("UPDATE user_videos SET is_hidden = 0, reason = NULL WHERE {still_bulk}"),
+            rusqlite::params![user_id.0, body.undo_token, 1],
+        )?;
+        conn.execute(
+            "DELETE FROM bulk_hides WHERE token = ?1",
+            [&body.undo_token],
+        )?;
+        crate::audit::record(
+            &conn,
+            Some(user_id.0),
+            crate::audit::VIDEO_BULK_UNDO,
+            "",
+            json!({"count": deleted + reset}),
+        );
+        Ok(deleted + reset)
+    })();
+    let restored = match result {
+        Ok(restored) => restored,
+        Err(e) => {
+            let _ = conn.execute_batch("ROLLBACK");
+            return Err(e.into());
+        }
+    };
+    conn.execute_batch("COMMIT")?;
+    super::unread::invalidate(&state.cache, user_id.0);
+    Ok(Json(json!({"restored": restored})))
+}
+
+#[cfg(test)]
+mod tests {
+    // Bulk Hide API Spec
+    //
+    // POST /api/videos/bulk-hide hides, as reason=bulk, every video currently
+    // in the caller's feed that matches all given filters (before_video,
+    // before, group, channel_id, shorts_only; at least one is required).
+    // Watch Later items are not in the feed and stay untouched. The returned
+    // undo_token restores exactly those rows for 15 minutes, once, and only
+    // for its owner; videos hidden again by hand since are left alone.
+    // An unknown before_video is a 404; a legacy TEXT published_at on it or
+    // on the feed rows is read as its time.
+    // The acting user is the dev-bypass first DB user (user 1).
+
+    use super::routes;
+    use crate::middleware::auth_middleware;
+    use crate::state::AppState;
+    use axum::body::to_bytes;
+    use axum::http::{Request, StatusCode};
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+
+    fn setup_state() -> AppState {
+        let state = AppState::test();
+        state
+            .db
+            .lock()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO users (email) VALUES ('a@example.com'), ('b@example.com');
+                 INSERT INTO channels (id, title) VALUES ('UC1', 'Ch1'), ('UC2', 'Ch2');
+                 INSERT INTO user_channels (user_id, channel_id) VALUES (1, 'UC1'), (1, 'UC2'), (2, 'UC1');
+                 INSERT INTO groups (user_id, name) VALUES (1, 'mine'), (2, 'theirs');
+                 INSERT INTO channel_groups (channel_id, group_id) VALUES ('UC2', 1);
+                 INSERT INTO videos (id, channel_id, title, published_at, is_short) VALUES
+                   ('v5', 'UC1', 'Five', 1700000500, 0),
+                   ('v4', 'UC2', 'Four', 1700000400, 1),
+                   ('v3', 'UC1', 'Three', 1700000300, 1),
+                   ('v2', 'UC2', 'Two', 1700000200, 0),
+                   ('v1', 'UC1', 'One', 1700000100, 0);",
+            )
+            .unwrap();
+        state
+    }
+
+    async fn call(state: &AppState, method: &str, uri: &str, body: Value) -> (StatusCode, Value) {
+        let resp = axum::Router::new()
+            .merge(routes())
+            .merge(super::super::feed::routes())
+            .merge(super::super::watch_later::routes())
+            .layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                auth_middleware,
+            ))
+            .with_state(state.clone())
+            .oneshot(
+                Request::builder()
+                    .method(method)
+                    .uri(uri)
+                    .header("content-type", "application/json")
+                    .body(axum::body::Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let status = resp.status();
+        let bytes = to_bytes(resp.into_body(), 1024 * 1024).await.unwrap();
+        (
+            status,
+            serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+        )
+    }
+
+    async fn feed_ids(state: &AppState) -> Vec<String> {
+        let (_, feed) = call(state, "GET", "/api/feed", Value::Null).await;
+        feed.as_array()
+            .unwrap()
+            .iter()
+            .map(|item| item["id"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    async fn bulk_hide(state: &AppState, filter: Value) -> Value {
+        let (status, result) = call(state, "POST", "/api/videos/bulk-hide", filter).await;
+        assert_eq!(status, StatusCode::OK, "{result}");
+        result
+    }
+
+    async fn undo(state: &AppState, token: &Value) -> (StatusCode, Value) {
+        call(
+            state,
+            "POST",
+            "/api/videos/bulk-hide/undo",
+            json!({"undo_token": token}),
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn before_video_must_exist() {
+        let state = setup_state();
+        let (status, body) = call(
+            &state,
+            "POST",
+            "/api/videos/bulk-hide",
+            json!({"before_video": "missing"}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["error"], "Video not found");
+    }
+
+    #[tokio::test]
+    async fn hides_feed_videos_below_a_video_as_bulk() {
+        let state = setup_state();
+        call(
+            &state,
+            "POST",
+            "/api/watch-later",
+            json!({"video_id": "v2"}),
+        )
+        .await;
+
+        let result = bulk_hide(&state, json!({"before_video": "v4"})).await;
+        assert_eq!(result["hidden"], 2, "v3 and v1; v2 is queued");
+        assert!(result["undo_token"].is_string());
+        assert!(result["undo_expires_at"].is_string());
+        assert_eq!(feed_ids(&state).await, ["v5", "v4"]);
+
+        let (_, history) = call(&state, "GET", "/api/history", Value::Null).await;
+        assert!(history.as_array().unwrap().is_empty(), "not real watches");
+        let (_, bulk) = call(&state, "GET", "/api/history?reason=bulk", Value::Null).await;
+        assert_eq!(bulk.as_array().unwrap().len(), 2);
+        let (_, queue) = call(&state, "GET", "/api/watch-later", Value::Null).await;
+        assert_eq!(queue[0]["id"], "v2");
+
+        let none = bulk_hide(&state, json!({"before_video": "v4"})).await;
+        assert_eq!(
+            none,
+            json!({"hidden": 0, "undo_token": null, "undo_expires_at": null})
+        );
+    }
+
+    #[tokio::test]
+    async fn filters_combine() {
+        let state = setup_state();
+        let result = bulk_hide(&state, json!({"group": 1, "shorts_only": true})).await;
+        assert_eq!(result["hidden"], 1);
+        assert_eq!(feed_ids(&state).await, ["v5", "v3", "v2", "v1"]);
+
+        let result = bulk_hide(
+            &state,
+            json!({"channel_id": "UC1", "before": "2023-11-14T22:20:00Z"}),
+        )
+        .await;
+        assert_eq!(result["hidden"], 2, "v3 and v1 were published before");
+        assert_eq!(feed_ids(&state).await, ["v5", "v2"]);
+    }
+
+    #[tokio::test]
+    async fn rejects_missing_or_foreign_filters() {
+        let state = setup_state();
+        for (filter, expected) in [
+            (json!({}), StatusCode::BAD_REQUEST),
+            (json!({"shorts_only": false}), StatusCode::BAD_REQUEST),
+            (json!({"before": "yesterday"}), StatusCode::BAD_REQUEST),
+            (json!({"group": 2}), StatusCode::NOT_FOUND),
+            (json!({"before_video": "nope"}), StatusCode::NOT_FOUND),
+        ] {
+            let (status, _) = call(&state, "POST", "/api/videos/bulk-hide", filter.clone()).await;
+            assert_eq!(status, expected, "{filter}");
+        }
+        assert_eq!(feed_ids(&state).await.len(), 5);
+    }
+
+    #[tokio::test]
+    async fn undo_restores_exactly_the_affected_rows_once() {
+        let state = setup_state();
+        {
+            let conn = state.db.lock().unwrap();
+            // A not-hidden row that must survive the round trip, and a video
+            // hidden beforehand that the bulk hide must not touch.
+            conn.execute_batch(
+                "INSERT INTO user_videos (user_id, video_id, is_hidden, created_at) VALUES (1, 'v1', 0, 42);
+                 INSERT INTO user_videos (user_id, video_id, is_hidden, reason) VALUES (1, 'v2', 1, 'dismissed');",
+            )
+            .unwrap();
+        }
+        let result = bulk_hide(&state, json!({"channel_id": "UC1"})).await;
+        assert_eq!(result["hidden"], 3);
+        let token = &result["undo_token"];
+
+        // Watched by hand after the bulk hide: stays hidden.
+        call(&state, "PATCH", "/api/videos/v5/hide", Value::Null).await;
+
+        let (status, _) = call(
+            &state,
+            "POST",
+            "/api/videos/bulk-hide/undo",
+            json!({"undo_token": "bogus"}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, restored) = undo(&state, token).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(restored, json!({"restored": 2}));
+        assert_eq!(feed_ids(&state).await, ["v4", "v3", "v1"]);
+        {
+            let conn = state.db.lock().unwrap();
+            let v1: (i64, Option<String>, i64) = conn
+                .query_row(
+                    "SELECT is_hidden, reason, created_at FROM user_videos WHERE user_id = 1 AND video_id = 'v1'",
+                    [],
+                    |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+                )
+                .unwrap();
+            assert_eq!(v1, (0, None, 42));
+            let v2: String = conn
+                .query_row(
+                    "SELECT reason FROM user_videos WHERE user_id = 1 AND video_id = 'v2'",
+                    [],
+                    |row| row.get(0),
+                )
+                .unwrap();
+            assert_eq!(v2, "dismissed");
+        }
+
+        let (status, _) = undo(&state, token).await;
+        assert_eq!(status, StatusCode::NOT_FOUND, "tokens are single-use");
+    }
+
+    #[tokio::test]
+    async fn undo_tokens_expire_and_belong_to_their_user() {
+        let state = setup_state();
+        let result = bulk_hide(&state, json!({"channel_id": "UC1"})).await;
+        let token = result["undo_token"].as_str().unwrap().to_string();
+
+        state
+            .db
+            .lock()
+            .unwrap()
+            .execute("UPDATE bulk_hides SET user_id = 2", [])
+            .unwrap();
+        let (status, _) = undo(&state, &json!(token)).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        state
+            .db
+            .lock()
+            .unwrap()
+            .execute("UPDATE bulk_hides SET user_id = 1, expires_at = 0", [])
+            .unwrap();
+        let (status, _) = undo(&state, &json!(token)).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(feed_ids(&state).await, ["v4", "v2"]);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::cursor::Cursor;
 use crate::error::AppError;
 use crate::middleware::UserId;
 use crate::openapi::*;
@@ -109,6 +110,7 @@ async fn get_channels(
 struct VideosQuery {
     limit: Option<i64>,
     offset: Option<i64>,
+    cursor: Option<String>,
 }
 
 #[utoipa::path(
@@ -116,14 +118,16 @@ struct VideosQuery {
     path = "/api/channels/{id}/videos",
     tag = "チャンネル",
     summary = "チャンネルの動画一覧",
-    description = "指定チャンネルの全動画を取得する (非表示状態含む)。",
+    description = "指定チャンネルの全動画を公開日時の降順 (公開日時不明の動画は最後) で取得する (非表示状態含む)。`cursor` の扱いは /api/feed と同じ。",
     params(
         ("id" = String, Path, description = "チャンネルID"),
         ("limit" = Option<i64>, Query, description = "取得件数 (デフォルト: 100, 最大: 500)"),
-        ("offset" = Option<i64>, Query, description = "オフセット (デフォルト: 0)"),
+        ("cursor" = Option<String>, Query, description = "ページ位置 (前のレスポンスの next_cursor。1ページ目は空文字)"),
+        ("offset" = Option<i64>, Query, description = "オフセット (cursor 省略時のみ。デフォルト: 0)"),
     ),
     responses(
-        (status = 200, description = "動画一覧", body = Vec<ChannelVideoItem>),
+        (status = 200, description = "動画一覧 (cursor 指定時。省略時は ChannelVideoItem の配列)", body = ChannelVideoPage),
+        (status = 400, description = "不正な cursor", body = ErrorResponse),
         (status = 401, description = "未認証", body = ErrorResponse),
     ),
 )]
@@ -133,26 +137,40 @@ async fn get_channel_videos(
     Path(id): Path<String>,
     Query(query): Query<VideosQuery>,
 ) -> Result<Json<Value>, AppError> {
-    let limit = query.limit.unwrap_or(100).min(500);
-    let offset = query.offset.unwrap_or(0);
-    let uid = user_id.0;
+    let limit = query.limit.unwrap_or(100).clamp(1, 500);
+    let offset = query.offset.unwrap_or(0).max(0);
+    let cursor_mode = query.cursor.is_some();
+    let cursor = Cursor::from_param(query.cursor.as_deref())?;
+
+    let mut params: Vec<rusqlite::types::Value> = vec![user_id.0.into(), id.into()];
+    let (after, limit_clause) = crate::cursor::paginate(
+        cursor.as_ref(),
+        cursor_mode,
+        "v.published_at",
+        "v.id",
+        limit,
+        offset,
+        &mut params,
+    );
+    let sql = format!(
+        "SELECT v.id, v.title, v.published_at, v.duration,
+                v.is_short, v.is_livestream, v.livestream_ended_at,
+                v.is_members_only,
+                COALESCE(uv.is_hidden, 0) as is_hidden
+         FROM videos v
+         LEFT JOIN user_videos uv ON uv.video_id = v.id AND uv.user_id = ?1
+         WHERE v.channel_id = ?2
+           {after}
+         ORDER BY v.published_at DESC NULLS LAST, v.id DESC
+         {limit_clause}"
+    );
 
     let rows = {
         let conn = state.db.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT v.id, v.title, v.published_at, v.duration,
-                    v.is_short, v.is_livestream, v.livestream_ended_at,
-                    v.is_members_only,
-                    COALESCE(uv.is_hidden, 0) as is_hidden
-             FROM videos v
-             LEFT JOIN user_videos uv ON uv.video_id = v.id AND uv.user_id = ?1
-             WHERE v.channel_id = ?2
-             ORDER BY v.published_at DESC
-             LIMIT ?3 OFFSET ?4",
-        )?;
+        let mut stmt = conn.prepare(&sql)?;
         let rows = stmt
-            .query_map(rusqlite::params![uid, id, limit, offset], |row| {
-                Ok(json!({
+            .query_map(rusqlite::params_from_iter(params), |row| {
+                let item = json!({
                     "id": row.get::<_, String>(0)?,
                     "title": row.get::<_, String>(1)?,
                     "published_at": crate::util::row_timestamp_to_rfc3339(row, 2)?,
@@ -162,12 +180,19 @@ async fn get_channel_videos(
                     "livestream_ended_at": crate::util::row_timestamp_to_rfc3339(row, 6)?,
                     "is_members_only": row.get::<_, i64>(7)?,
                     "is_hidden": row.get::<_, i64>(8)?,
-                }))
+                });
+                Ok((
+                    item,
+                    Cursor {
+                        key: crate::util::row_timestamp_to_unix(row, 2)?,
+                        id: row.get(0)?,
+                    },
+                ))
             })?
             .collect::<Result<Vec<_>, _>>()?;
         rows
     };
-    Ok(Json(Value::Array(rows)))
+    Ok(Json(crate::cursor::response(rows, cursor_mode, limit)))
 }
 
 /// Request body for browser-side YouTube subscription sync.
@@ -221,6 +246,7 @@ async fn sync_channels(
 
     let result =
         channel_sync::sync_subscriptions(&state, user_id.0, &body.channel_ids, &meta).await?;
+    super::unread::invalidate(&state.cache, user_id.0);
 
     // Subscribe newly added channels to WebSub hub (fire and forget)
     let added = result.added.clone();
@@ -232,26 +258,7 @@ async fn sync_channels(
         }
     });
 
-    // Unsubscribe orphaned channels from WebSub hub (fire and forget).
-    // Channels become orphaned when sync removes the last subscriber. The hub would
-    // otherwise continue pushing until the lease expires (~5 days). Sending an
-    // unsubscribe request stops pushes promptly.
-    if !result.removed_orphan_secrets.is_empty() {
-        let orphans = result.removed_orphan_secrets.clone();
-        let state_clone2 = state.clone();
-        tokio::spawn(async move {
-            let callback = state_clone2.config.websub_callback_url.clone();
-            for (ch_id, secret) in orphans {
-                if let Err(e) =
-                    hub::unsubscribe(&state_clone2.http, &ch_id, &callback, &secret).await
-                {
-                    tracing::warn!("[sync] WebSub unsubscribe failed for {}: {}", ch_id, e);
-                } else {
-                    tracing::info!("[sync] WebSub unsubscribe queued for {}", ch_id);
-                }
-            }
-        });
-    }
+    unsubscribe_orphans(&state, result.removed_orphan_secrets.clone());
 
     Ok(Json(json!({
         "added": result.added.len(),
@@ -259,6 +266,35 @@ async fn sync_channels(
     })))
 }
 
+/// Unsubscribe orphaned channels from WebSub hub (fire and forget).
+/// Channels become orphaned when sync removes the last subscriber. The hub would
+/// otherwise continue pushing until the lease expires (~5 days). Sending an
+/// unsubscribe request stops pushes promptly.
+pub(crate) fn unsubscribe_orphans(state: &AppState, orphans: Vec<(String, String)>) {
+    if orphans.is_empty() {
+        return;
+    }
+    let state_clone = state.clone();
+    tokio::spawn(async move {
+        let callback = state_clone.config.websub_callback_url.clone();
+        for (ch_id, secret) in orphans {
+            let result = hub::unsubscribe(&state_clone.http, &ch_id, &callback, &secret).await;
+            crate::audit::record(
+                &state_clone.db.lock().unwrap(),
+                None,
+                crate::audit::WEBSUB_UNSUBSCRIBE,
+                &ch_id,
+                crate::audit::hub_outcome(&result),
+            );
+            if let Err(e) = result {
+                tracing::warn!("[sync] WebSub unsubscribe failed for {}: {}", ch_id, e);
+            } else {
+                tracing::info!("[sync] WebSub unsubscribe queued for {}", ch_id);
+            }
+        }
+    });
+}
+
 /// Request body for manually adding a channel.
 #[derive(Deserialize, utoipa::ToSchema)]
 pub(crate) struct AddChannelBody {
@@ -304,12 +340,29 @@ async fn add_channel(
             "INSERT OR IGNORE INTO channels (id, title, thumbnail_url, upload_playlist_id, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
             rusqlite::params![channel_id, title, body.thumbnail_url, upload_playlist_id, now],
         )?;
-        conn.execute(
+        let inserted = conn.execute(
             "INSERT OR IGNORE INTO user_channels (user_id, channel_id, created_at) VALUES (?1, ?2, ?3)",
             rusqlite::params![user_id.0, channel_id, now],
         )?;
+        if inserted > 0 {
+            crate::webhooks::emit(
+                &conn,
+                &[user_id.0],
+                crate::webhooks::CHANNEL_ADDED,
+                crate::webhooks::channel_data(&conn, &channel_id),
+            );
+            crate::audit::record(
+                &conn,
+                Some(user_id.0),
+                crate::audit::CHANNEL_ADD,
+                &channel_id,
+                crate::audit::channel_ref(&conn, &channel_id),
+            );
+        }
     }
 
+    super::unread::invalidate(&state.cache, user_id.0);
+
     // Subscribe to WebSub (fire and forget)
     let state_clone = state.clone();
     let ch_id_clone = channel_id.clone();
@@ -409,6 +462,19 @@ async fn remove_channel(
 
     {
         let conn = state.db.lock().unwrap();
+        crate::webhooks::emit(
+            &conn,
+            &[user_id.0],
+            crate::webhooks::CHANNEL_REMOVED,
+            crate::webhooks::channel_data(&conn, &id),
+        );
+        crate::audit::record(
+            &conn,
+            Some(user_id.0),
+            crate::audit::CHANNEL_REMOVE,
+            &id,
+            crate::audit::channel_ref(&conn, &id),
+        );
         conn.execute(
             "DELETE FROM user_channels WHERE user_id = ?1 AND channel_id = ?2",
             rusqlite::params![user_id.0, id],
@@ -420,6 +486,7 @@ async fn remove_channel(
             rusqlite::params![id],
         )?;
     }
+    super::unread::invalidate(&state.cache, user_id.0);
 
     // Fire-and-forget WebSub unsubscribe for the now-orphaned channel.
     // The subscription row was CASCADE-deleted above; hub::unsubscribe notifies
@@ -433,9 +500,15 @@ async fn remove_channel(
         let channel_id = id.clone();
         tokio::spawn(async move {
             let callback = state_clone.config.websub_callback_url.clone();
-            if let Err(e) =
-                hub::unsubscribe(&state_clone.http, &channel_id, &callback, &secret).await
-            {
+            let result = hub::unsubscribe(&state_clone.http, &channel_id, &callback, &secret).await;
+            crate::audit::record(
+                &state_clone.db.lock().unwrap(),
+                None,
+                crate::audit::WEBSUB_UNSUBSCRIBE,
+                &channel_id,
+                crate::audit::hub_outcome(&result),
+            );
+            if let Err(e) = result {
                 tracing::warn!(
                     "[channels] WebSub unsubscribe failed for {}: {}",
                     channel_id,
@@ -512,6 +585,7 @@ async fn update_channel(
             ],
         )?;
     }
+    super::unread::invalidate(&state.cache, user_id.0);
     Ok(Json(json!({"ok": true})))
 }
 
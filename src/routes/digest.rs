@@ -0,0 +1,267 @@
+use crate::error::AppError;
+use crate::middleware::UserId;
+use crate::notify::digest::{FORMATS, FREQUENCIES};
+use crate::openapi::*;
+use crate::state::AppState;
+use axum::extract::{Extension, State};
+use axum::routing::get;
+use axum::{Json, Router};
+use rusqlite::OptionalExtension;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+pub fn routes() -> Router<AppState> {
+    Router::new().route(
+        "/api/digest",
+        get(get_digest).put(update_digest).delete(delete_digest),
+    )
+}
+
+fn load_digest(conn: &rusqlite::Connection, user_id: i64) -> Result<Value, AppError> {
+    let row = conn
+        .query_row(
+            "SELECT frequency, format, target_url, last_sent_at FROM digest_settings WHERE user_id = ?1",
+            [user_id],
+            |row| {
+                Ok(json!({
+                    "frequency": row.get::<_, String>(0)?,
+                    "format": row.get::<_, String>(1)?,
+                    "target_url": row.get::<_, String>(2)?,
+                    "last_sent_at": crate::util::row_timestamp_to_rfc3339(row, 3)?,
+                }))
+            },
+        )
+        .optional()?;
+    Ok(row.unwrap_or(Value::Null))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/digest",
+    tag = "通知",
+    summary = "ダイジェスト設定取得",
+    responses(
+        (status = 200, description = "ダイジェスト設定 (未設定なら null)", body = Option<DigestSettings>),
+        (status = 401, description = "未認証", body = ErrorResponse),
+    ),
+)]
+async fn get_digest(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+) -> Result<Json<Value>, AppError> {
+    let conn = state.db.lock().unwrap();
+    Ok(Json(load_digest(&conn, user_id.0)?))
+}
+
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+pub(crate) struct DigestBody {
+    /// 送信頻度 (daily / weekly)
+    pub(crate) frequency: Option<String>,
+    /// 本文の形式 (markdown / html, デフォルト: markdown)。html はメールで HTML 版も送る
+    pub(crate) format: Option<String>,
+    /// 送信先 URL (NOTIFIER_URLS と同じ形式: smtp://…, ntfy+https://… など)
+    pub(crate) target_url: Option<String>,
+}
+
+/// Validate `body` and create or replace the user's digest settings.
+pub(crate) fn save_digest(
+    conn: &rusqlite::Connection,
+    user_id: i64,
+    body: DigestBody,
+) -> Result<(), AppError> {
+    let frequency = body
+        .frequency
+        .filter(|f| FREQUENCIES.contains(&f.as_str()))
+        .ok_or_else(|| AppError::BadRequest("frequency must be daily or weekly".to_string()))?;
+    let format = body.format.unwrap_or_else(|| "markdown".to_string());
+    if !FORMATS.contains(&format.as_str()) {
+        return Err(AppError::BadRequest(
+            "format must be markdown or html".to_string(),
+        ));
+    }
+    let target_url = body
+        .target_url
+        .map(|u| u.trim().to_string())
+        .filter(|u| !u.is_empty())
+        .ok_or_else(|| AppError::BadRequest("target_url is required".to_string()))?;
+    crate::notify::parse_notifier_url(&target_url)
+        .map_err(|e| AppError::BadRequest(e.to_string()))?;
+
+    // Keep last_sent_at on update so changing the target never resends a window.
+    conn.execute(
+        "INSERT INTO digest_settings (user_id, frequency, format, target_url, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT(user_id) DO UPDATE SET
+           frequency = excluded.frequency,
+           format = excluded.format,
+           target_url = excluded.target_url",
+        rusqlite::params![
+            user_id,
+            frequency,
+            format,
+            target_url,
+            crate::util::now_unix()
+        ],
+    )?;
+    Ok(())
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/digest",
+    tag = "通知",
+    summary = "ダイジェスト設定",
+    description = "お気に入りチャンネルの未視聴動画 (非表示にしていないもの) を、前回のダイジェスト以降の分だけチャンネルごとにまとめて定期送信する。\n\n- 送信は24時間ごとの定期処理で行われる\n- 対象期間は公開日時ではなく受信日時で判定する (遅れて届いた動画も次のダイジェストに含まれる)\n- 初回は直近1期間 (daily: 1日, weekly: 7日) に受信した動画が対象\n- 表示対象の条件は RSS フィードと同じ",
+    request_body(content = DigestBody),
+    responses(
+        (status = 200, description = "更新後のダイジェスト設定", body = DigestSettings),
+        (status = 400, description = "バリデーションエラー", body = ErrorResponse),
+        (status = 401, description = "未認証", body = ErrorResponse),
+    ),
+)]
+async fn update_digest(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Json(body): Json<DigestBody>,
+) -> Result<Json<Value>, AppError> {
+    if let Some(target_url) = &body.target_url {
+        crate::outbound::check(target_url, &state.config)
+            .await
+            .map_err(AppError::BadRequest)?;
+    }
+    let conn = state.db.lock().unwrap();
+    save_digest(&conn, user_id.0, body)?;
+    Ok(Json(load_digest(&conn, user_id.0)?))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/digest",
+    tag = "通知",
+    summary = "ダイジェスト停止",
+    responses(
+        (status = 200, description = "成功", body = OkResponse),
+        (status = 401, description = "未認証", body = ErrorResponse),
+    ),
+)]
+async fn delete_digest(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+) -> Result<Json<Value>, AppError> {
+    let conn = state.db.lock().unwrap();
+    conn.execute(
+        "DELETE FROM digest_settings WHERE user_id = ?1",
+        [user_id.0],
+    )?;
+    Ok(Json(json!({"ok": true})))
+}
+
+#[cfg(test)]
+mod tests {
+    // Digest Settings API Spec
+    //
+    // PUT /api/digest upserts the caller's settings (frequency daily|weekly,
+    // format markdown|html, target_url a valid notifier URL) and keeps
+    // last_sent_at. GET returns null when unset; DELETE opts out.
+
+    use super::routes;
+    use crate::middleware::auth_middleware;
+    use crate::state::AppState;
+    use axum::body::to_bytes;
+    use axum::http::{Request, StatusCode};
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+
+    fn setup_state() -> AppState {
+        let state = AppState::test();
+        state
+            .db
+            .lock()
+            .unwrap()
+            .execute("INSERT INTO users (email) VALUES ('a@example.com')", [])
+            .unwrap();
+        state
+    }
+
+    async fn call(state: &AppState, method: &str, body: Value) -> (StatusCode, Value) {
+        let resp = axum::Router::new()
+            .merge(routes())
+            .layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                auth_middleware,
+            ))
+            .with_state(state.clone())
+            .oneshot(
+                Request::builder()
+                    .method(method)
+                    .uri("/api/digest")
+                    .header("content-type", "application/json")
+                    .body(axum::body::Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let status = resp.status();
+        let bytes = to_bytes(resp.into_body(), 1024 * 1024).await.unwrap();
+        (
+            status,
+            serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+        )
+    }
+
+    #[tokio::test]
+    async fn put_get_delete_round_trip_keeps_last_sent_at() {
+        let state = setup_state();
+        assert_eq!(call(&state, "GET", Value::Null).await.1, Value::Null);
+
+        let target = "smtp://mail.example.com/?from=a@example.com&to=b@example.com";
+        let (status, body) = call(
+            &state,
+            "PUT",
+            json!({"frequency": "weekly", "format": "html", "target_url": target}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["frequency"], "weekly");
+        assert_eq!(body["format"], "html");
+        assert_eq!(body["last_sent_at"], Value::Null);
+
+        state
+            .db
+            .lock()
+            .unwrap()
+            .execute("UPDATE digest_settings SET last_sent_at = 1750000000", [])
+            .unwrap();
+        let (_, body) = call(
+            &state,
+            "PUT",
+            json!({"frequency": "daily", "target_url": target}),
+        )
+        .await;
+        assert_eq!(body["frequency"], "daily");
+        assert_eq!(body["format"], "markdown");
+        assert_eq!(body["last_sent_at"], "2025-06-15T15:06:40Z");
+
+        assert_eq!(call(&state, "DELETE", Value::Null).await.0, StatusCode::OK);
+        assert_eq!(call(&state, "GET", Value::Null).await.1, Value::Null);
+    }
+
+    #[tokio::test]
+    async fn invalid_settings_are_rejected_with_400() {
+        let state = setup_state();
+        let target = "ntfy+https://ntfy.sh/digest";
+        for body in [
+            json!({"target_url": target}),
+            json!({"frequency": "hourly", "target_url": target}),
+            json!({"frequency": "daily", "format": "pdf", "target_url": target}),
+            json!({"frequency": "daily"}),
+            json!({"frequency": "daily", "target_url": "not a url"}),
+        ] {
+            assert_eq!(
+                call(&state, "PUT", body.clone()).await.0,
+                StatusCode::BAD_REQUEST,
+                "{body} must be rejected"
+            );
+        }
+    }
+}
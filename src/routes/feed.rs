@@ -1,3 +1,4 @@
+use crate::cursor::Cursor;
 use crate::error::AppError;
 use crate::middleware::UserId;
 use crate::openapi::*;
@@ -12,24 +13,202 @@ pub fn routes() -> Router<AppState> {
     Router::new()
         .route("/api/feed", get(get_feed))
         .route("/api/history", get(get_history))
+        .route("/api/history/stats", get(get_history_stats))
         .route("/api/videos/{id}/hide", patch(hide_video))
         .route("/api/videos/{id}/unhide", patch(unhide_video))
 }
 
+/// Why a video was hidden (`user_videos.reason`; a hidden row without one
+/// predates reasons and counts as watched). Only `watched` is a real watch:
+/// the others are videos the user never meant to see.
+pub(crate) const WATCHED: &str = "watched";
+pub(crate) const DISMISSED: &str = "dismissed";
+pub(crate) const AUTO_RULE: &str = "auto-rule";
+pub(crate) const BULK: &str = "bulk";
+pub(crate) const HIDE_REASONS: [&str; 4] = [WATCHED, DISMISSED, AUTO_RULE, BULK];
+
+fn validate_reason(reason: &str) -> Result<(), AppError> {
+    if HIDE_REASONS.contains(&reason) {
+        Ok(())
+    } else {
+        Err(AppError::BadRequest(format!(
+            "reason must be one of {}",
+            HIDE_REASONS.join(", ")
+        )))
+    }
+}
+
 #[derive(Deserialize)]
 struct FeedQuery {
     limit: Option<i64>,
     offset: Option<i64>,
+    cursor: Option<String>,
     group: Option<i64>,
+    // Optional narrowing, on top of the visibility rules.
+    /// Comma-separated group IDs; a video's channel must be in one of them.
+    groups: Option<String>,
+    /// Comma-separated group IDs whose channels are left out.
+    exclude_groups: Option<String>,
+    min_duration: Option<i64>,
+    max_duration: Option<i64>,
+    /// RFC 3339, or a `YYYY-MM-DD` date at midnight in the user's timezone.
+    published_after: Option<String>,
+    /// Exclusive; same formats as `published_after`.
+    published_before: Option<String>,
+    /// Comma-separated video types (see [`type_condition`]).
+    #[serde(rename = "type")]
+    types: Option<String>,
+    /// Whitespace-separated terms the title must all contain.
+    contains: Option<String>,
+    /// Whitespace-separated terms the title must not contain.
+    excludes: Option<String>,
+}
+
+fn type_condition(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "regular" => "(v.is_short = 0 AND v.is_livestream = 0)",
+        "short" => "v.is_short = 1",
+        "livestream" => "(v.is_livestream = 1 AND v.livestream_ended_at IS NULL)",
+        "ended-stream" => "(v.is_livestream = 1 AND v.livestream_ended_at IS NOT NULL)",
+        _ => return None,
+    })
+}
+
+fn parse_group_ids(value: &str, name: &str) -> Result<Vec<i64>, AppError> {
+    value
+        .split(',')
+        .map(|id| {
+            id.trim().parse().map_err(|_| {
+                AppError::BadRequest(format!("{name} must be comma-separated group IDs"))
+            })
+        })
+        .collect()
+}
+
+/// Unix seconds of a `published_after` / `published_before` value.
+pub(crate) fn parse_date(value: &str, name: &str, timezone: &str) -> Result<i64, AppError> {
+    if let Some(unix) = crate::util::rfc3339_to_unix(value) {
+        return Ok(unix);
+    }
+    let tz: chrono_tz::Tz = timezone.parse().unwrap_or(chrono_tz::UTC);
+    chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .and_then(|midnight| midnight.and_local_timezone(tz).earliest())
+        .map(|t| t.timestamp())
+        .ok_or_else(|| {
+            AppError::BadRequest(format!(
+                "{name} must be an RFC 3339 timestamp or a YYYY-MM-DD date"
+            ))
+        })
+}
+
+/// Append a parameter, returning its `?N` index.
+pub(crate) fn bind(
+    params: &mut Vec<rusqlite::types::Value>,
+    value: rusqlite::types::Value,
+) -> usize {
+    params.push(value);
+    params.len()
+}
+
+impl FeedQuery {
+    /// `AND ...` conditions for the filters that are set, binding their values
+    /// by appending to `params` (`?1` is the user ID).
+    fn filter_conditions(
+        &self,
+        conn: &rusqlite::Connection,
+        user_id: i64,
+        params: &mut Vec<rusqlite::types::Value>,
+    ) -> Result<String, AppError> {
+        let mut sql = String::new();
+
+        for (single, list, name, op) in [
+            (self.group, &self.groups, "groups", "IN"),
+            (None, &self.exclude_groups, "exclude_groups", "NOT IN"),
+        ] {
+            let mut ids = Vec::from_iter(single);
+            if let Some(list) = list {
+                ids.extend(parse_group_ids(list, name)?);
+            }
+            if ids.is_empty() {
+                continue;
+            }
+            let ids = ids
+                .into_iter()
+                .map(|id| format!("?{}", bind(params, id.into())))
+                .collect::<Vec<_>>()
+                .join(", ");
+            sql += &format!(
+                " AND v.channel_id {op} (SELECT cg.channel_id FROM channel_groups cg
+                                         JOIN groups g ON g.id = cg.group_id
+                                         WHERE g.user_id = ?1 AND cg.group_id IN ({ids}))"
+            );
+        }
+
+        if let Some(min) = self.min_duration {
+            sql += &format!(" AND v.duration_seconds >= ?{}", bind(params, min.into()));
+        }
+        if let Some(max) = self.max_duration {
+            sql += &format!(" AND v.duration_seconds <= ?{}", bind(params, max.into()));
+        }
+
+        if self.published_after.is_some() || self.published_before.is_some() {
+            let timezone: String = conn.query_row(
+                "SELECT timezone FROM users WHERE id = ?1",
+                [user_id],
+                |row| row.get(0),
+            )?;
+            if let Some(after) = &self.published_after {
+                let unix = parse_date(after, "published_after", &timezone)?;
+                sql += &format!(" AND v.published_at >= ?{}", bind(params, unix.into()));
+            }
+            if let Some(before) = &self.published_before {
+                let unix = parse_date(before, "published_before", &timezone)?;
+                sql += &format!(" AND v.published_at < ?{}", bind(params, unix.into()));
+            }
+        }
+
+        if let Some(types) = &self.types {
+            let conditions = types
+                .split(',')
+                .map(|name| {
+                    type_condition(name.trim()).ok_or_else(|| {
+                        AppError::BadRequest(
+                            "type must be regular, short, livestream or ended-stream".to_string(),
+                        )
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            sql += &format!(" AND ({})", conditions.join(" OR "));
+        }
+
+        for (value, op) in [(&self.contains, "LIKE"), (&self.excludes, "NOT LIKE")] {
+            for term in value.iter().flat_map(|v| v.split_whitespace()) {
+                let pattern = crate::search::like_pattern(term);
+                sql += &format!(
+                    " AND v.title {op} ?{} ESCAPE '\\'",
+                    bind(params, pattern.into())
+                );
+            }
+        }
+        Ok(sql)
+    }
 }
 
 #[derive(Deserialize)]
 struct HistoryQuery {
     limit: Option<i64>,
     offset: Option<i64>,
+    cursor: Option<String>,
+    /// A hide reason, or `all`. Defaults to watched.
+    reason: Option<String>,
 }
 
-fn video_json(row: &rusqlite::Row, watched_at_index: Option<usize>) -> rusqlite::Result<Value> {
+pub(crate) fn video_json(
+    row: &rusqlite::Row,
+    watched_at_index: Option<usize>,
+) -> rusqlite::Result<Value> {
     let mut value = json!({
         "id": row.get::<_, String>(0)?,
         "channel_id": row.get::<_, String>(1)?,
@@ -53,14 +232,25 @@ fn video_json(row: &rusqlite::Row, watched_at_index: Option<usize>) -> rusqlite:
     path = "/api/feed",
     tag = "動画フィード",
     summary = "動画一覧取得",
-    description = "ユーザーが購読しているチャンネルの動画を公開日時の降順で取得する。\n\n- ユーザーが非表示にした動画を除外\n- ライブ配信はユーザーの show_livestreams=1 の場合のみ表示\n- Shortsはユーザーの hide_shorts=1 のチャンネルでは除外\n- グループIDで絞り込み可能",
+    description = "ユーザーが購読しているチャンネルの動画を公開日時の降順 (公開日時不明の動画は最後、同時刻は動画IDの降順) で取得する。\n\n- ユーザーが非表示にした動画・ミュートルールに一致する動画・後で見るに追加した動画を除外\n- ライブ配信はユーザーの show_livestreams=1 の場合のみ表示\n- Shortsはユーザーの hide_shorts=1 のチャンネルでは除外\n- グループ・再生時間・公開日時・種別・タイトルで絞り込み可能 (指定した条件はすべて満たすもののみ)。再生時間の条件を指定すると、再生時間が未取得の動画は除外\n- `cursor` を指定すると `{items, next_cursor}` を返す。1ページ目は `cursor=` (空)、以降は前のレスポンスの `next_cursor` を渡す (null なら最後のページ)。新着や非表示で位置がずれない\n- `cursor` を省略すると従来どおり動画の配列を返す (`offset` 指定)\n- is_new=1 は前回の閲覧 (PUT /api/feed/last-seen) 以降に受信した動画",
     params(
         ("limit" = Option<i64>, Query, description = "取得件数 (デフォルト: 100, 最大: 500)"),
-        ("offset" = Option<i64>, Query, description = "オフセット (デフォルト: 0)"),
+        ("cursor" = Option<String>, Query, description = "ページ位置 (前のレスポンスの next_cursor。1ページ目は空文字)"),
+        ("offset" = Option<i64>, Query, description = "オフセット (cursor 省略時のみ。デフォルト: 0)"),
         ("group" = Option<i64>, Query, description = "グループIDで絞り込み"),
+        ("groups" = Option<String>, Query, description = "いずれかのグループに含まれるチャンネルに絞り込み (カンマ区切りのグループID。group と併用可)"),
+        ("exclude_groups" = Option<String>, Query, description = "これらのグループに含まれるチャンネルを除外 (カンマ区切りのグループID)"),
+        ("min_duration" = Option<i64>, Query, description = "最短の再生時間 (秒)"),
+        ("max_duration" = Option<i64>, Query, description = "最長の再生時間 (秒)"),
+        ("published_after" = Option<String>, Query, description = "この日時以降に公開 (RFC 3339、または YYYY-MM-DD。日付はユーザーのタイムゾーンの0時)"),
+        ("published_before" = Option<String>, Query, description = "この日時より前に公開 (published_after と同じ形式)"),
+        ("type" = Option<String>, Query, description = "種別 (カンマ区切りでいずれか): regular (通常動画) / short (Shorts) / livestream (配信中・配信予定) / ended-stream (配信終了)"),
+        ("contains" = Option<String>, Query, description = "タイトルに含む語 (空白区切り。すべて含むもの)"),
+        ("excludes" = Option<String>, Query, description = "タイトルに含まない語 (空白区切り。いずれかを含むものを除外)"),
     ),
     responses(
-        (status = 200, description = "動画一覧", body = Vec<FeedItem>),
+        (status = 200, description = "動画一覧 (cursor 指定時。省略時は FeedItem の配列)", body = FeedPage),
+        (status = 400, description = "不正な cursor・絞り込み条件", body = ErrorResponse),
         (status = 401, description = "未認証", body = ErrorResponse),
     ),
 )]
@@ -69,57 +259,61 @@ async fn get_feed(
     Extension(user_id): Extension<UserId>,
     Query(query): Query<FeedQuery>,
 ) -> Result<Json<Value>, AppError> {
-    let limit = query.limit.unwrap_or(100).min(500);
-    let offset = query.offset.unwrap_or(0);
-    let uid = user_id.0;
+    let limit = query.limit.unwrap_or(100).clamp(1, 500);
+    let offset = query.offset.unwrap_or(0).max(0);
+    let cursor_mode = query.cursor.is_some();
+    let cursor = Cursor::from_param(query.cursor.as_deref())?;
 
     let rows = {
         let conn = state.db.lock().unwrap();
-
-        let (group_join, group_where) = match query.group {
-            Some(_) => (
-                "JOIN channel_groups cg ON v.channel_id = cg.channel_id",
-                "AND cg.group_id = ?2",
-            ),
-            None => ("", ""),
-        };
-
+        let mut params: Vec<rusqlite::types::Value> = vec![user_id.0.into()];
+        let filter_where = query.filter_conditions(&conn, user_id.0, &mut params)?;
+        let (after, limit_clause) = crate::cursor::paginate(
+            cursor.as_ref(),
+            cursor_mode,
+            "v.published_at",
+            "v.id",
+            limit,
+            offset,
+            &mut params,
+        );
         let sql = format!(
             "SELECT v.id, v.channel_id, v.title, v.published_at,
-                    v.duration, v.is_short, v.is_livestream, v.livestream_ended_at,
-                    c.title as channel_title, c.thumbnail_url as channel_thumbnail
-             FROM videos v
-             JOIN channels c ON v.channel_id = c.id
-             JOIN user_channels uc ON uc.channel_id = c.id AND uc.user_id = ?1
-             {group_join}
-             LEFT JOIN user_videos uv ON uv.video_id = v.id AND uv.user_id = ?1
-             WHERE COALESCE(uv.is_hidden, 0) = 0
-               AND v.is_members_only = 0
-               AND (v.is_livestream = 0 OR uc.show_livestreams = 1)
-               AND (v.is_short = 0 OR uc.hide_shorts = 0)
-               {group_where}
-             ORDER BY v.published_at DESC
-             LIMIT ?{limit_idx} OFFSET ?{offset_idx}",
-            group_join = group_join,
-            group_where = group_where,
-            limit_idx = if query.group.is_some() { 3 } else { 2 },
-            offset_idx = if query.group.is_some() { 4 } else { 3 },
-        );
-
-        let map_row = |row: &rusqlite::Row| video_json(row, None);
-
+                v.duration, v.is_short, v.is_livestream, v.livestream_ended_at,
+                c.title as channel_title, c.thumbnail_url as channel_thumbnail,
+                {is_new}
+         FROM videos v
+         JOIN channels c ON v.channel_id = c.id
+         JOIN user_channels uc ON uc.channel_id = c.id AND uc.user_id = ?1
+         LEFT JOIN user_videos uv ON uv.video_id = v.id AND uv.user_id = ?1
+         WHERE {visible}
+           AND {not_queued}
+           {filter_where}
+           {after}
+         ORDER BY v.published_at DESC NULLS LAST, v.id DESC
+         {limit_clause}",
+            visible = crate::visibility::VISIBLE,
+            not_queued = crate::visibility::NOT_QUEUED,
+            is_new = super::unread::IS_NEW,
+        );
         let mut stmt = conn.prepare(&sql)?;
-        let rows = if let Some(group_id) = query.group {
-            stmt.query_map(rusqlite::params![uid, group_id, limit, offset], map_row)?
-                .collect::<Result<Vec<_>, _>>()?
-        } else {
-            stmt.query_map(rusqlite::params![uid, limit, offset], map_row)?
-                .collect::<Result<Vec<_>, _>>()?
-        };
+        let rows = stmt
+            .query_map(rusqlite::params_from_iter(params), |row| {
+                let mut value = video_json(row, None)?;
+                value["is_new"] = json!(row.get::<_, i64>(10)?);
+                Ok((
+                    value,
+                    Cursor {
+                        key: crate::util::row_timestamp_to_unix(row, 3)?,
+                        id: row.get(0)?,
+                    },
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
         rows
     };
 
-    Ok(Json(Value::Array(rows)))
+    Ok(Json(crate::cursor::response(rows, cursor_mode, limit)))
 }
 
 #[utoipa::path(
@@ -127,13 +321,16 @@ async fn get_feed(
     path = "/api/history",
     tag = "動画フィード",
     summary = "視聴履歴取得",
-    description = "ユーザーが視聴済み（非表示）にした動画を記録日時の降順で取得する。",
+    description = "ユーザーが視聴済みにした動画を記録日時の降順で取得する。`reason` で他の理由で非表示にした動画も取得できる。`cursor` の扱いは /api/feed と同じ (記録日時と動画IDで位置を決める)。",
     params(
+        ("reason" = Option<String>, Query, description = "非表示の理由: watched / dismissed / auto-rule / bulk / all (デフォルト: watched)"),
         ("limit" = Option<i64>, Query, description = "取得件数 (デフォルト: 100, 最大: 500)"),
-        ("offset" = Option<i64>, Query, description = "オフセット (デフォルト: 0)"),
+        ("cursor" = Option<String>, Query, description = "ページ位置 (前のレスポンスの next_cursor。1ページ目は空文字)"),
+        ("offset" = Option<i64>, Query, description = "オフセット (cursor 省略時のみ。デフォルト: 0)"),
     ),
     responses(
-        (status = 200, description = "視聴履歴", body = Vec<HistoryItem>),
+        (status = 200, description = "視聴履歴 (cursor 指定時。省略時は HistoryItem の配列)", body = HistoryPage),
+        (status = 400, description = "不正な cursor または reason", body = ErrorResponse),
         (status = 401, description = "未認証", body = ErrorResponse),
     ),
 )]
@@ -144,29 +341,111 @@ async fn get_history(
 ) -> Result<Json<Value>, AppError> {
     let limit = query.limit.unwrap_or(100).clamp(1, 500);
     let offset = query.offset.unwrap_or(0).max(0);
+    let cursor_mode = query.cursor.is_some();
+    let cursor = Cursor::from_param(query.cursor.as_deref())?;
+
+    let mut params: Vec<rusqlite::types::Value> = vec![user_id.0.into()];
+    let reason = match query.reason.as_deref().unwrap_or(WATCHED) {
+        "all" => String::new(),
+        reason => {
+            validate_reason(reason)?;
+            format!(
+                "AND COALESCE(uv.reason, '{WATCHED}') = ?{}",
+                bind(&mut params, reason.to_string().into())
+            )
+        }
+    };
+    let (after, limit_clause) = crate::cursor::paginate(
+        cursor.as_ref(),
+        cursor_mode,
+        "COALESCE(uv.created_at, 0)",
+        "v.id",
+        limit,
+        offset,
+        &mut params,
+    );
+    let sql = format!(
+        "SELECT v.id, v.channel_id, v.title, v.published_at,
+                v.duration, v.is_short, v.is_livestream, v.livestream_ended_at,
+                c.title AS channel_title, c.thumbnail_url AS channel_thumbnail,
+                uv.created_at, COALESCE(uv.reason, '{WATCHED}')
+         FROM user_videos uv
+         JOIN videos v ON v.id = uv.video_id
+         JOIN channels c ON c.id = v.channel_id
+         WHERE uv.user_id = ?1 AND uv.is_hidden = 1
+           {reason}
+           {after}
+         ORDER BY COALESCE(uv.created_at, 0) DESC, v.id DESC
+         {limit_clause}"
+    );
     let rows = {
         let conn = state.db.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT v.id, v.channel_id, v.title, v.published_at,
-                    v.duration, v.is_short, v.is_livestream, v.livestream_ended_at,
-                    c.title AS channel_title, c.thumbnail_url AS channel_thumbnail,
-                    uv.created_at
-             FROM user_videos uv
-             JOIN videos v ON v.id = uv.video_id
-             JOIN channels c ON c.id = v.channel_id
-             WHERE uv.user_id = ?1 AND uv.is_hidden = 1
-             ORDER BY COALESCE(uv.created_at, 0) DESC, v.id DESC
-             LIMIT ?2 OFFSET ?3",
-        )?;
+        let mut stmt = conn.prepare(&sql)?;
         let rows = stmt
-            .query_map(rusqlite::params![user_id.0, limit, offset], |row| {
-                video_json(row, Some(10))
+            .query_map(rusqlite::params_from_iter(params), |row| {
+                let mut value = video_json(row, Some(10))?;
+                value["reason"] = json!(row.get::<_, String>(11)?);
+                Ok((
+                    value,
+                    Cursor {
+                        key: Some(crate::util::row_timestamp_to_unix(row, 10)?.unwrap_or(0)),
+                        id: row.get(0)?,
+                    },
+                ))
             })?
             .collect::<Result<Vec<_>, _>>()?;
         rows
     };
 
-    Ok(Json(Value::Array(rows)))
+    Ok(Json(crate::cursor::response(rows, cursor_mode, limit)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/history/stats",
+    tag = "動画フィード",
+    summary = "視聴統計",
+    description = "視聴済みにした動画の件数と、非表示の理由ごとの件数を返す。watched は実際に視聴した動画 (reason=watched) のみを数える。",
+    responses(
+        (status = 200, description = "視聴統計", body = HistoryStats),
+        (status = 401, description = "未認証", body = ErrorResponse),
+    ),
+)]
+async fn get_history_stats(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+) -> Result<Json<Value>, AppError> {
+    let counts = {
+        let conn = state.db.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT COALESCE(reason, '{WATCHED}'), COUNT(*) FROM user_videos
+                 WHERE user_id = ?1 AND is_hidden = 1 GROUP BY 1"
+        ))?;
+        let counts = stmt
+            .query_map([user_id.0], |row| {
+                Ok((row.get::<_, Option<String>>(0)?, row.get::<_, i64>(1)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        counts
+    };
+    let mut by_reason = serde_json::Map::new();
+    for reason in HIDE_REASONS {
+        let count = counts
+            .iter()
+            .find(|(r, _)| r.as_deref() == Some(reason))
+            .map_or(0, |(_, n)| *n);
+        by_reason.insert(reason.to_string(), json!(count));
+    }
+    Ok(Json(json!({
+        "watched": by_reason[WATCHED],
+        "by_reason": by_reason,
+    })))
+}
+
+#[derive(Deserialize, Default, utoipa::ToSchema)]
+pub(crate) struct HideVideoBody {
+    /// 非表示の理由: watched (視聴済み) / dismissed (興味なし) / auto-rule (自動ルール) / bulk (一括) (デフォルト: watched)
+    reason: Option<String>,
 }
 
 #[utoipa::path(
@@ -174,10 +453,12 @@ async fn get_history(
     path = "/api/videos/{id}/hide",
     tag = "動画フィード",
     summary = "動画を非表示にする",
-    description = "指定した動画をユーザーのフィードから非表示にする。",
+    description = "指定した動画をユーザーのフィードから非表示にする。後で見るに入っていれば取り除く。\n\n本文は省略可。`reason` を省略すると視聴済み (watched) として記録し、視聴履歴に入る。既に非表示の動画は理由だけを更新する。",
     params(("id" = String, Path, description = "動画ID")),
+    request_body(content = Option<HideVideoBody>, example = json!({"reason": "dismissed"})),
     responses(
         (status = 200, description = "成功", body = OkResponse),
+        (status = 400, description = "不正な reason", body = ErrorResponse),
         (status = 401, description = "未認証", body = ErrorResponse),
     ),
 )]
@@ -185,13 +466,46 @@ async fn hide_video(
     State(state): State<AppState>,
     Extension(user_id): Extension<UserId>,
     Path(id): Path<String>,
+    body: Option<Json<Option<HideVideoBody>>>,
 ) -> Result<Json<Value>, AppError> {
+    // No body (or a JSON null) hides as watched, as before reasons existed.
+    let body = body.and_then(|Json(body)| body).unwrap_or_default();
+    let reason = body.reason.as_deref().unwrap_or(WATCHED);
+    validate_reason(reason)?;
     let conn = state.db.lock().unwrap();
+    let already_hidden = conn
+        .query_row(
+            "SELECT is_hidden FROM user_videos WHERE user_id = ?1 AND video_id = ?2",
+            rusqlite::params![user_id.0, id],
+            |row| row.get::<_, i64>(0),
+        )
+        .is_ok_and(|hidden| hidden == 1);
+    conn.execute(
+        "INSERT INTO user_videos (user_id, video_id, is_hidden, reason) VALUES (?1, ?2, 1, ?3)
+         ON CONFLICT(user_id, video_id) DO UPDATE SET is_hidden = 1, reason = excluded.reason",
+        rusqlite::params![user_id.0, id, reason],
+    )?;
+    // Watched or dismissed: either way there is nothing to come back to.
     conn.execute(
-        "INSERT INTO user_videos (user_id, video_id, is_hidden) VALUES (?1, ?2, 1)
-         ON CONFLICT(user_id, video_id) DO UPDATE SET is_hidden = 1",
+        "DELETE FROM watch_later WHERE user_id = ?1 AND video_id = ?2",
         rusqlite::params![user_id.0, id],
     )?;
+    super::unread::invalidate(&state.cache, user_id.0);
+    if !already_hidden {
+        crate::webhooks::emit(
+            &conn,
+            &[user_id.0],
+            crate::webhooks::VIDEO_HIDDEN,
+            json!({"video_id": id, "reason": reason}),
+        );
+        crate::audit::record(
+            &conn,
+            Some(user_id.0),
+            crate::audit::VIDEO_HIDE,
+            &id,
+            json!({"reason": reason}),
+        );
+    }
     Ok(Json(json!({"ok": true})))
 }
 
@@ -212,10 +526,20 @@ async fn unhide_video(
     Path(id): Path<String>,
 ) -> Result<Json<Value>, AppError> {
     let conn = state.db.lock().unwrap();
-    conn.execute(
+    let deleted = conn.execute(
         "DELETE FROM user_videos WHERE user_id = ?1 AND video_id = ?2",
         rusqlite::params![user_id.0, id],
     )?;
+    super::unread::invalidate(&state.cache, user_id.0);
+    if deleted > 0 {
+        crate::audit::record(
+            &conn,
+            Some(user_id.0),
+            crate::audit::VIDEO_UNHIDE,
+            &id,
+            Value::Null,
+        );
+    }
     Ok(Json(json!({"ok": true})))
 }
 
@@ -227,9 +551,22 @@ mod tests {
     // - Only show videos from channels the user subscribes to (user_channels)
     // - Exclude videos hidden by the user (user_videos.is_hidden=1)
     // - Exclude members-only videos (is_members_only=1)
+    // - Exclude videos queued in Watch Later (they are not hidden, so they
+    //   stay out of history too); hiding a video drops it from the queue
     // - Show livestreams only when user's show_livestreams=1 for that channel
     // - Sort by published_at DESC
     // - Group filter and pagination support
+    // - Filters: groups / exclude_groups (the user's own groups only),
+    //   min/max_duration on duration_seconds, published_after/before (RFC
+    //   3339, or dates at midnight in the user's timezone), type (regular,
+    //   short, livestream, ended-stream) and title contains/excludes
+    // - `cursor` pages ({items, next_cursor}) are keyed on (published_at, id)
+    //   for the feed and (created_at, video_id) for history, so inserts and
+    //   hides between pages neither duplicate nor skip rows; videos without a
+    //   published_at come last and are still reached
+    // - Hiding records a reason (watched by default, or dismissed / auto-rule
+    //   / bulk). History and its stats count only watched videos unless
+    //   `reason` asks for another (or `all`)
     //
     // All tests drive the real `get_feed` / `hide_video` / `unhide_video`
     // handlers over HTTP (oneshot). Requests pass through `auth_middleware`,
@@ -303,6 +640,7 @@ mod tests {
     fn app(state: &AppState) -> axum::Router {
         axum::Router::new()
             .merge(routes())
+            .merge(crate::routes::channels::routes())
             .layer(axum::middleware::from_fn_with_state(
                 state.clone(),
                 auth_middleware,
@@ -399,6 +737,22 @@ mod tests {
         assert_eq!(resp.status(), StatusCode::OK);
     }
 
+    /// PATCH /api/videos/{id}/hide with a JSON body, returning the status.
+    async fn hide_with(state: &AppState, video_id: &str, body: serde_json::Value) -> StatusCode {
+        app(state)
+            .oneshot(
+                Request::builder()
+                    .method("PATCH")
+                    .uri(format!("/api/videos/{video_id}/hide"))
+                    .header("content-type", "application/json")
+                    .body(axum::body::Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+            .status()
+    }
+
     async fn unhide(state: &AppState, video_id: &str) {
         let resp = app(state)
             .oneshot(
@@ -642,6 +996,77 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn history_shows_only_watched_videos_unless_a_reason_is_given() {
+        let state = setup_state();
+        for id in ["v1", "v2", "v3", "v4"] {
+            insert_video(&state, id, "UC1", "2024-01-01T00:00:00Z", 0);
+        }
+        hide(&state, "v1").await;
+        let dismissed = serde_json::json!({"reason": "dismissed"});
+        assert_eq!(hide_with(&state, "v2", dismissed).await, StatusCode::OK);
+        let watched = serde_json::json!({"reason": "watched"});
+        assert_eq!(hide_with(&state, "v3", watched).await, StatusCode::OK);
+        let bulk = serde_json::json!({"reason": "bulk"});
+        assert_eq!(hide_with(&state, "v4", bulk).await, StatusCode::OK);
+
+        assert!(feed_ids(&state, "").await.is_empty());
+        let mut watched = history_ids(&state, "").await;
+        watched.sort();
+        assert_eq!(watched, vec!["v1", "v3"]);
+        assert_eq!(history_ids(&state, "?reason=dismissed").await, vec!["v2"]);
+        assert_eq!(history_ids(&state, "?reason=all").await.len(), 4);
+        let item = history_json_as(&state, "?reason=bulk", None).await;
+        assert_eq!(item[0]["reason"], "bulk");
+
+        // Re-hiding only changes the reason.
+        let dismissed = serde_json::json!({"reason": "dismissed"});
+        assert_eq!(hide_with(&state, "v1", dismissed).await, StatusCode::OK);
+        assert_eq!(history_ids(&state, "").await, vec!["v3"]);
+
+        let resp = app(&state)
+            .oneshot(
+                Request::builder()
+                    .uri("/api/history/stats")
+                    .body(axum::body::Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = to_bytes(resp.into_body(), 1024 * 1024).await.unwrap();
+        let stats: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            stats,
+            serde_json::json!({
+                "watched": 1,
+                "by_reason": {"watched": 1, "dismissed": 2, "auto-rule": 0, "bulk": 1},
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn unknown_hide_reasons_are_rejected() {
+        let state = setup_state();
+        insert_video(&state, "v1", "UC1", "2024-01-01T00:00:00Z", 0);
+        let bogus = serde_json::json!({"reason": "bored"});
+        assert_eq!(
+            hide_with(&state, "v1", bogus).await,
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(feed_ids(&state, "").await, vec!["v1"]);
+
+        let resp = app(&state)
+            .oneshot(
+                Request::builder()
+                    .uri("/api/history?reason=bored")
+                    .body(axum::body::Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
     #[tokio::test]
     async fn feed_only_shows_subscribed_channels() {
         let state = setup_state();
@@ -693,4 +1118,325 @@ mod tests {
             vec!["v1"]
         );
     }
+
+    fn insert_filter_video(
+        state: &AppState,
+        id: &str,
+        title: &str,
+        published_at: i64,
+        duration_seconds: Option<i64>,
+        kind: (i64, i64, Option<i64>),
+    ) {
+        let (is_short, is_livestream, livestream_ended_at) = kind;
+        state
+            .db
+            .lock()
+            .unwrap()
+            .execute(
+                "INSERT INTO videos (id, channel_id, title, published_at, duration_seconds, is_short, is_livestream, livestream_ended_at)
+                 VALUES (?1, 'UC2', ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![id, title, published_at, duration_seconds, is_short, is_livestream, livestream_ended_at],
+            )
+            .unwrap();
+    }
+
+    /// UC2 shows livestreams, so every type is visible: a 10-minute regular
+    /// video, a Short, a live stream and an ended stream, a day apart.
+    fn setup_filter_videos(state: &AppState) {
+        const DAY: i64 = 86400;
+        let base = 1_704_067_200; // 2024-01-01T00:00:00Z
+        insert_filter_video(
+            state,
+            "regular",
+            "Rust tutorial",
+            base,
+            Some(600),
+            (0, 0, None),
+        );
+        insert_filter_video(
+            state,
+            "short",
+            "Rust tip #shorts",
+            base + DAY,
+            Some(30),
+            (1, 0, None),
+        );
+        insert_filter_video(
+            state,
+            "live",
+            "Live coding",
+            base + 2 * DAY,
+            None,
+            (0, 1, None),
+        );
+        insert_filter_video(
+            state,
+            "ended",
+            "Rust stream replay",
+            base + 3 * DAY,
+            Some(7200),
+            (0, 1, Some(base + 3 * DAY + 7200)),
+        );
+    }
+
+    async fn feed_status(state: &AppState, query: &str) -> StatusCode {
+        app(state)
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/api/feed{query}"))
+                    .body(axum::body::Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+            .status()
+    }
+
+    #[tokio::test]
+    async fn feed_filters_by_duration_and_date_range() {
+        let state = setup_state();
+        setup_filter_videos(&state);
+
+        assert_eq!(
+            feed_ids(&state, "?min_duration=60").await,
+            vec!["ended", "regular"],
+            "unknown durations are excluded once a duration filter is set"
+        );
+        assert_eq!(
+            feed_ids(&state, "?min_duration=60&max_duration=3600").await,
+            vec!["regular"]
+        );
+        assert_eq!(
+            feed_ids(
+                &state,
+                "?published_after=2024-01-02&published_before=2024-01-04"
+            )
+            .await,
+            vec!["live", "short"]
+        );
+        assert_eq!(
+            feed_ids(&state, "?published_after=2024-01-03T08:00:00%2B09:00").await,
+            vec!["ended", "live"]
+        );
+
+        // Plain dates start at midnight in the user's timezone.
+        state
+            .db
+            .lock()
+            .unwrap()
+            .execute("UPDATE users SET timezone = 'Asia/Tokyo' WHERE id = 1", [])
+            .unwrap();
+        assert_eq!(
+            feed_ids(&state, "?published_before=2024-01-02").await,
+            vec!["regular"],
+            "2024-01-02 JST midnight is 2024-01-01T15:00Z"
+        );
+    }
+
+    #[tokio::test]
+    async fn feed_filters_by_type_and_title() {
+        let state = setup_state();
+        setup_filter_videos(&state);
+
+        assert_eq!(feed_ids(&state, "?type=regular").await, vec!["regular"]);
+        assert_eq!(
+            feed_ids(&state, "?type=short,ended-stream").await,
+            vec!["ended", "short"]
+        );
+        assert_eq!(feed_ids(&state, "?type=livestream").await, vec!["live"]);
+        assert_eq!(
+            feed_ids(&state, "?contains=rust&excludes=%23shorts%20replay").await,
+            vec!["regular"]
+        );
+        assert_eq!(
+            feed_ids(&state, "?contains=RUST%20stream").await,
+            vec!["ended"]
+        );
+        assert!(
+            feed_ids(&state, "?contains=100%25").await.is_empty(),
+            "LIKE wildcards are literal"
+        );
+    }
+
+    #[tokio::test]
+    async fn feed_filters_by_multiple_and_excluded_groups() {
+        let state = setup_state();
+        insert_video(&state, "v1", "UC1", "2024-01-01T00:00:00Z", 0);
+        insert_video(&state, "v2", "UC2", "2024-01-02T00:00:00Z", 0);
+        let g1 = insert_group_with_channel(&state, "One", "UC1");
+        let g2 = insert_group_with_channel(&state, "Two", "UC2");
+
+        assert_eq!(
+            feed_ids(&state, &format!("?groups={g1},{g2}")).await,
+            vec!["v2", "v1"]
+        );
+        assert_eq!(
+            feed_ids(&state, &format!("?exclude_groups={g2}")).await,
+            vec!["v1"]
+        );
+        assert!(
+            feed_ids(&state, &format!("?group={g1}&exclude_groups={g1}"))
+                .await
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn invalid_filters_are_rejected() {
+        let state = setup_state();
+        for query in [
+            "?type=podcast",
+            "?groups=1,x",
+            "?published_after=yesterday",
+            "?published_before=2024-13-01",
+        ] {
+            assert_eq!(
+                feed_status(&state, query).await,
+                StatusCode::BAD_REQUEST,
+                "{query}"
+            );
+        }
+    }
+
+    /// GET {path}{query} in cursor mode, returning the page body.
+    async fn page(state: &AppState, path: &str, query: &str) -> serde_json::Value {
+        let resp = app(state)
+            .oneshot(
+                Request::builder()
+                    .uri(format!("{path}{query}"))
+                    .body(axum::body::Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = to_bytes(resp.into_body(), 1024 * 1024).await.unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    fn page_ids(page: &serde_json::Value) -> Vec<&str> {
+        page["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|v| v["id"].as_str().unwrap())
+            .collect()
+    }
+
+    /// Follow next_cursor from the first page, collecting every id.
+    async fn walk(state: &AppState, path: &str, limit: i64) -> Vec<String> {
+        let mut ids = Vec::new();
+        let mut cursor = String::new();
+        loop {
+            let body = page(state, path, &format!("?limit={limit}&cursor={cursor}")).await;
+            ids.extend(page_ids(&body).iter().map(|id| id.to_string()));
+            match body["next_cursor"].as_str() {
+                Some(next) => cursor = next.to_string(),
+                None => return ids,
+            }
+        }
+    }
+
+    fn insert_unix_video(state: &AppState, id: &str, published_at: Option<i64>) {
+        state
+            .db
+            .lock()
+            .unwrap()
+            .execute(
+                "INSERT INTO videos (id, channel_id, title, published_at) VALUES (?1, 'UC1', ?1, ?2)",
+                params![id, published_at],
+            )
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn feed_cursor_is_stable_across_inserts_and_hides() {
+        let state = setup_state();
+        for (i, id) in ["v1", "v2", "v3", "v4", "v5"].iter().enumerate() {
+            insert_unix_video(&state, id, Some(1_700_000_000 + i as i64));
+        }
+
+        let first = page(&state, "/api/feed", "?limit=2&cursor=").await;
+        assert_eq!(page_ids(&first), vec!["v5", "v4"]);
+        let cursor = first["next_cursor"].as_str().unwrap().to_string();
+
+        // A push lands above the first page and a video on it is hidden:
+        // with OFFSET the next page would repeat v4 or skip v3.
+        insert_unix_video(&state, "v6", Some(1_800_000_000));
+        hide(&state, "v5").await;
+
+        let second = page(&state, "/api/feed", &format!("?limit=2&cursor={cursor}")).await;
+        assert_eq!(page_ids(&second), vec!["v3", "v2"]);
+        let cursor = second["next_cursor"].as_str().unwrap().to_string();
+        let last = page(&state, "/api/feed", &format!("?limit=2&cursor={cursor}")).await;
+        assert_eq!(page_ids(&last), vec!["v1"]);
+        assert!(last["next_cursor"].is_null());
+    }
+
+    #[tokio::test]
+    async fn feed_cursor_orders_ties_by_id_and_reaches_undated_videos() {
+        let state = setup_state();
+        insert_unix_video(&state, "a", Some(100));
+        insert_unix_video(&state, "b", Some(100));
+        insert_unix_video(&state, "c", Some(200));
+        insert_unix_video(&state, "n1", None);
+        insert_unix_video(&state, "n2", None);
+
+        assert_eq!(
+            walk(&state, "/api/feed", 1).await,
+            vec!["c", "b", "a", "n2", "n1"]
+        );
+        assert_eq!(
+            walk(&state, "/api/channels/UC1/videos", 2).await,
+            vec!["c", "b", "a", "n2", "n1"]
+        );
+        // The legacy OFFSET array uses the same order.
+        assert_eq!(feed_ids(&state, "").await, vec!["c", "b", "a", "n2", "n1"]);
+    }
+
+    #[tokio::test]
+    async fn history_cursor_pages_by_marked_time() {
+        let state = setup_state();
+        for id in ["v1", "v2", "v3"] {
+            insert_unix_video(&state, id, Some(1_700_000_000));
+        }
+        state
+            .db
+            .lock()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO user_videos (user_id, video_id, is_hidden, created_at) VALUES
+                    (1, 'v1', 1, 10), (1, 'v2', 1, 30), (1, 'v3', 1, 30);",
+            )
+            .unwrap();
+
+        let first = page(&state, "/api/history", "?limit=2&cursor=").await;
+        assert_eq!(page_ids(&first), vec!["v3", "v2"]);
+        assert_eq!(first["items"][0]["watched_at"], "1970-01-01T00:00:30Z");
+        // Watching another video between pages does not shift the next one.
+        hide(&state, "v1").await;
+        insert_unix_video(&state, "v4", Some(1_700_000_000));
+        hide(&state, "v4").await;
+        let cursor = first["next_cursor"].as_str().unwrap();
+        let second = page(&state, "/api/history", &format!("?limit=2&cursor={cursor}")).await;
+        assert_eq!(page_ids(&second), vec!["v1"]);
+        assert!(second["next_cursor"].is_null());
+    }
+
+    #[tokio::test]
+    async fn invalid_cursor_is_rejected() {
+        let state = setup_state();
+        for path in ["/api/feed", "/api/history", "/api/channels/UC1/videos"] {
+            let resp = app(&state)
+                .oneshot(
+                    Request::builder()
+                        .uri(format!("{path}?cursor=not-a-cursor"))
+                        .body(axum::body::Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "{path}");
+        }
+    }
 }
@@ -5,6 +5,7 @@ use crate::state::AppState;
 use axum::extract::{Extension, Path, State};
 use axum::routing::{get, patch, put};
 use axum::{Json, Router};
+use rusqlite::OptionalExtension;
 use serde::Deserialize;
 use serde_json::{json, Value};
 
@@ -20,8 +21,8 @@ pub fn routes() -> Router<AppState> {
 }
 
 /// Validate a group name from a request body: required (non-empty) and at most
-/// 50 characters. Shared by create_group and update_group.
-fn validate_group_name(name: Option<String>) -> Result<String, AppError> {
+/// 50 characters. Shared by create_group, update_group and the account import.
+pub(crate) fn validate_group_name(name: Option<String>) -> Result<String, AppError> {
     let name = name
         .filter(|n| !n.is_empty())
         .ok_or_else(|| AppError::BadRequest("Name is required".to_string()))?;
@@ -110,6 +111,13 @@ async fn create_group(
         )?;
 
         let id = conn.last_insert_rowid();
+        crate::audit::record(
+            &conn,
+            Some(uid),
+            crate::audit::GROUP_CREATE,
+            &id.to_string(),
+            json!({"name": name}),
+        );
         json!({
             "id": id,
             "name": name,
@@ -117,6 +125,7 @@ async fn create_group(
             "created_at": crate::util::unix_to_rfc3339(now),
         })
     };
+    super::unread::invalidate(&state.cache, user_id.0);
     Ok((axum::http::StatusCode::CREATED, Json(row)))
 }
 
@@ -149,10 +158,19 @@ async fn update_group(
 
     {
         let conn = state.db.lock().unwrap();
-        conn.execute(
+        let updated = conn.execute(
             "UPDATE groups SET name = ?1 WHERE id = ?2 AND user_id = ?3",
             rusqlite::params![name, id, user_id.0],
         )?;
+        if updated > 0 {
+            crate::audit::record(
+                &conn,
+                Some(user_id.0),
+                crate::audit::GROUP_UPDATE,
+                &id.to_string(),
+                json!({"name": name}),
+            );
+        }
     }
     Ok(Json(json!({"ok": true})))
 }
@@ -192,6 +210,13 @@ async fn reorder_groups(
             }
         }
         conn.execute_batch("COMMIT")?;
+        crate::audit::record(
+            &conn,
+            Some(user_id.0),
+            crate::audit::GROUP_REORDER,
+            "",
+            json!({"order": body.order}),
+        );
     }
     Ok(Json(json!({"ok": true})))
 }
@@ -214,11 +239,28 @@ async fn delete_group(
 ) -> Result<Json<Value>, AppError> {
     {
         let conn = state.db.lock().unwrap();
+        let name: Option<String> = conn
+            .query_row(
+                "SELECT name FROM groups WHERE id = ?1 AND user_id = ?2",
+                rusqlite::params![id, user_id.0],
+                |row| row.get(0),
+            )
+            .optional()?;
         conn.execute(
             "DELETE FROM groups WHERE id = ?1 AND user_id = ?2",
             rusqlite::params![id, user_id.0],
         )?;
+        if let Some(name) = name {
+            crate::audit::record(
+                &conn,
+                Some(user_id.0),
+                crate::audit::GROUP_DELETE,
+                &id.to_string(),
+                json!({"name": name}),
+            );
+        }
     }
+    super::unread::invalidate(&state.cache, user_id.0);
     Ok(Json(json!({"ok": true})))
 }
 
@@ -310,7 +352,15 @@ async fn set_group_channels(
             return Err(e.into());
         }
         conn.execute_batch("COMMIT")?;
+        crate::audit::record(
+            &conn,
+            Some(user_id.0),
+            crate::audit::GROUP_CHANNELS,
+            &id.to_string(),
+            json!({"channel_ids": body.channel_ids}),
+        );
     }
+    super::unread::invalidate(&state.cache, user_id.0);
     Ok(Json(json!({"ok": true})))
 }
 
@@ -0,0 +1,467 @@
+use crate::error::AppError;
+use crate::login;
+use crate::notify::{Level, Message};
+use crate::openapi::*;
+use crate::state::AppState;
+use axum::extract::{Query, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{Html, IntoResponse};
+use axum::routing::{get, post};
+use axum::Form;
+use axum::{Json, Router};
+use serde::Deserialize;
+use serde_json::json;
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/login", post(request_login_link))
+        .route(
+            "/api/login/verify",
+            get(confirm_login_link).post(verify_login_link),
+        )
+        .route("/api/logout", post(logout))
+}
+
+fn smtp_url(state: &AppState) -> Result<&str, AppError> {
+    state
+        .config
+        .login_smtp_url
+        .as_deref()
+        .ok_or_else(|| AppError::NotFound("Email login is not enabled".to_string()))
+}
+
+/// Origin the login link points to.
+fn base_url(state: &AppState) -> String {
+    state
+        .config
+        .public_base_url
+        .clone()
+        .unwrap_or_else(|| format!("http://localhost:{}", state.config.port))
+}
+
+fn login_message(link: &str) -> Message {
+    Message {
+        level: Level::Info,
+        title: "Sign in to YouTube Sub Feed".to_string(),
+        description: format!(
+            "Open this link within {} minutes to sign in. If you did not request it, ignore this email.",
+            login::LINK_TTL_SECS / 60
+        ),
+        url: Some(link.to_string()),
+        image_url: None,
+        author: None,
+        fields: Vec::new(),
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        html: None,
+    }
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub(crate) struct LoginBody {
+    /// 登録済みのメールアドレス
+    email: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/login",
+    tag = "認証",
+    summary = "ログインリンク送信",
+    description = "LOGIN_SMTP_URL 設定時のみ有効。登録済みで無効化されていないメールアドレスに、15分間有効な1回限りのログインリンクを送信する。アドレスが登録済みかどうかは応答から判別できない (送信は応答後に行い、失敗してもログに残すだけで常に成功を返す)。同じユーザーへの送信は1分に1回まで。",
+    request_body(content = LoginBody),
+    responses(
+        (status = 200, description = "受付済み", body = OkResponse),
+        (status = 404, description = "メールログインが無効", body = ErrorResponse),
+    ),
+)]
+async fn request_login_link(
+    State(state): State<AppState>,
+    Json(body): Json<LoginBody>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let smtp_url = smtp_url(&state)?.to_string();
+    let issued = {
+        let conn = state.db.lock().unwrap();
+        login::issue_link(&conn, &body.email, crate::util::now_unix())?
+    };
+    if let Some((email, token)) = issued {
+        // Mailed in the background: neither the response nor its timing may
+        // tell a registered address from an unknown one.
+        let link = format!("{}/api/login/verify?token={token}", base_url(&state));
+        tokio::spawn(async move {
+            let result = match crate::notify::smtp_mailer(&smtp_url, &email) {
+                Ok(mailer) => mailer.send(&state.http, &login_message(&link)).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                tracing::warn!("[login] Failed to send login link: {}", e);
+            }
+        });
+    }
+    Ok(Json(json!({"ok": true})))
+}
+
+#[derive(Deserialize)]
+struct VerifyQuery {
+    token: String,
+}
+
+#[derive(Deserialize)]
+struct VerifyForm {
+    token: String,
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Page with a single button that POSTs the token back. Mail scanners and
+/// link previews fetch the link with GET, which must not use it up.
+fn confirmation_page(token: &str) -> String {
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<meta name="referrer" content="no-referrer">
+<title>Sign in to YouTube Sub Feed</title>
+</head>
+<body>
+<form method="post" action="/api/login/verify">
+<input type="hidden" name="token" value="{}">
+<button type="submit">Sign in to YouTube Sub Feed</button>
+</form>
+</body>
+</html>
+"#,
+        escape_html(token)
+    )
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/login/verify",
+    tag = "認証",
+    summary = "ログインリンク確認ページ",
+    description = "メールのログインリンクの遷移先。トークンは消費せず、ログインボタンだけの確認ページ (HTML) を返す。ボタンで同じパスに POST するとログインする。メールのリンクスキャナーやプレビューによる GET でリンクが使用済みにならないようにするため。",
+    params(("token" = String, Query, description = "ログインリンクのトークン")),
+    responses(
+        (status = 200, description = "確認ページ (text/html)"),
+        (status = 404, description = "メールログインが無効", body = ErrorResponse),
+    ),
+)]
+async fn confirm_login_link(
+    State(state): State<AppState>,
+    Query(query): Query<VerifyQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    smtp_url(&state)?;
+    Ok((
+        [(header::CACHE_CONTROL, "no-store")],
+        Html(confirmation_page(&query.token)),
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/login/verify",
+    tag = "認証",
+    summary = "ログインリンク使用",
+    description = "確認ページのフォームから送信される。トークンを消費してセッションを開始し、セッション Cookie (30日間有効) を付けてトップページへリダイレクトする。",
+    request_body(content_type = "application/x-www-form-urlencoded", description = "token=<ログインリンクのトークン>"),
+    responses(
+        (status = 303, description = "ログイン成功 (Set-Cookie 付きで / へリダイレクト)"),
+        (status = 401, description = "リンクが無効・期限切れ・使用済み", body = ErrorResponse),
+        (status = 404, description = "メールログインが無効", body = ErrorResponse),
+    ),
+)]
+async fn verify_login_link(
+    State(state): State<AppState>,
+    Form(form): Form<VerifyForm>,
+) -> Result<impl IntoResponse, AppError> {
+    smtp_url(&state)?;
+    let session = {
+        let conn = state.db.lock().unwrap();
+        login::redeem_link(&conn, &form.token, crate::util::now_unix())?
+    };
+    let secure = base_url(&state).starts_with("https://");
+    Ok((
+        StatusCode::SEE_OTHER,
+        [
+            (
+                header::SET_COOKIE,
+                login::session_cookie(Some(&session), secure),
+            ),
+            (header::LOCATION, "/".to_string()),
+        ],
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/logout",
+    tag = "認証",
+    summary = "ログアウト",
+    description = "メールログインのセッションを終了し、セッション Cookie を削除する。",
+    responses(
+        (status = 200, description = "成功", body = OkResponse),
+    ),
+)]
+async fn logout(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AppError> {
+    if let Some(token) = login::session_token(&headers) {
+        let conn = state.db.lock().unwrap();
+        login::end_session(&conn, &token)?;
+    }
+    let secure = base_url(&state).starts_with("https://");
+    Ok((
+        [(header::SET_COOKIE, login::session_cookie(None, secure))],
+        Json(json!({"ok": true})),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    // Magic-Link Login Routes Spec
+    //
+    // - Disabled (404) unless LOGIN_SMTP_URL is set.
+    // - POST /api/login mails a link to registered users only, in the
+    //   background, and answers the same for unknown addresses and when the
+    //   mail cannot be sent.
+    // - GET on the link only shows a confirmation page; POSTing its form uses
+    //   the link up and redirects to / with a session cookie that
+    //   auth_middleware accepts in production; logout ends the session.
+
+    use super::routes;
+    use crate::middleware::auth_middleware;
+    use crate::notify::stand_in::SmtpStandIn;
+    use crate::state::AppState;
+    use axum::body::to_bytes;
+    use axum::http::{header, Request, StatusCode};
+    use axum::response::Response;
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+
+    fn setup_state(stand_in: &SmtpStandIn) -> AppState {
+        let mut state = AppState::test();
+        state.config.is_production = true;
+        state.config.public_base_url = Some("https://feed.example.com".to_string());
+        state.config.login_smtp_url = Some(format!(
+            "smtp+plain://127.0.0.1:{}/?from=feed@example.com",
+            stand_in.addr.port()
+        ));
+        state
+            .db
+            .lock()
+            .unwrap()
+            .execute("INSERT INTO users (email) VALUES ('a@example.com')", [])
+            .unwrap();
+        state
+    }
+
+    async fn send(state: &AppState, request: Request<axum::body::Body>) -> Response {
+        axum::Router::new()
+            .merge(routes())
+            .merge(
+                crate::routes::auth::routes().layer(axum::middleware::from_fn_with_state(
+                    state.clone(),
+                    auth_middleware,
+                )),
+            )
+            .with_state(state.clone())
+            .oneshot(request)
+            .await
+            .unwrap()
+    }
+
+    async fn post_login(state: &AppState, email: &str) -> StatusCode {
+        send(
+            state,
+            Request::post("/api/login")
+                .header("content-type", "application/json")
+                .body(json!({"email": email}).to_string().into())
+                .unwrap(),
+        )
+        .await
+        .status()
+    }
+
+    async fn me(state: &AppState, cookie: &str) -> StatusCode {
+        send(
+            state,
+            Request::get("/api/auth/me")
+                .header(header::COOKIE, cookie)
+                .body(axum::body::Body::empty())
+                .unwrap(),
+        )
+        .await
+        .status()
+    }
+
+    /// Wait for the background send to reach the stand-in.
+    async fn wait_for_mail(stand_in: &SmtpStandIn) {
+        for _ in 0..200 {
+            if !stand_in.messages.lock().unwrap().is_empty() {
+                return;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        panic!("no mail was sent");
+    }
+
+    async fn redeem(state: &AppState, token: &str) -> Response {
+        send(
+            state,
+            Request::post("/api/login/verify")
+                .header("content-type", "application/x-www-form-urlencoded")
+                .body(format!("token={token}").into())
+                .unwrap(),
+        )
+        .await
+    }
+
+    fn mailed_link(stand_in: &SmtpStandIn) -> String {
+        // The body is quoted-printable: undo soft line breaks and `=3D`.
+        let data = stand_in.messages.lock().unwrap()[0]
+            .data
+            .replace("=\r\n", "")
+            .replace("=3D", "=");
+        let start = data
+            .find("https://feed.example.com/api/login/verify?token=")
+            .unwrap()
+            + "https://feed.example.com".len();
+        data[start..].split_whitespace().next().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn magic_link_signs_in_with_a_session_cookie() {
+        let stand_in = SmtpStandIn::start().await;
+        let state = setup_state(&stand_in);
+
+        assert_eq!(post_login(&state, "A@example.com").await, StatusCode::OK);
+        wait_for_mail(&stand_in).await;
+        let mail = stand_in.messages.lock().unwrap()[0].to.clone();
+        assert_eq!(mail, vec!["<a@example.com>"]);
+        let link = mailed_link(&stand_in);
+        let token = link.rsplit_once("token=").unwrap().1.to_string();
+
+        // Opening the link (or a scanner fetching it) does not use it up.
+        for _ in 0..2 {
+            let resp = send(
+                &state,
+                Request::get(&link).body(axum::body::Body::empty()).unwrap(),
+            )
+            .await;
+            assert_eq!(resp.status(), StatusCode::OK);
+            assert!(resp.headers().get(header::SET_COOKIE).is_none());
+            let body = to_bytes(resp.into_body(), 64 * 1024).await.unwrap();
+            let body = String::from_utf8(body.to_vec()).unwrap();
+            assert!(body.contains(r#"<form method="post" action="/api/login/verify">"#));
+            assert!(body.contains(&format!(r#"name="token" value="{token}""#)));
+        }
+
+        let resp = redeem(&state, &token).await;
+        assert_eq!(resp.status(), StatusCode::SEE_OTHER);
+        assert_eq!(resp.headers()[header::LOCATION], "/");
+        let set_cookie = resp.headers()[header::SET_COOKIE].to_str().unwrap();
+        assert!(set_cookie.contains("HttpOnly") && set_cookie.contains("Secure"));
+        let cookie = set_cookie.split(';').next().unwrap().to_string();
+
+        assert_eq!(me(&state, &cookie).await, StatusCode::OK);
+        assert_eq!(
+            me(&state, "ysf_session=forged").await,
+            StatusCode::UNAUTHORIZED
+        );
+
+        // The link is single-use.
+        assert_eq!(
+            redeem(&state, &token).await.status(),
+            StatusCode::UNAUTHORIZED
+        );
+
+        let resp = send(
+            &state,
+            Request::post("/api/logout")
+                .header(header::COOKIE, &cookie)
+                .body(axum::body::Body::empty())
+                .unwrap(),
+        )
+        .await;
+        assert!(resp.headers()[header::SET_COOKIE]
+            .to_str()
+            .unwrap()
+            .contains("Max-Age=0"));
+        assert_eq!(me(&state, &cookie).await, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn unregistered_email_gets_the_same_answer_but_no_mail() {
+        let stand_in = SmtpStandIn::start().await;
+        let state = setup_state(&stand_in);
+
+        assert_eq!(
+            post_login(&state, "stranger@example.com").await,
+            StatusCode::OK
+        );
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        assert!(stand_in.messages.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn mail_failures_do_not_change_the_answer() {
+        let stand_in = SmtpStandIn::start().await;
+        let mut state = setup_state(&stand_in);
+        let closed = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = closed.local_addr().unwrap().port();
+        drop(closed);
+        state.config.login_smtp_url = Some(format!(
+            "smtp+plain://127.0.0.1:{port}/?from=feed@example.com"
+        ));
+
+        assert_eq!(post_login(&state, "a@example.com").await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn confirmation_page_escapes_the_token() {
+        let stand_in = SmtpStandIn::start().await;
+        let state = setup_state(&stand_in);
+        let resp = send(
+            &state,
+            Request::get("/api/login/verify?token=%22%3E%3Cscript%3E")
+                .body(axum::body::Body::empty())
+                .unwrap(),
+        )
+        .await;
+        let body = to_bytes(resp.into_body(), 64 * 1024).await.unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(!body.contains("<script>"));
+        assert!(body.contains("&quot;&gt;&lt;script&gt;"));
+    }
+
+    #[tokio::test]
+    async fn login_is_404_when_not_configured() {
+        let stand_in = SmtpStandIn::start().await;
+        let mut state = setup_state(&stand_in);
+        state.config.login_smtp_url = None;
+
+        assert_eq!(
+            post_login(&state, "a@example.com").await,
+            StatusCode::NOT_FOUND
+        );
+        let resp = send(
+            &state,
+            Request::get("/api/login/verify?token=x")
+                .body(axum::body::Body::empty())
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        let body = to_bytes(resp.into_body(), 1024).await.unwrap();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["error"], "Email login is not enabled");
+    }
+}
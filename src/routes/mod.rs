@@ -1,9 +1,26 @@
+pub mod account;
+pub mod audit_log;
 pub mod auth;
+pub mod bulk_hide;
 pub mod channels;
+pub mod digest;
 pub mod feed;
 pub mod groups;
+pub mod login;
+pub mod mute_rules;
 pub mod news;
+pub mod notification_rules;
+pub mod outbox;
+pub mod push;
+pub mod reminders;
 pub mod rss;
+pub mod rss_tokens;
+pub mod search;
+pub mod tokens;
+pub mod unread;
+pub mod users;
+pub mod watch_later;
+pub mod webhooks;
 pub mod websub;
 
 use crate::middleware::auth_middleware;
@@ -23,14 +40,35 @@ use utoipa_swagger_ui::SwaggerUi;
     info(
         title = "YouTube Sub Feed API",
         version = "0.2.0",
-        description = "YouTubeの登録チャンネルの最新動画を公開日時の降順で一覧表示するWebアプリのAPI。\n\n## 認証\n\nCloudflare Access による認証。`Cf-Access-Authenticated-User-Email` ヘッダでユーザー識別。\nローカル開発では最初の DB ユーザーが自動的に使用される。\n\n## データベース\n\n| テーブル | 説明 |\n|---|---|\n| channels | 登録チャンネル |\n| videos | 動画 (FK: channels, CASCADE DELETE) |\n| groups | チャンネルグループ |\n| channel_groups | チャンネル×グループ (多対多) |\n| users | ユーザー (email 識別) |\n| channel_subscriptions | WebSub 購読情報 |",
+        description = "YouTubeの登録チャンネルの最新動画を公開日時の降順で一覧表示するWebアプリのAPI。\n\n## 認証\n\nCloudflare Access による認証。`Cf-Access-Authenticated-User-Email` ヘッダ (`AUTH_HEADER` で Authelia / oauth2-proxy / Tailscale Serve 等のヘッダに変更可、`TRUSTED_PROXIES` で送信元を制限) でユーザー識別。`CF_ACCESS_TEAM_DOMAIN` / `CF_ACCESS_AUD` 設定時は `Cf-Access-Jwt-Assertion` の JWT を JWKS で検証し、その email クレームで識別する。\nスクリプト等からは `POST /api/tokens` で発行した個人用 API トークンを `Authorization: Bearer <token>` で送って呼び出せる (スコープで操作を制限)。\n`LOGIN_SMTP_URL` 設定時は、リバースプロキシの代わりにメールのログインリンク (`POST /api/login`) で発行されるセッション Cookie でも認証できる。\nローカル開発では最初の DB ユーザーが自動的に使用される。\n\n## データベース\n\n| テーブル | 説明 |\n|---|---|\n| channels | 登録チャンネル |\n| videos | 動画 (FK: channels, CASCADE DELETE) |\n| video_search | 動画タイトル・チャンネル名の全文検索インデックス (FTS5 trigram) |\n| groups | チャンネルグループ |\n| channel_groups | チャンネル×グループ (多対多) |\n| users | ユーザー (email 識別、master が招待・無効化) |\n| api_tokens | 個人用 API トークン (ハッシュ・スコープ・有効期限) |\n| login_links | メールログインの1回限りのリンク (ハッシュ保存) |\n| login_sessions | メールログインのセッション (ハッシュ保存) |\n| rss_feed_tokens | ラベル付き RSS フィードトークン (グループ/お気に入り) |\n| channel_subscriptions | WebSub 購読情報 |\n| notification_rules | ユーザーごとの通知ルール |\n| mute_rules | ユーザーごとのミュートルール (タイトルのキーワード/正規表現) |\n| watch_later | ユーザーごとの後で見るキュー (順序付き) |\n| bulk_hides / bulk_hide_videos | 一括非表示の取り消し用トークンと対象動画 (15分で失効) |\n| notification_queue | 静音時間中に保留された通知 |\n| notification_outbox | 通知の配信キュー・配信ログ (再送管理) |\n| digest_settings | ダイジェスト設定・前回送信日時 |\n| reminder_settings | 配信リマインダー設定 |\n| stream_reminders_sent | 送信済みの配信リマインダー (重複送信防止) |\n| vapid_keys | Web Push 用 VAPID 鍵ペア |\n| push_subscriptions | ブラウザのプッシュ購読 |\n| webhooks | ユーザー登録の送信 Webhook |\n| webhook_deliveries | Webhook 配信キュー・配信ログ |\n| audit_log | 監査ログ (ユーザー・サーバーの操作履歴) |",
     ),
     paths(
         auth::me,
+        auth::update_me,
+        login::request_login_link,
+        login::confirm_login_link,
+        login::verify_login_link,
+        login::logout,
+        tokens::get_tokens,
+        tokens::create_token,
+        tokens::delete_token,
         feed::get_feed,
+        unread::get_unread_counts,
+        unread::get_new_count,
+        unread::update_last_seen,
         feed::get_history,
+        feed::get_history_stats,
         feed::hide_video,
         feed::unhide_video,
+        bulk_hide::bulk_hide,
+        bulk_hide::undo_bulk_hide,
+        watch_later::get_watch_later,
+        watch_later::add_to_watch_later,
+        watch_later::remove_from_watch_later,
+        watch_later::reorder_watch_later,
+        watch_later::pop_next,
+        watch_later::get_play_all,
+        search::search_videos,
         channels::get_channels,
         channels::get_channel_videos,
         channels::add_channel,
@@ -45,17 +83,92 @@ use utoipa_swagger_ui::SwaggerUi;
         groups::get_group_channels,
         groups::set_group_channels,
         rss::get_rss_feed,
+        rss_tokens::get_rss_token,
+        rss_tokens::rotate_rss_token,
+        rss_tokens::revoke_rss_token,
+        rss_tokens::get_feed_tokens,
+        rss_tokens::create_feed_token,
+        rss_tokens::rotate_feed_token,
+        rss_tokens::delete_feed_token,
         news::get_news,
+        notification_rules::get_rules,
+        notification_rules::create_rule,
+        notification_rules::update_rule,
+        notification_rules::delete_rule,
+        mute_rules::get_mute_rules,
+        mute_rules::create_mute_rule,
+        mute_rules::preview_mute_rule,
+        mute_rules::update_mute_rule,
+        mute_rules::delete_mute_rule,
+        digest::get_digest,
+        digest::update_digest,
+        digest::delete_digest,
+        reminders::get_reminders,
+        reminders::update_reminders,
+        reminders::delete_reminders,
+        push::get_vapid_public_key,
+        push::get_subscriptions,
+        push::add_subscription,
+        push::delete_subscription,
+        webhooks::get_webhooks,
+        webhooks::create_webhook,
+        webhooks::update_webhook,
+        webhooks::delete_webhook,
+        webhooks::get_deliveries,
+        outbox::get_notifications,
+        outbox::resend_notification,
+        users::get_users,
+        users::invite_user,
+        users::update_user,
+        users::delete_user,
+        audit_log::get_audit_log,
+        account::export_account,
+        account::import_account,
+        account::delete_account,
     ),
     components(schemas(
         openapi::ErrorResponse,
         openapi::OkResponse,
         openapi::FeedItem,
+        openapi::FeedPage,
         openapi::HistoryItem,
+        openapi::HistoryPage,
+        openapi::HistoryStats,
+        openapi::UnreadCounts,
+        openapi::NewCount,
+        openapi::LastSeenResponse,
+        openapi::BulkHideResult,
+        openapi::BulkUndoResult,
+        openapi::WatchLaterItem,
+        openapi::PlayAllResponse,
+        openapi::SearchItem,
         openapi::ChannelItem,
         openapi::ChannelVideoItem,
+        openapi::ChannelVideoPage,
         openapi::GroupItem,
         openapi::MeResponse,
+        openapi::RssTokenResponse,
+        openapi::FeedTokenItem,
+        openapi::ApiTokenItem,
+        openapi::CreatedApiToken,
+        openapi::NotificationRuleItem,
+        openapi::MuteRuleItem,
+        openapi::MutePreview,
+        openapi::DigestSettings,
+        openapi::ReminderSettings,
+        openapi::VapidPublicKeyResponse,
+        openapi::PushSubscriptionItem,
+        openapi::WebhookItem,
+        openapi::CreatedWebhook,
+        openapi::WebhookDeliveryItem,
+        openapi::OutboxItem,
+        openapi::UserItem,
+        openapi::AuditLogItem,
+        openapi::AccountImportResult,
+        auth::UpdateMeBody,
+        login::LoginBody,
+        tokens::CreateTokenBody,
+        rss_tokens::CreateFeedTokenBody,
         channels::UpdateChannelBody,
         channels::AddChannelBody,
         channels::SyncChannelsBody,
@@ -64,13 +177,41 @@ use utoipa_swagger_ui::SwaggerUi;
         groups::UpdateGroupBody,
         groups::ReorderBody,
         groups::SetChannelsBody,
+        feed::HideVideoBody,
+        bulk_hide::BulkHideBody,
+        bulk_hide::UndoBulkHideBody,
+        unread::LastSeenBody,
+        watch_later::AddWatchLaterBody,
+        watch_later::ReorderWatchLaterBody,
+        notification_rules::RuleBody,
+        mute_rules::MuteRuleBody,
+        digest::DigestBody,
+        reminders::ReminderBody,
+        push::AddSubscriptionBody,
+        push::PushSubscriptionKeys,
+        webhooks::CreateWebhookBody,
+        webhooks::UpdateWebhookBody,
+        users::InviteUserBody,
+        users::UpdateUserBody,
+        account::AccountExport,
+        account::ExportSettings,
+        account::ExportSubscription,
+        account::ExportGroup,
+        account::ExportVideo,
+        account::ExportWatchLater,
+        account::ExportRule,
+        account::ExportMuteRule,
+        account::DeleteAccountBody,
     )),
     tags(
-        (name = "認証", description = "Cloudflare Access 認証・ユーザー識別"),
-        (name = "動画フィード", description = "動画一覧の取得・非表示/復元"),
+        (name = "認証", description = "Cloudflare Access / 信頼済みリバースプロキシによる認証・ユーザー識別・メールログイン・個人用 API トークン"),
+        (name = "動画フィード", description = "動画一覧の取得・検索・非表示/復元・後で見る・ミュートルール"),
         (name = "チャンネル", description = "登録チャンネルの管理・手動追加・同期"),
         (name = "グループ", description = "チャンネルグループの管理・並び替え・割り当て"),
-        (name = "RSS", description = "お気に入りチャンネルのRSSフィード配信"),
+        (name = "RSS", description = "お気に入り・グループの RSS フィード配信とトークン管理"),
+        (name = "通知", description = "ユーザーごとの新着通知ルール (チャンネル/グループ/キーワード・静音時間)・定期ダイジェスト・配信リマインダー・ブラウザプッシュ・送信 Webhook"),
+        (name = "管理", description = "インスタンス管理・ユーザー招待/管理 (master ユーザーのみ)"),
+        (name = "アカウント", description = "自分のデータのエクスポート/インポート (インスタンス間の移行)・アカウント削除"),
     ),
 )]
 struct ApiDoc;
@@ -82,15 +223,32 @@ pub fn build_router(state: AppState) -> Router {
             get(|| async { axum::Json(serde_json::json!({"ok": true})) }),
         )
         .merge(rss::routes())
+        .merge(login::routes())
         .merge(websub::routes());
 
     // auth::me is protected (requires Cf-Access header / dev bypass)
     let protected = Router::new()
         .merge(auth::routes())
+        .merge(account::routes())
+        .merge(tokens::routes())
+        .merge(rss_tokens::routes())
         .merge(feed::routes())
+        .merge(bulk_hide::routes())
+        .merge(unread::routes())
+        .merge(watch_later::routes())
+        .merge(search::routes())
         .merge(channels::routes())
         .merge(groups::routes())
         .merge(news::routes())
+        .merge(notification_rules::routes())
+        .merge(mute_rules::routes())
+        .merge(digest::routes())
+        .merge(reminders::routes())
+        .merge(push::routes())
+        .merge(webhooks::routes())
+        .merge(outbox::routes())
+        .merge(users::routes())
+        .merge(audit_log::routes())
         .layer(axum::middleware::from_fn_with_state(
             state.clone(),
             auth_middleware,
@@ -226,7 +384,7 @@ mod tests {
     // API Endpoints Spec
     //
     // Auth is Cloudflare Access (Cf-Access-Authenticated-User-Email header).
-    // /api/health, /api/rss, /api/websub/callback are public; every other /api/*
+    // /api/health, /api/rss, /api/login*, /api/logout, /api/websub/callback are public; every other /api/*
     // route sits behind auth_middleware. These tests drive the *real* Router from
     // build_router (via oneshot), so the routing/auth wiring is what's verified —
     // not a hand-maintained inventory table.
@@ -264,8 +422,10 @@ mod tests {
             // every protected route before the handler runs.
             let protected: &[(&str, &str)] = &[
                 ("GET", "/api/auth/me"),
+                ("PATCH", "/api/auth/me"),
                 ("GET", "/api/feed"),
                 ("GET", "/api/history"),
+                ("GET", "/api/search?q=x"),
                 ("GET", "/api/news"),
                 ("PATCH", "/api/videos/abc/hide"),
                 ("PATCH", "/api/videos/abc/unhide"),
@@ -282,6 +442,45 @@ mod tests {
                 ("PUT", "/api/groups/reorder"),
                 ("DELETE", "/api/groups/1"),
                 ("PUT", "/api/groups/1/channels"),
+                ("GET", "/api/notification-rules"),
+                ("POST", "/api/notification-rules"),
+                ("PUT", "/api/notification-rules/1"),
+                ("DELETE", "/api/notification-rules/1"),
+                ("GET", "/api/digest"),
+                ("PUT", "/api/digest"),
+                ("DELETE", "/api/digest"),
+                ("GET", "/api/reminders"),
+                ("PUT", "/api/reminders"),
+                ("DELETE", "/api/reminders"),
+                ("GET", "/api/push/vapid-public-key"),
+                ("GET", "/api/push/subscriptions"),
+                ("POST", "/api/push/subscriptions"),
+                ("DELETE", "/api/push/subscriptions/1"),
+                ("GET", "/api/webhooks"),
+                ("POST", "/api/webhooks"),
+                ("PATCH", "/api/webhooks/1"),
+                ("DELETE", "/api/webhooks/1"),
+                ("GET", "/api/webhooks/1/deliveries"),
+                ("GET", "/api/admin/notifications"),
+                ("POST", "/api/admin/notifications/1/resend"),
+                ("GET", "/api/rss/token"),
+                ("POST", "/api/rss/token"),
+                ("DELETE", "/api/rss/token"),
+                ("GET", "/api/rss/feeds"),
+                ("POST", "/api/rss/feeds"),
+                ("POST", "/api/rss/feeds/1/rotate"),
+                ("DELETE", "/api/rss/feeds/1"),
+                ("GET", "/api/tokens"),
+                ("POST", "/api/tokens"),
+                ("DELETE", "/api/tokens/1"),
+                ("GET", "/api/admin/users"),
+                ("POST", "/api/admin/users"),
+                ("PATCH", "/api/admin/users/1"),
+                ("DELETE", "/api/admin/users/1"),
+                ("GET", "/api/admin/audit-log"),
+                ("GET", "/api/account/export"),
+                ("POST", "/api/account/import"),
+                ("DELETE", "/api/account"),
             ];
             for (method, uri) in protected {
                 assert_eq!(
@@ -299,6 +498,10 @@ mod tests {
             let public: &[(&str, &str)] = &[
                 ("GET", "/api/health"),
                 ("GET", "/api/rss"),
+                ("POST", "/api/login"),
+                ("GET", "/api/login/verify"),
+                ("POST", "/api/login/verify"),
+                ("POST", "/api/logout"),
                 ("GET", "/api/websub/callback"),
                 ("POST", "/api/websub/callback"),
             ];
@@ -0,0 +1,605 @@
+use crate::error::AppError;
+use crate::middleware::UserId;
+use crate::openapi::*;
+use crate::state::AppState;
+use axum::extract::{Extension, Path, State};
+use axum::routing::{get, post, put};
+use axum::{Json, Router};
+use rusqlite::Connection;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/api/mute-rules",
+            get(get_mute_rules).post(create_mute_rule),
+        )
+        .route("/api/mute-rules/preview", post(preview_mute_rule))
+        .route(
+            "/api/mute-rules/{id}",
+            put(update_mute_rule).delete(delete_mute_rule),
+        )
+}
+
+/// At most this many videos are listed by a preview; `count` is exact.
+const PREVIEW_LIMIT: i64 = 50;
+
+const MUTE_RULE_COLUMNS: &str = "id, pattern, is_regex, channel_id, group_id, created_at";
+
+fn mute_rule_json(row: &rusqlite::Row) -> rusqlite::Result<Value> {
+    Ok(json!({
+        "id": row.get::<_, i64>(0)?,
+        "pattern": row.get::<_, String>(1)?,
+        "is_regex": row.get::<_, i64>(2)?,
+        "channel_id": row.get::<_, Option<String>>(3)?,
+        "group_id": row.get::<_, Option<i64>>(4)?,
+        "created_at": crate::util::row_timestamp_to_rfc3339(row, 5)?,
+    }))
+}
+
+fn load_mute_rule(conn: &Connection, user_id: i64, id: i64) -> Result<Value, AppError> {
+    conn.query_row(
+        &format!("SELECT {MUTE_RULE_COLUMNS} FROM mute_rules WHERE id = ?1 AND user_id = ?2"),
+        rusqlite::params![id, user_id],
+        mute_rule_json,
+    )
+    .map_err(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => {
+            AppError::NotFound("Mute rule not found".to_string())
+        }
+        e => e.into(),
+    })
+}
+
+#[derive(Default, Deserialize, utoipa::ToSchema)]
+pub(crate) struct MuteRuleBody {
+    /// タイトルのキーワード (大文字小文字を区別しない, 200文字以内)
+    pub(crate) pattern: Option<String>,
+    /// pattern を正規表現として扱う (0/1)
+    pub(crate) is_regex: Option<i64>,
+    /// 対象チャンネルID (group_id と排他。両方未指定なら購読中の全チャンネル)
+    pub(crate) channel_id: Option<String>,
+    /// 対象グループID (channel_id と排他)
+    pub(crate) group_id: Option<i64>,
+}
+
+pub(crate) struct ValidMuteRule {
+    pattern: String,
+    is_regex: i64,
+    channel_id: Option<String>,
+    group_id: Option<i64>,
+}
+
+/// Validate a mute rule body. Scope targets must belong to the caller, the
+/// same as for notification rules.
+pub(crate) fn validate_mute_rule(
+    conn: &Connection,
+    user_id: i64,
+    body: MuteRuleBody,
+) -> Result<ValidMuteRule, AppError> {
+    let pattern = body
+        .pattern
+        .map(|p| p.trim().to_string())
+        .filter(|p| !p.is_empty())
+        .ok_or_else(|| AppError::BadRequest("pattern is required".to_string()))?;
+    if pattern.chars().count() > 200 {
+        return Err(AppError::BadRequest(
+            "pattern must be 200 characters or less".to_string(),
+        ));
+    }
+
+    let is_regex = body.is_regex.unwrap_or(0);
+    if is_regex != 0 && is_regex != 1 {
+        return Err(AppError::BadRequest("is_regex must be 0 or 1".to_string()));
+    }
+    if is_regex == 1 && !crate::visibility::is_valid_regex(&pattern) {
+        return Err(AppError::BadRequest("Invalid regex".to_string()));
+    }
+
+    if body.channel_id.is_some() && body.group_id.is_some() {
+        return Err(AppError::BadRequest(
+            "channel_id and group_id are mutually exclusive".to_string(),
+        ));
+    }
+    if let Some(channel_id) = &body.channel_id {
+        let subscribed: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM user_channels WHERE user_id = ?1 AND channel_id = ?2)",
+            rusqlite::params![user_id, channel_id],
+            |row| row.get(0),
+        )?;
+        if !subscribed {
+            return Err(AppError::BadRequest("Channel not subscribed".to_string()));
+        }
+    }
+    if let Some(group_id) = body.group_id {
+        let owned: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM groups WHERE id = ?1 AND user_id = ?2)",
+            rusqlite::params![group_id, user_id],
+            |row| row.get(0),
+        )?;
+        if !owned {
+            return Err(AppError::BadRequest("Group not found".to_string()));
+        }
+    }
+
+    Ok(ValidMuteRule {
+        pattern,
+        is_regex,
+        channel_id: body.channel_id,
+        group_id: body.group_id,
+    })
+}
+
+/// Insert a validated mute rule, returning its id.
+pub(crate) fn insert_mute_rule(
+    conn: &Connection,
+    user_id: i64,
+    rule: &ValidMuteRule,
+) -> Result<i64, AppError> {
+    conn.execute(
+        "INSERT INTO mute_rules (user_id, pattern, is_regex, channel_id, group_id, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        rusqlite::params![
+            user_id,
+            rule.pattern,
+            rule.is_regex,
+            rule.channel_id,
+            rule.group_id,
+            crate::util::now_unix(),
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/mute-rules",
+    tag = "動画フィード",
+    summary = "ミュートルール一覧",
+    responses(
+        (status = 200, description = "ミュートルール一覧 (作成順)", body = Vec<MuteRuleItem>),
+        (status = 401, description = "未認証", body = ErrorResponse),
+    ),
+)]
+async fn get_mute_rules(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+) -> Result<Json<Value>, AppError> {
+    let rows = {
+        let conn = state.db.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {MUTE_RULE_COLUMNS} FROM mute_rules WHERE user_id = ?1 ORDER BY id"
+        ))?;
+        let rows = stmt
+            .query_map([user_id.0], mute_rule_json)?
+            .collect::<Result<Vec<_>, _>>()?;
+        rows
+    };
+    Ok(Json(Value::Array(rows)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/mute-rules",
+    tag = "動画フィード",
+    summary = "ミュートルール作成",
+    description = "タイトルがルールに一致する動画を /api/feed・/api/search・/api/rss・/api/news・ダイジェスト・通知 (通知ルール・ブラウザプッシュ・配信リマインダー) から除外する。\n\n- pattern はタイトルの部分一致 (is_regex=1 で正規表現)。大文字小文字を区別しない\n- channel_id / group_id で対象を限定 (未指定なら購読中の全チャンネル)\n- 非表示と違い視聴履歴には入らず、ルールを削除すれば再び表示される",
+    request_body(content = MuteRuleBody),
+    responses(
+        (status = 201, description = "作成されたミュートルール", body = MuteRuleItem),
+        (status = 400, description = "バリデーションエラー", body = ErrorResponse),
+        (status = 401, description = "未認証", body = ErrorResponse),
+    ),
+)]
+async fn create_mute_rule(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Json(body): Json<MuteRuleBody>,
+) -> Result<(axum::http::StatusCode, Json<Value>), AppError> {
+    let conn = state.db.lock().unwrap();
+    let rule = validate_mute_rule(&conn, user_id.0, body)?;
+    let id = insert_mute_rule(&conn, user_id.0, &rule)?;
+    let created = load_mute_rule(&conn, user_id.0, id)?;
+    super::unread::invalidate(&state.cache, user_id.0);
+    Ok((axum::http::StatusCode::CREATED, Json(created)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/mute-rules/preview",
+    tag = "動画フィード",
+    summary = "ミュートルールのプレビュー",
+    description = "ルールを保存せずに、今のフィードからそのルールで新たに隠れる動画を返す (公開日時の降順、最大50件)。既存のミュートルールや非表示で既に隠れている動画は含まない。",
+    request_body(content = MuteRuleBody),
+    responses(
+        (status = 200, description = "隠れる動画", body = MutePreview),
+        (status = 400, description = "バリデーションエラー", body = ErrorResponse),
+        (status = 401, description = "未認証", body = ErrorResponse),
+    ),
+)]
+async fn preview_mute_rule(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Json(body): Json<MuteRuleBody>,
+) -> Result<Json<Value>, AppError> {
+    let conn = state.db.lock().unwrap();
+    let rule = validate_mute_rule(&conn, user_id.0, body)?;
+    let from_where = format!(
+        "FROM videos v
+         JOIN channels c ON v.channel_id = c.id
+         JOIN user_channels uc ON uc.channel_id = c.id AND uc.user_id = ?1
+         LEFT JOIN user_videos uv ON uv.video_id = v.id AND uv.user_id = ?1
+         WHERE {visible}
+           AND (?2 IS NULL OR v.channel_id = ?2)
+           AND (?3 IS NULL OR v.channel_id IN (SELECT channel_id FROM channel_groups WHERE group_id = ?3))
+           AND title_matches(?4, ?5, v.title)",
+        visible = crate::visibility::VISIBLE,
+    );
+    let params = rusqlite::params![
+        user_id.0,
+        rule.channel_id,
+        rule.group_id,
+        rule.pattern,
+        rule.is_regex,
+    ];
+
+    let count: i64 = conn.query_row(&format!("SELECT COUNT(*) {from_where}"), params, |row| {
+        row.get(0)
+    })?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT v.id, v.channel_id, v.title, v.published_at,
+                v.duration, v.is_short, v.is_livestream, v.livestream_ended_at,
+                c.title as channel_title, c.thumbnail_url as channel_thumbnail
+         {from_where}
+         ORDER BY v.published_at DESC NULLS LAST, v.id DESC
+         LIMIT {PREVIEW_LIMIT}"
+    ))?;
+    let items = stmt
+        .query_map(params, |row| super::feed::video_json(row, None))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Json(json!({"count": count, "items": items})))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/mute-rules/{id}",
+    tag = "動画フィード",
+    summary = "ミュートルール更新",
+    description = "ルール全体を置き換える (未指定の項目はデフォルト値に戻る)。",
+    params(("id" = i64, Path, description = "ミュートルールID")),
+    request_body(content = MuteRuleBody),
+    responses(
+        (status = 200, description = "更新後のミュートルール", body = MuteRuleItem),
+        (status = 400, description = "バリデーションエラー", body = ErrorResponse),
+        (status = 401, description = "未認証", body = ErrorResponse),
+        (status = 404, description = "ルールが存在しない", body = ErrorResponse),
+    ),
+)]
+async fn update_mute_rule(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Path(id): Path<i64>,
+    Json(body): Json<MuteRuleBody>,
+) -> Result<Json<Value>, AppError> {
+    let conn = state.db.lock().unwrap();
+    let rule = validate_mute_rule(&conn, user_id.0, body)?;
+    let updated = conn.execute(
+        "UPDATE mute_rules SET pattern = ?1, is_regex = ?2, channel_id = ?3, group_id = ?4
+         WHERE id = ?5 AND user_id = ?6",
+        rusqlite::params![
+            rule.pattern,
+            rule.is_regex,
+            rule.channel_id,
+            rule.group_id,
+            id,
+            user_id.0,
+        ],
+    )?;
+    if updated == 0 {
+        return Err(AppError::NotFound("Mute rule not found".to_string()));
+    }
+    super::unread::invalidate(&state.cache, user_id.0);
+    Ok(Json(load_mute_rule(&conn, user_id.0, id)?))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/mute-rules/{id}",
+    tag = "動画フィード",
+    summary = "ミュートルール削除",
+    params(("id" = i64, Path, description = "ミュートルールID")),
+    responses(
+        (status = 200, description = "成功", body = OkResponse),
+        (status = 401, description = "未認証", body = ErrorResponse),
+        (status = 404, description = "ルールが存在しない", body = ErrorResponse),
+    ),
+)]
+async fn delete_mute_rule(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Path(id): Path<i64>,
+) -> Result<Json<Value>, AppError> {
+    let deleted = {
+        let conn = state.db.lock().unwrap();
+        conn.execute(
+            "DELETE FROM mute_rules WHERE id = ?1 AND user_id = ?2",
+            rusqlite::params![id, user_id.0],
+        )?
+    };
+    if deleted == 0 {
+        return Err(AppError::NotFound("Mute rule not found".to_string()));
+    }
+    super::unread::invalidate(&state.cache, user_id.0);
+    Ok(Json(json!({"ok": true})))
+}
+
+#[cfg(test)]
+mod tests {
+    // Mute Rule API Spec
+    //
+    // CRUD over the caller's own mute rules. pattern is required; regex
+    // patterns must compile; channel_id / group_id are exclusive and must
+    // belong to the caller. Muted videos disappear from /api/feed, /api/rss
+    // and /api/news alike; preview lists what a rule would newly hide.
+    // The acting user is the dev-bypass first DB user (user 1).
+
+    use super::routes;
+    use crate::middleware::auth_middleware;
+    use crate::state::AppState;
+    use axum::body::to_bytes;
+    use axum::http::{Request, StatusCode};
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+
+    fn setup_state() -> AppState {
+        let state = AppState::test();
+        state
+            .db
+            .lock()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO users (email, rss_token) VALUES ('a@example.com', 'tok-a');
+                 INSERT INTO users (email) VALUES ('b@example.com');
+                 INSERT INTO channels (id, title) VALUES ('UC1', 'Ch1'), ('UC2', 'Ch2');
+                 INSERT INTO user_channels (user_id, channel_id, is_favorite)
+                   VALUES (1, 'UC1', 1), (1, 'UC2', 1);
+                 INSERT INTO groups (user_id, name, sort_order, created_at) VALUES (1, 'mine', 0, 0);
+                 INSERT INTO groups (user_id, name, sort_order, created_at) VALUES (2, 'theirs', 0, 0);
+                 INSERT INTO channel_groups (channel_id, group_id) VALUES ('UC2', 1);
+                 INSERT INTO videos (id, channel_id, title, published_at) VALUES
+                   ('v1', 'UC1', 'Game SPOILERS inside', 1700000400),
+                   ('v2', 'UC2', 'spoiler-free review', 1700000300),
+                   ('v3', 'UC1', '[Clip] best moments', 1700000200),
+                   ('v4', 'UC2', 'Morning stream', 1700000100);",
+            )
+            .unwrap();
+        state
+    }
+
+    async fn call_raw(
+        state: &AppState,
+        method: &str,
+        uri: &str,
+        body: Value,
+    ) -> (StatusCode, String) {
+        let resp = axum::Router::new()
+            .merge(routes())
+            .merge(super::super::feed::routes())
+            .merge(super::super::news::routes())
+            .layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                auth_middleware,
+            ))
+            .merge(super::super::rss::routes())
+            .with_state(state.clone())
+            .oneshot(
+                Request::builder()
+                    .method(method)
+                    .uri(uri)
+                    .header("content-type", "application/json")
+                    .body(axum::body::Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let status = resp.status();
+        let bytes = to_bytes(resp.into_body(), 1024 * 1024).await.unwrap();
+        (status, String::from_utf8(bytes.to_vec()).unwrap())
+    }
+
+    async fn call(state: &AppState, method: &str, uri: &str, body: Value) -> (StatusCode, Value) {
+        let (status, body) = call_raw(state, method, uri, body).await;
+        (status, serde_json::from_str(&body).unwrap_or(Value::Null))
+    }
+
+    fn ids(items: &Value) -> Vec<&str> {
+        items
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|item| item["id"].as_str().unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn create_list_update_delete_round_trip() {
+        let state = setup_state();
+        let (status, created) = call(
+            &state,
+            "POST",
+            "/api/mute-rules",
+            json!({"pattern": " spoiler ", "channel_id": "UC1"}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(created["pattern"], "spoiler");
+        assert_eq!(created["is_regex"], 0);
+        assert_eq!(created["channel_id"], "UC1");
+        let id = created["id"].as_i64().unwrap();
+
+        let (_, list) = call(&state, "GET", "/api/mute-rules", Value::Null).await;
+        assert_eq!(list.as_array().unwrap().len(), 1);
+
+        let (status, updated) = call(
+            &state,
+            "PUT",
+            &format!("/api/mute-rules/{id}"),
+            json!({"pattern": "^\\[clip\\]", "is_regex": 1, "group_id": 1}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(updated["is_regex"], 1);
+        assert_eq!(updated["channel_id"], Value::Null);
+        assert_eq!(updated["group_id"], 1);
+
+        let (status, _) = call(
+            &state,
+            "DELETE",
+            &format!("/api/mute-rules/{id}"),
+            Value::Null,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = call(
+            &state,
+            "DELETE",
+            &format!("/api/mute-rules/{id}"),
+            Value::Null,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn invalid_bodies_are_rejected_with_400() {
+        let state = setup_state();
+        for (body, error) in [
+            (json!({}), "pattern is required"),
+            (json!({"pattern": "  "}), "pattern is required"),
+            (json!({"pattern": "(", "is_regex": 1}), "Invalid regex"),
+            (
+                json!({"pattern": "a", "is_regex": 2}),
+                "is_regex must be 0 or 1",
+            ),
+            (
+                json!({"pattern": "a", "channel_id": "UC1", "group_id": 1}),
+                "channel_id and group_id are mutually exclusive",
+            ),
+            (
+                json!({"pattern": "a", "channel_id": "UCother"}),
+                "Channel not subscribed",
+            ),
+            (json!({"pattern": "a", "group_id": 2}), "Group not found"),
+        ] {
+            let (status, resp) = call(&state, "POST", "/api/mute-rules", body.clone()).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+            assert_eq!(resp["error"], error, "{body}");
+        }
+    }
+
+    #[tokio::test]
+    async fn muted_videos_are_dropped_from_feed_rss_and_news() {
+        let state = setup_state();
+        call(
+            &state,
+            "POST",
+            "/api/mute-rules",
+            json!({"pattern": "spoiler"}),
+        )
+        .await;
+        call(
+            &state,
+            "POST",
+            "/api/mute-rules",
+            json!({"pattern": "^\\[clip\\]", "is_regex": 1, "channel_id": "UC1"}),
+        )
+        .await;
+
+        let (_, feed) = call(&state, "GET", "/api/feed", Value::Null).await;
+        assert_eq!(ids(&feed), ["v4"]);
+
+        let (_, news) = call(&state, "GET", "/api/news", Value::Null).await;
+        assert_eq!(ids(&news["items"]), ["v4"]);
+
+        let (status, rss) = call_raw(&state, "GET", "/api/rss?token=tok-a", Value::Null).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(rss.contains("<guid isPermaLink=\"false\">v4</guid>"));
+        for muted in ["v1", "v2", "v3"] {
+            assert!(!rss.contains(&format!(">{muted}</guid>")), "{muted} in RSS");
+        }
+    }
+
+    #[tokio::test]
+    async fn preview_lists_what_a_rule_would_newly_hide() {
+        let state = setup_state();
+        let (status, preview) = call(
+            &state,
+            "POST",
+            "/api/mute-rules/preview",
+            json!({"pattern": "SPOILER"}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(preview["count"], 2);
+        assert_eq!(ids(&preview["items"]), ["v1", "v2"]);
+        assert_eq!(preview["items"][0]["channel_title"], "Ch1");
+
+        let (_, preview) = call(
+            &state,
+            "POST",
+            "/api/mute-rules/preview",
+            json!({"pattern": "spoiler", "group_id": 1}),
+        )
+        .await;
+        assert_eq!(ids(&preview["items"]), ["v2"]);
+
+        // Already muted or hidden videos are not listed again; nothing is saved.
+        call(
+            &state,
+            "POST",
+            "/api/mute-rules",
+            json!({"pattern": "review"}),
+        )
+        .await;
+        state
+            .db
+            .lock()
+            .unwrap()
+            .execute(
+                "INSERT INTO user_videos (user_id, video_id, is_hidden) VALUES (1, 'v1', 1)",
+                [],
+            )
+            .unwrap();
+        let (_, preview) = call(
+            &state,
+            "POST",
+            "/api/mute-rules/preview",
+            json!({"pattern": "spoiler"}),
+        )
+        .await;
+        assert_eq!(preview["count"], 0);
+        let (_, list) = call(&state, "GET", "/api/mute-rules", Value::Null).await;
+        assert_eq!(list.as_array().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn rules_of_other_users_are_invisible() {
+        let state = setup_state();
+        state
+            .db
+            .lock()
+            .unwrap()
+            .execute(
+                "INSERT INTO mute_rules (user_id, pattern) VALUES (2, 'morning')",
+                [],
+            )
+            .unwrap();
+        let (_, list) = call(&state, "GET", "/api/mute-rules", Value::Null).await;
+        assert_eq!(list, json!([]));
+        let (status, _) = call(&state, "PUT", "/api/mute-rules/1", json!({"pattern": "x"})).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (_, feed) = call(&state, "GET", "/api/feed", Value::Null).await;
+        assert_eq!(ids(&feed).len(), 4);
+    }
+}
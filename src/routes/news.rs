@@ -39,20 +39,18 @@ async fn get_news(
 ) -> Result<impl IntoResponse, AppError> {
     let items = {
         let conn = state.db.lock().unwrap();
-        let mut stmt = conn.prepare(
+        let mut stmt = conn.prepare(&format!(
             "SELECT v.id, v.title, v.published_at, c.title as channel_title
              FROM videos v
              JOIN channels c ON v.channel_id = c.id
              JOIN user_channels uc ON uc.channel_id = c.id AND uc.user_id = ?1
              LEFT JOIN user_videos uv ON uv.video_id = v.id AND uv.user_id = ?1
              WHERE uc.is_favorite = 1
-               AND COALESCE(uv.is_hidden, 0) = 0
-               AND v.is_members_only = 0
-               AND (v.is_livestream = 0 OR uc.show_livestreams = 1)
-               AND (v.is_short = 0 OR uc.hide_shorts = 0)
+               AND {visible}
              ORDER BY v.published_at DESC
              LIMIT 50",
-        )?;
+            visible = crate::visibility::VISIBLE,
+        ))?;
         let items = stmt
             .query_map(rusqlite::params![user_id], |row| {
                 Ok(NewsItem {
@@ -75,7 +73,7 @@ async fn get_news(
     ))
 }
 
-fn resolve_base_url(headers: &HeaderMap, config: &Config) -> String {
+pub(crate) fn resolve_base_url(headers: &HeaderMap, config: &Config) -> String {
     if let Some(url) = &config.public_base_url {
         return url.clone();
     }
@@ -0,0 +1,543 @@
+use crate::error::AppError;
+use crate::middleware::UserId;
+use crate::notify::rules::{format_hhmm, parse_hhmm};
+use crate::openapi::*;
+use crate::state::AppState;
+use axum::extract::{Extension, Path, State};
+use axum::routing::{get, put};
+use axum::{Json, Router};
+use rusqlite::Connection;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/notification-rules", get(get_rules).post(create_rule))
+        .route(
+            "/api/notification-rules/{id}",
+            put(update_rule).delete(delete_rule),
+        )
+}
+
+const RULE_COLUMNS: &str = "id, name, target_url, channel_id, group_id, keyword, is_regex,
+     exclude_shorts, exclude_livestreams, quiet_start, quiet_end, is_enabled, created_at";
+
+fn rule_json(row: &rusqlite::Row) -> rusqlite::Result<Value> {
+    Ok(json!({
+        "id": row.get::<_, i64>(0)?,
+        "name": row.get::<_, String>(1)?,
+        "target_url": row.get::<_, String>(2)?,
+        "channel_id": row.get::<_, Option<String>>(3)?,
+        "group_id": row.get::<_, Option<i64>>(4)?,
+        "keyword": row.get::<_, Option<String>>(5)?,
+        "is_regex": row.get::<_, i64>(6)?,
+        "exclude_shorts": row.get::<_, i64>(7)?,
+        "exclude_livestreams": row.get::<_, i64>(8)?,
+        "quiet_start": row.get::<_, Option<i64>>(9)?.map(format_hhmm),
+        "quiet_end": row.get::<_, Option<i64>>(10)?.map(format_hhmm),
+        "is_enabled": row.get::<_, i64>(11)?,
+        "created_at": crate::util::row_timestamp_to_rfc3339(row, 12)?,
+    }))
+}
+
+fn load_rule(conn: &Connection, user_id: i64, id: i64) -> Result<Value, AppError> {
+    conn.query_row(
+        &format!("SELECT {RULE_COLUMNS} FROM notification_rules WHERE id = ?1 AND user_id = ?2"),
+        rusqlite::params![id, user_id],
+        rule_json,
+    )
+    .map_err(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => {
+            AppError::NotFound("Notification rule not found".to_string())
+        }
+        e => e.into(),
+    })
+}
+
+#[derive(Default, Deserialize, utoipa::ToSchema)]
+pub(crate) struct RuleBody {
+    /// ルール名 (任意, 50文字以内)
+    pub(crate) name: Option<String>,
+    /// 通知先 URL (NOTIFIER_URLS と同じ形式: ntfy+https://…, discord+https://…, smtp://… など)
+    pub(crate) target_url: Option<String>,
+    /// 対象チャンネルID (group_id と排他。両方未指定なら購読中の全チャンネル)
+    pub(crate) channel_id: Option<String>,
+    /// 対象グループID (channel_id と排他)
+    pub(crate) group_id: Option<i64>,
+    /// タイトルのキーワード (大文字小文字を区別しない。未指定なら全動画)
+    pub(crate) keyword: Option<String>,
+    /// keyword を正規表現として扱う (0/1)
+    pub(crate) is_regex: Option<i64>,
+    /// Shorts を除外 (0/1)
+    pub(crate) exclude_shorts: Option<i64>,
+    /// ライブ配信を除外 (0/1)
+    pub(crate) exclude_livestreams: Option<i64>,
+    /// 静音時間の開始 (HH:MM, ユーザーのタイムゾーン)
+    pub(crate) quiet_start: Option<String>,
+    /// 静音時間の終了 (HH:MM, ユーザーのタイムゾーン)
+    pub(crate) quiet_end: Option<String>,
+    /// 有効 (0/1, デフォルト: 1)
+    pub(crate) is_enabled: Option<i64>,
+}
+
+pub(crate) struct ValidRule {
+    name: String,
+    target_url: String,
+    channel_id: Option<String>,
+    group_id: Option<i64>,
+    keyword: Option<String>,
+    is_regex: i64,
+    exclude_shorts: i64,
+    exclude_livestreams: i64,
+    quiet_start: Option<u32>,
+    quiet_end: Option<u32>,
+    is_enabled: i64,
+}
+
+/// Validate a rule body. Scope targets must belong to the caller so a rule
+/// can never observe another user's channels or groups.
+pub(crate) fn validate_rule(
+    conn: &Connection,
+    user_id: i64,
+    body: RuleBody,
+) -> Result<ValidRule, AppError> {
+    let name = body.name.unwrap_or_default();
+    if name.chars().count() > 50 {
+        return Err(AppError::BadRequest(
+            "Name must be 50 characters or less".to_string(),
+        ));
+    }
+
+    let target_url = body
+        .target_url
+        .map(|u| u.trim().to_string())
+        .filter(|u| !u.is_empty())
+        .ok_or_else(|| AppError::BadRequest("target_url is required".to_string()))?;
+    crate::notify::parse_notifier_url(&target_url)
+        .map_err(|e| AppError::BadRequest(e.to_string()))?;
+
+    if body.channel_id.is_some() && body.group_id.is_some() {
+        return Err(AppError::BadRequest(
+            "channel_id and group_id are mutually exclusive".to_string(),
+        ));
+    }
+    if let Some(channel_id) = &body.channel_id {
+        let subscribed: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM user_channels WHERE user_id = ?1 AND channel_id = ?2)",
+            rusqlite::params![user_id, channel_id],
+            |row| row.get(0),
+        )?;
+        if !subscribed {
+            return Err(AppError::BadRequest("Channel not subscribed".to_string()));
+        }
+    }
+    if let Some(group_id) = body.group_id {
+        let owned: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM groups WHERE id = ?1 AND user_id = ?2)",
+            rusqlite::params![group_id, user_id],
+            |row| row.get(0),
+        )?;
+        if !owned {
+            return Err(AppError::BadRequest("Group not found".to_string()));
+        }
+    }
+
+    for (val, name) in [
+        (body.is_regex, "is_regex"),
+        (body.exclude_shorts, "exclude_shorts"),
+        (body.exclude_livestreams, "exclude_livestreams"),
+        (body.is_enabled, "is_enabled"),
+    ] {
+        if let Some(v) = val {
+            if v != 0 && v != 1 {
+                return Err(AppError::BadRequest(format!("{} must be 0 or 1", name)));
+            }
+        }
+    }
+    let is_regex = body.is_regex.unwrap_or(0);
+
+    let keyword = body
+        .keyword
+        .map(|k| k.trim().to_string())
+        .filter(|k| !k.is_empty());
+    if let Some(keyword) = &keyword {
+        if keyword.chars().count() > 200 {
+            return Err(AppError::BadRequest(
+                "keyword must be 200 characters or less".to_string(),
+            ));
+        }
+        if is_regex == 1 && !crate::visibility::is_valid_regex(keyword) {
+            return Err(AppError::BadRequest("Invalid regex".to_string()));
+        }
+    }
+
+    let parse = |value: Option<String>, name: &str| -> Result<Option<u32>, AppError> {
+        value
+            .map(|v| {
+                parse_hhmm(&v).ok_or_else(|| AppError::BadRequest(format!("{name} must be HH:MM")))
+            })
+            .transpose()
+    };
+    let quiet_start = parse(body.quiet_start, "quiet_start")?;
+    let quiet_end = parse(body.quiet_end, "quiet_end")?;
+    if quiet_start.is_some() != quiet_end.is_some() {
+        return Err(AppError::BadRequest(
+            "quiet_start and quiet_end must be set together".to_string(),
+        ));
+    }
+
+    Ok(ValidRule {
+        name,
+        target_url,
+        channel_id: body.channel_id,
+        group_id: body.group_id,
+        keyword,
+        is_regex,
+        exclude_shorts: body.exclude_shorts.unwrap_or(0),
+        exclude_livestreams: body.exclude_livestreams.unwrap_or(0),
+        quiet_start,
+        quiet_end,
+        is_enabled: body.is_enabled.unwrap_or(1),
+    })
+}
+
+/// Insert a validated rule, returning its id.
+pub(crate) fn insert_rule(
+    conn: &Connection,
+    user_id: i64,
+    rule: &ValidRule,
+) -> Result<i64, AppError> {
+    conn.execute(
+        "INSERT INTO notification_rules
+         (user_id, name, target_url, channel_id, group_id, keyword, is_regex, exclude_shorts,
+          exclude_livestreams, quiet_start, quiet_end, is_enabled, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+        rusqlite::params![
+            user_id,
+            rule.name,
+            rule.target_url,
+            rule.channel_id,
+            rule.group_id,
+            rule.keyword,
+            rule.is_regex,
+            rule.exclude_shorts,
+            rule.exclude_livestreams,
+            rule.quiet_start,
+            rule.quiet_end,
+            rule.is_enabled,
+            crate::util::now_unix(),
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/notification-rules",
+    tag = "通知",
+    summary = "通知ルール一覧",
+    responses(
+        (status = 200, description = "通知ルール一覧 (作成順)", body = Vec<NotificationRuleItem>),
+        (status = 401, description = "未認証", body = ErrorResponse),
+    ),
+)]
+async fn get_rules(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+) -> Result<Json<Value>, AppError> {
+    let rows = {
+        let conn = state.db.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {RULE_COLUMNS} FROM notification_rules WHERE user_id = ?1 ORDER BY id"
+        ))?;
+        let rows = stmt
+            .query_map([user_id.0], rule_json)?
+            .collect::<Result<Vec<_>, _>>()?;
+        rows
+    };
+    Ok(Json(Value::Array(rows)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/notification-rules",
+    tag = "通知",
+    summary = "通知ルール作成",
+    description = "新着動画 (WebSub プッシュ) がルールに一致したとき、target_url に通知する。\n\n- channel_id / group_id で対象を限定 (未指定なら購読中の全チャンネル)\n- keyword はタイトルの部分一致 (is_regex=1 で正規表現)\n- チャンネルの hide_shorts / show_livestreams 設定も適用される\n- 静音時間中の通知は溜めておき、終了時にまとめて送信",
+    request_body(content = RuleBody),
+    responses(
+        (status = 201, description = "作成された通知ルール", body = NotificationRuleItem),
+        (status = 400, description = "バリデーションエラー", body = ErrorResponse),
+        (status = 401, description = "未認証", body = ErrorResponse),
+    ),
+)]
+async fn create_rule(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Json(body): Json<RuleBody>,
+) -> Result<(axum::http::StatusCode, Json<Value>), AppError> {
+    if let Some(target_url) = &body.target_url {
+        crate::outbound::check(target_url, &state.config)
+            .await
+            .map_err(AppError::BadRequest)?;
+    }
+    let conn = state.db.lock().unwrap();
+    let rule = validate_rule(&conn, user_id.0, body)?;
+    let id = insert_rule(&conn, user_id.0, &rule)?;
+    let created = load_rule(&conn, user_id.0, id)?;
+    Ok((axum::http::StatusCode::CREATED, Json(created)))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/notification-rules/{id}",
+    tag = "通知",
+    summary = "通知ルール更新",
+    description = "ルール全体を置き換える (未指定の項目はデフォルト値に戻る)。",
+    params(("id" = i64, Path, description = "通知ルールID")),
+    request_body(content = RuleBody),
+    responses(
+        (status = 200, description = "更新後の通知ルール", body = NotificationRuleItem),
+        (status = 400, description = "バリデーションエラー", body = ErrorResponse),
+        (status = 401, description = "未認証", body = ErrorResponse),
+        (status = 404, description = "ルールが存在しない", body = ErrorResponse),
+    ),
+)]
+async fn update_rule(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Path(id): Path<i64>,
+    Json(body): Json<RuleBody>,
+) -> Result<Json<Value>, AppError> {
+    if let Some(target_url) = &body.target_url {
+        crate::outbound::check(target_url, &state.config)
+            .await
+            .map_err(AppError::BadRequest)?;
+    }
+    let conn = state.db.lock().unwrap();
+    let rule = validate_rule(&conn, user_id.0, body)?;
+    let updated = conn.execute(
+        "UPDATE notification_rules
+         SET name = ?1, target_url = ?2, channel_id = ?3, group_id = ?4, keyword = ?5,
+             is_regex = ?6, exclude_shorts = ?7, exclude_livestreams = ?8,
+             quiet_start = ?9, quiet_end = ?10, is_enabled = ?11
+         WHERE id = ?12 AND user_id = ?13",
+        rusqlite::params![
+            rule.name,
+            rule.target_url,
+            rule.channel_id,
+            rule.group_id,
+            rule.keyword,
+            rule.is_regex,
+            rule.exclude_shorts,
+            rule.exclude_livestreams,
+            rule.quiet_start,
+            rule.quiet_end,
+            rule.is_enabled,
+            id,
+            user_id.0,
+        ],
+    )?;
+    if updated == 0 {
+        return Err(AppError::NotFound(
+            "Notification rule not found".to_string(),
+        ));
+    }
+    Ok(Json(load_rule(&conn, user_id.0, id)?))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/notification-rules/{id}",
+    tag = "通知",
+    summary = "通知ルール削除",
+    description = "静音時間中に溜まっている未送信の通知も破棄される。",
+    params(("id" = i64, Path, description = "通知ルールID")),
+    responses(
+        (status = 200, description = "成功", body = OkResponse),
+        (status = 401, description = "未認証", body = ErrorResponse),
+        (status = 404, description = "ルールが存在しない", body = ErrorResponse),
+    ),
+)]
+async fn delete_rule(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Path(id): Path<i64>,
+) -> Result<Json<Value>, AppError> {
+    let deleted = {
+        let conn = state.db.lock().unwrap();
+        conn.execute(
+            "DELETE FROM notification_rules WHERE id = ?1 AND user_id = ?2",
+            rusqlite::params![id, user_id.0],
+        )?
+    };
+    if deleted == 0 {
+        return Err(AppError::NotFound(
+            "Notification rule not found".to_string(),
+        ));
+    }
+    Ok(Json(json!({"ok": true})))
+}
+
+#[cfg(test)]
+mod tests {
+    // Notification Rule API Spec
+    //
+    // CRUD over the caller's own rules. target_url must be a valid notifier URL;
+    // channel_id / group_id are exclusive and must belong to the caller; regex
+    // keywords must compile; quiet_start / quiet_end are HH:MM and set together.
+    // The acting user is the dev-bypass first DB user (user 1).
+
+    use super::routes;
+    use crate::middleware::auth_middleware;
+    use crate::state::AppState;
+    use axum::body::to_bytes;
+    use axum::http::{Request, StatusCode};
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+
+    fn setup_state() -> AppState {
+        let state = AppState::test();
+        {
+            let conn = state.db.lock().unwrap();
+            conn.execute("INSERT INTO users (email) VALUES ('a@example.com')", [])
+                .unwrap();
+            conn.execute("INSERT INTO users (email) VALUES ('b@example.com')", [])
+                .unwrap();
+            conn.execute("INSERT INTO channels (id, title) VALUES ('UC1', 'Ch1')", [])
+                .unwrap();
+            conn.execute("INSERT INTO channels (id, title) VALUES ('UC2', 'Ch2')", [])
+                .unwrap();
+            conn.execute(
+                "INSERT INTO user_channels (user_id, channel_id) VALUES (1, 'UC1')",
+                [],
+            )
+            .unwrap();
+            conn.execute(
+                "INSERT INTO groups (user_id, name, sort_order, created_at) VALUES (2, 'theirs', 0, 0)",
+                [],
+            )
+            .unwrap();
+        }
+        state
+    }
+
+    async fn call(state: &AppState, method: &str, uri: &str, body: Value) -> (StatusCode, Value) {
+        let resp = axum::Router::new()
+            .merge(routes())
+            .layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                auth_middleware,
+            ))
+            .with_state(state.clone())
+            .oneshot(
+                Request::builder()
+                    .method(method)
+                    .uri(uri)
+                    .header("content-type", "application/json")
+                    .body(axum::body::Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let status = resp.status();
+        let bytes = to_bytes(resp.into_body(), 1024 * 1024).await.unwrap();
+        (
+            status,
+            serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+        )
+    }
+
+    #[tokio::test]
+    async fn create_list_update_delete_round_trip() {
+        let state = setup_state();
+        let (status, created) = call(
+            &state,
+            "POST",
+            "/api/notification-rules",
+            json!({
+                "name": "night",
+                "target_url": "ntfy+https://ntfy.sh/mine",
+                "channel_id": "UC1",
+                "keyword": "live",
+                "quiet_start": "23:00",
+                "quiet_end": "07:30",
+            }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(created["channel_id"], "UC1");
+        assert_eq!(created["quiet_start"], "23:00");
+        assert_eq!(created["quiet_end"], "07:30");
+        assert_eq!(created["is_enabled"], 1);
+        let id = created["id"].as_i64().unwrap();
+
+        let (_, list) = call(&state, "GET", "/api/notification-rules", Value::Null).await;
+        assert_eq!(list.as_array().unwrap().len(), 1);
+
+        let (status, updated) = call(
+            &state,
+            "PUT",
+            &format!("/api/notification-rules/{id}"),
+            json!({"target_url": "ntfy+https://ntfy.sh/mine", "is_enabled": 0}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(updated["is_enabled"], 0);
+        assert_eq!(updated["channel_id"], Value::Null, "PUT replaces the rule");
+        assert_eq!(updated["quiet_start"], Value::Null);
+
+        let uri = format!("/api/notification-rules/{id}");
+        let (status, _) = call(&state, "DELETE", &uri, Value::Null).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = call(&state, "DELETE", &uri, Value::Null).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn invalid_bodies_are_rejected_with_400() {
+        let state = setup_state();
+        let target = "webhook+https://example.com/hook";
+        for body in [
+            json!({}),
+            json!({"target_url": "telegram+https://example.com"}),
+            json!({"target_url": target, "channel_id": "UC2"}),
+            json!({"target_url": target, "group_id": 1}),
+            json!({"target_url": target, "channel_id": "UC1", "group_id": 1}),
+            json!({"target_url": target, "keyword": "(", "is_regex": 1}),
+            json!({"target_url": target, "is_regex": 2}),
+            json!({"target_url": target, "quiet_start": "23:00"}),
+            json!({"target_url": target, "quiet_start": "25:00", "quiet_end": "07:00"}),
+            json!({"target_url": target, "name": "あ".repeat(51)}),
+        ] {
+            let (status, _) = call(&state, "POST", "/api/notification-rules", body.clone()).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{body} must be rejected");
+        }
+    }
+
+    #[tokio::test]
+    async fn rules_of_other_users_are_invisible() {
+        let state = setup_state();
+        {
+            let conn = state.db.lock().unwrap();
+            conn.execute(
+                "INSERT INTO notification_rules (user_id, name, target_url, created_at)
+                 VALUES (2, '', 'ntfy+https://ntfy.sh/b', 0)",
+                [],
+            )
+            .unwrap();
+        }
+        let (_, list) = call(&state, "GET", "/api/notification-rules", Value::Null).await;
+        assert_eq!(list, json!([]));
+        let (status, _) = call(
+            &state,
+            "PUT",
+            "/api/notification-rules/1",
+            json!({"target_url": "ntfy+https://ntfy.sh/a"}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = call(&state, "DELETE", "/api/notification-rules/1", Value::Null).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,284 @@
+use crate::error::AppError;
+use crate::middleware::{require_master, UserId};
+use crate::notify::outbox;
+use crate::openapi::*;
+use crate::state::AppState;
+use axum::extract::{Extension, Path, Query, State};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use rusqlite::{Connection, OptionalExtension};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/admin/notifications", get(get_notifications))
+        .route(
+            "/api/admin/notifications/{id}/resend",
+            post(resend_notification),
+        )
+}
+
+const STATUSES: [&str; 3] = ["pending", "sent", "failed"];
+
+const OUTBOX_COLUMNS: &str =
+    "id, target, title, status, attempts, last_error, next_attempt_at, created_at, sent_at";
+
+/// Row mapper; the stored target reference is shown as the backend it
+/// points to (see `outbox::describe_target`).
+fn outbox_json<'a>(
+    conn: &'a Connection,
+    config: &'a crate::config::Config,
+) -> impl Fn(&rusqlite::Row) -> rusqlite::Result<Value> + 'a {
+    move |row| {
+        let status: String = row.get(3)?;
+        let next_attempt_at = if status == "pending" {
+            crate::util::row_timestamp_to_rfc3339(row, 6)?
+        } else {
+            None
+        };
+        Ok(json!({
+            "id": row.get::<_, i64>(0)?,
+            "target": outbox::describe_target(conn, config, &row.get::<_, String>(1)?),
+            "title": row.get::<_, String>(2)?,
+            "status": status,
+            "attempts": row.get::<_, i64>(4)?,
+            "last_error": row.get::<_, Option<String>>(5)?,
+            "next_attempt_at": next_attempt_at,
+            "created_at": crate::util::row_timestamp_to_rfc3339(row, 7)?,
+            "sent_at": crate::util::row_timestamp_to_rfc3339(row, 8)?,
+        }))
+    }
+}
+
+fn load_entry(
+    conn: &Connection,
+    config: &crate::config::Config,
+    id: i64,
+) -> Result<Option<Value>, AppError> {
+    Ok(conn
+        .query_row(
+            &format!("SELECT {OUTBOX_COLUMNS} FROM notification_outbox WHERE id = ?1"),
+            [id],
+            outbox_json(conn, config),
+        )
+        .optional()?)
+}
+
+#[derive(Deserialize)]
+struct NotificationsQuery {
+    status: Option<String>,
+    limit: Option<i64>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/notifications",
+    tag = "管理",
+    summary = "通知配信ログ",
+    description = "通知バックエンド (NOTIFIER_URLS / DISCORD_WEBHOOK_URL / 通知ルール) への配信ログを新しい順に返す。\n\n- pending: 再送待ち (指数バックオフ 30秒〜1時間)\n- failed: 8回失敗、送信先 URL が無効、または送信先が削除済み\n- sent: 送信済み (7日で削除)\n\nログには送信先 URL ではなく参照 (`instance:…` / `rule:<id>` / `reminder:<user_id>`) のみを保存し、送信時に URL を引き直す。表示は URL のトークン等を隠した `scheme://host` で、削除済みの送信先は参照のまま表示する。master ユーザーのみ。",
+    params(
+        ("status" = Option<String>, Query, description = "pending / sent / failed (デフォルト: failed)"),
+        ("limit" = Option<i64>, Query, description = "取得件数 (デフォルト: 50, 最大: 200)"),
+    ),
+    responses(
+        (status = 200, description = "配信ログ", body = Vec<OutboxItem>),
+        (status = 400, description = "不正な status", body = ErrorResponse),
+        (status = 401, description = "未認証", body = ErrorResponse),
+        (status = 403, description = "master 以外", body = ErrorResponse),
+    ),
+)]
+async fn get_notifications(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Query(query): Query<NotificationsQuery>,
+) -> Result<Json<Value>, AppError> {
+    let status = query.status.unwrap_or_else(|| "failed".to_string());
+    if !STATUSES.contains(&status.as_str()) {
+        return Err(AppError::BadRequest(
+            "status must be pending, sent or failed".to_string(),
+        ));
+    }
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+
+    let conn = state.db.lock().unwrap();
+    require_master(&conn, user_id)?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {OUTBOX_COLUMNS} FROM notification_outbox WHERE status = ?1 ORDER BY id DESC LIMIT ?2"
+    ))?;
+    let rows = stmt
+        .query_map(
+            rusqlite::params![status, limit],
+            outbox_json(&conn, &state.config),
+        )?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Json(Value::Array(rows)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/notifications/{id}/resend",
+    tag = "管理",
+    summary = "失敗した通知の再送",
+    description = "failed の配信を即座に再送する。再送に失敗した場合は再び pending となり、通常の再送スケジュール (最大8回) に戻る。master ユーザーのみ。",
+    params(("id" = i64, Path, description = "配信ID")),
+    responses(
+        (status = 200, description = "再送後の配信", body = OutboxItem),
+        (status = 401, description = "未認証", body = ErrorResponse),
+        (status = 403, description = "master 以外", body = ErrorResponse),
+        (status = 404, description = "failed の配信が存在しない", body = ErrorResponse),
+    ),
+)]
+async fn resend_notification(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Path(id): Path<i64>,
+) -> Result<Json<Value>, AppError> {
+    {
+        let conn = state.db.lock().unwrap();
+        require_master(&conn, user_id)?;
+    }
+    if !outbox::resend(&state, id).await {
+        return Err(AppError::NotFound(
+            "Failed notification not found".to_string(),
+        ));
+    }
+    let conn = state.db.lock().unwrap();
+    load_entry(&conn, &state.config, id)?
+        .map(Json)
+        .ok_or_else(|| AppError::NotFound("Failed notification not found".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    // Notification Outbox Admin API Spec
+    //
+    // Master-only. GET lists deliveries by status (failed by default) with
+    // the target reference resolved and reduced to scheme://host; POST
+    // …/resend retries a failed delivery immediately. Other users get 403.
+
+    use super::routes;
+    use crate::middleware::auth_middleware;
+    use crate::notify::stand_in::HttpStandIn;
+    use crate::state::AppState;
+    use axum::body::to_bytes;
+    use axum::http::{Request, StatusCode};
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    fn setup_state(role: &str) -> AppState {
+        let state = AppState::test();
+        state
+            .db
+            .lock()
+            .unwrap()
+            .execute(
+                "INSERT INTO users (email, role) VALUES ('a@example.com', ?1)",
+                [role],
+            )
+            .unwrap();
+        state
+    }
+
+    fn insert_failed(state: &AppState, target: &str) {
+        let payload = serde_json::to_string(&crate::notify::Message::warning("t", "d")).unwrap();
+        state
+            .db
+            .lock()
+            .unwrap()
+            .execute(
+                "INSERT INTO notification_outbox (target, title, payload, status, attempts, last_error, next_attempt_at, created_at)
+                 VALUES (?1, 't', ?2, 'failed', 8, 'HTTP 500', 0, 1750000000)",
+                rusqlite::params![target, payload],
+            )
+            .unwrap();
+    }
+
+    async fn call(state: &AppState, method: &str, uri: &str) -> (StatusCode, Value) {
+        let resp = axum::Router::new()
+            .merge(routes())
+            .layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                auth_middleware,
+            ))
+            .with_state(state.clone())
+            .oneshot(
+                Request::builder()
+                    .method(method)
+                    .uri(uri)
+                    .body(axum::body::Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let status = resp.status();
+        let bytes = to_bytes(resp.into_body(), 1024 * 1024).await.unwrap();
+        (
+            status,
+            serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+        )
+    }
+
+    #[tokio::test]
+    async fn lists_failed_deliveries_with_redacted_targets() {
+        let mut state = setup_state("master");
+        let url = "discord+https://discord.com/api/webhooks/1/token";
+        state.config.notifier_urls = vec![url.to_string()];
+        insert_failed(&state, &crate::notify::outbox::instance_target(url));
+
+        let (status, body) = call(&state, "GET", "/api/admin/notifications").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body[0]["target"], "discord+https://discord.com");
+        assert_eq!(body[0]["last_error"], "HTTP 500");
+        assert_eq!(body[0]["next_attempt_at"], Value::Null);
+
+        let (_, sent) = call(&state, "GET", "/api/admin/notifications?status=sent").await;
+        assert_eq!(sent, Value::Array(vec![]));
+        assert_eq!(
+            call(&state, "GET", "/api/admin/notifications?status=lost")
+                .await
+                .0,
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    #[tokio::test]
+    async fn resend_delivers_a_failed_notification() {
+        let stand_in = HttpStandIn::start().await;
+        let mut state = setup_state("master");
+        state.config.notifier_urls = vec![stand_in.url("/hook")];
+        insert_failed(
+            &state,
+            &crate::notify::outbox::instance_target(&stand_in.url("/hook")),
+        );
+
+        let (status, body) = call(&state, "POST", "/api/admin/notifications/1/resend").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["status"], "sent");
+        assert_eq!(stand_in.requests().len(), 1);
+
+        // No longer failed → nothing to resend.
+        assert_eq!(
+            call(&state, "POST", "/api/admin/notifications/1/resend")
+                .await
+                .0,
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    #[tokio::test]
+    async fn members_are_forbidden() {
+        let state = setup_state("member");
+        insert_failed(&state, "rule:1");
+        assert_eq!(
+            call(&state, "GET", "/api/admin/notifications").await.0,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            call(&state, "POST", "/api/admin/notifications/1/resend")
+                .await
+                .0,
+            StatusCode::FORBIDDEN
+        );
+    }
+}
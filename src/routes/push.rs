@@ -0,0 +1,309 @@
+use crate::error::AppError;
+use crate::middleware::UserId;
+use crate::notify::web_push;
+use crate::openapi::*;
+use crate::state::AppState;
+use axum::extract::{Extension, Path, State};
+use axum::routing::{delete, get};
+use axum::{Json, Router};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/push/vapid-public-key", get(get_vapid_public_key))
+        .route(
+            "/api/push/subscriptions",
+            get(get_subscriptions).post(add_subscription),
+        )
+        .route("/api/push/subscriptions/{id}", delete(delete_subscription))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/push/vapid-public-key",
+    tag = "通知",
+    summary = "VAPID 公開鍵取得",
+    description = "ブラウザの `pushManager.subscribe({ applicationServerKey })` に渡す公開鍵 (base64url)。初回アクセス時に鍵ペアを生成して DB に保存する。",
+    responses(
+        (status = 200, description = "VAPID 公開鍵", body = VapidPublicKeyResponse),
+        (status = 401, description = "未認証", body = ErrorResponse),
+    ),
+)]
+async fn get_vapid_public_key(State(state): State<AppState>) -> Result<Json<Value>, AppError> {
+    let keys = {
+        let conn = state.db.lock().unwrap();
+        web_push::vapid_keys(&conn)?
+    };
+    Ok(Json(json!({"public_key": keys.public_key})))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/push/subscriptions",
+    tag = "通知",
+    summary = "プッシュ購読一覧",
+    responses(
+        (status = 200, description = "ログインユーザーのプッシュ購読一覧", body = Vec<PushSubscriptionItem>),
+        (status = 401, description = "未認証", body = ErrorResponse),
+    ),
+)]
+async fn get_subscriptions(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+) -> Result<Json<Value>, AppError> {
+    let rows = {
+        let conn = state.db.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, endpoint, created_at FROM push_subscriptions WHERE user_id = ?1 ORDER BY id",
+        )?;
+        let rows = stmt
+            .query_map([user_id.0], |row| {
+                Ok(json!({
+                    "id": row.get::<_, i64>(0)?,
+                    "endpoint": row.get::<_, String>(1)?,
+                    "created_at": crate::util::row_timestamp_to_rfc3339(row, 2)?,
+                }))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        rows
+    };
+    Ok(Json(Value::Array(rows)))
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub(crate) struct PushSubscriptionKeys {
+    /// ブラウザの P-256 公開鍵 (base64url)
+    p256dh: String,
+    /// 認証シークレット (base64url, 16バイト)
+    auth: String,
+}
+
+/// ブラウザの `PushSubscription.toJSON()` そのまま
+#[derive(Deserialize, utoipa::ToSchema)]
+pub(crate) struct AddSubscriptionBody {
+    /// プッシュサービスのエンドポイント URL
+    endpoint: String,
+    keys: PushSubscriptionKeys,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/push/subscriptions",
+    tag = "通知",
+    summary = "プッシュ購読登録",
+    description = "ブラウザのプッシュ購読を登録する。お気に入りチャンネルの新着動画 (WebSub プッシュ) が、暗号化 (RFC 8291) されてブラウザに届く。\n\n同じ endpoint が既に登録されていれば鍵と所有ユーザーを更新する。",
+    request_body(content = AddSubscriptionBody),
+    responses(
+        (status = 201, description = "登録されたプッシュ購読", body = PushSubscriptionItem),
+        (status = 400, description = "不正な購読情報", body = ErrorResponse),
+        (status = 401, description = "未認証", body = ErrorResponse),
+    ),
+)]
+async fn add_subscription(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Json(body): Json<AddSubscriptionBody>,
+) -> Result<(axum::http::StatusCode, Json<Value>), AppError> {
+    web_push::validate_subscription(&body.endpoint, &body.keys.p256dh, &body.keys.auth)
+        .map_err(|e| AppError::BadRequest(e.to_string()))?;
+    crate::outbound::check(&body.endpoint, &state.config)
+        .await
+        .map_err(AppError::BadRequest)?;
+
+    let conn = state.db.lock().unwrap();
+    // A browser re-subscribing (or another account signing in on the same
+    // browser) reuses the endpoint; the latest registration wins.
+    conn.execute(
+        "INSERT INTO push_subscriptions (user_id, endpoint, p256dh, auth, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT(endpoint) DO UPDATE SET
+           user_id = excluded.user_id,
+           p256dh = excluded.p256dh,
+           auth = excluded.auth",
+        rusqlite::params![
+            user_id.0,
+            body.endpoint,
+            body.keys.p256dh,
+            body.keys.auth,
+            crate::util::now_unix()
+        ],
+    )?;
+    let row = conn.query_row(
+        "SELECT id, endpoint, created_at FROM push_subscriptions WHERE endpoint = ?1",
+        [&body.endpoint],
+        |row| {
+            Ok(json!({
+                "id": row.get::<_, i64>(0)?,
+                "endpoint": row.get::<_, String>(1)?,
+                "created_at": crate::util::row_timestamp_to_rfc3339(row, 2)?,
+            }))
+        },
+    )?;
+    Ok((axum::http::StatusCode::CREATED, Json(row)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/push/subscriptions/{id}",
+    tag = "通知",
+    summary = "プッシュ購読解除",
+    params(("id" = i64, Path, description = "プッシュ購読ID")),
+    responses(
+        (status = 200, description = "成功", body = OkResponse),
+        (status = 401, description = "未認証", body = ErrorResponse),
+        (status = 404, description = "購読が存在しない", body = ErrorResponse),
+    ),
+)]
+async fn delete_subscription(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Path(id): Path<i64>,
+) -> Result<Json<Value>, AppError> {
+    let deleted = {
+        let conn = state.db.lock().unwrap();
+        conn.execute(
+            "DELETE FROM push_subscriptions WHERE id = ?1 AND user_id = ?2",
+            rusqlite::params![id, user_id.0],
+        )?
+    };
+    if deleted == 0 {
+        return Err(AppError::NotFound(
+            "Push subscription not found".to_string(),
+        ));
+    }
+    Ok(Json(json!({"ok": true})))
+}
+
+#[cfg(test)]
+mod tests {
+    // Push Subscription API Spec
+    //
+    // The SPA fetches the VAPID public key, subscribes with the browser's
+    // PushManager and POSTs PushSubscription.toJSON(). Re-registering an
+    // endpoint updates it in place; users only see and delete their own.
+
+    use super::routes;
+    use crate::middleware::auth_middleware;
+    use crate::state::AppState;
+    use axum::body::to_bytes;
+    use axum::http::{Request, StatusCode};
+    use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    use base64::Engine;
+    use p256::elliptic_curve::sec1::ToEncodedPoint;
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+
+    fn setup_state() -> AppState {
+        let state = AppState::test();
+        state
+            .db
+            .lock()
+            .unwrap()
+            .execute("INSERT INTO users (email) VALUES ('a@example.com')", [])
+            .unwrap();
+        state
+    }
+
+    async fn call(state: &AppState, method: &str, uri: &str, body: Value) -> (StatusCode, Value) {
+        let resp = axum::Router::new()
+            .merge(routes())
+            .layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                auth_middleware,
+            ))
+            .with_state(state.clone())
+            .oneshot(
+                Request::builder()
+                    .method(method)
+                    .uri(uri)
+                    .header("content-type", "application/json")
+                    .body(axum::body::Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let status = resp.status();
+        let bytes = to_bytes(resp.into_body(), 1024 * 1024).await.unwrap();
+        (
+            status,
+            serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+        )
+    }
+
+    fn subscription(endpoint: &str) -> Value {
+        let key = p256::SecretKey::random(&mut rand::rngs::OsRng);
+        json!({
+            "endpoint": endpoint,
+            "expirationTime": null,
+            "keys": {
+                "p256dh": URL_SAFE_NO_PAD.encode(key.public_key().to_encoded_point(false)),
+                "auth": URL_SAFE_NO_PAD.encode([1u8; 16]),
+            },
+        })
+    }
+
+    #[tokio::test]
+    async fn vapid_public_key_is_stable() {
+        let state = setup_state();
+        let (status, first) = call(&state, "GET", "/api/push/vapid-public-key", Value::Null).await;
+        assert_eq!(status, StatusCode::OK);
+        let (_, second) = call(&state, "GET", "/api/push/vapid-public-key", Value::Null).await;
+        assert_eq!(first["public_key"], second["public_key"]);
+    }
+
+    #[tokio::test]
+    async fn subscribe_resubscribe_list_and_delete() {
+        let state = setup_state();
+        let endpoint = "https://push.example.com/send/abc";
+        let (status, created) = call(
+            &state,
+            "POST",
+            "/api/push/subscriptions",
+            subscription(endpoint),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(created["endpoint"], endpoint);
+
+        // Same endpoint with rotated keys: still one row, same id.
+        let (_, again) = call(
+            &state,
+            "POST",
+            "/api/push/subscriptions",
+            subscription(endpoint),
+        )
+        .await;
+        assert_eq!(again["id"], created["id"]);
+        let (_, list) = call(&state, "GET", "/api/push/subscriptions", Value::Null).await;
+        assert_eq!(list.as_array().unwrap().len(), 1);
+
+        let uri = format!("/api/push/subscriptions/{}", created["id"]);
+        assert_eq!(
+            call(&state, "DELETE", &uri, Value::Null).await.0,
+            StatusCode::OK
+        );
+        assert_eq!(
+            call(&state, "DELETE", &uri, Value::Null).await.0,
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    #[tokio::test]
+    async fn invalid_subscriptions_are_rejected_with_400() {
+        let state = setup_state();
+        let mut bad_key = subscription("https://push.example.com/a");
+        bad_key["keys"]["p256dh"] = json!("AAAA");
+        let mut bad_auth = subscription("https://push.example.com/a");
+        bad_auth["keys"]["auth"] = json!("AAAA");
+        for body in [subscription("not a url"), bad_key, bad_auth] {
+            assert_eq!(
+                call(&state, "POST", "/api/push/subscriptions", body.clone())
+                    .await
+                    .0,
+                StatusCode::BAD_REQUEST,
+                "{body} must be rejected"
+            );
+        }
+    }
+}
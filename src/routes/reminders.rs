@@ -0,0 +1,237 @@
+use crate::error::AppError;
+use crate::middleware::UserId;
+use crate::notify::reminders::MINUTES_BEFORE_RANGE;
+use crate::openapi::*;
+use crate::state::AppState;
+use axum::extract::{Extension, State};
+use axum::routing::get;
+use axum::{Json, Router};
+use rusqlite::OptionalExtension;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+pub fn routes() -> Router<AppState> {
+    Router::new().route(
+        "/api/reminders",
+        get(get_reminders)
+            .put(update_reminders)
+            .delete(delete_reminders),
+    )
+}
+
+fn load_reminders(conn: &rusqlite::Connection, user_id: i64) -> Result<Value, AppError> {
+    let row = conn
+        .query_row(
+            "SELECT minutes_before, target_url FROM reminder_settings WHERE user_id = ?1",
+            [user_id],
+            |row| {
+                Ok(json!({
+                    "minutes_before": row.get::<_, i64>(0)?,
+                    "target_url": row.get::<_, String>(1)?,
+                }))
+            },
+        )
+        .optional()?;
+    Ok(row.unwrap_or(Value::Null))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/reminders",
+    tag = "通知",
+    summary = "配信リマインダー設定取得",
+    responses(
+        (status = 200, description = "配信リマインダー設定 (未設定なら null)", body = Option<ReminderSettings>),
+        (status = 401, description = "未認証", body = ErrorResponse),
+    ),
+)]
+async fn get_reminders(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+) -> Result<Json<Value>, AppError> {
+    let conn = state.db.lock().unwrap();
+    Ok(Json(load_reminders(&conn, user_id.0)?))
+}
+
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+pub(crate) struct ReminderBody {
+    /// 開始何分前に通知するか (1〜1440, デフォルト: 10)
+    pub(crate) minutes_before: Option<i64>,
+    /// 送信先 URL (NOTIFIER_URLS と同じ形式: smtp://…, ntfy+https://… など)
+    pub(crate) target_url: Option<String>,
+}
+
+/// Validate `body` and create or replace the user's reminder settings.
+pub(crate) fn save_reminders(
+    conn: &rusqlite::Connection,
+    user_id: i64,
+    body: ReminderBody,
+) -> Result<(), AppError> {
+    let minutes_before = body.minutes_before.unwrap_or(10);
+    if !MINUTES_BEFORE_RANGE.contains(&minutes_before) {
+        return Err(AppError::BadRequest(
+            "minutes_before must be between 1 and 1440".to_string(),
+        ));
+    }
+    let target_url = body
+        .target_url
+        .map(|u| u.trim().to_string())
+        .filter(|u| !u.is_empty())
+        .ok_or_else(|| AppError::BadRequest("target_url is required".to_string()))?;
+    crate::notify::parse_notifier_url(&target_url)
+        .map_err(|e| AppError::BadRequest(e.to_string()))?;
+
+    conn.execute(
+        "INSERT INTO reminder_settings (user_id, minutes_before, target_url, created_at)
+         VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(user_id) DO UPDATE SET
+           minutes_before = excluded.minutes_before,
+           target_url = excluded.target_url",
+        rusqlite::params![user_id, minutes_before, target_url, crate::util::now_unix()],
+    )?;
+    Ok(())
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/reminders",
+    tag = "通知",
+    summary = "配信リマインダー設定",
+    description = "ライブ配信・プレミア公開の開始予定時刻 (liveStreamingDetails.scheduledStartTime) の N 分前と、配信開始を検知した時に通知する。\n\n- 対象はライブ配信表示 (show_livestreams=1) にしているチャンネルの、非表示にしていない動画\n- 開始予定時刻を過ぎた配信は開始を検知するまで5分ごとに再確認する (YOUTUBE_API_KEY が必要)\n- 各動画につき予告・開始それぞれ1回だけ送信する",
+    request_body(content = ReminderBody),
+    responses(
+        (status = 200, description = "更新後の配信リマインダー設定", body = ReminderSettings),
+        (status = 400, description = "バリデーションエラー", body = ErrorResponse),
+        (status = 401, description = "未認証", body = ErrorResponse),
+    ),
+)]
+async fn update_reminders(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Json(body): Json<ReminderBody>,
+) -> Result<Json<Value>, AppError> {
+    if let Some(target_url) = &body.target_url {
+        crate::outbound::check(target_url, &state.config)
+            .await
+            .map_err(AppError::BadRequest)?;
+    }
+    let conn = state.db.lock().unwrap();
+    save_reminders(&conn, user_id.0, body)?;
+    Ok(Json(load_reminders(&conn, user_id.0)?))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/reminders",
+    tag = "通知",
+    summary = "配信リマインダー停止",
+    responses(
+        (status = 200, description = "成功", body = OkResponse),
+        (status = 401, description = "未認証", body = ErrorResponse),
+    ),
+)]
+async fn delete_reminders(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+) -> Result<Json<Value>, AppError> {
+    let conn = state.db.lock().unwrap();
+    conn.execute(
+        "DELETE FROM reminder_settings WHERE user_id = ?1",
+        [user_id.0],
+    )?;
+    Ok(Json(json!({"ok": true})))
+}
+
+#[cfg(test)]
+mod tests {
+    // Stream Reminder Settings API Spec
+    //
+    // PUT /api/reminders upserts the caller's settings (minutes_before
+    // 1..=1440, default 10; target_url a valid notifier URL). GET returns
+    // null when unset; DELETE opts out.
+
+    use super::routes;
+    use crate::middleware::auth_middleware;
+    use crate::state::AppState;
+    use axum::body::to_bytes;
+    use axum::http::{Request, StatusCode};
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+
+    fn setup_state() -> AppState {
+        let state = AppState::test();
+        state
+            .db
+            .lock()
+            .unwrap()
+            .execute("INSERT INTO users (email) VALUES ('a@example.com')", [])
+            .unwrap();
+        state
+    }
+
+    async fn call(state: &AppState, method: &str, body: Value) -> (StatusCode, Value) {
+        let resp = axum::Router::new()
+            .merge(routes())
+            .layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                auth_middleware,
+            ))
+            .with_state(state.clone())
+            .oneshot(
+                Request::builder()
+                    .method(method)
+                    .uri("/api/reminders")
+                    .header("content-type", "application/json")
+                    .body(axum::body::Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let status = resp.status();
+        let bytes = to_bytes(resp.into_body(), 1024 * 1024).await.unwrap();
+        (
+            status,
+            serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+        )
+    }
+
+    #[tokio::test]
+    async fn put_get_delete_round_trip() {
+        let state = setup_state();
+        assert_eq!(call(&state, "GET", Value::Null).await.1, Value::Null);
+
+        let target = "ntfy+https://ntfy.sh/streams";
+        let (status, body) = call(&state, "PUT", json!({"target_url": target})).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, json!({"minutes_before": 10, "target_url": target}));
+
+        let (_, body) = call(
+            &state,
+            "PUT",
+            json!({"minutes_before": 30, "target_url": target}),
+        )
+        .await;
+        assert_eq!(body["minutes_before"], 30);
+
+        assert_eq!(call(&state, "DELETE", Value::Null).await.0, StatusCode::OK);
+        assert_eq!(call(&state, "GET", Value::Null).await.1, Value::Null);
+    }
+
+    #[tokio::test]
+    async fn invalid_settings_are_rejected_with_400() {
+        let state = setup_state();
+        let target = "ntfy+https://ntfy.sh/streams";
+        for body in [
+            json!({"minutes_before": 0, "target_url": target}),
+            json!({"minutes_before": 1441, "target_url": target}),
+            json!({"minutes_before": 10}),
+            json!({"target_url": "not a url"}),
+        ] {
+            assert_eq!(
+                call(&state, "PUT", body.clone()).await.0,
+                StatusCode::BAD_REQUEST,
+                "{body} must be rejected"
+            );
+        }
+    }
+}
@@ -6,6 +6,7 @@ use axum::http::header;
 use axum::response::IntoResponse;
 use axum::routing::get;
 use axum::Router;
+use rusqlite::{Connection, OptionalExtension};
 use serde::Deserialize;
 
 pub fn routes() -> Router<AppState> {
@@ -29,13 +30,13 @@ struct RssQuery {
     path = "/api/rss",
     tag = "RSS",
     summary = "お気に入りチャンネルのRSSフィード",
-    description = "お気に入り (is_favorite=1) チャンネルの動画をRSS 2.0形式で配信する。hide_shorts=1 のチャンネルではShortsを除外する。認証不要。token パラメータ（UUID）でユーザーを特定する。",
+    description = "お気に入り (is_favorite=1) チャンネルの動画をRSS 2.0形式で配信する。hide_shorts=1 のチャンネルではShortsを除外する。認証不要。token パラメータ（UUID）でユーザーを特定する。\n\nユーザーの RSS トークン (/api/rss/token) ならお気に入り、ラベル付きフィードトークン (/api/rss/feeds) ならそのスコープ (グループまたはお気に入り) の動画を配信する。token 省略時は RSS_TOKENLESS_FALLBACK が有効な場合のみ最初のユーザーのお気に入りを返す。",
     params(
-        ("token" = Option<String>, Query, description = "RSSトークン (UUID)"),
+        ("token" = Option<String>, Query, description = "RSSトークン / フィードトークン (UUID)"),
     ),
     responses(
         (status = 200, description = "RSS 2.0 XML", content_type = "application/rss+xml"),
-        (status = 404, description = "トークンが無効", body = ErrorResponse),
+        (status = 404, description = "トークンが無効、または token 省略時のフォールバックが無効", body = ErrorResponse),
     ),
 )]
 async fn get_rss_feed(
@@ -45,40 +46,37 @@ async fn get_rss_feed(
     let items = {
         let conn = state.db.lock().unwrap();
 
-        let user_id: i64 = match query.token {
-            Some(ref token) => conn
+        // group_id None = the user's favorites.
+        let (user_id, group_id): (i64, Option<i64>) = match query.token {
+            Some(ref token) => resolve_token(&conn, token)?,
+            // Fallback: return first user's feed for backward compatibility with existing
+            // RSS consumers (e.g. Discord webhook via rss_checker). Turned off with
+            // RSS_TOKENLESS_FALLBACK=0.
+            None if state.config.rss_tokenless_fallback => conn
                 .query_row(
-                    "SELECT id FROM users WHERE rss_token = ?1",
-                    [token],
-                    |row| row.get(0),
+                    "SELECT id FROM users WHERE disabled_at IS NULL ORDER BY id LIMIT 1",
+                    [],
+                    |row| Ok((row.get::<_, i64>(0)?, None)),
                 )
-                .map_err(|_| AppError::NotFound("Invalid RSS token".to_string()))?,
-            // Fallback: return first user's feed for backward compatibility with existing
-            // RSS consumers (e.g. Discord webhook via rss_checker).
-            // TODO: Remove once all RSS consumers are updated to use ?token=<uuid>.
-            None => conn
-                .query_row("SELECT id FROM users ORDER BY id LIMIT 1", [], |row| {
-                    row.get::<_, i64>(0)
-                })
                 .map_err(|_| AppError::NotFound("No users found".to_string()))?,
+            None => return Err(AppError::NotFound("RSS token required".to_string())),
         };
 
-        let mut stmt = conn.prepare(
+        let mut stmt = conn.prepare(&format!(
             "SELECT v.id, v.title, v.published_at, c.title as channel_title
              FROM videos v
              JOIN channels c ON v.channel_id = c.id
              JOIN user_channels uc ON uc.channel_id = c.id AND uc.user_id = ?1
              LEFT JOIN user_videos uv ON uv.video_id = v.id AND uv.user_id = ?1
-             WHERE uc.is_favorite = 1
-               AND COALESCE(uv.is_hidden, 0) = 0
-               AND v.is_members_only = 0
-               AND (v.is_livestream = 0 OR uc.show_livestreams = 1)
-               AND (v.is_short = 0 OR uc.hide_shorts = 0)
+             WHERE ((?2 IS NULL AND uc.is_favorite = 1)
+                    OR uc.channel_id IN (SELECT channel_id FROM channel_groups WHERE group_id = ?2))
+               AND {visible}
              ORDER BY v.published_at DESC
              LIMIT 100",
-        )?;
+            visible = crate::visibility::VISIBLE,
+        ))?;
         let items = stmt
-            .query_map(rusqlite::params![user_id], |row| {
+            .query_map(rusqlite::params![user_id, group_id], |row| {
                 Ok(RssItem {
                     video_id: row.get(0)?,
                     title: row.get(1)?,
@@ -98,6 +96,37 @@ async fn get_rss_feed(
     ))
 }
 
+/// Owner and scope of a token: the user's own `rss_token` serves favorites,
+/// a labeled feed token serves its group (or favorites when it has none).
+/// Tokens of disabled users are treated as unknown.
+fn resolve_token(conn: &Connection, token: &str) -> Result<(i64, Option<i64>), AppError> {
+    let own = conn
+        .query_row(
+            "SELECT id FROM users WHERE rss_token = ?1 AND disabled_at IS NULL",
+            [token],
+            |row| Ok((row.get(0)?, None)),
+        )
+        .optional()?;
+    let resolved = match own {
+        Some(resolved) => Some(resolved),
+        None => conn
+            .query_row(
+                "SELECT t.user_id, t.group_id FROM rss_feed_tokens t
+                 JOIN users u ON u.id = t.user_id
+                 WHERE t.token = ?1 AND u.disabled_at IS NULL",
+                [token],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?,
+    };
+    resolved.ok_or_else(|| AppError::NotFound("Invalid RSS token".to_string()))
+}
+
+/// Subscription URL handed out by the token endpoints.
+pub(crate) fn feed_url(base_url: &str, token: &str) -> String {
+    format!("{base_url}/api/rss?token={token}")
+}
+
 fn build_rss_xml(items: &[RssItem]) -> String {
     let mut xml = String::from(
         r#"<?xml version="1.0" encoding="UTF-8"?>
@@ -160,8 +189,10 @@ mod tests {
     // - Respects user's livestream filter
     // - Sorted by published_at DESC, limited to 100
     // - No authentication required (public endpoint with rss_token param)
-    // - token resolves to the owning user; missing token falls back to first user;
-    //   an unknown token is a 404.
+    // - token resolves to the owning user; missing token falls back to first user
+    //   unless RSS_TOKENLESS_FALLBACK is off; an unknown token is a 404.
+    // - A labeled feed token (rss_feed_tokens) serves its group's channels, or the
+    //   favorites when it has no group; tokens of disabled users are unknown.
     //
     // These tests drive the real `get_rss_feed` handler over HTTP (oneshot) so the
     // handler's own SQL — including `AND v.is_members_only = 0` and the token→user
@@ -452,6 +483,62 @@ mod tests {
         assert_eq!(rss_video_ids(&body), vec!["v1"]);
     }
 
+    #[tokio::test]
+    async fn rss_without_token_is_404_when_fallback_is_disabled() {
+        let mut state = setup_state();
+        state.config.rss_tokenless_fallback = false;
+        insert_video(&state, "v1", "UC_fav", "2024-01-02T00:00:00Z", 0);
+
+        let (status, _) = get_rss(&state, None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = get_rss(&state, Some("tok-1")).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn feed_token_serves_its_group_or_favorites() {
+        let state = setup_state();
+        insert_video(&state, "v_fav", "UC_fav", "2024-01-02T00:00:00Z", 0);
+        insert_video(&state, "v_grouped", "UC_nofav", "2024-01-03T00:00:00Z", 0);
+        {
+            let conn = state.db.lock().unwrap();
+            conn.execute(
+                "INSERT INTO groups (user_id, name, sort_order, created_at) VALUES (1, 'G', 0, 0)",
+                [],
+            )
+            .unwrap();
+            conn.execute(
+                "INSERT INTO channel_groups (channel_id, group_id) VALUES ('UC_nofav', 1)",
+                [],
+            )
+            .unwrap();
+            conn.execute(
+                "INSERT INTO rss_feed_tokens (user_id, label, token, group_id) VALUES (1, 'group', 'ft-group', 1)",
+                [],
+            )
+            .unwrap();
+            conn.execute(
+                "INSERT INTO rss_feed_tokens (user_id, label, token, group_id) VALUES (1, 'fav', 'ft-fav', NULL)",
+                [],
+            )
+            .unwrap();
+        }
+
+        let (_, body) = get_rss(&state, Some("ft-group")).await;
+        assert_eq!(rss_video_ids(&body), vec!["v_grouped"]);
+        let (_, body) = get_rss(&state, Some("ft-fav")).await;
+        assert_eq!(rss_video_ids(&body), vec!["v_fav"]);
+
+        state
+            .db
+            .lock()
+            .unwrap()
+            .execute("UPDATE users SET disabled_at = 1", [])
+            .unwrap();
+        let (status, _) = get_rss(&state, Some("ft-group")).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
     #[test]
     fn test_escape_xml() {
         assert_eq!(
@@ -0,0 +1,493 @@
+use crate::error::AppError;
+use crate::middleware::UserId;
+use crate::openapi::*;
+use crate::routes::news::resolve_base_url;
+use crate::routes::rss::feed_url;
+use crate::state::AppState;
+use axum::extract::{Extension, Path, State};
+use axum::http::HeaderMap;
+use axum::routing::{delete, get, post};
+use axum::{Json, Router};
+use rusqlite::{Connection, OptionalExtension};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/api/rss/token",
+            get(get_rss_token)
+                .post(rotate_rss_token)
+                .delete(revoke_rss_token),
+        )
+        .route(
+            "/api/rss/feeds",
+            get(get_feed_tokens).post(create_feed_token),
+        )
+        .route("/api/rss/feeds/{id}", delete(delete_feed_token))
+        .route("/api/rss/feeds/{id}/rotate", post(rotate_feed_token))
+}
+
+fn new_token() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+fn token_json(base_url: &str, token: Option<String>) -> Value {
+    let url = token.as_deref().map(|t| feed_url(base_url, t));
+    json!({"token": token, "url": url})
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/rss/token",
+    tag = "RSS",
+    summary = "RSSトークン取得",
+    description = "お気に入りチャンネルの RSS フィード (/api/rss) 用トークンと購読 URL を返す。失効済みなら null。",
+    responses(
+        (status = 200, description = "RSSトークン", body = RssTokenResponse),
+        (status = 401, description = "未認証", body = ErrorResponse),
+    ),
+)]
+async fn get_rss_token(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, AppError> {
+    let token: Option<String> = {
+        let conn = state.db.lock().unwrap();
+        conn.query_row(
+            "SELECT rss_token FROM users WHERE id = ?1",
+            [user_id.0],
+            |row| row.get(0),
+        )?
+    };
+    Ok(Json(token_json(
+        &resolve_base_url(&headers, &state.config),
+        token,
+    )))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/rss/token",
+    tag = "RSS",
+    summary = "RSSトークン再発行",
+    description = "新しい RSS トークンを発行する。古いトークン (失効済みを含む) は即座に無効になる。",
+    responses(
+        (status = 200, description = "新しいRSSトークン", body = RssTokenResponse),
+        (status = 401, description = "未認証", body = ErrorResponse),
+    ),
+)]
+async fn rotate_rss_token(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, AppError> {
+    let token = new_token();
+    {
+        let conn = state.db.lock().unwrap();
+        conn.execute(
+            "UPDATE users SET rss_token = ?1, updated_at = ?2 WHERE id = ?3",
+            rusqlite::params![token, crate::util::now_unix(), user_id.0],
+        )?;
+        crate::audit::record(
+            &conn,
+            Some(user_id.0),
+            crate::audit::RSS_TOKEN_ROTATE,
+            "",
+            Value::Null,
+        );
+    }
+    Ok(Json(token_json(
+        &resolve_base_url(&headers, &state.config),
+        Some(token),
+    )))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/rss/token",
+    tag = "RSS",
+    summary = "RSSトークン失効",
+    description = "RSS トークンを失効させる。再び使うには再発行する。ラベル付きフィードトークンには影響しない。",
+    responses(
+        (status = 200, description = "成功", body = OkResponse),
+        (status = 401, description = "未認証", body = ErrorResponse),
+    ),
+)]
+async fn revoke_rss_token(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+) -> Result<Json<Value>, AppError> {
+    let conn = state.db.lock().unwrap();
+    conn.execute(
+        "UPDATE users SET rss_token = NULL, updated_at = ?1 WHERE id = ?2",
+        rusqlite::params![crate::util::now_unix(), user_id.0],
+    )?;
+    crate::audit::record(
+        &conn,
+        Some(user_id.0),
+        crate::audit::RSS_TOKEN_REVOKE,
+        "",
+        Value::Null,
+    );
+    Ok(Json(json!({"ok": true})))
+}
+
+const FEED_TOKEN_SELECT: &str = "SELECT t.id, t.label, t.token, t.group_id, g.name, t.created_at
+     FROM rss_feed_tokens t LEFT JOIN groups g ON g.id = t.group_id";
+
+fn feed_token_json(base_url: &str, row: &rusqlite::Row) -> rusqlite::Result<Value> {
+    let token: String = row.get(2)?;
+    Ok(json!({
+        "id": row.get::<_, i64>(0)?,
+        "label": row.get::<_, String>(1)?,
+        "url": feed_url(base_url, &token),
+        "token": token,
+        "group_id": row.get::<_, Option<i64>>(3)?,
+        "group_name": row.get::<_, Option<String>>(4)?,
+        "created_at": crate::util::row_timestamp_to_rfc3339(row, 5)?,
+    }))
+}
+
+fn load_feed_token(
+    conn: &Connection,
+    base_url: &str,
+    user_id: i64,
+    id: i64,
+) -> Result<Value, AppError> {
+    conn.query_row(
+        &format!("{FEED_TOKEN_SELECT} WHERE t.id = ?1 AND t.user_id = ?2"),
+        rusqlite::params![id, user_id],
+        |row| feed_token_json(base_url, row),
+    )
+    .optional()?
+    .ok_or_else(|| AppError::NotFound("Feed token not found".to_string()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/rss/feeds",
+    tag = "RSS",
+    summary = "フィードトークン一覧",
+    responses(
+        (status = 200, description = "ラベル付きフィードトークン一覧", body = Vec<FeedTokenItem>),
+        (status = 401, description = "未認証", body = ErrorResponse),
+    ),
+)]
+async fn get_feed_tokens(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, AppError> {
+    let base_url = resolve_base_url(&headers, &state.config);
+    let conn = state.db.lock().unwrap();
+    let mut stmt = conn.prepare(&format!(
+        "{FEED_TOKEN_SELECT} WHERE t.user_id = ?1 ORDER BY t.id"
+    ))?;
+    let rows = stmt
+        .query_map([user_id.0], |row| feed_token_json(&base_url, row))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Json(Value::Array(rows)))
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub(crate) struct CreateFeedTokenBody {
+    /// ラベル (例: 会社の RSS リーダー)
+    label: String,
+    /// 対象グループID (省略時: お気に入りチャンネル)
+    group_id: Option<i64>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/rss/feeds",
+    tag = "RSS",
+    summary = "フィードトークン発行",
+    description = "グループまたはお気に入りに限定した RSS フィード用のトークンを発行する。RSS リーダーごとに発行しておけば、個別に再発行・失効できる。グループを削除するとそのトークンも削除される。",
+    request_body(content = CreateFeedTokenBody),
+    responses(
+        (status = 201, description = "発行されたフィードトークン", body = FeedTokenItem),
+        (status = 400, description = "バリデーションエラー", body = ErrorResponse),
+        (status = 401, description = "未認証", body = ErrorResponse),
+    ),
+)]
+async fn create_feed_token(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    headers: HeaderMap,
+    Json(body): Json<CreateFeedTokenBody>,
+) -> Result<(axum::http::StatusCode, Json<Value>), AppError> {
+    let label = body.label.trim().to_string();
+    if label.is_empty() {
+        return Err(AppError::BadRequest("label is required".to_string()));
+    }
+    let base_url = resolve_base_url(&headers, &state.config);
+    let conn = state.db.lock().unwrap();
+    if let Some(group_id) = body.group_id {
+        let owned: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM groups WHERE id = ?1 AND user_id = ?2)",
+            rusqlite::params![group_id, user_id.0],
+            |row| row.get(0),
+        )?;
+        if !owned {
+            return Err(AppError::BadRequest("Group not found".to_string()));
+        }
+    }
+    conn.execute(
+        "INSERT INTO rss_feed_tokens (user_id, label, token, group_id, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        rusqlite::params![
+            user_id.0,
+            label,
+            new_token(),
+            body.group_id,
+            crate::util::now_unix()
+        ],
+    )?;
+    let id = conn.last_insert_rowid();
+    crate::audit::record(
+        &conn,
+        Some(user_id.0),
+        crate::audit::FEED_TOKEN_CREATE,
+        &id.to_string(),
+        json!({"label": label, "group_id": body.group_id}),
+    );
+    let created = load_feed_token(&conn, &base_url, user_id.0, id)?;
+    Ok((axum::http::StatusCode::CREATED, Json(created)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/rss/feeds/{id}/rotate",
+    tag = "RSS",
+    summary = "フィードトークン再発行",
+    description = "トークンだけを新しくする (ラベル・スコープはそのまま)。古い URL は即座に無効になる。",
+    params(("id" = i64, Path, description = "フィードトークンID")),
+    responses(
+        (status = 200, description = "再発行されたフィードトークン", body = FeedTokenItem),
+        (status = 401, description = "未認証", body = ErrorResponse),
+        (status = 404, description = "フィードトークンが存在しない", body = ErrorResponse),
+    ),
+)]
+async fn rotate_feed_token(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    headers: HeaderMap,
+    Path(id): Path<i64>,
+) -> Result<Json<Value>, AppError> {
+    let base_url = resolve_base_url(&headers, &state.config);
+    let conn = state.db.lock().unwrap();
+    let rotated = conn.execute(
+        "UPDATE rss_feed_tokens SET token = ?1 WHERE id = ?2 AND user_id = ?3",
+        rusqlite::params![new_token(), id, user_id.0],
+    )?;
+    if rotated > 0 {
+        crate::audit::record(
+            &conn,
+            Some(user_id.0),
+            crate::audit::FEED_TOKEN_ROTATE,
+            &id.to_string(),
+            Value::Null,
+        );
+    }
+    Ok(Json(load_feed_token(&conn, &base_url, user_id.0, id)?))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/rss/feeds/{id}",
+    tag = "RSS",
+    summary = "フィードトークン失効",
+    params(("id" = i64, Path, description = "フィードトークンID")),
+    responses(
+        (status = 200, description = "成功", body = OkResponse),
+        (status = 401, description = "未認証", body = ErrorResponse),
+        (status = 404, description = "フィードトークンが存在しない", body = ErrorResponse),
+    ),
+)]
+async fn delete_feed_token(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Path(id): Path<i64>,
+) -> Result<Json<Value>, AppError> {
+    let conn = state.db.lock().unwrap();
+    let deleted = conn.execute(
+        "DELETE FROM rss_feed_tokens WHERE id = ?1 AND user_id = ?2",
+        rusqlite::params![id, user_id.0],
+    )?;
+    if deleted == 0 {
+        return Err(AppError::NotFound("Feed token not found".to_string()));
+    }
+    crate::audit::record(
+        &conn,
+        Some(user_id.0),
+        crate::audit::FEED_TOKEN_DELETE,
+        &id.to_string(),
+        Value::Null,
+    );
+    Ok(Json(json!({"ok": true})))
+}
+
+#[cfg(test)]
+mod tests {
+    // RSS Token Management Spec
+    //
+    // - /api/rss/token: read, rotate (the old token stops working at once) and
+    //   revoke the user's own favorites token.
+    // - /api/rss/feeds: labeled tokens scoped to one of the caller's groups or
+    //   to favorites; each can be rotated or deleted on its own.
+    // - Responses carry a ready-to-use subscription URL.
+
+    use super::routes;
+    use crate::middleware::auth_middleware;
+    use crate::state::AppState;
+    use axum::body::to_bytes;
+    use axum::http::{Request, StatusCode};
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+
+    fn setup_state() -> AppState {
+        let mut state = AppState::test();
+        state.config.public_base_url = Some("https://feed.example.com".to_string());
+        {
+            let conn = state.db.lock().unwrap();
+            conn.execute(
+                "INSERT INTO users (email, rss_token) VALUES ('a@example.com', 'tok-1')",
+                [],
+            )
+            .unwrap();
+            conn.execute("INSERT INTO users (email) VALUES ('b@example.com')", [])
+                .unwrap();
+            conn.execute(
+                "INSERT INTO groups (user_id, name, sort_order, created_at) VALUES (1, 'Music', 0, 0)",
+                [],
+            )
+            .unwrap();
+            conn.execute(
+                "INSERT INTO groups (user_id, name, sort_order, created_at) VALUES (2, 'theirs', 0, 0)",
+                [],
+            )
+            .unwrap();
+        }
+        state
+    }
+
+    async fn call(state: &AppState, method: &str, uri: &str, body: Value) -> (StatusCode, Value) {
+        let app = axum::Router::new()
+            .merge(routes())
+            .layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                auth_middleware,
+            ))
+            .with_state(state.clone());
+        let resp = app
+            .oneshot(
+                Request::builder()
+                    .method(method)
+                    .uri(uri)
+                    .header("content-type", "application/json")
+                    .body(axum::body::Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let status = resp.status();
+        let bytes = to_bytes(resp.into_body(), 1024 * 1024).await.unwrap();
+        (
+            status,
+            serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+        )
+    }
+
+    #[tokio::test]
+    async fn rss_token_can_be_rotated_and_revoked() {
+        let state = setup_state();
+        let (_, current) = call(&state, "GET", "/api/rss/token", Value::Null).await;
+        assert_eq!(current["token"], "tok-1");
+        assert_eq!(
+            current["url"],
+            "https://feed.example.com/api/rss?token=tok-1"
+        );
+
+        let (status, rotated) = call(&state, "POST", "/api/rss/token", Value::Null).await;
+        assert_eq!(status, StatusCode::OK);
+        let token = rotated["token"].as_str().unwrap();
+        assert_eq!(token.len(), 36);
+        assert_ne!(token, "tok-1");
+
+        call(&state, "DELETE", "/api/rss/token", Value::Null).await;
+        let (_, revoked) = call(&state, "GET", "/api/rss/token", Value::Null).await;
+        assert_eq!(revoked, json!({"token": null, "url": null}));
+    }
+
+    #[tokio::test]
+    async fn feed_tokens_are_scoped_labeled_and_individually_managed() {
+        let state = setup_state();
+        let (status, music) = call(
+            &state,
+            "POST",
+            "/api/rss/feeds",
+            json!({"label": "reader", "group_id": 1}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(music["group_name"], "Music");
+        let (_, favorites) =
+            call(&state, "POST", "/api/rss/feeds", json!({"label": "phone"})).await;
+        assert!(favorites["group_id"].is_null());
+
+        let id = music["id"].as_i64().unwrap();
+        let (_, rotated) = call(
+            &state,
+            "POST",
+            &format!("/api/rss/feeds/{id}/rotate"),
+            Value::Null,
+        )
+        .await;
+        assert_ne!(rotated["token"], music["token"]);
+        assert_eq!(rotated["label"], "reader");
+
+        assert_eq!(
+            call(
+                &state,
+                "DELETE",
+                &format!("/api/rss/feeds/{id}"),
+                Value::Null
+            )
+            .await
+            .0,
+            StatusCode::OK
+        );
+        let (_, list) = call(&state, "GET", "/api/rss/feeds", Value::Null).await;
+        assert_eq!(list.as_array().unwrap().len(), 1);
+        assert_eq!(list[0]["label"], "phone");
+        assert_eq!(
+            call(
+                &state,
+                "DELETE",
+                &format!("/api/rss/feeds/{id}"),
+                Value::Null
+            )
+            .await
+            .0,
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    #[tokio::test]
+    async fn feed_token_requires_label_and_own_group() {
+        let state = setup_state();
+        for body in [
+            json!({"label": " "}),
+            json!({"label": "x", "group_id": 2}),
+            json!({"label": "x", "group_id": 99}),
+        ] {
+            assert_eq!(
+                call(&state, "POST", "/api/rss/feeds", body.clone()).await.0,
+                StatusCode::BAD_REQUEST,
+                "{body} must be rejected"
+            );
+        }
+    }
+}
@@ -0,0 +1,288 @@
+use crate::error::AppError;
+use crate::middleware::UserId;
+use crate::openapi::*;
+use crate::state::AppState;
+use axum::extract::{Extension, Query, State};
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+pub fn routes() -> Router<AppState> {
+    Router::new().route("/api/search", get(search_videos))
+}
+
+#[derive(Deserialize)]
+struct SearchQuery {
+    q: Option<String>,
+    scope: Option<String>,
+    group: Option<i64>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+}
+
+/// Visible in the feed: a subscribed channel and the same rules as `get_feed`.
+fn feed_visible() -> String {
+    format!(
+        "(uc.user_id IS NOT NULL AND {})",
+        crate::visibility::VISIBLE
+    )
+}
+
+/// In the watch history: the same rows as `get_history` by default.
+const IN_HISTORY: &str =
+    "(COALESCE(uv.is_hidden, 0) = 1 AND COALESCE(uv.reason, 'watched') = 'watched')";
+
+#[utoipa::path(
+    get,
+    path = "/api/search",
+    tag = "動画フィード",
+    summary = "動画検索",
+    description = "動画タイトルとチャンネル名を部分一致で検索し、公開日時の降順で返す。\n\n- 空白区切りの語はすべて含むものに一致 (語ごとにタイトルかチャンネル名のどちらかに含まれればよい)。最大8語\n- 全角・半角、大文字・小文字を区別しない\n- scope=feed (デフォルト): フィードと同じ表示ルール (購読中のチャンネル、非表示・メンバー限定を除外、チャンネルごとのライブ配信・Shorts 設定)\n- scope=history: 視聴履歴 (視聴済みにした動画) のみ\n- scope=all: フィードと視聴履歴の両方",
+    params(
+        ("q" = String, Query, description = "検索語"),
+        ("scope" = Option<String>, Query, description = "検索範囲: feed / history / all (デフォルト: feed)"),
+        ("group" = Option<i64>, Query, description = "グループIDで絞り込み"),
+        ("limit" = Option<i64>, Query, description = "取得件数 (デフォルト: 50, 最大: 200)"),
+        ("offset" = Option<i64>, Query, description = "オフセット (デフォルト: 0)"),
+    ),
+    responses(
+        (status = 200, description = "検索結果", body = Vec<SearchItem>),
+        (status = 400, description = "検索語が空、または不正な scope", body = ErrorResponse),
+        (status = 401, description = "未認証", body = ErrorResponse),
+    ),
+)]
+async fn search_videos(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Query(query): Query<SearchQuery>,
+) -> Result<Json<Value>, AppError> {
+    let parsed = crate::search::parse_query(query.q.as_deref().unwrap_or(""))
+        .ok_or_else(|| AppError::BadRequest("q is required".to_string()))?;
+    let visibility = match query.scope.as_deref().unwrap_or("feed") {
+        "feed" => feed_visible(),
+        "history" => IN_HISTORY.to_string(),
+        "all" => format!("({} OR {IN_HISTORY})", feed_visible()),
+        _ => {
+            return Err(AppError::BadRequest(
+                "scope must be feed, history or all".to_string(),
+            ))
+        }
+    };
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    let mut params: Vec<rusqlite::types::Value> = vec![user_id.0.into()];
+    let mut conditions = vec![visibility];
+    if let Some(fts) = parsed.fts {
+        params.push(fts.into());
+        conditions.push(format!("s.video_search MATCH ?{}", params.len()));
+    }
+    for like in parsed.likes {
+        params.push(like.into());
+        conditions.push(format!(
+            "(s.title || ' ' || s.channel_title) LIKE ?{} ESCAPE '\\'",
+            params.len()
+        ));
+    }
+    if let Some(group_id) = query.group {
+        params.push(group_id.into());
+        conditions.push(format!(
+            "v.channel_id IN (SELECT cg.channel_id FROM channel_groups cg
+                              JOIN groups g ON g.id = cg.group_id
+                              WHERE cg.group_id = ?{} AND g.user_id = ?1)",
+            params.len()
+        ));
+    }
+    params.push(limit.into());
+    params.push(offset.into());
+    let sql = format!(
+        "SELECT v.id, v.channel_id, v.title, v.published_at,
+                v.duration, v.is_short, v.is_livestream, v.livestream_ended_at,
+                c.title AS channel_title, c.thumbnail_url AS channel_thumbnail,
+                COALESCE(uv.is_hidden, 0)
+         FROM video_search s
+         JOIN videos v ON v.rowid = s.rowid
+         JOIN channels c ON c.id = v.channel_id
+         LEFT JOIN user_channels uc ON uc.channel_id = v.channel_id AND uc.user_id = ?1
+         LEFT JOIN user_videos uv ON uv.video_id = v.id AND uv.user_id = ?1
+         WHERE {conditions}
+         ORDER BY v.published_at DESC, v.id DESC
+         LIMIT ?{limit_idx} OFFSET ?{offset_idx}",
+        conditions = conditions.join("\n           AND "),
+        limit_idx = params.len() - 1,
+        offset_idx = params.len(),
+    );
+
+    let rows = {
+        let conn = state.db.lock().unwrap();
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt
+            .query_map(rusqlite::params_from_iter(params), |row| {
+                let mut value = super::feed::video_json(row, None)?;
+                value["is_hidden"] = json!(row.get::<_, i64>(10)?);
+                Ok(value)
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        rows
+    };
+
+    Ok(Json(Value::Array(rows)))
+}
+
+#[cfg(test)]
+mod tests {
+    // Search Spec
+    //
+    // - GET /api/search matches every whitespace-separated term against the
+    //   video title or channel name, ignoring width and case; Japanese
+    //   substrings match, including terms shorter than three characters.
+    // - scope=feed applies the feed's rules: subscribed channels only, no
+    //   hidden or members-only videos, per-channel livestream/Shorts settings.
+    // - scope=history searches only hidden videos; scope=all searches both.
+    // - Results are newest first and can be narrowed to one of the user's
+    //   groups. An empty query or unknown scope is a 400.
+
+    use super::routes;
+    use crate::middleware::auth_middleware;
+    use crate::state::AppState;
+    use axum::body::to_bytes;
+    use axum::http::{Request, StatusCode};
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    fn setup_state() -> AppState {
+        let state = AppState::test();
+        {
+            let conn = state.db.lock().unwrap();
+            conn.execute_batch(
+                "INSERT INTO users (email) VALUES ('a@example.com'), ('b@example.com');
+                 INSERT INTO channels (id, title) VALUES ('UC1', 'Rust ニュース'), ('UC2', 'Cooking'), ('UC3', 'Other');
+                 INSERT INTO user_channels (user_id, channel_id, show_livestreams, hide_shorts)
+                   VALUES (1, 'UC1', 0, 1), (1, 'UC2', 1, 0), (2, 'UC3', 1, 0);
+                 INSERT INTO groups (user_id, name) VALUES (1, 'Tech');
+                 INSERT INTO channel_groups (channel_id, group_id) VALUES ('UC1', 1);
+                 INSERT INTO videos (id, channel_id, title, published_at, is_short, is_livestream, is_members_only) VALUES
+                   ('v1', 'UC1', 'ＲＵＳＴ入門 第1回', 100, 0, 0, 0),
+                   ('v2', 'UC1', 'Rust 入門 第2回', 200, 0, 0, 0),
+                   ('v3', 'UC1', 'Rust short', 300, 1, 0, 0),
+                   ('v4', 'UC1', 'Rust live', 400, 0, 1, 0),
+                   ('v5', 'UC1', 'Rust members', 500, 0, 0, 1),
+                   ('v6', 'UC2', 'カレーの作り方', 600, 0, 0, 0),
+                   ('v7', 'UC3', 'Rust elsewhere', 700, 0, 0, 0);
+                 INSERT INTO user_videos (user_id, video_id, is_hidden) VALUES (1, 'v2', 1);",
+            )
+            .unwrap();
+            crate::search::sync_index(&conn);
+        }
+        state
+    }
+
+    fn encode_non_ascii(query: &str) -> String {
+        query
+            .chars()
+            .map(|c| match c.is_ascii() {
+                true => c.to_string(),
+                false => urlencoding::encode(&c.to_string()).into_owned(),
+            })
+            .collect()
+    }
+
+    async fn search(state: &AppState, query: &str) -> (StatusCode, Value) {
+        let resp = axum::Router::new()
+            .merge(routes())
+            .layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                auth_middleware,
+            ))
+            .with_state(state.clone())
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/api/search?{}", encode_non_ascii(query)))
+                    .header("Cf-Access-Authenticated-User-Email", "a@example.com")
+                    .body(axum::body::Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let status = resp.status();
+        let bytes = to_bytes(resp.into_body(), 1024 * 1024).await.unwrap();
+        (
+            status,
+            serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+        )
+    }
+
+    async fn ids(state: &AppState, query: &str) -> Vec<String> {
+        let (status, body) = search(state, query).await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        body.as_array()
+            .unwrap()
+            .iter()
+            .map(|v| v["id"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn feed_scope_applies_feed_visibility() {
+        let state = setup_state();
+        // v2 is hidden, v3 a Short in a hide_shorts channel, v4 a livestream
+        // without show_livestreams, v5 members-only, v7 not subscribed.
+        assert_eq!(ids(&state, "q=rust").await, vec!["v1"]);
+    }
+
+    #[tokio::test]
+    async fn terms_match_title_or_channel_ignoring_width_and_case() {
+        let state = setup_state();
+        assert_eq!(
+            ids(&state, "q=%EF%BD%92%EF%BD%95%EF%BD%93%EF%BD%94").await,
+            vec!["v1"]
+        );
+        assert_eq!(
+            ids(&state, "q=ニュース%20第1").await,
+            vec!["v1"],
+            "channel + title"
+        );
+        assert_eq!(ids(&state, "q=カレー").await, vec!["v6"]);
+        assert_eq!(
+            ids(&state, "q=作り").await,
+            vec!["v6"],
+            "two-character term"
+        );
+        assert!(
+            ids(&state, "q=rust%20カレー").await.is_empty(),
+            "all terms must match"
+        );
+    }
+
+    #[tokio::test]
+    async fn history_and_all_scopes_include_hidden_videos() {
+        let state = setup_state();
+        assert_eq!(ids(&state, "q=入門&scope=history").await, vec!["v2"]);
+        assert_eq!(ids(&state, "q=入門&scope=all").await, vec!["v2", "v1"]);
+        let (_, body) = search(&state, "q=入門&scope=all").await;
+        assert_eq!(body[0]["is_hidden"], 1);
+        assert_eq!(body[1]["is_hidden"], 0);
+    }
+
+    #[tokio::test]
+    async fn group_filter_and_paging() {
+        let state = setup_state();
+        assert_eq!(ids(&state, "q=の&group=1").await, Vec::<String>::new());
+        assert_eq!(
+            ids(&state, "q=入門&scope=all&group=1&limit=1&offset=1").await,
+            vec!["v1"]
+        );
+    }
+
+    #[tokio::test]
+    async fn empty_query_and_unknown_scope_are_rejected() {
+        let state = setup_state();
+        assert_eq!(search(&state, "q=%20").await.0, StatusCode::BAD_REQUEST);
+        assert_eq!(search(&state, "").await.0, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            search(&state, "q=rust&scope=everything").await.0,
+            StatusCode::BAD_REQUEST
+        );
+    }
+}
@@ -0,0 +1,352 @@
+use crate::api_tokens::{self, SCOPES};
+use crate::error::AppError;
+use crate::middleware::UserId;
+use crate::openapi::*;
+use crate::state::AppState;
+use axum::extract::{Extension, Path, State};
+use axum::routing::{delete, get};
+use axum::{Json, Router};
+use rusqlite::{Connection, OptionalExtension};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/tokens", get(get_tokens).post(create_token))
+        .route("/api/tokens/{id}", delete(delete_token))
+}
+
+const TOKEN_COLUMNS: &str = "id, name, token_prefix, scopes, expires_at, last_used_at, created_at";
+
+/// Longest allowed lifetime (10 years).
+const MAX_EXPIRES_IN_DAYS: i64 = 3650;
+
+fn token_json(row: &rusqlite::Row) -> rusqlite::Result<Value> {
+    let scopes: String = row.get(3)?;
+    Ok(json!({
+        "id": row.get::<_, i64>(0)?,
+        "name": row.get::<_, String>(1)?,
+        "prefix": row.get::<_, String>(2)?,
+        "scopes": scopes.split(',').collect::<Vec<_>>(),
+        "expires_at": crate::util::row_timestamp_to_rfc3339(row, 4)?,
+        "last_used_at": crate::util::row_timestamp_to_rfc3339(row, 5)?,
+        "created_at": crate::util::row_timestamp_to_rfc3339(row, 6)?,
+    }))
+}
+
+fn load_token(conn: &Connection, user_id: i64, id: i64) -> Result<Value, AppError> {
+    conn.query_row(
+        &format!("SELECT {TOKEN_COLUMNS} FROM api_tokens WHERE id = ?1 AND user_id = ?2"),
+        rusqlite::params![id, user_id],
+        token_json,
+    )
+    .optional()?
+    .ok_or_else(|| AppError::NotFound("Token not found".to_string()))
+}
+
+/// Stored comma-separated in the order of `SCOPES`, without duplicates.
+fn validate_scopes(scopes: &[String]) -> Result<String, AppError> {
+    if let Some(unknown) = scopes.iter().find(|s| !SCOPES.contains(&s.as_str())) {
+        return Err(AppError::BadRequest(format!("Unknown scope: {}", unknown)));
+    }
+    let selected: Vec<&str> = SCOPES
+        .iter()
+        .copied()
+        .filter(|s| scopes.iter().any(|given| given == s))
+        .collect();
+    if selected.is_empty() {
+        return Err(AppError::BadRequest("scopes must not be empty".to_string()));
+    }
+    Ok(selected.join(","))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/tokens",
+    tag = "認証",
+    summary = "API トークン一覧",
+    responses(
+        (status = 200, description = "ログインユーザーの API トークン一覧 (トークン本体は含まない)", body = Vec<ApiTokenItem>),
+        (status = 401, description = "未認証", body = ErrorResponse),
+    ),
+)]
+async fn get_tokens(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+) -> Result<Json<Value>, AppError> {
+    let conn = state.db.lock().unwrap();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {TOKEN_COLUMNS} FROM api_tokens WHERE user_id = ?1 ORDER BY id"
+    ))?;
+    let rows = stmt
+        .query_map([user_id.0], token_json)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Json(Value::Array(rows)))
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub(crate) struct CreateTokenBody {
+    /// トークン名 (用途のメモ。例: CLI, ブラウザ拡張)
+    name: String,
+    /// スコープ: feed:read / videos:hide / channels:manage / admin
+    scopes: Vec<String>,
+    /// 有効期限 (日数, 1〜3650。省略時: 無期限)
+    expires_in_days: Option<i64>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/tokens",
+    tag = "認証",
+    summary = "API トークン発行",
+    description = "スクリプトやブラウザ拡張から `Authorization: Bearer <token>` で API を呼ぶための個人用トークンを発行する。\n\n- feed:read: フィード・履歴・検索・ニュース・チャンネル/グループの参照 (GET)\n- videos:hide: 動画の非表示/復元\n- channels:manage: チャンネル・グループの追加/変更/削除/同期\n- admin: 上記すべてと設定系・通知系の API (master なら /api/admin/* も)\n\nトークンはハッシュ化して保存され、このレスポンスでのみ返される。トークンの管理 (/api/tokens) はトークンでは行えない。",
+    request_body(content = CreateTokenBody),
+    responses(
+        (status = 201, description = "発行されたトークン (token を含む)", body = CreatedApiToken),
+        (status = 400, description = "バリデーションエラー", body = ErrorResponse),
+        (status = 401, description = "未認証", body = ErrorResponse),
+    ),
+)]
+async fn create_token(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Json(body): Json<CreateTokenBody>,
+) -> Result<(axum::http::StatusCode, Json<Value>), AppError> {
+    let name = body.name.trim().to_string();
+    if name.is_empty() {
+        return Err(AppError::BadRequest("name is required".to_string()));
+    }
+    let scopes = validate_scopes(&body.scopes)?;
+    let now = crate::util::now_unix();
+    let expires_at = match body.expires_in_days {
+        Some(days) if (1..=MAX_EXPIRES_IN_DAYS).contains(&days) => Some(now + days * 86400),
+        Some(_) => {
+            return Err(AppError::BadRequest(
+                "expires_in_days must be between 1 and 3650".to_string(),
+            ))
+        }
+        None => None,
+    };
+    let token = api_tokens::generate();
+
+    let conn = state.db.lock().unwrap();
+    conn.execute(
+        "INSERT INTO api_tokens (user_id, name, token_hash, token_prefix, scopes, expires_at, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        rusqlite::params![
+            user_id.0,
+            name,
+            api_tokens::hash(&token),
+            api_tokens::display_prefix(&token),
+            scopes,
+            expires_at,
+            now
+        ],
+    )?;
+    let id = conn.last_insert_rowid();
+    crate::audit::record(
+        &conn,
+        Some(user_id.0),
+        crate::audit::TOKEN_CREATE,
+        &id.to_string(),
+        json!({"name": name, "scopes": scopes}),
+    );
+    let mut created = load_token(&conn, user_id.0, id)?;
+    created["token"] = json!(token);
+    Ok((axum::http::StatusCode::CREATED, Json(created)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/tokens/{id}",
+    tag = "認証",
+    summary = "API トークン失効",
+    params(("id" = i64, Path, description = "トークンID")),
+    responses(
+        (status = 200, description = "成功", body = OkResponse),
+        (status = 401, description = "未認証", body = ErrorResponse),
+        (status = 404, description = "トークンが存在しない", body = ErrorResponse),
+    ),
+)]
+async fn delete_token(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Path(id): Path<i64>,
+) -> Result<Json<Value>, AppError> {
+    let conn = state.db.lock().unwrap();
+    let deleted = conn.execute(
+        "DELETE FROM api_tokens WHERE id = ?1 AND user_id = ?2",
+        rusqlite::params![id, user_id.0],
+    )?;
+    if deleted == 0 {
+        return Err(AppError::NotFound("Token not found".to_string()));
+    }
+    crate::audit::record(
+        &conn,
+        Some(user_id.0),
+        crate::audit::TOKEN_DELETE,
+        &id.to_string(),
+        Value::Null,
+    );
+    Ok(Json(json!({"ok": true})))
+}
+
+#[cfg(test)]
+mod tests {
+    // API Token Management Spec
+    //
+    // POST returns the plaintext token once (only its hash is stored); GET
+    // lists the caller's tokens with prefix, scopes and timestamps; DELETE
+    // revokes. The issued token authenticates as Bearer within its scopes
+    // and cannot reach /api/tokens itself.
+
+    use super::routes;
+    use crate::middleware::auth_middleware;
+    use crate::state::AppState;
+    use axum::body::to_bytes;
+    use axum::http::{Request, StatusCode};
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+
+    fn setup_state() -> AppState {
+        let state = AppState::test();
+        state
+            .db
+            .lock()
+            .unwrap()
+            .execute("INSERT INTO users (email) VALUES ('a@example.com')", [])
+            .unwrap();
+        state
+    }
+
+    async fn call(
+        state: &AppState,
+        method: &str,
+        uri: &str,
+        bearer: Option<&str>,
+        body: Value,
+    ) -> (StatusCode, Value) {
+        let app = axum::Router::new()
+            .merge(routes())
+            .merge(crate::routes::feed::routes())
+            .merge(crate::routes::channels::routes())
+            .layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                auth_middleware,
+            ))
+            .with_state(state.clone());
+        let mut req = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json");
+        if let Some(token) = bearer {
+            req = req.header("Authorization", format!("Bearer {token}"));
+        }
+        let resp = app
+            .oneshot(req.body(axum::body::Body::from(body.to_string())).unwrap())
+            .await
+            .unwrap();
+        let status = resp.status();
+        let bytes = to_bytes(resp.into_body(), 1024 * 1024).await.unwrap();
+        (
+            status,
+            serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+        )
+    }
+
+    #[tokio::test]
+    async fn issued_token_works_as_bearer_within_its_scopes() {
+        let mut state = setup_state();
+        // Production: no dev bypass, so only the token authenticates.
+        state.config.is_production = true;
+        let token = {
+            let conn = state.db.lock().unwrap();
+            let token = crate::api_tokens::generate();
+            conn.execute(
+                "INSERT INTO api_tokens (user_id, name, token_hash, token_prefix, scopes, created_at)
+                 VALUES (1, 'setup', ?1, 'ysf_', 'admin', 0)",
+                [crate::api_tokens::hash(&token)],
+            )
+            .unwrap();
+            token
+        };
+        // Token management needs the proxy identity, not a token.
+        let (status, _) = call(
+            &state,
+            "POST",
+            "/api/tokens",
+            Some(&token),
+            json!({"name": "cli", "scopes": ["feed:read"]}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        state.config.is_production = false;
+        let (status, created) = call(
+            &state,
+            "POST",
+            "/api/tokens",
+            None,
+            json!({"name": "cli", "scopes": ["videos:hide", "feed:read"], "expires_in_days": 30}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(created["scopes"], json!(["feed:read", "videos:hide"]));
+        assert!(created["expires_at"].is_string());
+        let cli = created["token"].as_str().unwrap().to_string();
+        assert!(cli.starts_with(created["prefix"].as_str().unwrap()));
+
+        state.config.is_production = true;
+        let (status, _) = call(&state, "GET", "/api/feed", Some(&cli), Value::Null).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = call(
+            &state,
+            "POST",
+            "/api/channels",
+            Some(&cli),
+            json!({"channel_id": "UCxxxxxxxxxxxxxxxxxxxxxx"}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let (status, _) = call(&state, "GET", "/api/feed", Some("ysf_bogus"), Value::Null).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        state.config.is_production = false;
+        let (_, list) = call(&state, "GET", "/api/tokens", None, Value::Null).await;
+        assert_eq!(list.as_array().unwrap().len(), 2);
+        assert!(list[1]["last_used_at"].is_string());
+        assert!(list[1].get("token").is_none(), "never listed in plaintext");
+
+        let id = created["id"].as_i64().unwrap();
+        let uri = format!("/api/tokens/{id}");
+        assert_eq!(
+            call(&state, "DELETE", &uri, None, Value::Null).await.0,
+            StatusCode::OK
+        );
+        assert_eq!(
+            call(&state, "DELETE", &uri, None, Value::Null).await.0,
+            StatusCode::NOT_FOUND
+        );
+        let (status, _) = call(&state, "GET", "/api/feed", Some(&cli), Value::Null).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED, "revoked");
+    }
+
+    #[tokio::test]
+    async fn invalid_token_requests_are_rejected_with_400() {
+        let state = setup_state();
+        for body in [
+            json!({"name": " ", "scopes": ["feed:read"]}),
+            json!({"name": "x", "scopes": []}),
+            json!({"name": "x", "scopes": ["root"]}),
+            json!({"name": "x", "scopes": ["admin"], "expires_in_days": 0}),
+        ] {
+            assert_eq!(
+                call(&state, "POST", "/api/tokens", None, body.clone())
+                    .await
+                    .0,
+                StatusCode::BAD_REQUEST,
+                "{body} must be rejected"
+            );
+        }
+    }
+}
@@ -0,0 +1,456 @@
+use crate::cache::Cache;
+use crate::error::AppError;
+use crate::middleware::UserId;
+use crate::openapi::*;
+use crate::state::AppState;
+use axum::extract::{Extension, State};
+use axum::routing::{get, put};
+use axum::{Json, Router};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/feed/unread-counts", get(get_unread_counts))
+        .route("/api/feed/new-count", get(get_new_count))
+        .route("/api/feed/last-seen", put(update_last_seen))
+}
+
+/// 1 when the `uc` user's feed has gained `v` since they last marked it seen
+/// (`users.last_seen_at`; never marked means everything is new). Videos
+/// without a `fetched_at` predate the marker and are never new.
+pub const IS_NEW: &str = "COALESCE(v.fetched_at > (
+             SELECT COALESCE(last_seen_at, 0) FROM users WHERE id = uc.user_id), 0)";
+
+const CACHE_PREFIX: &str = "unread_counts:";
+
+/// Backstop for changes that do not invalidate explicitly (enrichment
+/// reclassifying a Short or a members-only video).
+const CACHE_TTL_SECS: u64 = 300;
+
+fn cache_key(user_id: i64) -> String {
+    format!("{CACHE_PREFIX}{user_id}")
+}
+
+/// Drop a user's cached counts after anything deciding their feed changed:
+/// hidden or queued videos, mute rules, subscriptions, channel settings or
+/// groups.
+pub fn invalidate(cache: &Cache, user_id: i64) {
+    cache.remove(&cache_key(user_id));
+}
+
+/// Drop everyone's cached counts after new videos arrived.
+pub fn invalidate_all(cache: &Cache) {
+    cache.remove_prefix(CACHE_PREFIX);
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/feed/unread-counts",
+    tag = "動画フィード",
+    summary = "未視聴件数",
+    description = "フィードに表示される動画 (/api/feed と同じ条件。非表示・ミュート・後で見るを除く) の件数を、全体・グループごと・チャンネルごとに返す。\n\n- groups はユーザーのすべてのグループ (0件を含む)\n- channels は未視聴の動画があるチャンネルのみ\n- 結果はキャッシュされ、新着動画の受信と、非表示・後で見る・ミュートルール・購読・チャンネル設定・グループの変更で更新される",
+    responses(
+        (status = 200, description = "未視聴件数", body = UnreadCounts),
+        (status = 401, description = "未認証", body = ErrorResponse),
+    ),
+)]
+async fn get_unread_counts(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+) -> Result<Json<Value>, AppError> {
+    let key = cache_key(user_id.0);
+    if let Some(cached) = state.cache.get(&key) {
+        return Ok(Json(cached));
+    }
+
+    // Per-channel counts with the feed's predicates, and the group totals
+    // summed from them, in one pass.
+    let sql = format!(
+        "WITH unread AS (
+           SELECT v.channel_id, COUNT(*) AS n
+           FROM videos v
+           JOIN user_channels uc ON uc.channel_id = v.channel_id AND uc.user_id = ?1
+           LEFT JOIN user_videos uv ON uv.video_id = v.id AND uv.user_id = ?1
+           WHERE {visible}
+             AND {not_queued}
+           GROUP BY v.channel_id
+         )
+         SELECT 'channel', channel_id, n FROM unread
+         UNION ALL
+         SELECT 'group', CAST(g.id AS TEXT), COALESCE(SUM(u.n), 0)
+         FROM groups g
+         LEFT JOIN channel_groups cg ON cg.group_id = g.id
+         LEFT JOIN unread u ON u.channel_id = cg.channel_id
+         WHERE g.user_id = ?1
+         GROUP BY g.id",
+        visible = crate::visibility::VISIBLE,
+        not_queued = crate::visibility::NOT_QUEUED,
+    );
+    let rows = {
+        let conn = state.db.lock().unwrap();
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt
+            .query_map([user_id.0], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, i64>(2)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        rows
+    };
+
+    let mut total = 0;
+    let mut groups = Map::new();
+    let mut channels = Map::new();
+    for (kind, id, count) in rows {
+        if kind == "channel" {
+            total += count;
+            channels.insert(id, json!(count));
+        } else {
+            groups.insert(id, json!(count));
+        }
+    }
+    let counts = json!({"total": total, "groups": groups, "channels": channels});
+    state.cache.set(&key, counts.clone(), Some(CACHE_TTL_SECS));
+    Ok(Json(counts))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/feed/new-count",
+    tag = "動画フィード",
+    summary = "新着件数",
+    description = "フィードに表示される動画 (/api/feed と同じ条件) のうち、前回の閲覧 (PUT /api/feed/last-seen) 以降に受信したものの件数を返す。",
+    responses(
+        (status = 200, description = "新着件数と閲覧位置", body = NewCount),
+        (status = 401, description = "未認証", body = ErrorResponse),
+    ),
+)]
+async fn get_new_count(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+) -> Result<Json<Value>, AppError> {
+    let conn = state.db.lock().unwrap();
+    let (count, last_seen_at): (i64, Option<i64>) = conn.query_row(
+        &format!(
+            "SELECT (SELECT COUNT(*)
+                     FROM videos v
+                     JOIN user_channels uc ON uc.channel_id = v.channel_id AND uc.user_id = ?1
+                     LEFT JOIN user_videos uv ON uv.video_id = v.id AND uv.user_id = ?1
+                     WHERE {visible}
+                       AND {not_queued}
+                       AND {is_new}),
+                    last_seen_at
+             FROM users WHERE id = ?1",
+            visible = crate::visibility::VISIBLE,
+            not_queued = crate::visibility::NOT_QUEUED,
+            is_new = IS_NEW,
+        ),
+        [user_id.0],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+    Ok(Json(json!({
+        "count": count,
+        "last_seen_at": last_seen_at.and_then(crate::util::unix_to_rfc3339),
+    })))
+}
+
+#[derive(Deserialize, Default, utoipa::ToSchema)]
+pub(crate) struct LastSeenBody {
+    /// 閲覧した日時 (RFC 3339。省略時は現在時刻)
+    last_seen_at: Option<String>,
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/feed/last-seen",
+    tag = "動画フィード",
+    summary = "閲覧位置の更新",
+    description = "フィードをここまで見たことを記録する。閲覧位置はサーバーに保存され、すべての端末で共有される。本文は省略可 (現在時刻)。\n\n閲覧位置は戻らない: 記録済みより古い日時を送っても変わらない (別の端末が先に進めた位置を保つ)。未来の日時は現在時刻に丸める。",
+    request_body(content = Option<LastSeenBody>),
+    responses(
+        (status = 200, description = "更新後の閲覧位置", body = LastSeenResponse),
+        (status = 400, description = "不正な日時", body = ErrorResponse),
+        (status = 401, description = "未認証", body = ErrorResponse),
+    ),
+)]
+async fn update_last_seen(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    body: Option<Json<Option<LastSeenBody>>>,
+) -> Result<Json<Value>, AppError> {
+    let body = body.and_then(|Json(body)| body).unwrap_or_default();
+    let now = crate::util::now_unix();
+    let seen = match body.last_seen_at.as_deref() {
+        Some(value) => crate::util::rfc3339_to_unix(value)
+            .ok_or_else(|| {
+                AppError::BadRequest("last_seen_at must be an RFC 3339 timestamp".to_string())
+            })?
+            .min(now),
+        None => now,
+    };
+    let conn = state.db.lock().unwrap();
+    // Only forward: a device that catches up late must not mark the videos
+    // another device has already seen as new again.
+    let last_seen_at: i64 = conn.query_row(
+        "UPDATE users SET last_seen_at = MAX(COALESCE(last_seen_at, 0), ?2)
+         WHERE id = ?1 RETURNING last_seen_at",
+        rusqlite::params![user_id.0, seen],
+        |row| row.get(0),
+    )?;
+    Ok(Json(json!({
+        "last_seen_at": crate::util::unix_to_rfc3339(last_seen_at),
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    // Unread Counts API Spec
+    //
+    // GET /api/feed/unread-counts counts what /api/feed would list (same
+    // visibility rules, Watch Later excluded) in total, per group (every
+    // group of the user, zeros included) and per channel (non-zero only).
+    // The result is cached per user; hiding, unhiding, Watch Later, mute
+    // rule, subscription, channel setting and group changes invalidate that
+    // user's entry, and new pushed videos invalidate all.
+    //
+    // Feed items carry is_new for videos fetched after the user's server-side
+    // last_seen_at (all of them before the first visit is recorded); new-count
+    // counts those among the feed's items. PUT /api/feed/last-seen moves the
+    // cursor forward only, so devices cannot undo each other's progress.
+    // The acting user is the dev-bypass first DB user (user 1).
+
+    use super::{invalidate_all, routes};
+    use crate::middleware::auth_middleware;
+    use crate::state::AppState;
+    use axum::body::to_bytes;
+    use axum::http::{Request, StatusCode};
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+
+    fn setup_state() -> AppState {
+        let state = AppState::test();
+        state
+            .db
+            .lock()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO users (email) VALUES ('a@example.com'), ('b@example.com');
+                 INSERT INTO channels (id, title) VALUES ('UC1', 'Ch1'), ('UC2', 'Ch2'), ('UC3', 'Ch3');
+                 INSERT INTO user_channels (user_id, channel_id, hide_shorts) VALUES
+                   (1, 'UC1', 0), (1, 'UC2', 1), (1, 'UC3', 0), (2, 'UC1', 0);
+                 INSERT INTO groups (user_id, name) VALUES (1, 'both'), (1, 'empty'), (2, 'theirs');
+                 INSERT INTO channel_groups (channel_id, group_id) VALUES ('UC1', 1), ('UC2', 1), ('UC1', 3);
+                 INSERT INTO videos (id, channel_id, title, is_short, is_members_only) VALUES
+                   ('a1', 'UC1', 'A1', 0, 0), ('a2', 'UC1', 'A2', 0, 0), ('a3', 'UC1', 'A3', 0, 1),
+                   ('b1', 'UC2', 'B1', 0, 0), ('b2', 'UC2', 'B2', 1, 0),
+                   ('c1', 'UC3', 'C1', 0, 0);
+                 INSERT INTO mute_rules (user_id, pattern) VALUES (1, 'C1');",
+            )
+            .unwrap();
+        state
+    }
+
+    async fn call(state: &AppState, method: &str, uri: &str, body: Value) -> (StatusCode, Value) {
+        let resp = axum::Router::new()
+            .merge(routes())
+            .merge(super::super::feed::routes())
+            .merge(super::super::watch_later::routes())
+            .merge(super::super::mute_rules::routes())
+            .merge(super::super::channels::routes())
+            .merge(super::super::groups::routes())
+            .layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                auth_middleware,
+            ))
+            .with_state(state.clone())
+            .oneshot(
+                Request::builder()
+                    .method(method)
+                    .uri(uri)
+                    .header("content-type", "application/json")
+                    .body(axum::body::Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let status = resp.status();
+        let bytes = to_bytes(resp.into_body(), 1024 * 1024).await.unwrap();
+        (
+            status,
+            serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+        )
+    }
+
+    async fn counts(state: &AppState) -> Value {
+        let (status, counts) = call(state, "GET", "/api/feed/unread-counts", Value::Null).await;
+        assert_eq!(status, StatusCode::OK);
+        counts
+    }
+
+    #[tokio::test]
+    async fn counts_match_the_feed_per_group_and_channel() {
+        let state = setup_state();
+        assert_eq!(
+            counts(&state).await,
+            json!({
+                "total": 3,
+                "groups": {"1": 3, "2": 0},
+                "channels": {"UC1": 2, "UC2": 1},
+            })
+        );
+        let (_, feed) = call(&state, "GET", "/api/feed", Value::Null).await;
+        assert_eq!(feed.as_array().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn hide_and_watch_later_invalidate_the_cached_counts() {
+        let state = setup_state();
+        assert_eq!(counts(&state).await["total"], 3);
+
+        call(&state, "PATCH", "/api/videos/a1/hide", Value::Null).await;
+        assert_eq!(counts(&state).await["channels"]["UC1"], 1);
+
+        call(
+            &state,
+            "POST",
+            "/api/watch-later",
+            json!({"video_id": "b1"}),
+        )
+        .await;
+        let after_queue = counts(&state).await;
+        assert_eq!(after_queue["total"], 1);
+        assert_eq!(after_queue["channels"].get("UC2"), None);
+
+        call(&state, "PATCH", "/api/videos/a1/unhide", Value::Null).await;
+        assert_eq!(counts(&state).await["total"], 2);
+    }
+
+    #[tokio::test]
+    async fn feed_settings_invalidate_the_cached_counts() {
+        let state = setup_state();
+        assert_eq!(counts(&state).await["total"], 3);
+
+        call(&state, "DELETE", "/api/mute-rules/1", Value::Null).await;
+        assert_eq!(counts(&state).await["channels"]["UC3"], 1);
+
+        call(
+            &state,
+            "PATCH",
+            "/api/channels/UC2",
+            json!({"hide_shorts": 0}),
+        )
+        .await;
+        assert_eq!(counts(&state).await["channels"]["UC2"], 2);
+
+        call(
+            &state,
+            "PUT",
+            "/api/groups/2/channels",
+            json!({"channelIds": ["UC3"]}),
+        )
+        .await;
+        assert_eq!(counts(&state).await["groups"]["2"], 1);
+
+        call(&state, "DELETE", "/api/channels/UC3", Value::Null).await;
+        let after_remove = counts(&state).await;
+        assert_eq!(after_remove["total"], 4);
+        assert_eq!(after_remove["groups"]["2"], 0);
+
+        call(&state, "POST", "/api/mute-rules", json!({"pattern": "A1"})).await;
+        assert_eq!(counts(&state).await["total"], 3);
+    }
+
+    #[tokio::test]
+    async fn counts_are_cached_until_new_videos_arrive() {
+        let state = setup_state();
+        assert_eq!(counts(&state).await["total"], 3);
+        state
+            .db
+            .lock()
+            .unwrap()
+            .execute(
+                "INSERT INTO videos (id, channel_id, title) VALUES ('a4', 'UC1', 'A4')",
+                [],
+            )
+            .unwrap();
+        assert_eq!(counts(&state).await["total"], 3, "served from the cache");
+
+        invalidate_all(&state.cache);
+        assert_eq!(counts(&state).await["total"], 4);
+    }
+
+    #[tokio::test]
+    async fn is_new_and_new_count_follow_the_last_seen_cursor() {
+        let state = setup_state();
+        state
+            .db
+            .lock()
+            .unwrap()
+            .execute_batch(
+                "UPDATE videos SET fetched_at = 1000 WHERE id IN ('a1', 'b1');
+                 UPDATE videos SET fetched_at = 3000 WHERE id = 'a2';",
+            )
+            .unwrap();
+
+        let (_, new) = call(&state, "GET", "/api/feed/new-count", Value::Null).await;
+        assert_eq!(new, json!({"count": 3, "last_seen_at": null}));
+
+        let (status, seen) = call(
+            &state,
+            "PUT",
+            "/api/feed/last-seen",
+            json!({"last_seen_at": "1970-01-01T00:33:20Z"}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(seen["last_seen_at"], "1970-01-01T00:33:20Z");
+
+        let (_, feed) = call(&state, "GET", "/api/feed", Value::Null).await;
+        let flags: Vec<(&str, i64)> = feed
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|item| {
+                (
+                    item["id"].as_str().unwrap(),
+                    item["is_new"].as_i64().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(flags, [("b1", 0), ("a2", 1), ("a1", 0)]);
+        let (_, new) = call(&state, "GET", "/api/feed/new-count", Value::Null).await;
+        assert_eq!(new["count"], 1);
+
+        // Another device reporting an older position does not move it back.
+        let (_, seen) = call(
+            &state,
+            "PUT",
+            "/api/feed/last-seen",
+            json!({"last_seen_at": "1970-01-01T00:00:10Z"}),
+        )
+        .await;
+        assert_eq!(seen["last_seen_at"], "1970-01-01T00:33:20Z");
+
+        let (status, _) = call(&state, "PUT", "/api/feed/last-seen", Value::Null).await;
+        assert_eq!(status, StatusCode::OK);
+        let (_, new) = call(&state, "GET", "/api/feed/new-count", Value::Null).await;
+        assert_eq!(new["count"], 0);
+    }
+
+    #[tokio::test]
+    async fn last_seen_rejects_invalid_timestamps() {
+        let state = setup_state();
+        let (status, _) = call(
+            &state,
+            "PUT",
+            "/api/feed/last-seen",
+            json!({"last_seen_at": "yesterday"}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+}
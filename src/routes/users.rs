@@ -0,0 +1,547 @@
+use crate::error::AppError;
+use crate::middleware::{require_master, UserId};
+use crate::openapi::*;
+use crate::state::AppState;
+use axum::extract::{Extension, Path, State};
+use axum::routing::{get, patch};
+use axum::{Json, Router};
+use rusqlite::{Connection, OptionalExtension};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/admin/users", get(get_users).post(invite_user))
+        .route(
+            "/api/admin/users/{id}",
+            patch(update_user).delete(delete_user),
+        )
+}
+
+const ROLES: [&str; 2] = ["master", "member"];
+
+const USER_COLUMNS: &str = "u.id, u.email, u.role, u.disabled_at IS NOT NULL, u.created_at,
+     (SELECT COUNT(*) FROM user_channels uc WHERE uc.user_id = u.id)";
+
+fn user_json(row: &rusqlite::Row) -> rusqlite::Result<Value> {
+    Ok(json!({
+        "id": row.get::<_, i64>(0)?,
+        "email": row.get::<_, String>(1)?,
+        "role": row.get::<_, String>(2)?,
+        "is_disabled": row.get::<_, i64>(3)?,
+        "created_at": crate::util::row_timestamp_to_rfc3339(row, 4)?,
+        "channel_count": row.get::<_, i64>(5)?,
+    }))
+}
+
+fn load_user(conn: &Connection, id: i64) -> Result<Option<Value>, AppError> {
+    Ok(conn
+        .query_row(
+            &format!("SELECT {USER_COLUMNS} FROM users u WHERE u.id = ?1"),
+            [id],
+            user_json,
+        )
+        .optional()?)
+}
+
+fn validate_role(role: &str) -> Result<(), AppError> {
+    if ROLES.contains(&role) {
+        Ok(())
+    } else {
+        Err(AppError::BadRequest(
+            "role must be master or member".to_string(),
+        ))
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/users",
+    tag = "管理",
+    summary = "ユーザー一覧",
+    description = "招待済みを含む全ユーザーを登録順に返す。master ユーザーのみ。",
+    responses(
+        (status = 200, description = "ユーザー一覧", body = Vec<UserItem>),
+        (status = 401, description = "未認証", body = ErrorResponse),
+        (status = 403, description = "master 以外", body = ErrorResponse),
+    ),
+)]
+async fn get_users(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+) -> Result<Json<Value>, AppError> {
+    let conn = state.db.lock().unwrap();
+    require_master(&conn, user_id)?;
+    let mut stmt = conn.prepare(&format!("SELECT {USER_COLUMNS} FROM users u ORDER BY u.id"))?;
+    let rows = stmt
+        .query_map([], user_json)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Json(Value::Array(rows)))
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub(crate) struct InviteUserBody {
+    /// 招待するメールアドレス (認証プロキシが渡すものと同じ。大文字小文字は区別しない)
+    email: String,
+    /// ロール (master / member, デフォルト: member)
+    role: Option<String>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/users",
+    tag = "管理",
+    summary = "ユーザー招待",
+    description = "メールアドレスを登録し、そのユーザーが Cloudflare Access (または認証プロキシ) 経由でログインできるようにする。未登録のメールアドレスは最初のユーザー以外拒否される。master ユーザーのみ。",
+    request_body(content = InviteUserBody),
+    responses(
+        (status = 201, description = "招待したユーザー", body = UserItem),
+        (status = 400, description = "不正なメールアドレス・ロール、または登録済み", body = ErrorResponse),
+        (status = 401, description = "未認証", body = ErrorResponse),
+        (status = 403, description = "master 以外", body = ErrorResponse),
+    ),
+)]
+async fn invite_user(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Json(body): Json<InviteUserBody>,
+) -> Result<(axum::http::StatusCode, Json<Value>), AppError> {
+    let email = body.email.trim().to_lowercase();
+    let valid = email.len() <= 254
+        && !email.contains(char::is_whitespace)
+        && email
+            .split_once('@')
+            .is_some_and(|(local, domain)| !local.is_empty() && !domain.is_empty());
+    if !valid {
+        return Err(AppError::BadRequest("Invalid email".to_string()));
+    }
+    let role = body.role.unwrap_or_else(|| "member".to_string());
+    validate_role(&role)?;
+
+    let conn = state.db.lock().unwrap();
+    require_master(&conn, user_id)?;
+    let exists = conn
+        .query_row(
+            "SELECT 1 FROM users WHERE email = ?1 COLLATE NOCASE",
+            [&email],
+            |_| Ok(()),
+        )
+        .optional()?
+        .is_some();
+    if exists {
+        return Err(AppError::BadRequest("User already exists".to_string()));
+    }
+    let now = crate::util::now_unix();
+    conn.execute(
+        "INSERT INTO users (email, role, rss_token, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?4)",
+        rusqlite::params![email, role, uuid::Uuid::new_v4().to_string(), now],
+    )?;
+    let id = conn.last_insert_rowid();
+    crate::audit::record(
+        &conn,
+        Some(user_id.0),
+        crate::audit::USER_INVITE,
+        &id.to_string(),
+        json!({"email": email, "role": role}),
+    );
+    tracing::info!("[admin] Invited {} as {} (id={})", email, role, id);
+    let user = load_user(&conn, id)?
+        .ok_or_else(|| AppError::Internal("Invited user vanished".to_string()))?;
+    Ok((axum::http::StatusCode::CREATED, Json(user)))
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub(crate) struct UpdateUserBody {
+    /// ロール (master / member)
+    role: Option<String>,
+    /// 無効化 (0: 有効, 1: 無効。無効なユーザーはログインできない)
+    is_disabled: Option<i64>,
+}
+
+#[utoipa::path(
+    patch,
+    path = "/api/admin/users/{id}",
+    tag = "管理",
+    summary = "ユーザー更新",
+    description = "ロールの変更・アカウントの無効化/有効化。自分自身のロール変更・無効化はできない。master ユーザーのみ。",
+    params(("id" = i64, Path, description = "ユーザーID")),
+    request_body(content = UpdateUserBody),
+    responses(
+        (status = 200, description = "更新後のユーザー", body = UserItem),
+        (status = 400, description = "バリデーションエラー", body = ErrorResponse),
+        (status = 401, description = "未認証", body = ErrorResponse),
+        (status = 403, description = "master 以外", body = ErrorResponse),
+        (status = 404, description = "ユーザーが存在しない", body = ErrorResponse),
+    ),
+)]
+async fn update_user(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Path(id): Path<i64>,
+    Json(body): Json<UpdateUserBody>,
+) -> Result<Json<Value>, AppError> {
+    if let Some(role) = &body.role {
+        validate_role(role)?;
+    }
+    if let Some(v) = body.is_disabled {
+        if v != 0 && v != 1 {
+            return Err(AppError::BadRequest(
+                "is_disabled must be 0 or 1".to_string(),
+            ));
+        }
+    }
+
+    let conn = state.db.lock().unwrap();
+    require_master(&conn, user_id)?;
+    if load_user(&conn, id)?.is_none() {
+        return Err(AppError::NotFound("User not found".to_string()));
+    }
+    // The acting master must not lock themselves (and possibly everyone) out.
+    if id == user_id.0 && (body.role.as_deref() == Some("member") || body.is_disabled == Some(1)) {
+        return Err(AppError::BadRequest(
+            "Cannot demote or disable yourself".to_string(),
+        ));
+    }
+
+    let now = crate::util::now_unix();
+    if let Some(role) = &body.role {
+        conn.execute(
+            "UPDATE users SET role = ?1, updated_at = ?2 WHERE id = ?3",
+            rusqlite::params![role, now, id],
+        )?;
+    }
+    if let Some(v) = body.is_disabled {
+        // Keep the original timestamp when already disabled.
+        conn.execute(
+            "UPDATE users SET disabled_at = CASE WHEN ?1 = 1 THEN COALESCE(disabled_at, ?2) END,
+                              updated_at = ?2
+             WHERE id = ?3",
+            rusqlite::params![v, now, id],
+        )?;
+    }
+    crate::audit::record(
+        &conn,
+        Some(user_id.0),
+        crate::audit::USER_UPDATE,
+        &id.to_string(),
+        json!({"role": body.role, "is_disabled": body.is_disabled}),
+    );
+    load_user(&conn, id)?
+        .map(Json)
+        .ok_or_else(|| AppError::NotFound("User not found".to_string()))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/admin/users/{id}",
+    tag = "管理",
+    summary = "ユーザー削除",
+    description = "ユーザーと、その登録チャンネル・非表示/視聴履歴・グループ・通知設定などすべてのデータを削除する。他に登録者がいなくなったチャンネルは WebSub 購読を解除して削除する。自分自身は削除できない。master ユーザーのみ。",
+    params(("id" = i64, Path, description = "ユーザーID")),
+    responses(
+        (status = 200, description = "成功", body = OkResponse),
+        (status = 400, description = "自分自身", body = ErrorResponse),
+        (status = 401, description = "未認証", body = ErrorResponse),
+        (status = 403, description = "master 以外", body = ErrorResponse),
+        (status = 404, description = "ユーザーが存在しない", body = ErrorResponse),
+    ),
+)]
+async fn delete_user(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Path(id): Path<i64>,
+) -> Result<Json<Value>, AppError> {
+    let email = {
+        let conn = state.db.lock().unwrap();
+        require_master(&conn, user_id)?;
+        let user = load_user(&conn, id)?
+            .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+        user["email"].clone()
+    };
+    if id == user_id.0 {
+        return Err(AppError::BadRequest("Cannot delete yourself".to_string()));
+    }
+
+    let removed = remove_user(&state, id).await?;
+    crate::audit::record(
+        &state.db.lock().unwrap(),
+        Some(user_id.0),
+        crate::audit::USER_DELETE,
+        &id.to_string(),
+        json!({"email": email, "channels_removed": removed}),
+    );
+    tracing::info!("[admin] Deleted user {} ({} channels removed)", id, removed);
+    Ok(Json(json!({"ok": true})))
+}
+
+/// Delete a user and everything they own, returning how many channels they
+/// were subscribed to.
+pub(crate) async fn remove_user(state: &AppState, id: i64) -> Result<usize, AppError> {
+    // Unsubscribing from everything goes through the sync diff so orphaned
+    // channels are cleaned up (and unsubscribed at the hub) exactly as when
+    // the user removes them; the rest of the user's data cascades.
+    let result = crate::sync::channel_sync::sync_subscriptions(
+        state,
+        id,
+        &[],
+        &std::collections::HashMap::new(),
+    )
+    .await?;
+    super::channels::unsubscribe_orphans(state, result.removed_orphan_secrets);
+
+    let conn = state.db.lock().unwrap();
+    conn.execute("DELETE FROM users WHERE id = ?1", [id])?;
+    Ok(result.removed.len())
+}
+
+#[cfg(test)]
+mod tests {
+    // User Administration API Spec
+    //
+    // Master-only (members get 403 from the middleware and the handlers).
+    // - POST invites an email (lower-cased, member by default); duplicates → 400
+    // - GET lists every user with role, disabled flag and channel count
+    // - PATCH changes role / disables; the caller cannot demote or disable
+    //   themselves
+    // - DELETE removes the user with all their data; channels nobody else
+    //   subscribes to are deleted, shared ones survive
+
+    use super::routes;
+    use crate::middleware::auth_middleware;
+    use crate::state::AppState;
+    use axum::body::to_bytes;
+    use axum::http::{Request, StatusCode};
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+
+    fn setup_state() -> AppState {
+        let state = AppState::test();
+        state
+            .db
+            .lock()
+            .unwrap()
+            .execute(
+                "INSERT INTO users (email, role) VALUES ('admin@example.com', 'master')",
+                [],
+            )
+            .unwrap();
+        state
+    }
+
+    async fn call(
+        state: &AppState,
+        method: &str,
+        uri: &str,
+        as_email: &str,
+        body: Value,
+    ) -> (StatusCode, Value) {
+        let resp = axum::Router::new()
+            .merge(routes())
+            .layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                auth_middleware,
+            ))
+            .with_state(state.clone())
+            .oneshot(
+                Request::builder()
+                    .method(method)
+                    .uri(uri)
+                    .header("Cf-Access-Authenticated-User-Email", as_email)
+                    .header("content-type", "application/json")
+                    .body(axum::body::Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let status = resp.status();
+        let bytes = to_bytes(resp.into_body(), 1024 * 1024).await.unwrap();
+        (
+            status,
+            serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+        )
+    }
+
+    const ADMIN: &str = "admin@example.com";
+
+    #[tokio::test]
+    async fn invite_list_and_member_login() {
+        let state = setup_state();
+        let (status, user) = call(
+            &state,
+            "POST",
+            "/api/admin/users",
+            ADMIN,
+            json!({"email": " Bob@Example.com "}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(user["email"], "bob@example.com");
+        assert_eq!(user["role"], "member");
+        assert_eq!(user["is_disabled"], 0);
+
+        let (status, _) = call(
+            &state,
+            "POST",
+            "/api/admin/users",
+            ADMIN,
+            json!({"email": "BOB@example.com"}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "duplicate invitation");
+        for body in [
+            json!({"email": "not-an-email"}),
+            json!({"email": "c@example.com", "role": "owner"}),
+        ] {
+            let (status, _) = call(&state, "POST", "/api/admin/users", ADMIN, body).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+        }
+
+        let (_, users) = call(&state, "GET", "/api/admin/users", ADMIN, Value::Null).await;
+        let emails: Vec<&str> = users
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|u| u["email"].as_str().unwrap())
+            .collect();
+        assert_eq!(emails, ["admin@example.com", "bob@example.com"]);
+
+        // The invited member can now sign in, but not administer.
+        let (status, _) = call(
+            &state,
+            "GET",
+            "/api/admin/users",
+            "bob@example.com",
+            Value::Null,
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn disable_and_promote_but_never_lock_out_yourself() {
+        let state = setup_state();
+        call(
+            &state,
+            "POST",
+            "/api/admin/users",
+            ADMIN,
+            json!({"email": "bob@example.com"}),
+        )
+        .await;
+
+        let (status, user) = call(
+            &state,
+            "PATCH",
+            "/api/admin/users/2",
+            ADMIN,
+            json!({"is_disabled": 1}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(user["is_disabled"], 1);
+        let (status, _) = call(
+            &state,
+            "GET",
+            "/api/admin/users",
+            "bob@example.com",
+            Value::Null,
+        )
+        .await;
+        assert_eq!(
+            status,
+            StatusCode::UNAUTHORIZED,
+            "disabled users cannot log in"
+        );
+
+        let (_, user) = call(
+            &state,
+            "PATCH",
+            "/api/admin/users/2",
+            ADMIN,
+            json!({"is_disabled": 0, "role": "master"}),
+        )
+        .await;
+        assert_eq!(user["is_disabled"], 0);
+        assert_eq!(user["role"], "master");
+        let (status, _) = call(
+            &state,
+            "GET",
+            "/api/admin/users",
+            "bob@example.com",
+            Value::Null,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        for body in [json!({"is_disabled": 1}), json!({"role": "member"})] {
+            let (status, _) = call(&state, "PATCH", "/api/admin/users/1", ADMIN, body).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+        }
+        let (status, _) = call(
+            &state,
+            "PATCH",
+            "/api/admin/users/2",
+            ADMIN,
+            json!({"is_disabled": 2}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = call(
+            &state,
+            "PATCH",
+            "/api/admin/users/99",
+            ADMIN,
+            json!({"role": "member"}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn delete_removes_user_data_and_orphaned_channels_only() {
+        let state = setup_state();
+        {
+            let conn = state.db.lock().unwrap();
+            conn.execute_batch(
+                "INSERT INTO users (email, role) VALUES ('bob@example.com', 'member');
+                 INSERT INTO channels (id, title) VALUES ('UC_shared', 'S'), ('UC_bob', 'B');
+                 INSERT INTO user_channels (user_id, channel_id) VALUES (1, 'UC_shared'), (2, 'UC_shared'), (2, 'UC_bob');
+                 INSERT INTO videos (id, channel_id, title) VALUES ('v1', 'UC_shared', 'V');
+                 INSERT INTO user_videos (user_id, video_id, is_hidden) VALUES (2, 'v1', 1);
+                 INSERT INTO groups (user_id, name) VALUES (2, 'Music');
+                 INSERT INTO channel_groups (channel_id, group_id) VALUES ('UC_shared', 1);",
+            )
+            .unwrap();
+        }
+
+        let (status, _) = call(&state, "DELETE", "/api/admin/users/1", ADMIN, Value::Null).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "cannot delete yourself");
+
+        let (status, _) = call(&state, "DELETE", "/api/admin/users/2", ADMIN, Value::Null).await;
+        assert_eq!(status, StatusCode::OK);
+
+        {
+            let conn = state.db.lock().unwrap();
+            let count = |sql: &str| -> i64 { conn.query_row(sql, [], |row| row.get(0)).unwrap() };
+            assert_eq!(count("SELECT COUNT(*) FROM users"), 1);
+            assert_eq!(
+                count("SELECT COUNT(*) FROM user_channels WHERE user_id = 2"),
+                0
+            );
+            assert_eq!(count("SELECT COUNT(*) FROM user_videos"), 0);
+            assert_eq!(count("SELECT COUNT(*) FROM groups"), 0);
+            assert_eq!(count("SELECT COUNT(*) FROM channel_groups"), 0);
+            assert_eq!(
+                count("SELECT COUNT(*) FROM channels WHERE id = 'UC_shared'"),
+                1
+            );
+            assert_eq!(
+                count("SELECT COUNT(*) FROM channels WHERE id = 'UC_bob'"),
+                0
+            );
+        }
+
+        let (status, _) = call(&state, "DELETE", "/api/admin/users/2", ADMIN, Value::Null).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+}
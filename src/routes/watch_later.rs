@@ -0,0 +1,495 @@
+use crate::error::AppError;
+use crate::middleware::UserId;
+use crate::openapi::*;
+use crate::state::AppState;
+use axum::extract::{Extension, Path, State};
+use axum::routing::{delete, get, post, put};
+use axum::{Json, Router};
+use rusqlite::{Connection, OptionalExtension};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/api/watch-later",
+            get(get_watch_later).post(add_to_watch_later),
+        )
+        .route("/api/watch-later/reorder", put(reorder_watch_later))
+        .route("/api/watch-later/next", post(pop_next))
+        .route("/api/watch-later/play-all", get(get_play_all))
+        .route(
+            "/api/watch-later/{video_id}",
+            delete(remove_from_watch_later),
+        )
+}
+
+/// YouTube's `watch_videos` accepts at most this many IDs.
+const PLAY_ALL_LIMIT: i64 = 50;
+
+const ITEM_SELECT: &str = "SELECT v.id, v.channel_id, v.title, v.published_at,
+            v.duration, v.is_short, v.is_livestream, v.livestream_ended_at,
+            c.title AS channel_title, c.thumbnail_url AS channel_thumbnail,
+            wl.created_at
+     FROM watch_later wl
+     JOIN videos v ON v.id = wl.video_id
+     JOIN channels c ON c.id = v.channel_id
+     WHERE wl.user_id = ?1";
+
+fn item_json(row: &rusqlite::Row) -> rusqlite::Result<Value> {
+    let mut value = super::feed::video_json(row, None)?;
+    value["added_at"] = json!(crate::util::row_timestamp_to_rfc3339(row, 10)?);
+    Ok(value)
+}
+
+/// The queue's video IDs, front first.
+fn queued_ids(conn: &Connection, user_id: i64) -> rusqlite::Result<Vec<String>> {
+    let mut stmt = conn.prepare(
+        "SELECT video_id FROM watch_later WHERE user_id = ?1 ORDER BY position, created_at",
+    )?;
+    let ids = stmt
+        .query_map([user_id], |row| row.get(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(ids)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/watch-later",
+    tag = "動画フィード",
+    summary = "後で見る一覧",
+    description = "後で見るに追加した動画をキューの順に返す。",
+    responses(
+        (status = 200, description = "後で見る一覧 (先頭から順)", body = Vec<WatchLaterItem>),
+        (status = 401, description = "未認証", body = ErrorResponse),
+    ),
+)]
+async fn get_watch_later(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+) -> Result<Json<Value>, AppError> {
+    let rows = {
+        let conn = state.db.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "{ITEM_SELECT} ORDER BY wl.position, wl.created_at"
+        ))?;
+        let rows = stmt
+            .query_map([user_id.0], item_json)?
+            .collect::<Result<Vec<_>, _>>()?;
+        rows
+    };
+    Ok(Json(Value::Array(rows)))
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub(crate) struct AddWatchLaterBody {
+    /// 動画ID
+    video_id: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/watch-later",
+    tag = "動画フィード",
+    summary = "後で見るに追加",
+    description = "動画をキューの末尾に追加する。追加した動画はフィードから外れるが、視聴済み (非表示・視聴履歴) にはならない。既に入っている動画は位置を変えない。",
+    request_body(content = AddWatchLaterBody),
+    responses(
+        (status = 200, description = "成功", body = OkResponse),
+        (status = 401, description = "未認証", body = ErrorResponse),
+        (status = 404, description = "購読中のチャンネルの動画ではない", body = ErrorResponse),
+    ),
+)]
+async fn add_to_watch_later(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Json(body): Json<AddWatchLaterBody>,
+) -> Result<Json<Value>, AppError> {
+    let conn = state.db.lock().unwrap();
+    let subscribed: bool = conn.query_row(
+        "SELECT EXISTS(
+           SELECT 1 FROM videos v
+           JOIN user_channels uc ON uc.channel_id = v.channel_id AND uc.user_id = ?1
+           WHERE v.id = ?2)",
+        rusqlite::params![user_id.0, body.video_id],
+        |row| row.get(0),
+    )?;
+    if !subscribed {
+        return Err(AppError::NotFound("Video not found".to_string()));
+    }
+    conn.execute(
+        "INSERT OR IGNORE INTO watch_later (user_id, video_id, position, created_at)
+         SELECT ?1, ?2, COALESCE(MAX(position) + 1, 0), ?3 FROM watch_later WHERE user_id = ?1",
+        rusqlite::params![user_id.0, body.video_id, crate::util::now_unix()],
+    )?;
+    super::unread::invalidate(&state.cache, user_id.0);
+    Ok(Json(json!({"ok": true})))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/watch-later/{video_id}",
+    tag = "動画フィード",
+    summary = "後で見るから削除",
+    description = "動画をキューから取り除く。動画はフィードに戻る。",
+    params(("video_id" = String, Path, description = "動画ID")),
+    responses(
+        (status = 200, description = "成功", body = OkResponse),
+        (status = 401, description = "未認証", body = ErrorResponse),
+        (status = 404, description = "キューに入っていない", body = ErrorResponse),
+    ),
+)]
+async fn remove_from_watch_later(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Path(video_id): Path<String>,
+) -> Result<Json<Value>, AppError> {
+    let deleted = {
+        let conn = state.db.lock().unwrap();
+        conn.execute(
+            "DELETE FROM watch_later WHERE user_id = ?1 AND video_id = ?2",
+            rusqlite::params![user_id.0, video_id],
+        )?
+    };
+    if deleted == 0 {
+        return Err(AppError::NotFound("Video not in watch later".to_string()));
+    }
+    super::unread::invalidate(&state.cache, user_id.0);
+    Ok(Json(json!({"ok": true})))
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub(crate) struct ReorderWatchLaterBody {
+    /// 動画IDの配列 (この順で先頭に並べる。含まれない動画は元の順でその後ろに続く)
+    order: Vec<String>,
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/watch-later/reorder",
+    tag = "動画フィード",
+    summary = "後で見るの並び替え",
+    request_body(content = ReorderWatchLaterBody, example = json!({"order": ["dQw4w9WgXcQ", "9bZkp7q19f0"]})),
+    responses(
+        (status = 200, description = "成功", body = OkResponse),
+        (status = 401, description = "未認証", body = ErrorResponse),
+    ),
+)]
+async fn reorder_watch_later(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Json(body): Json<ReorderWatchLaterBody>,
+) -> Result<Json<Value>, AppError> {
+    let conn = state.db.lock().unwrap();
+    let current = queued_ids(&conn, user_id.0)?;
+    let mut order: Vec<&String> = Vec::with_capacity(current.len());
+    for id in &body.order {
+        if current.contains(id) && !order.contains(&id) {
+            order.push(id);
+        }
+    }
+    for id in &current {
+        if !order.contains(&id) {
+            order.push(id);
+        }
+    }
+
+    conn.execute_batch("BEGIN")?;
+    for (i, id) in order.iter().enumerate() {
+        if let Err(e) = conn.execute(
+            "UPDATE watch_later SET position = ?1 WHERE user_id = ?2 AND video_id = ?3",
+            rusqlite::params![i as i64, user_id.0, id],
+        ) {
+            let _ = conn.execute_batch("ROLLBACK");
+            return Err(e.into());
+        }
+    }
+    conn.execute_batch("COMMIT")?;
+    Ok(Json(json!({"ok": true})))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/watch-later/next",
+    tag = "動画フィード",
+    summary = "後で見るの先頭を取り出す",
+    description = "キューの先頭の動画を取り除いて返す。視聴済みにはしない (見終わったら PATCH /api/videos/{id}/hide)。",
+    responses(
+        (status = 200, description = "取り出した動画", body = WatchLaterItem),
+        (status = 401, description = "未認証", body = ErrorResponse),
+        (status = 404, description = "キューが空", body = ErrorResponse),
+    ),
+)]
+async fn pop_next(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+) -> Result<Json<Value>, AppError> {
+    let conn = state.db.lock().unwrap();
+    let next = conn
+        .query_row(
+            &format!("{ITEM_SELECT} ORDER BY wl.position, wl.created_at LIMIT 1"),
+            [user_id.0],
+            item_json,
+        )
+        .optional()?
+        .ok_or_else(|| AppError::NotFound("Watch later queue is empty".to_string()))?;
+    conn.execute(
+        "DELETE FROM watch_later WHERE user_id = ?1 AND video_id = ?2",
+        rusqlite::params![user_id.0, next["id"].as_str()],
+    )?;
+    super::unread::invalidate(&state.cache, user_id.0);
+    Ok(Json(next))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/watch-later/play-all",
+    tag = "動画フィード",
+    summary = "後で見るの連続再生 URL",
+    description = "キューの先頭から最大50件を YouTube でまとめて再生する `watch_videos?video_ids=` の URL を返す。キューが空なら url は null。",
+    responses(
+        (status = 200, description = "連続再生 URL", body = PlayAllResponse),
+        (status = 401, description = "未認証", body = ErrorResponse),
+    ),
+)]
+async fn get_play_all(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+) -> Result<Json<Value>, AppError> {
+    let ids = {
+        let conn = state.db.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT video_id FROM watch_later WHERE user_id = ?1
+             ORDER BY position, created_at LIMIT {PLAY_ALL_LIMIT}"
+        ))?;
+        let ids = stmt
+            .query_map([user_id.0], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        ids
+    };
+    let url = (!ids.is_empty()).then(|| {
+        format!(
+            "https://www.youtube.com/watch_videos?video_ids={}",
+            ids.join(",")
+        )
+    });
+    Ok(Json(json!({"url": url, "count": ids.len()})))
+}
+
+#[cfg(test)]
+mod tests {
+    // Watch Later API Spec
+    //
+    // An ordered per-user queue of videos from subscribed channels. Queued
+    // videos leave /api/feed but are not hidden, so they never show up in
+    // /api/history; hiding (watching) a video drops it from the queue.
+    // reorder moves the listed IDs to the front; next pops the front item;
+    // play-all builds a YouTube watch_videos URL from the first 50 items.
+    // The acting user is the dev-bypass first DB user (user 1).
+
+    use super::routes;
+    use crate::middleware::auth_middleware;
+    use crate::state::AppState;
+    use axum::body::to_bytes;
+    use axum::http::{Request, StatusCode};
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+
+    fn setup_state() -> AppState {
+        let state = AppState::test();
+        state
+            .db
+            .lock()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO users (email) VALUES ('a@example.com'), ('b@example.com');
+                 INSERT INTO channels (id, title) VALUES ('UC1', 'Ch1'), ('UC2', 'Ch2');
+                 INSERT INTO user_channels (user_id, channel_id) VALUES (1, 'UC1'), (2, 'UC2');
+                 INSERT INTO videos (id, channel_id, title, published_at) VALUES
+                   ('v1', 'UC1', 'First', 1700000300),
+                   ('v2', 'UC1', 'Second', 1700000200),
+                   ('v3', 'UC1', 'Third', 1700000100),
+                   ('x1', 'UC2', 'Not subscribed', 1700000400);",
+            )
+            .unwrap();
+        state
+    }
+
+    async fn call(state: &AppState, method: &str, uri: &str, body: Value) -> (StatusCode, Value) {
+        let resp = axum::Router::new()
+            .merge(routes())
+            .merge(super::super::feed::routes())
+            .layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                auth_middleware,
+            ))
+            .with_state(state.clone())
+            .oneshot(
+                Request::builder()
+                    .method(method)
+                    .uri(uri)
+                    .header("content-type", "application/json")
+                    .body(axum::body::Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let status = resp.status();
+        let bytes = to_bytes(resp.into_body(), 1024 * 1024).await.unwrap();
+        (
+            status,
+            serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+        )
+    }
+
+    fn ids(items: &Value) -> Vec<&str> {
+        items
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|item| item["id"].as_str().unwrap())
+            .collect()
+    }
+
+    async fn add(state: &AppState, video_id: &str) -> StatusCode {
+        call(
+            state,
+            "POST",
+            "/api/watch-later",
+            json!({"video_id": video_id}),
+        )
+        .await
+        .0
+    }
+
+    #[tokio::test]
+    async fn add_appends_in_order_and_ignores_duplicates() {
+        let state = setup_state();
+        for id in ["v3", "v1", "v3"] {
+            assert_eq!(add(&state, id).await, StatusCode::OK);
+        }
+        let (status, list) = call(&state, "GET", "/api/watch-later", Value::Null).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(ids(&list), ["v3", "v1"]);
+        assert_eq!(list[0]["title"], "Third");
+        assert_eq!(list[0]["channel_title"], "Ch1");
+        assert!(list[0]["added_at"].is_string());
+    }
+
+    #[tokio::test]
+    async fn add_rejects_unknown_or_unsubscribed_videos() {
+        let state = setup_state();
+        assert_eq!(add(&state, "nope").await, StatusCode::NOT_FOUND);
+        assert_eq!(add(&state, "x1").await, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn queued_videos_leave_the_feed_without_entering_history() {
+        let state = setup_state();
+        add(&state, "v2").await;
+
+        let (_, feed) = call(&state, "GET", "/api/feed", Value::Null).await;
+        assert_eq!(ids(&feed), ["v1", "v3"]);
+        let (_, history) = call(&state, "GET", "/api/history", Value::Null).await;
+        assert!(history.as_array().unwrap().is_empty());
+
+        let (status, _) = call(&state, "DELETE", "/api/watch-later/v2", Value::Null).await;
+        assert_eq!(status, StatusCode::OK);
+        let (_, feed) = call(&state, "GET", "/api/feed", Value::Null).await;
+        assert_eq!(ids(&feed), ["v1", "v2", "v3"]);
+
+        let (status, _) = call(&state, "DELETE", "/api/watch-later/v2", Value::Null).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn hiding_a_video_removes_it_from_the_queue() {
+        let state = setup_state();
+        add(&state, "v1").await;
+        add(&state, "v2").await;
+        let (status, _) = call(&state, "PATCH", "/api/videos/v1/hide", Value::Null).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (_, list) = call(&state, "GET", "/api/watch-later", Value::Null).await;
+        assert_eq!(ids(&list), ["v2"]);
+        let (_, history) = call(&state, "GET", "/api/history", Value::Null).await;
+        assert_eq!(ids(&history), ["v1"]);
+    }
+
+    #[tokio::test]
+    async fn reorder_moves_listed_ids_to_the_front() {
+        let state = setup_state();
+        for id in ["v1", "v2", "v3"] {
+            add(&state, id).await;
+        }
+        let (status, _) = call(
+            &state,
+            "PUT",
+            "/api/watch-later/reorder",
+            json!({"order": ["v3", "unknown", "v3"]}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let (_, list) = call(&state, "GET", "/api/watch-later", Value::Null).await;
+        assert_eq!(ids(&list), ["v3", "v1", "v2"]);
+
+        // New items still go to the back after a reorder.
+        call(&state, "DELETE", "/api/watch-later/v1", Value::Null).await;
+        add(&state, "v1").await;
+        let (_, list) = call(&state, "GET", "/api/watch-later", Value::Null).await;
+        assert_eq!(ids(&list), ["v3", "v2", "v1"]);
+    }
+
+    #[tokio::test]
+    async fn next_pops_the_front_item_until_empty() {
+        let state = setup_state();
+        add(&state, "v2").await;
+        add(&state, "v1").await;
+
+        let (status, item) = call(&state, "POST", "/api/watch-later/next", Value::Null).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(item["id"], "v2");
+        let (_, item) = call(&state, "POST", "/api/watch-later/next", Value::Null).await;
+        assert_eq!(item["id"], "v1");
+        let (status, _) = call(&state, "POST", "/api/watch-later/next", Value::Null).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        // Popping is not watching: both are back in the feed, not in history.
+        let (_, feed) = call(&state, "GET", "/api/feed", Value::Null).await;
+        assert_eq!(ids(&feed), ["v1", "v2", "v3"]);
+    }
+
+    #[tokio::test]
+    async fn play_all_url_covers_the_first_fifty_items() {
+        let state = setup_state();
+        let (_, empty) = call(&state, "GET", "/api/watch-later/play-all", Value::Null).await;
+        assert_eq!(empty, json!({"url": null, "count": 0}));
+
+        {
+            let conn = state.db.lock().unwrap();
+            for i in 0..60 {
+                conn.execute(
+                    "INSERT INTO videos (id, channel_id, title) VALUES (?1, 'UC1', 't')",
+                    [format!("q{i:02}")],
+                )
+                .unwrap();
+                conn.execute(
+                    "INSERT INTO watch_later (user_id, video_id, position) VALUES (1, ?1, ?2)",
+                    rusqlite::params![format!("q{i:02}"), i],
+                )
+                .unwrap();
+            }
+        }
+        let (_, play) = call(&state, "GET", "/api/watch-later/play-all", Value::Null).await;
+        assert_eq!(play["count"], 50);
+        let url = play["url"].as_str().unwrap();
+        let expected: Vec<String> = (0..50).map(|i| format!("q{i:02}")).collect();
+        assert_eq!(
+            url,
+            format!(
+                "https://www.youtube.com/watch_videos?video_ids={}",
+                expected.join(",")
+            )
+        );
+    }
+}
@@ -0,0 +1,507 @@
+use crate::error::AppError;
+use crate::middleware::UserId;
+use crate::openapi::*;
+use crate::state::AppState;
+use crate::webhooks::EVENTS;
+use axum::extract::{Extension, Path, Query, State};
+use axum::routing::get;
+use axum::{Json, Router};
+use rusqlite::{Connection, OptionalExtension};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/webhooks", get(get_webhooks).post(create_webhook))
+        .route(
+            "/api/webhooks/{id}",
+            axum::routing::patch(update_webhook).delete(delete_webhook),
+        )
+        .route("/api/webhooks/{id}/deliveries", get(get_deliveries))
+}
+
+fn webhook_json(row: &rusqlite::Row) -> rusqlite::Result<Value> {
+    let events: String = row.get(2)?;
+    Ok(json!({
+        "id": row.get::<_, i64>(0)?,
+        "url": row.get::<_, String>(1)?,
+        "events": events.split(',').collect::<Vec<_>>(),
+        "is_enabled": row.get::<_, i64>(3)?,
+        "created_at": crate::util::row_timestamp_to_rfc3339(row, 4)?,
+    }))
+}
+
+fn load_webhook(conn: &Connection, user_id: i64, id: i64) -> Result<Value, AppError> {
+    conn.query_row(
+        "SELECT id, url, events, is_enabled, created_at FROM webhooks WHERE id = ?1 AND user_id = ?2",
+        rusqlite::params![id, user_id],
+        webhook_json,
+    )
+    .optional()?
+    .ok_or_else(|| AppError::NotFound("Webhook not found".to_string()))
+}
+
+fn validate_url(url: &str) -> Result<String, AppError> {
+    let url = url.trim();
+    match reqwest::Url::parse(url) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") && parsed.has_host() => {
+            Ok(url.to_string())
+        }
+        _ => Err(AppError::BadRequest(
+            "url must be an http(s) URL".to_string(),
+        )),
+    }
+}
+
+/// Stored comma-separated in the order of `EVENTS`, without duplicates.
+fn validate_events(events: &[String]) -> Result<String, AppError> {
+    if let Some(unknown) = events.iter().find(|e| !EVENTS.contains(&e.as_str())) {
+        return Err(AppError::BadRequest(format!("Unknown event: {}", unknown)));
+    }
+    let selected: Vec<&str> = EVENTS
+        .iter()
+        .copied()
+        .filter(|e| events.iter().any(|s| s == e))
+        .collect();
+    if selected.is_empty() {
+        return Err(AppError::BadRequest("events must not be empty".to_string()));
+    }
+    Ok(selected.join(","))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/webhooks",
+    tag = "通知",
+    summary = "Webhook 一覧",
+    responses(
+        (status = 200, description = "ログインユーザーの Webhook 一覧 (secret は含まない)", body = Vec<WebhookItem>),
+        (status = 401, description = "未認証", body = ErrorResponse),
+    ),
+)]
+async fn get_webhooks(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+) -> Result<Json<Value>, AppError> {
+    let conn = state.db.lock().unwrap();
+    let mut stmt = conn.prepare(
+        "SELECT id, url, events, is_enabled, created_at FROM webhooks WHERE user_id = ?1 ORDER BY id",
+    )?;
+    let rows = stmt
+        .query_map([user_id.0], webhook_json)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Json(Value::Array(rows)))
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub(crate) struct CreateWebhookBody {
+    /// 送信先 URL (http / https)
+    url: String,
+    /// 購読するイベント (省略時: すべて)。video.created / video.enriched / video.hidden / channel.added / channel.removed
+    events: Option<Vec<String>>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/webhooks",
+    tag = "通知",
+    summary = "Webhook 登録",
+    description = "イベント発生時に JSON `{event, created_at, data}` を POST する Webhook を登録する。\n\n- `X-Hub-Signature-256: sha256=<HMAC-SHA256(secret, body)>` で署名される\n- `X-Webhook-Event` にイベント名、`X-Webhook-Delivery` に配信ID (再送でも同じ値) が付く\n- 2xx 以外・通信エラーは指数バックオフ (30秒〜1時間) で最大8回まで再送する\n- secret はこのレスポンスでのみ返される",
+    request_body(content = CreateWebhookBody),
+    responses(
+        (status = 201, description = "登録された Webhook (secret を含む)", body = CreatedWebhook),
+        (status = 400, description = "バリデーションエラー", body = ErrorResponse),
+        (status = 401, description = "未認証", body = ErrorResponse),
+    ),
+)]
+async fn create_webhook(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Json(body): Json<CreateWebhookBody>,
+) -> Result<(axum::http::StatusCode, Json<Value>), AppError> {
+    let url = validate_url(&body.url)?;
+    crate::outbound::check(&url, &state.config)
+        .await
+        .map_err(AppError::BadRequest)?;
+    let events = match body.events {
+        Some(events) => validate_events(&events)?,
+        None => EVENTS.join(","),
+    };
+    let secret = crate::websub::signature::generate_secret();
+
+    let conn = state.db.lock().unwrap();
+    conn.execute(
+        "INSERT INTO webhooks (user_id, url, secret, events, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+        rusqlite::params![user_id.0, url, secret, events, crate::util::now_unix()],
+    )?;
+    let mut created = load_webhook(&conn, user_id.0, conn.last_insert_rowid())?;
+    created["secret"] = json!(secret);
+    Ok((axum::http::StatusCode::CREATED, Json(created)))
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub(crate) struct UpdateWebhookBody {
+    /// 送信先 URL (http / https)
+    url: Option<String>,
+    /// 購読するイベント
+    events: Option<Vec<String>>,
+    /// 有効/無効 (0: 無効, 1: 有効)。無効中のイベントは記録されない
+    is_enabled: Option<i64>,
+}
+
+#[utoipa::path(
+    patch,
+    path = "/api/webhooks/{id}",
+    tag = "通知",
+    summary = "Webhook 更新",
+    params(("id" = i64, Path, description = "Webhook ID")),
+    request_body(content = UpdateWebhookBody),
+    responses(
+        (status = 200, description = "更新後の Webhook", body = WebhookItem),
+        (status = 400, description = "バリデーションエラー", body = ErrorResponse),
+        (status = 401, description = "未認証", body = ErrorResponse),
+        (status = 404, description = "Webhook が存在しない", body = ErrorResponse),
+    ),
+)]
+async fn update_webhook(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Path(id): Path<i64>,
+    Json(body): Json<UpdateWebhookBody>,
+) -> Result<Json<Value>, AppError> {
+    let url = body.url.as_deref().map(validate_url).transpose()?;
+    if let Some(url) = &url {
+        crate::outbound::check(url, &state.config)
+            .await
+            .map_err(AppError::BadRequest)?;
+    }
+    let events = body.events.as_deref().map(validate_events).transpose()?;
+    if body.is_enabled.is_some_and(|v| v != 0 && v != 1) {
+        return Err(AppError::BadRequest(
+            "is_enabled must be 0 or 1".to_string(),
+        ));
+    }
+
+    let conn = state.db.lock().unwrap();
+    load_webhook(&conn, user_id.0, id)?;
+    conn.execute(
+        "UPDATE webhooks SET
+           url = COALESCE(?1, url),
+           events = COALESCE(?2, events),
+           is_enabled = COALESCE(?3, is_enabled)
+         WHERE id = ?4 AND user_id = ?5",
+        rusqlite::params![url, events, body.is_enabled, id, user_id.0],
+    )?;
+    Ok(Json(load_webhook(&conn, user_id.0, id)?))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/webhooks/{id}",
+    tag = "通知",
+    summary = "Webhook 削除",
+    description = "Webhook と配信ログを削除する。未送信の配信も破棄される。",
+    params(("id" = i64, Path, description = "Webhook ID")),
+    responses(
+        (status = 200, description = "成功", body = OkResponse),
+        (status = 401, description = "未認証", body = ErrorResponse),
+        (status = 404, description = "Webhook が存在しない", body = ErrorResponse),
+    ),
+)]
+async fn delete_webhook(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Path(id): Path<i64>,
+) -> Result<Json<Value>, AppError> {
+    let deleted = {
+        let conn = state.db.lock().unwrap();
+        conn.execute(
+            "DELETE FROM webhooks WHERE id = ?1 AND user_id = ?2",
+            rusqlite::params![id, user_id.0],
+        )?
+    };
+    if deleted == 0 {
+        return Err(AppError::NotFound("Webhook not found".to_string()));
+    }
+    Ok(Json(json!({"ok": true})))
+}
+
+#[derive(Deserialize)]
+struct DeliveriesQuery {
+    limit: Option<i64>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/webhooks/{id}/deliveries",
+    tag = "通知",
+    summary = "Webhook 配信ログ",
+    description = "新しい順の配信ログ。status は pending (送信待ち・再送待ち) / delivered / failed (再送上限到達)。完了した配信は30日で削除される。",
+    params(
+        ("id" = i64, Path, description = "Webhook ID"),
+        ("limit" = Option<i64>, Query, description = "取得件数 (デフォルト: 50, 最大: 200)"),
+    ),
+    responses(
+        (status = 200, description = "配信ログ", body = Vec<WebhookDeliveryItem>),
+        (status = 401, description = "未認証", body = ErrorResponse),
+        (status = 404, description = "Webhook が存在しない", body = ErrorResponse),
+    ),
+)]
+async fn get_deliveries(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Path(id): Path<i64>,
+    Query(query): Query<DeliveriesQuery>,
+) -> Result<Json<Value>, AppError> {
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let conn = state.db.lock().unwrap();
+    load_webhook(&conn, user_id.0, id)?;
+    let mut stmt = conn.prepare(
+        "SELECT id, event, status, attempts, response_status, last_error,
+                next_attempt_at, created_at, delivered_at, payload
+         FROM webhook_deliveries
+         WHERE webhook_id = ?1
+         ORDER BY id DESC
+         LIMIT ?2",
+    )?;
+    let rows = stmt
+        .query_map(rusqlite::params![id, limit], |row| {
+            let status: String = row.get(2)?;
+            let next_attempt_at = if status == "pending" {
+                crate::util::row_timestamp_to_rfc3339(row, 6)?
+            } else {
+                None
+            };
+            let payload: String = row.get(9)?;
+            Ok(json!({
+                "id": row.get::<_, i64>(0)?,
+                "event": row.get::<_, String>(1)?,
+                "status": status,
+                "attempts": row.get::<_, i64>(3)?,
+                "response_status": row.get::<_, Option<i64>>(4)?,
+                "last_error": row.get::<_, Option<String>>(5)?,
+                "next_attempt_at": next_attempt_at,
+                "created_at": crate::util::row_timestamp_to_rfc3339(row, 7)?,
+                "delivered_at": crate::util::row_timestamp_to_rfc3339(row, 8)?,
+                "payload": serde_json::from_str::<Value>(&payload).unwrap_or(Value::Null),
+            }))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Json(Value::Array(rows)))
+}
+
+#[cfg(test)]
+mod tests {
+    // Webhook API Spec
+    //
+    // Users manage their own hooks (url + subscribed events). The signing
+    // secret is returned only on creation. Events the user triggers — here
+    // hiding a video — show up in the hook's delivery log as pending rows.
+    // URLs that reach a non-public address are refused unless
+    // ALLOW_PRIVATE_TARGETS is set (the test state sets it).
+
+    use super::routes;
+    use crate::middleware::auth_middleware;
+    use crate::state::AppState;
+    use axum::body::to_bytes;
+    use axum::http::{Request, StatusCode};
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+
+    fn setup_state() -> AppState {
+        let state = AppState::test();
+        state
+            .db
+            .lock()
+            .unwrap()
+            .execute("INSERT INTO users (email) VALUES ('a@example.com')", [])
+            .unwrap();
+        state
+    }
+
+    async fn call(state: &AppState, method: &str, uri: &str, body: Value) -> (StatusCode, Value) {
+        let resp = axum::Router::new()
+            .merge(routes())
+            .merge(crate::routes::feed::routes())
+            .layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                auth_middleware,
+            ))
+            .with_state(state.clone())
+            .oneshot(
+                Request::builder()
+                    .method(method)
+                    .uri(uri)
+                    .header("content-type", "application/json")
+                    .body(axum::body::Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let status = resp.status();
+        let bytes = to_bytes(resp.into_body(), 1024 * 1024).await.unwrap();
+        (
+            status,
+            serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+        )
+    }
+
+    #[tokio::test]
+    async fn create_list_update_and_delete() {
+        let state = setup_state();
+        let (status, created) = call(
+            &state,
+            "POST",
+            "/api/webhooks",
+            json!({"url": "https://hooks.example.com/yt", "events": ["channel.added", "video.created"]}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(created["secret"].as_str().unwrap().len(), 64);
+        // Normalised to the canonical event order.
+        assert_eq!(created["events"], json!(["video.created", "channel.added"]));
+
+        let (_, list) = call(&state, "GET", "/api/webhooks", Value::Null).await;
+        assert_eq!(list.as_array().unwrap().len(), 1);
+        assert!(list[0].get("secret").is_none(), "secret must not be listed");
+
+        let uri = format!("/api/webhooks/{}", created["id"]);
+        let (status, updated) = call(&state, "PATCH", &uri, json!({"is_enabled": 0})).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(updated["is_enabled"], 0);
+        assert_eq!(updated["url"], "https://hooks.example.com/yt");
+
+        assert_eq!(
+            call(&state, "DELETE", &uri, Value::Null).await.0,
+            StatusCode::OK
+        );
+        assert_eq!(
+            call(&state, "PATCH", &uri, json!({"is_enabled": 1}))
+                .await
+                .0,
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    #[tokio::test]
+    async fn omitted_events_subscribe_to_everything() {
+        let state = setup_state();
+        let (_, created) = call(
+            &state,
+            "POST",
+            "/api/webhooks",
+            json!({"url": "http://localhost:9000/hook"}),
+        )
+        .await;
+        assert_eq!(created["events"].as_array().unwrap().len(), 5);
+    }
+
+    #[tokio::test]
+    async fn invalid_webhooks_are_rejected_with_400() {
+        let state = setup_state();
+        for body in [
+            json!({"url": "not a url"}),
+            json!({"url": "ftp://example.com/hook"}),
+            json!({"url": "https://example.com/hook", "events": []}),
+            json!({"url": "https://example.com/hook", "events": ["video.deleted"]}),
+        ] {
+            assert_eq!(
+                call(&state, "POST", "/api/webhooks", body.clone()).await.0,
+                StatusCode::BAD_REQUEST,
+                "{body} must be rejected"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn private_urls_are_rejected_with_400() {
+        let mut state = setup_state();
+        state.config.allow_private_targets = false;
+        for url in [
+            "http://127.0.0.1:3000/api/users",
+            "http://169.254.169.254/latest/meta-data",
+            "http://[::1]/hook",
+            "http://10.0.0.1/hook",
+        ] {
+            let (status, body) = call(&state, "POST", "/api/webhooks", json!({"url": url})).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{url} must be rejected");
+            assert!(body["error"].as_str().unwrap().contains("non-public"));
+        }
+
+        let (status, created) = call(
+            &state,
+            "POST",
+            "/api/webhooks",
+            json!({"url": "https://1.1.1.1/hook"}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let uri = format!("/api/webhooks/{}", created["id"]);
+        assert_eq!(
+            call(&state, "PATCH", &uri, json!({"url": "http://192.168.1.1/"}))
+                .await
+                .0,
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    #[tokio::test]
+    async fn hiding_a_video_is_logged_once_as_pending_delivery() {
+        let state = setup_state();
+        state
+            .db
+            .lock()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO channels (id, title) VALUES ('UC1', 'Ch');
+                 INSERT INTO videos (id, channel_id, title) VALUES ('vid1', 'UC1', 'V');",
+            )
+            .unwrap();
+        let (_, hook) = call(
+            &state,
+            "POST",
+            "/api/webhooks",
+            json!({"url": "https://hooks.example.com/yt", "events": ["video.hidden"]}),
+        )
+        .await;
+
+        call(&state, "PATCH", "/api/videos/vid1/hide", Value::Null).await;
+        // Hiding an already hidden video is not a new event.
+        call(&state, "PATCH", "/api/videos/vid1/hide", Value::Null).await;
+
+        let uri = format!("/api/webhooks/{}/deliveries", hook["id"]);
+        let (status, log) = call(&state, "GET", &uri, Value::Null).await;
+        assert_eq!(status, StatusCode::OK);
+        let log = log.as_array().unwrap();
+        assert_eq!(log.len(), 1);
+        assert_eq!(log[0]["event"], "video.hidden");
+        assert_eq!(log[0]["status"], "pending");
+        assert_eq!(log[0]["payload"]["data"]["video_id"], "vid1");
+    }
+
+    #[tokio::test]
+    async fn other_users_webhooks_are_not_found() {
+        let state = setup_state();
+        {
+            let conn = state.db.lock().unwrap();
+            conn.execute("INSERT INTO users (email) VALUES ('b@example.com')", [])
+                .unwrap();
+            conn.execute(
+                "INSERT INTO webhooks (id, user_id, url, secret, events) VALUES (7, 2, 'https://x.example', 's', 'video.created')",
+                [],
+            )
+            .unwrap();
+        }
+        for (method, uri) in [
+            ("PATCH", "/api/webhooks/7"),
+            ("DELETE", "/api/webhooks/7"),
+            ("GET", "/api/webhooks/7/deliveries"),
+        ] {
+            assert_eq!(
+                call(&state, method, uri, json!({})).await.0,
+                StatusCode::NOT_FOUND,
+                "{method} {uri}"
+            );
+        }
+    }
+}
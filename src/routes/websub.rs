@@ -82,6 +82,13 @@ pub async fn verification(
                 return (StatusCode::NOT_FOUND, "unknown channel").into_response();
             }
 
+            crate::audit::record(
+                &conn,
+                None,
+                crate::audit::WEBSUB_VERIFY,
+                &channel_id,
+                serde_json::json!({"mode": "subscribe", "lease_seconds": lease}),
+            );
             tracing::info!(
                 "[websub] Subscription verified: {} (lease {}s)",
                 channel_id,
@@ -109,6 +116,13 @@ pub async fn verification(
                 return (StatusCode::NOT_FOUND, "not pending unsubscribe").into_response();
             }
 
+            crate::audit::record(
+                &conn,
+                None,
+                crate::audit::WEBSUB_VERIFY,
+                &channel_id,
+                serde_json::json!({"mode": "unsubscribe"}),
+            );
             tracing::info!("[websub] Unsubscription verified: {}", channel_id);
         }
         other => {
@@ -196,7 +210,9 @@ pub async fn notification(
         let channel_title = lookup_channel_title(&conn, &channel_id);
         let newly_inserted = partition_new_entries(&conn, &channel_id, &entries, now);
         log_new_videos(&channel_title, &channel_id, &newly_inserted);
-        newly_inserted.iter().map(|e| e.video_id.clone()).collect()
+        let ids: Vec<String> = newly_inserted.iter().map(|e| e.video_id.clone()).collect();
+        crate::webhooks::emit_video_events(&conn, crate::webhooks::VIDEO_CREATED, &ids);
+        ids
     };
 
     if new_video_ids.is_empty() {
@@ -209,6 +225,7 @@ pub async fn notification(
         );
         return StatusCode::OK;
     }
+    super::unread::invalidate_all(&state.cache);
 
     // Enrich the new rows (duration / Shorts / livestream) via the API-key-only
     // YouTube Data API, spawned so the hub gets its 200 OK without waiting.
@@ -216,14 +233,31 @@ pub async fn notification(
     // (video_enrich::backfill_missing_details) — rows stay details_checked_at
     // NULL until a batch succeeds. is_members_only is out of scope (needs the
     // removed OAuth-based UUMO check) and remains 0.
+    //
+    // New-video notifications go out once enrichment has settled, so the
+    // Shorts/livestream flags used for per-user filtering are as accurate as
+    // they will get. A failed enrichment still notifies with what is known.
+    // Instance-wide backends and browser push subscriptions get favorites;
+    // each user's notification_rules are evaluated separately.
     let state_clone = state.clone();
     tokio::spawn(async move {
-        if let Err(e) =
-            crate::sync::video_enrich::enrich_videos(&state_clone, &channel_id, &new_video_ids)
-                .await
+        match crate::sync::video_enrich::enrich_videos(&state_clone, &channel_id, &new_video_ids)
+            .await
         {
-            tracing::warn!("[websub] enrichment failed for {}: {}", channel_id, e);
+            Ok(_) => {
+                let conn = state_clone.db.lock().unwrap();
+                crate::webhooks::emit_enriched(&conn, &new_video_ids);
+            }
+            Err(e) => tracing::warn!("[websub] enrichment failed for {}: {}", channel_id, e),
         }
+
+        let videos = {
+            let conn = state_clone.db.lock().unwrap();
+            crate::notify::notifiable_new_videos(&conn, &new_video_ids)
+        };
+        crate::notify::notify_new_videos(&state_clone, &videos).await;
+        crate::notify::web_push::notify_new_videos(&state_clone, &videos).await;
+        crate::notify::rules::dispatch(&state_clone, &new_video_ids).await;
     });
 
     StatusCode::OK
@@ -270,20 +304,24 @@ fn partition_new_entries<'a>(
         );
 
         match result {
-            Ok(()) => newly_inserted.push(entry),
+            Ok(()) => {
+                index_for_search(conn, &entry.video_id);
+                newly_inserted.push(entry);
+            }
             Err(rusqlite::Error::QueryReturnedNoRows) => {
                 // Repair an unknown or legacy publication timestamp when a
-                // valid Atom timestamp is redelivered.
+                // valid Atom timestamp is redelivered. fetched_at keeps the
+                // first delivery: it drives the feed's "new since last visit".
                 let _ = conn.execute(
                     "UPDATE videos
                      SET title = ?1,
                          published_at = CASE
                              WHEN ?3 IS NOT NULL AND (published_at IS NULL OR typeof(published_at) != 'integer')
-                             THEN ?3 ELSE published_at END,
-                         fetched_at = ?4
+                             THEN ?3 ELSE published_at END
                      WHERE id = ?2",
-                    rusqlite::params![entry.title, entry.video_id, entry.published, now],
+                    rusqlite::params![entry.title, entry.video_id, entry.published],
                 );
+                index_for_search(conn, &entry.video_id);
             }
             Err(e) => {
                 tracing::warn!(
@@ -298,6 +336,18 @@ fn partition_new_entries<'a>(
     newly_inserted
 }
 
+/// Keep the search index in step with the title just written. A failure only
+/// makes the video unsearchable until the next startup's `sync_index`.
+fn index_for_search(conn: &rusqlite::Connection, video_id: &str) {
+    if let Err(e) = crate::search::index_video(conn, video_id) {
+        tracing::warn!(
+            "[websub] search index update failed for {}: {}",
+            video_id,
+            e
+        );
+    }
+}
+
 fn log_new_videos(channel_title: &str, channel_id: &str, entries: &[&AtomEntry]) {
     for entry in entries {
         tracing::info!(
@@ -648,6 +698,35 @@ mod tests {
             published_at, 1777161600,
             "legacy timestamps must be repaired"
         );
+        let fetched_at: String = conn
+            .query_row(
+                "SELECT fetched_at FROM videos WHERE id = 'existing'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(
+            fetched_at, "2024-01-01T00:00:00Z",
+            "a redelivery must not make a known video new again"
+        );
+
+        // Both paths keep the search index current.
+        let indexed: Vec<String> = conn
+            .prepare("SELECT video_id FROM video_search ORDER BY video_id")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(indexed, vec!["existing", "fresh1", "fresh2"]);
+        let title: String = conn
+            .query_row(
+                "SELECT title FROM video_search WHERE video_id = 'existing'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(title, "new title");
     }
 
     #[test]
@@ -0,0 +1,221 @@
+//! Full-text index over video titles and channel names, used by
+//! `/api/search`.
+//!
+//! `video_search` is an FTS5 table with the trigram tokenizer: it matches
+//! substrings, so Japanese titles are searchable without word segmentation.
+//! Text is folded with [`fold`] both when it is indexed and when it is
+//! searched. Each row's FTS rowid is the rowid of its `videos` row, which
+//! keeps re-indexing and the delete trigger to single-row operations;
+//! [`sync_index`] repairs rows that drifted (e.g. after a migration rebuilt
+//! `videos`) at startup.
+
+use rusqlite::{Connection, OptionalExtension};
+
+/// At most this many terms of a query are used.
+const MAX_TERMS: usize = 8;
+
+/// Trigram needs three characters to use the index; shorter terms are
+/// matched with LIKE instead.
+const MIN_MATCH_CHARS: usize = 3;
+
+/// NFKC (full-width ASCII → half-width, half-width katakana → full-width,
+/// compatibility characters → their usual form), then lowercase.
+pub fn fold(text: &str) -> String {
+    icu_normalizer::ComposingNormalizerBorrowed::new_nfkc()
+        .normalize(text)
+        .to_lowercase()
+}
+
+/// (Re)index one video with its current title and channel name.
+pub fn index_video(conn: &Connection, video_id: &str) -> rusqlite::Result<()> {
+    let row: Option<(i64, String, String)> = conn
+        .query_row(
+            "SELECT v.rowid, v.title, COALESCE(c.title, '')
+             FROM videos v LEFT JOIN channels c ON c.id = v.channel_id
+             WHERE v.id = ?1",
+            [video_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()?;
+    let Some((rowid, title, channel_title)) = row else {
+        return Ok(());
+    };
+    conn.execute("DELETE FROM video_search WHERE rowid = ?1", [rowid])?;
+    conn.execute(
+        "INSERT INTO video_search (rowid, video_id, title, channel_title) VALUES (?1, ?2, ?3, ?4)",
+        rusqlite::params![rowid, video_id, fold(&title), fold(&channel_title)],
+    )?;
+    Ok(())
+}
+
+/// Drop index rows that no longer match their video and index the videos
+/// that are missing. Returns the number of videos indexed.
+pub fn sync_index(conn: &Connection) -> usize {
+    if let Err(e) = conn.execute(
+        "DELETE FROM video_search
+         WHERE video_id IS NOT (SELECT id FROM videos WHERE rowid = video_search.rowid)",
+        [],
+    ) {
+        tracing::warn!("[search] Failed to prune the search index: {}", e);
+        return 0;
+    }
+    let missing: Vec<String> = match conn
+        .prepare("SELECT id FROM videos WHERE rowid NOT IN (SELECT rowid FROM video_search)")
+        .and_then(|mut stmt| {
+            stmt.query_map([], |row| row.get(0))?
+                .collect::<Result<Vec<_>, _>>()
+        }) {
+        Ok(ids) => ids,
+        Err(e) => {
+            tracing::warn!("[search] Failed to list unindexed videos: {}", e);
+            return 0;
+        }
+    };
+    let mut indexed = 0;
+    for id in &missing {
+        match index_video(conn, id) {
+            Ok(()) => indexed += 1,
+            Err(e) => tracing::warn!("[search] Failed to index {}: {}", id, e),
+        }
+    }
+    if indexed > 0 {
+        tracing::info!("[search] Indexed {} videos", indexed);
+    }
+    indexed
+}
+
+/// A parsed query: an FTS5 MATCH expression for the terms long enough for
+/// the trigram index, and LIKE patterns for the rest. All terms must match
+/// (each in the title or the channel name).
+#[derive(Debug, PartialEq)]
+pub struct Query {
+    pub fts: Option<String>,
+    pub likes: Vec<String>,
+}
+
+pub fn parse_query(q: &str) -> Option<Query> {
+    let mut terms: Vec<String> = Vec::new();
+    for term in fold(q).split_whitespace() {
+        if !terms.iter().any(|t| t == term) {
+            terms.push(term.to_string());
+        }
+    }
+    if terms.is_empty() {
+        return None;
+    }
+    terms.truncate(MAX_TERMS);
+
+    let (long, short): (Vec<_>, Vec<_>) = terms
+        .into_iter()
+        .partition(|t| t.chars().count() >= MIN_MATCH_CHARS);
+    let fts = (!long.is_empty()).then(|| {
+        long.iter()
+            .map(|t| format!("\"{}\"", t.replace('"', "\"\"")))
+            .collect::<Vec<_>>()
+            .join(" ")
+    });
+    let likes = short.iter().map(|t| like_pattern(t)).collect();
+    Some(Query { fts, likes })
+}
+
+/// `%term%` with LIKE wildcards escaped, for `LIKE ? ESCAPE '\'`.
+pub fn like_pattern(term: &str) -> String {
+    let escaped = term
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{escaped}%")
+}
+
+#[cfg(test)]
+mod tests {
+    // Search Index Spec
+    //
+    // - Titles and channel names are folded (NFKC + lowercase) so full-width,
+    //   half-width and case variants find each other.
+    // - index_video replaces a video's row; deleting the video removes it.
+    // - sync_index indexes missing videos and drops stale rows.
+    // - Queries split on whitespace; terms of 3+ characters go to FTS5 MATCH
+    //   (quoted), shorter ones to escaped LIKE patterns.
+
+    use super::*;
+
+    fn setup() -> Connection {
+        let conn = crate::db::open_memory();
+        conn.execute_batch(
+            "INSERT INTO channels (id, title) VALUES ('UC1', 'ＡＢＣニュース');
+             INSERT INTO videos (id, channel_id, title) VALUES ('v1', 'UC1', 'ﾆｭｰｽ Rust 入門');",
+        )
+        .unwrap();
+        conn
+    }
+
+    fn search(conn: &Connection, fts: &str) -> Vec<String> {
+        let mut stmt = conn
+            .prepare("SELECT video_id FROM video_search WHERE video_search MATCH ?1")
+            .unwrap();
+        stmt.query_map([fts], |row| row.get(0))
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn fold_unifies_width_and_case() {
+        assert_eq!(fold("ＡＢＣ　ｶﾞｲﾄﾞ Ｒｕｓｔ"), "abc ガイド rust");
+    }
+
+    #[test]
+    fn indexed_text_is_folded_and_follows_updates_and_deletes() {
+        let conn = setup();
+        index_video(&conn, "v1").unwrap();
+        assert_eq!(search(&conn, "\"ニュース\""), vec!["v1"]);
+        assert_eq!(search(&conn, "\"abcニュ\""), vec!["v1"], "channel name");
+
+        conn.execute("UPDATE videos SET title = 'Go 入門' WHERE id = 'v1'", [])
+            .unwrap();
+        index_video(&conn, "v1").unwrap();
+        assert!(search(&conn, "\"rust\"").is_empty());
+        assert_eq!(search(&conn, "\"go 入門\""), vec!["v1"]);
+
+        conn.execute("DELETE FROM videos WHERE id = 'v1'", [])
+            .unwrap();
+        let rows: i64 = conn
+            .query_row("SELECT COUNT(*) FROM video_search", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(rows, 0);
+    }
+
+    #[test]
+    fn sync_index_adds_missing_and_drops_stale_rows() {
+        let conn = setup();
+        conn.execute(
+            "INSERT INTO video_search (rowid, video_id, title, channel_title) VALUES (99, 'gone', 'x', 'y')",
+            [],
+        )
+        .unwrap();
+        assert_eq!(sync_index(&conn), 1);
+        assert_eq!(sync_index(&conn), 0, "idempotent");
+        assert_eq!(search(&conn, "\"rust\""), vec!["v1"]);
+        assert!(search(&conn, "\"gone\"").is_empty());
+    }
+
+    #[test]
+    fn parse_query_splits_long_and_short_terms() {
+        assert_eq!(parse_query("  \u{3000} "), None);
+        assert_eq!(
+            parse_query("Ｒｕｓｔ 入門 a_b 入門 \"x\"\"y"),
+            Some(Query {
+                fts: Some("\"rust\" \"a_b\" \"\"\"x\"\"\"\"y\"".to_string()),
+                likes: vec!["%入門%".to_string()],
+            })
+        );
+        assert_eq!(
+            parse_query("5%"),
+            Some(Query {
+                fts: None,
+                likes: vec!["%5\\%%".to_string()],
+            })
+        );
+    }
+}
@@ -23,8 +23,17 @@ impl AppState {
                 public_base_url: None,
                 gis_client_id: String::new(),
                 discord_webhook_url: None,
+                notifier_urls: Vec::new(),
                 websub_callback_url: "http://localhost:3000/api/websub/callback".to_string(),
                 youtube_api_key: None,
+                vapid_subject: None,
+                cf_access: None,
+                auth_header: "Cf-Access-Authenticated-User-Email".to_string(),
+                trusted_proxies: None,
+                rss_tokenless_fallback: true,
+                login_smtp_url: None,
+                // HTTP stand-ins listen on 127.0.0.1.
+                allow_private_targets: true,
                 is_production: false,
             },
             http: reqwest::Client::new(),
@@ -80,29 +80,29 @@ pub async fn sync_subscriptions(
 
         conn.execute_batch("BEGIN")?;
 
+        let mut added_refs = Vec::new();
+        let mut removed_refs = Vec::new();
         let result = (|| -> Result<(), rusqlite::Error> {
             // Iterate over the deduplicated remote_set (not the original slice)
             // so each channel is processed exactly once.
             for channel_id in &remote_set {
                 if !local_ids.contains(channel_id) {
-                    let upload_playlist_id = crate::youtube::derive_upload_playlist_id(channel_id);
-                    let (title, thumbnail_url) = titles
-                        .get(channel_id)
-                        .map(|m| (m.title.as_str(), m.thumbnail_url.as_deref()))
-                        .unwrap_or((channel_id.as_str(), None));
-                    conn.execute(
-                        "INSERT OR IGNORE INTO channels (id, title, thumbnail_url, upload_playlist_id, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
-                        rusqlite::params![channel_id, title, thumbnail_url, upload_playlist_id, now],
-                    )?;
-                    conn.execute(
-                        "INSERT OR IGNORE INTO user_channels (user_id, channel_id, created_at) VALUES (?1, ?2, ?3)",
-                        rusqlite::params![user_id, channel_id, now],
-                    )?;
+                    subscribe(&conn, user_id, channel_id, titles, now)?;
+                    added_refs.push(crate::audit::channel_ref(&conn, channel_id));
                     added.push(channel_id.clone());
                 }
             }
 
             for local_id in &to_remove {
+                // Before the DELETE so the payload still carries the title of
+                // channels that are about to be orphaned.
+                crate::webhooks::emit(
+                    &conn,
+                    &[user_id],
+                    crate::webhooks::CHANNEL_REMOVED,
+                    crate::webhooks::channel_data(&conn, local_id),
+                );
+                removed_refs.push(crate::audit::channel_ref(&conn, local_id));
                 conn.execute(
                     "DELETE FROM user_channels WHERE user_id = ?1 AND channel_id = ?2",
                     rusqlite::params![user_id, local_id],
@@ -110,6 +110,18 @@ pub async fn sync_subscriptions(
             }
             removed = to_remove;
 
+            // The diff is the only trace of channels a partial browser list
+            // removed, so titles are captured before orphan cleanup drops them.
+            if !added_refs.is_empty() || !removed_refs.is_empty() {
+                crate::audit::record(
+                    &conn,
+                    Some(user_id),
+                    crate::audit::CHANNEL_SYNC,
+                    "",
+                    serde_json::json!({"added": added_refs, "removed": removed_refs}),
+                );
+            }
+
             // Batch cleanup: delete orphaned channels (no subscribers left).
             // channel_subscriptions rows are CASCADE-deleted via FK.
             conn.execute(
@@ -143,6 +155,40 @@ pub async fn sync_subscriptions(
     })
 }
 
+/// Subscribe `user_id` to `channel_id`, creating the channel row from
+/// `titles` when it is new, and emit `channel.added`. Returns whether the
+/// subscription is new. Runs inside the caller's transaction.
+pub fn subscribe(
+    conn: &rusqlite::Connection,
+    user_id: i64,
+    channel_id: &str,
+    titles: &std::collections::HashMap<String, ChannelMeta>,
+    now: i64,
+) -> Result<bool, rusqlite::Error> {
+    let upload_playlist_id = crate::youtube::derive_upload_playlist_id(channel_id);
+    let (title, thumbnail_url) = titles
+        .get(channel_id)
+        .map(|m| (m.title.as_str(), m.thumbnail_url.as_deref()))
+        .unwrap_or((channel_id, None));
+    conn.execute(
+        "INSERT OR IGNORE INTO channels (id, title, thumbnail_url, upload_playlist_id, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+        rusqlite::params![channel_id, title, thumbnail_url, upload_playlist_id, now],
+    )?;
+    let inserted = conn.execute(
+        "INSERT OR IGNORE INTO user_channels (user_id, channel_id, created_at) VALUES (?1, ?2, ?3)",
+        rusqlite::params![user_id, channel_id, now],
+    )?;
+    if inserted > 0 {
+        crate::webhooks::emit(
+            conn,
+            &[user_id],
+            crate::webhooks::CHANNEL_ADDED,
+            crate::webhooks::channel_data(conn, channel_id),
+        );
+    }
+    Ok(inserted > 0)
+}
+
 /// For each channel in `to_remove` that becomes orphaned (no other subscribers),
 /// collect its WebSub `hub_secret` and mark its subscription row
 /// `verification_status = 'pending_unsubscribe'`.
@@ -13,4 +13,44 @@ pub fn start_sync(state: AppState) {
         initial_setup::run_initial_setup(&state_clone).await;
         periodic_refresh::start(state_clone);
     });
+
+    // Upcoming livestream / premiere reminders.
+    let state_clone = state.clone();
+    tokio::spawn(async move {
+        let interval =
+            std::time::Duration::from_secs(crate::notify::reminders::CHECK_INTERVAL_SECS);
+        loop {
+            tokio::time::sleep(interval).await;
+            crate::notify::reminders::run(&state_clone).await;
+        }
+    });
+
+    // Retry notifier deliveries that failed on their first attempt.
+    let state_clone = state.clone();
+    tokio::spawn(async move {
+        let interval = std::time::Duration::from_secs(crate::notify::outbox::WORKER_INTERVAL_SECS);
+        loop {
+            tokio::time::sleep(interval).await;
+            crate::notify::outbox::deliver_due(&state_clone).await;
+        }
+    });
+
+    // Deliver (and retry) queued outgoing webhook events.
+    let state_clone = state.clone();
+    tokio::spawn(async move {
+        let interval = std::time::Duration::from_secs(crate::webhooks::WORKER_INTERVAL_SECS);
+        loop {
+            tokio::time::sleep(interval).await;
+            crate::webhooks::deliver_due(&state_clone).await;
+        }
+    });
+
+    // Deliver notification batches held back by users' quiet hours.
+    tokio::spawn(async move {
+        let interval = std::time::Duration::from_secs(crate::notify::rules::FLUSH_INTERVAL_SECS);
+        loop {
+            tokio::time::sleep(interval).await;
+            crate::notify::rules::flush_due(&state).await;
+        }
+    });
 }
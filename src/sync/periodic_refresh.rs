@@ -13,6 +13,7 @@ const RENEW_THRESHOLD_SECONDS: i64 = 2 * 24 * 60 * 60; // 2 days
 ///   2. Renew WebSub subscriptions nearing expiry
 ///   3. Backfill video details (duration / Shorts / livestream) for rows the
 ///      push-time enrichment missed, via the API-key-only YouTube Data API
+///   4. Send the daily / weekly digests that are due
 ///
 /// New videos arrive exclusively via WebSub push notifications — this loop
 /// never discovers videos, it only maintains subscriptions and repairs
@@ -54,6 +55,10 @@ async fn run_once(state: &AppState) {
     //    not as a separate startup task — so push enrichment and backfill never
     //    race over freshly inserted IDs, and retries ride the same 24h cycle.
     crate::sync::video_enrich::backfill_missing_details(state).await;
+
+    // 4. Digests run after the backfill so they list complete durations and
+    //    never include videos later classified as members-only.
+    crate::notify::digest::send_due(state).await;
 }
 
 fn find_channels_missing_subscription(state: &AppState) -> Vec<String> {
@@ -112,7 +117,15 @@ pub(crate) async fn register_new_subscription(state: &AppState, channel_id: &str
         }
     };
 
-    if let Err(e) = hub::subscribe(&state.http, channel_id, callback, &secret).await {
+    let result = hub::subscribe(&state.http, channel_id, callback, &secret).await;
+    crate::audit::record(
+        &state.db.lock().unwrap(),
+        None,
+        crate::audit::WEBSUB_SUBSCRIBE,
+        channel_id,
+        crate::audit::hub_outcome(&result),
+    );
+    if let Err(e) = result {
         tracing::error!("[refresh] subscribe failed for {}: {}", channel_id, e);
         notify_subscribe_failure(state, channel_id, &e.to_string()).await;
     } else {
@@ -132,8 +145,7 @@ async fn notify_subscribe_failure(state: &AppState, channel_id: &str, error: &st
         .set("websub_subscribe_err", serde_json::json!(true), Some(3600));
 
     notify_warning(
-        &state.http,
-        &state.config,
+        state,
         "WebSub購読エラー",
         &format!(
             "チャンネル {} の購読リクエストに失敗: {}\n(1時間、以降の同種エラーはサイレント抑制)",
@@ -180,7 +192,17 @@ async fn renew_expiring_subscriptions(state: &AppState, callback: &str) {
     tracing::info!("[refresh] Renewing {} subscriptions", to_renew.len());
 
     for (channel_id, secret) in &to_renew {
-        if let Err(e) = hub::subscribe(&state.http, channel_id, callback, secret).await {
+        let result = hub::subscribe(&state.http, channel_id, callback, secret).await;
+        let mut detail = crate::audit::hub_outcome(&result);
+        detail["renewal"] = serde_json::json!(true);
+        crate::audit::record(
+            &state.db.lock().unwrap(),
+            None,
+            crate::audit::WEBSUB_SUBSCRIBE,
+            channel_id,
+            detail,
+        );
+        if let Err(e) = result {
             tracing::warn!("[refresh] Renewal failed for {}: {}", channel_id, e);
         }
     }
@@ -38,7 +38,8 @@ pub async fn enrich_videos(
 ///
 /// - Ongoing live/premiere: duration stays NULL (the API reports a "PT0S"
 ///   placeholder while live); the pending-query's livestream clause re-checks
-///   it daily until actualEndTime appears.
+///   it daily until actualEndTime appears. Its scheduled / actual start feed
+///   the stream reminders (notify::reminders).
 /// - A row returned without duration is skipped — left unchecked, so the
 ///   daily backfill retries it. Marking it checked would freeze the missing
 ///   duration forever.
@@ -51,12 +52,27 @@ pub fn apply_video_details(
     now: i64,
 ) {
     for d in details {
+        let scheduled_start_at = d
+            .scheduled_start_at
+            .as_deref()
+            .and_then(crate::util::rfc3339_to_unix);
+        let live_started_at = d
+            .live_started_at
+            .as_deref()
+            .and_then(crate::util::rfc3339_to_unix);
         let result = if d.is_ongoing_live() {
             conn.execute(
                 "UPDATE videos SET is_livestream = 1, details_checked_at = ?1,
-                        shorts_classifier_version = ?2
-                 WHERE id = ?3",
-                rusqlite::params![now, SHORTS_CLASSIFIER_VERSION, d.id],
+                        shorts_classifier_version = ?2,
+                        scheduled_start_at = ?3, live_started_at = ?4
+                 WHERE id = ?5",
+                rusqlite::params![
+                    now,
+                    SHORTS_CLASSIFIER_VERSION,
+                    scheduled_start_at,
+                    live_started_at,
+                    d.id
+                ],
             )
         } else {
             if d.duration.is_none() {
@@ -74,8 +90,10 @@ pub fn apply_video_details(
             conn.execute(
                 "UPDATE videos SET duration = ?1, is_short = ?2, is_livestream = ?3,
                         livestream_ended_at = ?4, details_checked_at = ?5,
-                        shorts_classifier_version = ?6
-                 WHERE id = ?7",
+                        shorts_classifier_version = ?6,
+                        scheduled_start_at = ?7, live_started_at = ?8,
+                        duration_seconds = ?9
+                 WHERE id = ?10",
                 rusqlite::params![
                     d.duration,
                     is_short as i64,
@@ -83,6 +101,11 @@ pub fn apply_video_details(
                     ended_at,
                     now,
                     SHORTS_CLASSIFIER_VERSION,
+                    scheduled_start_at,
+                    live_started_at,
+                    d.duration
+                        .as_deref()
+                        .and_then(crate::duration::duration_seconds),
                     d.id
                 ],
             )
@@ -238,6 +261,8 @@ mod tests {
             duration: Some("PT3M".into()),
             is_livestream: false,
             livestream_ended_at: None,
+            scheduled_start_at: None,
+            live_started_at: None,
             player_width: Some(720),
             player_height: Some(1280),
         }];
@@ -247,6 +272,17 @@ mod tests {
         }
         let (duration, is_short, _, _, checked) = video_row(&state, "v_short");
         assert_eq!(duration.as_deref(), Some("PT3M"));
+        let seconds: Option<i64> = state
+            .db
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT duration_seconds FROM videos WHERE id = 'v_short'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(seconds, Some(180));
         assert_eq!(is_short, 1);
         assert_eq!(checked, Some(1000));
         let version: i64 = state
@@ -270,6 +306,8 @@ mod tests {
             duration: Some("PT45S".into()),
             is_livestream: false,
             livestream_ended_at: None,
+            scheduled_start_at: None,
+            live_started_at: None,
             player_width: None,
             player_height: None,
         }];
@@ -293,6 +331,8 @@ mod tests {
             duration: None,
             is_livestream: false,
             livestream_ended_at: None,
+            scheduled_start_at: None,
+            live_started_at: None,
             player_width: None,
             player_height: None,
         }];
@@ -320,6 +360,8 @@ mod tests {
             duration: Some("PT0S".into()),
             is_livestream: true,
             livestream_ended_at: None,
+            scheduled_start_at: None,
+            live_started_at: None,
             player_width: Some(720),
             player_height: Some(1280),
         }];
@@ -348,6 +390,8 @@ mod tests {
             duration: Some("PT1H2M".into()),
             is_livestream: true,
             livestream_ended_at: Some("2024-01-15T10:00:00Z".into()),
+            scheduled_start_at: None,
+            live_started_at: None,
             player_width: Some(720),
             player_height: Some(1280),
         }];
@@ -362,6 +406,32 @@ mod tests {
         assert_eq!(checked, Some(2000));
     }
 
+    #[test]
+    fn upcoming_live_stores_its_schedule_as_unix_time() {
+        let state = setup_conn();
+        let details = vec![VideoDetails {
+            id: "v_live".into(),
+            duration: Some("P0D".into()),
+            is_livestream: true,
+            livestream_ended_at: None,
+            scheduled_start_at: Some("2024-01-15T10:00:00Z".into()),
+            live_started_at: None,
+            player_width: None,
+            player_height: None,
+        }];
+        let conn = state.db.lock().unwrap();
+        apply_video_details(&conn, &details, &["v_live".to_string()], 1000);
+        let (scheduled, started): (Option<i64>, Option<i64>) = conn
+            .query_row(
+                "SELECT scheduled_start_at, live_started_at FROM videos WHERE id = 'v_live'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(scheduled, Some(1705312800));
+        assert_eq!(started, None);
+    }
+
     #[test]
     fn video_absent_from_response_is_marked_checked() {
         // Deleted/private videos never appear in videos.list responses; without
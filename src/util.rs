@@ -34,6 +34,26 @@ pub fn row_timestamp_to_rfc3339(
     }
 }
 
+/// Unix seconds of a timestamp column, accepting legacy RFC 3339 TEXT like
+/// [`row_timestamp_to_rfc3339`].
+pub fn row_timestamp_to_unix(
+    row: &rusqlite::Row<'_>,
+    index: usize,
+) -> rusqlite::Result<Option<i64>> {
+    use rusqlite::types::ValueRef;
+    match row.get_ref(index)? {
+        ValueRef::Integer(value) => Ok(Some(value)),
+        ValueRef::Text(value) => Ok(std::str::from_utf8(value).ok().and_then(rfc3339_to_unix)),
+        _ => Ok(None),
+    }
+}
+
+/// Delay before retry number `attempts` (1-based) of a failed outbound
+/// delivery: 30s, 1m, 2m, … capped at 1h.
+pub fn retry_delay_secs(attempts: i64) -> i64 {
+    (30 << (attempts - 1).clamp(0, 16)).min(60 * 60)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -48,4 +68,12 @@ mod tests {
         assert_eq!(rfc3339_to_unix("2024-01-15 10:00:00"), None);
         assert_eq!(rfc3339_to_unix("not-a-date"), None);
     }
+
+    #[test]
+    fn retry_delay_doubles_and_caps_at_one_hour() {
+        assert_eq!(retry_delay_secs(1), 30);
+        assert_eq!(retry_delay_secs(2), 60);
+        assert_eq!(retry_delay_secs(5), 480);
+        assert_eq!(retry_delay_secs(10), 3600);
+    }
 }
@@ -0,0 +1,213 @@
+//! Which videos a user gets to see, shared by every output that lists or
+//! announces videos (`/api/feed`, `/api/search`, `/api/rss`, `/api/news`,
+//! digests, notifications) so they cannot drift apart.
+//!
+//! The predicates are SQL fragments over the aliases `v` (videos), `uc` (the
+//! user's `user_channels` row) and `uv` (the user's `user_videos` row, LEFT
+//! JOINed). A video is visible when the user has not hidden it, it is not
+//! members-only, the channel's `show_livestreams` / `hide_shorts` settings let
+//! it through, and none of the user's `mute_rules` matches it. Videos saved to
+//! Watch Later additionally leave `/api/feed` ([`NOT_QUEUED`]) but nothing
+//! else: they are neither watched nor uninteresting.
+//!
+//! A mute rule is a case-insensitive title keyword, or a regex when
+//! `is_regex = 1`, optionally scoped to one channel or one group. Notification
+//! rule keywords use the same matching. SQLite has no regex support, so it is
+//! done by the `title_matches(pattern, is_regex, title)` function that
+//! [`register_functions`] installs on every connection.
+
+use rusqlite::functions::FunctionFlags;
+use rusqlite::Connection;
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+
+macro_rules! channel_settings {
+    () => {
+        "(v.is_livestream = 0 OR uc.show_livestreams = 1)
+           AND (v.is_short = 0 OR uc.hide_shorts = 0)"
+    };
+}
+
+macro_rules! not_muted {
+    () => {
+        "NOT EXISTS (
+             SELECT 1 FROM mute_rules m
+             WHERE m.user_id = uc.user_id
+               AND (m.channel_id IS NULL OR m.channel_id = v.channel_id)
+               AND (m.group_id IS NULL OR EXISTS (
+                     SELECT 1 FROM channel_groups mg
+                     WHERE mg.group_id = m.group_id AND mg.channel_id = v.channel_id))
+               AND title_matches(m.pattern, m.is_regex, v.title))"
+    };
+}
+
+/// The channel's livestream / Shorts settings accept the video.
+pub const CHANNEL_SETTINGS: &str = channel_settings!();
+
+/// No mute rule of the `uc` user matches the video.
+pub const NOT_MUTED: &str = not_muted!();
+
+/// The user's hidden flag and the members-only exclusion on top of
+/// [`CHANNEL_SETTINGS`] and [`NOT_MUTED`]: what every video list shows.
+pub const VISIBLE: &str = concat!(
+    "COALESCE(uv.is_hidden, 0) = 0
+           AND v.is_members_only = 0
+           AND ",
+    channel_settings!(),
+    "
+           AND ",
+    not_muted!()
+);
+
+/// The video is not in the `uc` user's Watch Later queue.
+pub const NOT_QUEUED: &str = "NOT EXISTS (
+             SELECT 1 FROM watch_later wl
+             WHERE wl.user_id = uc.user_id AND wl.video_id = v.id)";
+
+/// A one-row `v` made of bound parameters, for checking a video that is at
+/// hand rather than queried: `?1` channel_id, `?2` title, `?3` is_short,
+/// `?4` is_livestream.
+pub const BOUND_VIDEO: &str =
+    "(SELECT ?1 AS channel_id, ?2 AS title, ?3 AS is_short, ?4 AS is_livestream) v";
+
+/// Compiled title regexes by pattern. Rules are few, but previews can try
+/// arbitrary patterns, so the cache is dropped once it grows past this.
+const REGEX_CACHE_LIMIT: usize = 256;
+
+static REGEX_CACHE: LazyLock<Mutex<HashMap<String, Option<regex_lite::Regex>>>> =
+    LazyLock::new(Default::default);
+
+/// Whether a rule pattern (mute or notification) matches `title`: a
+/// case-insensitive substring, or a case-insensitive regex. An invalid regex
+/// (only possible for rows written before validation) matches nothing.
+pub fn title_matches(pattern: &str, is_regex: bool, title: &str) -> bool {
+    if !is_regex {
+        return title.to_lowercase().contains(&pattern.to_lowercase());
+    }
+    let mut cache = REGEX_CACHE.lock().unwrap();
+    if cache.len() >= REGEX_CACHE_LIMIT && !cache.contains_key(pattern) {
+        cache.clear();
+    }
+    cache
+        .entry(pattern.to_string())
+        .or_insert_with(|| regex_lite::Regex::new(&format!("(?i){pattern}")).ok())
+        .as_ref()
+        .is_some_and(|re| re.is_match(title))
+}
+
+/// Whether `pattern` is accepted as a regex by [`title_matches`].
+pub fn is_valid_regex(pattern: &str) -> bool {
+    regex_lite::Regex::new(&format!("(?i){pattern}")).is_ok()
+}
+
+/// Install `title_matches` on a connection. Must run before any query that
+/// uses [`NOT_MUTED`] / [`VISIBLE`] or matches rule keywords.
+pub fn register_functions(conn: &Connection) -> rusqlite::Result<()> {
+    conn.create_scalar_function(
+        "title_matches",
+        3,
+        FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+        |ctx| {
+            let pattern: Option<String> = ctx.get(0)?;
+            let is_regex: i64 = ctx.get(1)?;
+            let title: Option<String> = ctx.get(2)?;
+            Ok(match (pattern, title) {
+                (Some(pattern), Some(title)) => title_matches(&pattern, is_regex != 0, &title),
+                _ => false,
+            })
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    // Visibility Spec
+    //
+    // - Title patterns (mute and notification rules alike) are
+    //   case-insensitive substrings, or case-insensitive regexes when is_regex
+    //   is set; an invalid regex matches nothing.
+    // - A mute rule applies to its owner only, optionally limited to one
+    //   channel or one group.
+
+    use super::*;
+
+    #[test]
+    fn title_matches_keyword_case_insensitively_or_by_regex() {
+        assert!(title_matches("ASMR", false, "Relaxing asmr for sleep"));
+        assert!(!title_matches("asmr", false, "Morning stream"));
+        assert!(title_matches(
+            r"^\[?(切り抜き|clip)",
+            true,
+            "Clip: best moments"
+        ));
+        assert!(title_matches(
+            r"^\[?(切り抜き|clip)",
+            true,
+            "[切り抜き] 神回"
+        ));
+        assert!(!title_matches(
+            r"^\[?(切り抜き|clip)",
+            true,
+            "Full stream clip"
+        ));
+        assert!(
+            !title_matches("(", true, "("),
+            "an invalid regex matches nothing"
+        );
+    }
+
+    fn visible_ids(conn: &Connection, user_id: i64) -> Vec<String> {
+        let sql = format!(
+            "SELECT v.id FROM videos v
+             JOIN user_channels uc ON uc.channel_id = v.channel_id AND uc.user_id = ?1
+             LEFT JOIN user_videos uv ON uv.video_id = v.id AND uv.user_id = ?1
+             WHERE {VISIBLE}
+             ORDER BY v.id"
+        );
+        let mut stmt = conn.prepare(&sql).unwrap();
+        let ids = stmt
+            .query_map([user_id], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        ids
+    }
+
+    #[test]
+    fn mute_rules_apply_to_their_owner_and_scope() {
+        let conn = crate::db::open_memory();
+        conn.execute_batch(
+            "INSERT INTO users (email) VALUES ('a@example.com'), ('b@example.com');
+             INSERT INTO channels (id, title) VALUES ('UC1', 'Ch1'), ('UC2', 'Ch2');
+             INSERT INTO user_channels (user_id, channel_id) VALUES (1, 'UC1'), (1, 'UC2'), (2, 'UC1');
+             INSERT INTO groups (user_id, name) VALUES (1, 'G');
+             INSERT INTO channel_groups (channel_id, group_id) VALUES ('UC2', 1);
+             INSERT INTO videos (id, channel_id, title) VALUES
+               ('v1', 'UC1', 'Spoiler talk'), ('v2', 'UC2', 'Spoiler talk'),
+               ('v3', 'UC1', 'Game night'), ('v4', 'UC2', 'Game night');",
+        )
+        .unwrap();
+        let all = ["v1", "v2", "v3", "v4"];
+        assert_eq!(visible_ids(&conn, 1), all);
+
+        conn.execute(
+            "INSERT INTO mute_rules (user_id, pattern) VALUES (1, 'spoiler')",
+            [],
+        )
+        .unwrap();
+        assert_eq!(visible_ids(&conn, 1), ["v3", "v4"]);
+        assert_eq!(
+            visible_ids(&conn, 2),
+            ["v1", "v3"],
+            "other users unaffected"
+        );
+
+        conn.execute_batch(
+            "DELETE FROM mute_rules;
+             INSERT INTO mute_rules (user_id, pattern, channel_id) VALUES (1, 'spoiler', 'UC1');
+             INSERT INTO mute_rules (user_id, pattern, is_regex, group_id) VALUES (1, '^game', 1, 1);",
+        )
+        .unwrap();
+        assert_eq!(visible_ids(&conn, 1), ["v2", "v3"]);
+    }
+}
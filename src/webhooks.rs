@@ -0,0 +1,467 @@
+//! User-registered outbound webhooks for feed events.
+//!
+//! Events are recorded as rows in `webhook_deliveries` at the moment they
+//! happen (inside the request or push that caused them), one row per matching
+//! hook. A background worker ([`deliver_due`]) POSTs due rows and retries
+//! failures with exponential backoff, so the table doubles as the delivery
+//! log shown by `/api/webhooks/{id}/deliveries`.
+//!
+//! Every POST carries:
+//! - `X-Hub-Signature-256: sha256=<hex HMAC-SHA256(secret, body)>` — the same
+//!   scheme as WebSub / GitHub, so receivers can reuse existing verifiers
+//! - `X-Webhook-Event` and `X-Webhook-Delivery` (delivery row id; stable
+//!   across retries, usable for de-duplication)
+//!
+//! Hook URLs must reach a public address ([`crate::outbound`]); that is
+//! checked when a hook is saved and again before every POST.
+
+use crate::state::AppState;
+use hmac::{Hmac, Mac};
+use rusqlite::Connection;
+use serde_json::{json, Value};
+use sha2::Sha256;
+use std::time::Duration;
+
+pub const VIDEO_CREATED: &str = "video.created";
+pub const VIDEO_ENRICHED: &str = "video.enriched";
+pub const VIDEO_HIDDEN: &str = "video.hidden";
+pub const CHANNEL_ADDED: &str = "channel.added";
+pub const CHANNEL_REMOVED: &str = "channel.removed";
+
+pub const EVENTS: [&str; 5] = [
+    VIDEO_CREATED,
+    VIDEO_ENRICHED,
+    VIDEO_HIDDEN,
+    CHANNEL_ADDED,
+    CHANNEL_REMOVED,
+];
+
+/// How often the worker looks for due deliveries.
+pub const WORKER_INTERVAL_SECS: u64 = 10;
+/// Attempts before a delivery is marked failed (~1h of retries in total).
+const MAX_ATTEMPTS: i64 = 8;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// Finished deliveries are kept this long for the delivery log.
+const LOG_RETENTION_SECS: i64 = 30 * 24 * 60 * 60;
+
+/// `sha256=<hex>` signature of `body` for the `X-Hub-Signature-256` header.
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC can take any key length");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Active users subscribed to a channel — the audience of its video events.
+pub fn channel_subscribers(conn: &Connection, channel_id: &str) -> Vec<i64> {
+    conn.prepare(
+        "SELECT uc.user_id FROM user_channels uc
+         JOIN users u ON u.id = uc.user_id
+         WHERE uc.channel_id = ?1 AND u.disabled_at IS NULL",
+    )
+    .and_then(|mut stmt| {
+        stmt.query_map([channel_id], |row| row.get(0))?
+            .collect::<Result<Vec<i64>, _>>()
+    })
+    .unwrap_or_default()
+}
+
+/// Queue `event` for every enabled hook of `user_ids` subscribed to it.
+/// Never fails the caller: webhook bookkeeping must not break the feed.
+pub fn emit(conn: &Connection, user_ids: &[i64], event: &str, data: Value) {
+    if user_ids.is_empty() {
+        return;
+    }
+    let now = crate::util::now_unix();
+    let payload = json!({
+        "event": event,
+        "created_at": crate::util::unix_to_rfc3339(now),
+        "data": data,
+    })
+    .to_string();
+    let placeholders = vec!["?"; user_ids.len()].join(",");
+    let sql = format!(
+        "INSERT INTO webhook_deliveries (webhook_id, event, payload, status, attempts, next_attempt_at, created_at)
+         SELECT w.id, ?, ?, 'pending', 0, ?, ?
+         FROM webhooks w
+         WHERE w.is_enabled = 1
+           AND (',' || w.events || ',') LIKE '%,' || ? || ',%'
+           AND w.user_id IN ({placeholders})"
+    );
+    let mut params: Vec<rusqlite::types::Value> = vec![
+        event.to_string().into(),
+        payload.into(),
+        now.into(),
+        now.into(),
+        event.to_string().into(),
+    ];
+    params.extend(user_ids.iter().map(|&id| id.into()));
+    if let Err(e) = conn.execute(&sql, rusqlite::params_from_iter(params)) {
+        tracing::warn!("[webhook] failed to queue {}: {}", event, e);
+    }
+}
+
+/// Shared payload of `video.created` / `video.enriched`.
+pub fn video_data(video: &crate::notify::NewVideo) -> Value {
+    json!({
+        "video_id": video.id,
+        "channel_id": video.channel_id,
+        "channel_title": video.channel_title,
+        "title": video.title,
+        "published_at": video.published_at.and_then(crate::util::unix_to_rfc3339),
+        "duration": video.duration,
+        "is_short": video.is_short,
+        "is_livestream": video.is_livestream,
+        "url": format!("https://www.youtube.com/watch?v={}", video.id),
+    })
+}
+
+/// Emit a video event for each given video to the channel's subscribers.
+pub fn emit_video_events(conn: &Connection, event: &str, video_ids: &[String]) {
+    for video in crate::notify::load_new_videos(conn, video_ids) {
+        let users = channel_subscribers(conn, &video.channel_id);
+        emit(conn, &users, event, video_data(&video));
+    }
+}
+
+/// `video.enriched` for the videos that actually received details — skipped
+/// enrichment (no API key) and rows the API left out don't count.
+pub fn emit_enriched(conn: &Connection, video_ids: &[String]) {
+    let enriched: Vec<String> = video_ids
+        .iter()
+        .filter(|id| {
+            conn.query_row(
+                "SELECT 1 FROM videos WHERE id = ?1 AND details_checked_at IS NOT NULL",
+                [id],
+                |_| Ok(()),
+            )
+            .is_ok()
+        })
+        .cloned()
+        .collect();
+    emit_video_events(conn, VIDEO_ENRICHED, &enriched);
+}
+
+/// Payload of `channel.added` / `channel.removed`.
+pub fn channel_data(conn: &Connection, channel_id: &str) -> Value {
+    let title: Option<String> = conn
+        .query_row(
+            "SELECT title FROM channels WHERE id = ?1",
+            [channel_id],
+            |row| row.get(0),
+        )
+        .ok();
+    json!({
+        "channel_id": channel_id,
+        "title": title,
+        "url": format!("https://www.youtube.com/channel/{}", channel_id),
+    })
+}
+
+struct Due {
+    id: i64,
+    event: String,
+    payload: String,
+    attempts: i64,
+    url: String,
+    secret: String,
+}
+
+fn take_due(conn: &Connection, now: i64) -> Vec<Due> {
+    conn.prepare(
+        "SELECT d.id, d.event, d.payload, d.attempts, w.url, w.secret
+         FROM webhook_deliveries d
+         JOIN webhooks w ON w.id = d.webhook_id
+         WHERE d.status = 'pending' AND d.next_attempt_at <= ?1
+         ORDER BY d.id
+         LIMIT 100",
+    )
+    .and_then(|mut stmt| {
+        stmt.query_map([now], |row| {
+            Ok(Due {
+                id: row.get(0)?,
+                event: row.get(1)?,
+                payload: row.get(2)?,
+                attempts: row.get(3)?,
+                url: row.get(4)?,
+                secret: row.get(5)?,
+            })
+        })?
+        .collect()
+    })
+    .unwrap_or_default()
+}
+
+async fn post(state: &AppState, due: &Due) -> (Option<u16>, Result<(), String>) {
+    let http = match crate::outbound::client_for(&due.url, &state.config).await {
+        Ok(http) => http,
+        Err(e) => return (None, Err(format!("refused: {e}"))),
+    };
+    let result = http
+        .post(&due.url)
+        .timeout(REQUEST_TIMEOUT)
+        .header("Content-Type", "application/json")
+        .header(
+            "X-Hub-Signature-256",
+            sign(&due.secret, due.payload.as_bytes()),
+        )
+        .header("X-Webhook-Event", &due.event)
+        .header("X-Webhook-Delivery", due.id.to_string())
+        .body(due.payload.clone())
+        .send()
+        .await;
+    match result {
+        Ok(resp) if resp.status().is_success() => (Some(resp.status().as_u16()), Ok(())),
+        Ok(resp) => (
+            Some(resp.status().as_u16()),
+            Err(format!("HTTP {}", resp.status())),
+        ),
+        Err(e) => (None, Err(e.without_url().to_string())),
+    }
+}
+
+/// POST every due delivery once and reschedule or finalise it.
+pub async fn deliver_due(state: &AppState) {
+    let now = crate::util::now_unix();
+    let due = {
+        let conn = state.db.lock().unwrap();
+        let _ = conn.execute(
+            "DELETE FROM webhook_deliveries WHERE status != 'pending' AND created_at < ?1",
+            [now - LOG_RETENTION_SECS],
+        );
+        take_due(&conn, now)
+    };
+
+    for delivery in due {
+        let (response_status, result) = post(state, &delivery).await;
+        let attempts = delivery.attempts + 1;
+        let finished = crate::util::now_unix();
+        let conn = state.db.lock().unwrap();
+        let update = match &result {
+            Ok(()) => conn.execute(
+                "UPDATE webhook_deliveries
+                 SET status = 'delivered', attempts = ?1, response_status = ?2,
+                     last_error = NULL, delivered_at = ?3
+                 WHERE id = ?4",
+                rusqlite::params![attempts, response_status, finished, delivery.id],
+            ),
+            Err(error) => {
+                let (status, next) = if attempts >= MAX_ATTEMPTS {
+                    tracing::warn!(
+                        "[webhook] delivery {} ({}) failed permanently: {}",
+                        delivery.id,
+                        delivery.event,
+                        error
+                    );
+                    ("failed", None)
+                } else {
+                    (
+                        "pending",
+                        Some(finished + crate::util::retry_delay_secs(attempts)),
+                    )
+                };
+                conn.execute(
+                    "UPDATE webhook_deliveries
+                     SET status = ?1, attempts = ?2, response_status = ?3,
+                         last_error = ?4, next_attempt_at = COALESCE(?5, next_attempt_at)
+                     WHERE id = ?6",
+                    rusqlite::params![status, attempts, response_status, error, next, delivery.id],
+                )
+            }
+        };
+        if let Err(e) = update {
+            tracing::warn!("[webhook] failed to record delivery {}: {}", delivery.id, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // Webhook Delivery Spec
+    //
+    // emit() queues one pending row per enabled hook of the audience that
+    // subscribes to the event. deliver_due() POSTs the JSON payload signed
+    // with X-Hub-Signature-256; 2xx marks it delivered, anything else
+    // reschedules it with exponential backoff until MAX_ATTEMPTS. A URL that
+    // resolves to a non-public address is refused without a request.
+
+    use super::*;
+    use crate::notify::stand_in::HttpStandIn;
+
+    fn setup(url: &str, events: &str) -> AppState {
+        let state = AppState::test();
+        {
+            let conn = state.db.lock().unwrap();
+            conn.execute("INSERT INTO users (email) VALUES ('a@example.com')", [])
+                .unwrap();
+            conn.execute(
+                "INSERT INTO webhooks (user_id, url, secret, events) VALUES (1, ?1, 'topsecret', ?2)",
+                rusqlite::params![url, events],
+            )
+            .unwrap();
+        }
+        state
+    }
+
+    fn delivery(state: &AppState) -> (String, i64, Option<i64>, Option<String>, i64) {
+        state
+            .db
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT status, attempts, response_status, last_error, next_attempt_at FROM webhook_deliveries",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)),
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn sign_matches_known_hmac_sha256() {
+        // echo -n 'hello' | openssl dgst -sha256 -hmac 'key'
+        assert_eq!(
+            sign("key", b"hello"),
+            "sha256=9307b3b915efb5171ff14d8cb55fbcc798c6c0ef1456d66ded1a6aa723a58b7b"
+        );
+    }
+
+    #[test]
+    fn emit_only_queues_subscribed_enabled_hooks_of_the_audience() {
+        let state = setup("https://hooks.example.com/a", "video.created,channel.added");
+        let conn = state.db.lock().unwrap();
+        conn.execute(
+            "INSERT INTO webhooks (user_id, url, secret, events, is_enabled) VALUES (1, 'https://hooks.example.com/b', 's', 'video.created', 0)",
+            [],
+        )
+        .unwrap();
+
+        emit(&conn, &[1], VIDEO_HIDDEN, json!({}));
+        emit(&conn, &[2], VIDEO_CREATED, json!({}));
+        emit(&conn, &[], VIDEO_CREATED, json!({}));
+        emit(&conn, &[1, 2], VIDEO_CREATED, json!({"video_id": "v1"}));
+
+        let rows: Vec<(i64, String)> = conn
+            .prepare("SELECT webhook_id, event FROM webhook_deliveries")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(rows, vec![(1, "video.created".to_string())]);
+    }
+
+    #[test]
+    fn emit_matches_whole_event_names_only() {
+        // "video.created" must not match a hook subscribed to a longer name
+        // sharing the prefix, nor vice versa.
+        let state = setup("https://hooks.example.com/a", "video.createdx");
+        let conn = state.db.lock().unwrap();
+        emit(&conn, &[1], VIDEO_CREATED, json!({}));
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM webhook_deliveries", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(count, 0);
+    }
+
+    #[tokio::test]
+    async fn successful_delivery_is_signed_and_marked_delivered() {
+        let hook = HttpStandIn::start().await;
+        let state = setup(&hook.url("/hook"), "channel.added");
+        {
+            let conn = state.db.lock().unwrap();
+            emit(
+                &conn,
+                &[1],
+                CHANNEL_ADDED,
+                json!({"channel_id": "UC1", "title": "Ch"}),
+            );
+        }
+
+        deliver_due(&state).await;
+
+        let requests = hook.requests();
+        assert_eq!(requests.len(), 1);
+        let req = &requests[0];
+        assert_eq!(req.header("x-webhook-event"), Some("channel.added"));
+        assert_eq!(req.header("x-webhook-delivery"), Some("1"));
+        assert_eq!(
+            req.header("x-hub-signature-256"),
+            Some(sign("topsecret", &req.body).as_str())
+        );
+        let body = req.json();
+        assert_eq!(body["event"], "channel.added");
+        assert_eq!(body["data"]["channel_id"], "UC1");
+
+        let (status, attempts, response_status, last_error, _) = delivery(&state);
+        assert_eq!(status, "delivered");
+        assert_eq!(attempts, 1);
+        assert_eq!(response_status, Some(200));
+        assert_eq!(last_error, None);
+
+        // Delivered rows are not sent again.
+        deliver_due(&state).await;
+        assert_eq!(hook.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn failed_delivery_backs_off_then_gives_up() {
+        let hook = HttpStandIn::start_with_status(500).await;
+        let state = setup(&hook.url("/hook"), "video.hidden");
+        {
+            let conn = state.db.lock().unwrap();
+            emit(&conn, &[1], VIDEO_HIDDEN, json!({"video_id": "v1"}));
+        }
+
+        let before = crate::util::now_unix();
+        deliver_due(&state).await;
+        let (status, attempts, response_status, last_error, next) = delivery(&state);
+        assert_eq!(status, "pending");
+        assert_eq!(attempts, 1);
+        assert_eq!(response_status, Some(500));
+        assert!(last_error.unwrap().contains("500"));
+        assert!(next >= before + 30, "retry must be scheduled in the future");
+
+        // Not due yet: no second request.
+        deliver_due(&state).await;
+        assert_eq!(hook.requests().len(), 1);
+
+        // Last allowed attempt fails → failed, no further retries.
+        state
+            .db
+            .lock()
+            .unwrap()
+            .execute(
+                "UPDATE webhook_deliveries SET next_attempt_at = 0, attempts = ?1",
+                [MAX_ATTEMPTS - 1],
+            )
+            .unwrap();
+        deliver_due(&state).await;
+        let (status, attempts, ..) = delivery(&state);
+        assert_eq!(status, "failed");
+        assert_eq!(attempts, MAX_ATTEMPTS);
+        deliver_due(&state).await;
+        assert_eq!(hook.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn private_urls_are_refused_at_send_time() {
+        // Saved while ALLOW_PRIVATE_TARGETS was on, or its DNS changed since.
+        let hook = HttpStandIn::start().await;
+        let mut state = setup(&hook.url("/hook"), "video.hidden");
+        state.config.allow_private_targets = false;
+        {
+            let conn = state.db.lock().unwrap();
+            emit(&conn, &[1], VIDEO_HIDDEN, json!({"video_id": "v1"}));
+        }
+
+        deliver_due(&state).await;
+        let (status, attempts, response_status, last_error, _) = delivery(&state);
+        assert_eq!(status, "pending");
+        assert_eq!(attempts, 1);
+        assert_eq!(response_status, None);
+        assert!(last_error.unwrap().contains("non-public"));
+        assert!(hook.requests().is_empty());
+    }
+}
@@ -18,6 +18,10 @@ pub struct VideoDetails {
     pub is_livestream: bool,
     /// RFC3339 end time of a finished livestream/premiere.
     pub livestream_ended_at: Option<String>,
+    /// RFC3339 announced start of an upcoming livestream/premiere.
+    pub scheduled_start_at: Option<String>,
+    /// RFC3339 time the livestream/premiere actually went live.
+    pub live_started_at: Option<String>,
     /// Dimensions of the embedded player, scaled within a square boundary.
     /// Missing values classify as a regular video.
     pub player_width: Option<u64>,
@@ -94,6 +98,12 @@ pub fn parse_video_details(data: &Value) -> Result<Vec<VideoDetails>, FetchError
                 livestream_ended_at: item["liveStreamingDetails"]["actualEndTime"]
                     .as_str()
                     .map(|s| s.to_string()),
+                scheduled_start_at: item["liveStreamingDetails"]["scheduledStartTime"]
+                    .as_str()
+                    .map(|s| s.to_string()),
+                live_started_at: item["liveStreamingDetails"]["actualStartTime"]
+                    .as_str()
+                    .map(|s| s.to_string()),
                 // Google Discovery represents int64 fields as JSON strings.
                 // Keep accepting numbers too so fixtures and proxy-normalized
                 // responses remain compatible.
@@ -261,6 +271,36 @@ mod tests {
         assert!(details[0].is_ongoing_live());
     }
 
+    #[test]
+    fn upcoming_stream_keeps_its_schedule_until_it_starts() {
+        let data = json!({"items": [
+            {
+                "id": "v_upcoming",
+                "contentDetails": {"duration": "P0D"},
+                "liveStreamingDetails": {"scheduledStartTime": "2024-01-01T12:00:00Z"}
+            },
+            {
+                "id": "v_started",
+                "contentDetails": {"duration": "PT0S"},
+                "liveStreamingDetails": {
+                    "scheduledStartTime": "2024-01-01T12:00:00Z",
+                    "actualStartTime": "2024-01-01T12:03:00Z"
+                }
+            }
+        ]});
+        let details = parse_video_details(&data).unwrap();
+        assert!(details[0].is_ongoing_live());
+        assert_eq!(
+            details[0].scheduled_start_at.as_deref(),
+            Some("2024-01-01T12:00:00Z")
+        );
+        assert_eq!(details[0].live_started_at, None);
+        assert_eq!(
+            details[1].live_started_at.as_deref(),
+            Some("2024-01-01T12:03:00Z")
+        );
+    }
+
     #[test]
     fn ended_livestream_is_not_ongoing() {
         let data = json!({"items": [{